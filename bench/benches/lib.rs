@@ -1,57 +1,59 @@
 // $ cargo +nightly bench --package bench
 //    Compiling bench v0.0.0 (safe-regex-rs/bench)
-//     Finished bench [optimized] target(s) in 7.89s
-//      Running safe-regex-rs/target/release/deps/lib-c7a79fd1a7d4629d
+//     Finished `bench` profile [optimized] target(s) in 59.06s
+//      Running benches/lib.rs (target/release/deps/lib-e494cddeb9c03725)
 //
-// running 40 tests
-// test capture10_regex                  ... bench:       2,720 ns/iter (+/- 1,105)
-// test capture10_safe_regex             ... bench:       1,518 ns/iter (+/- 465)
-// test datetime_capture_100_regex       ... bench:         203 ns/iter (+/- 56)
-// test datetime_capture_100_safe_regex  ... bench:       1,979 ns/iter (+/- 228)
-// test datetime_capture_10kb_regex      ... bench:      12,562 ns/iter (+/- 1,496)
-// test datetime_capture_10kb_safe_regex ... bench:     204,491 ns/iter (+/- 18,534)
-// test datetime_capture_1kb_regex       ... bench:       1,147 ns/iter (+/- 127)
-// test datetime_capture_1kb_safe_regex  ... bench:      20,047 ns/iter (+/- 2,411)
-// test datetime_parse_regex             ... bench:         394 ns/iter (+/- 161)
-// test datetime_parse_safe_regex        ... bench:         299 ns/iter (+/- 33)
-// test pem_base64_regex                 ... bench:         129 ns/iter (+/- 15)
-// test pem_base64_safe_regex            ... bench:       5,768 ns/iter (+/- 657)
-// test phone_capture_100kb_regex        ... bench:     189,552 ns/iter (+/- 18,942)
-// test phone_capture_100kb_safe_regex   ... bench:     995,685 ns/iter (+/- 145,972)
-// test phone_capture_10kb_regex         ... bench:      19,032 ns/iter (+/- 2,239)
-// test phone_capture_10kb_safe_regex    ... bench:     104,156 ns/iter (+/- 11,140)
-// test phone_capture_1kb_regex          ... bench:       1,892 ns/iter (+/- 344)
-// test phone_capture_1kb_safe_regex     ... bench:       9,927 ns/iter (+/- 1,115)
-// test phone_capture_1mb_regex          ... bench:   1,945,744 ns/iter (+/- 225,035)
-// test phone_capture_1mb_safe_regex     ... bench:  10,556,256 ns/iter (+/- 945,806)
-// test repeat10_regex                   ... bench:          37 ns/iter (+/- 4)
-// test repeat10_safe_regex              ... bench:         109 ns/iter (+/- 35)
-// test repeat20_regex                   ... bench:         209 ns/iter (+/- 32)
-// test repeat20_safe_regex              ... bench:         602 ns/iter (+/- 65)
-// test repeat30_regex                   ... bench:          70 ns/iter (+/- 7)
-// test repeat30_safe_regex              ... bench:       1,322 ns/iter (+/- 211)
-// test repeat_capture10_regex           ... bench:         346 ns/iter (+/- 379)
-// test repeat_capture10_safe_regex      ... bench:         242 ns/iter (+/- 37)
-// test repeat_capture20_regex           ... bench:         329 ns/iter (+/- 39)
-// test repeat_capture20_safe_regex      ... bench:       1,016 ns/iter (+/- 95)
-// test repeat_capture30_regex           ... bench:         372 ns/iter (+/- 74)
-// test repeat_capture30_safe_regex      ... bench:       2,372 ns/iter (+/- 224)
-// test string_search_100_regex          ... bench:          30 ns/iter (+/- 3)
-// test string_search_100_safe_regex     ... bench:         546 ns/iter (+/- 59)
-// test string_search_100kb_regex        ... bench:       1,239 ns/iter (+/- 233)
-// test string_search_100kb_safe_regex   ... bench:     544,677 ns/iter (+/- 66,326)
-// test string_search_10kb_regex         ... bench:         114 ns/iter (+/- 13)
-// test string_search_10kb_safe_regex    ... bench:      54,988 ns/iter (+/- 4,956)
-// test string_search_1kb_regex          ... bench:          29 ns/iter (+/- 2)
-// test string_search_1kb_safe_regex     ... bench:       5,484 ns/iter (+/- 485)
+// running 42 tests
+// test capture10_regex                  ... bench:       2,254 ns/iter (+/- 200)
+// test capture10_safe_regex             ... bench:       1,253 ns/iter (+/- 480)
+// test capture20_regex                  ... bench:       7,530 ns/iter (+/- 3,675)
+// test capture20_safe_regex             ... bench:      16,346 ns/iter (+/- 8,359)
+// test datetime_capture_100_regex       ... bench:          63 ns/iter (+/- 29)
+// test datetime_capture_100_safe_regex  ... bench:          34 ns/iter (+/- 7)
+// test datetime_capture_10kb_regex      ... bench:       1,807 ns/iter (+/- 174)
+// test datetime_capture_10kb_safe_regex ... bench:     402,641 ns/iter (+/- 85,576)
+// test datetime_capture_1kb_regex       ... bench:         180 ns/iter (+/- 116)
+// test datetime_capture_1kb_safe_regex  ... bench:      44,162 ns/iter (+/- 14,294)
+// test datetime_parse_regex             ... bench:         185 ns/iter (+/- 64)
+// test datetime_parse_safe_regex        ... bench:          92 ns/iter (+/- 13)
+// test pem_base64_regex                 ... bench:         210 ns/iter (+/- 35)
+// test pem_base64_safe_regex            ... bench:         144 ns/iter (+/- 14)
+// test phone_capture_100kb_regex        ... bench:     231,999 ns/iter (+/- 36,481)
+// test phone_capture_100kb_safe_regex   ... bench:   1,746,169 ns/iter (+/- 1,047,463)
+// test phone_capture_10kb_regex         ... bench:      16,661 ns/iter (+/- 1,917)
+// test phone_capture_10kb_safe_regex    ... bench:     174,088 ns/iter (+/- 94,244)
+// test phone_capture_1kb_regex          ... bench:       1,857 ns/iter (+/- 245)
+// test phone_capture_1kb_safe_regex     ... bench:      17,521 ns/iter (+/- 8,689)
+// test phone_capture_1mb_regex          ... bench:   2,641,286 ns/iter (+/- 616,633)
+// test phone_capture_1mb_safe_regex     ... bench:  20,366,805 ns/iter (+/- 9,758,482)
+// test repeat10_regex                   ... bench:          57 ns/iter (+/- 9)
+// test repeat10_safe_regex              ... bench:          12 ns/iter (+/- 4)
+// test repeat20_regex                   ... bench:         150 ns/iter (+/- 47)
+// test repeat20_safe_regex              ... bench:          13 ns/iter (+/- 2)
+// test repeat30_regex                   ... bench:         115 ns/iter (+/- 10)
+// test repeat30_safe_regex              ... bench:          13 ns/iter (+/- 1)
+// test repeat_capture10_regex           ... bench:         168 ns/iter (+/- 19)
+// test repeat_capture10_safe_regex      ... bench:         114 ns/iter (+/- 33)
+// test repeat_capture20_regex           ... bench:         209 ns/iter (+/- 22)
+// test repeat_capture20_safe_regex      ... bench:         192 ns/iter (+/- 46)
+// test repeat_capture30_regex           ... bench:         212 ns/iter (+/- 69)
+// test repeat_capture30_safe_regex      ... bench:         299 ns/iter (+/- 77)
+// test string_search_100_regex          ... bench:          81 ns/iter (+/- 52)
+// test string_search_100_safe_regex     ... bench:          28 ns/iter (+/- 13)
+// test string_search_100kb_regex        ... bench:       7,443 ns/iter (+/- 3,041)
+// test string_search_100kb_safe_regex   ... bench:      24,098 ns/iter (+/- 10,123)
+// test string_search_10kb_regex         ... bench:         676 ns/iter (+/- 242)
+// test string_search_10kb_safe_regex    ... bench:       2,425 ns/iter (+/- 832)
+// test string_search_1kb_regex          ... bench:          79 ns/iter (+/- 38)
+// test string_search_1kb_safe_regex     ... bench:         197 ns/iter (+/- 83)
 //
-// test result: ok. 0 passed; 0 failed; 0 ignored; 40 measured; 0 filtered out; finished in 146.77s
+// test result: ok. 0 passed; 0 failed; 0 ignored; 42 measured; 0 filtered out; finished in 192.83s
 #![allow(soft_unstable)]
 #![feature(test)]
 #![forbid(unsafe_code)]
 extern crate test;
 use regex::bytes::Regex;
-use safe_regex::{regex, Matcher0, Matcher1, Matcher10, Matcher3, Matcher5};
+use safe_regex::{regex, Matcher, Matcher0, Matcher1, Matcher10, Matcher3, Matcher5};
 use test::Bencher;
 
 fn rand_bytes_without_z(n: usize) -> Vec<u8> {
@@ -208,17 +210,17 @@ fn capture10_safe_regex(b: &mut Bencher) {
     b.iter(|| re.match_slices(b"aaaaaaaaaa"));
 }
 
-// #[bench]
-// fn capture20_regex(b: &mut Bencher) {
-//     let re = Regex::new(r"^(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)aaaaaaaaaaaaaaaaaaaa$").unwrap();
-//     b.iter(|| re.captures(b"aaaaaaaaaaaaaaaaaaaa"));
-// }
-//
-// #[bench]
-// fn capture20_safe_regex(b: &mut Bencher) {
-//     let re: Matcher<_> = regex!(br"(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)aaaaaaaaaaaaaaaaaaaa");
-//     b.iter(|| re.match_slices(b"aaaaaaaaaaaaaaaaaaaa"));
-// }
+#[bench]
+fn capture20_regex(b: &mut Bencher) {
+    let re = Regex::new(r"^(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)aaaaaaaaaaaaaaaaaaaa$").unwrap();
+    b.iter(|| re.captures(b"aaaaaaaaaaaaaaaaaaaa"));
+}
+
+#[bench]
+fn capture20_safe_regex(b: &mut Bencher) {
+    let re: Matcher<_, 20> = regex!(br"(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)aaaaaaaaaaaaaaaaaaaa");
+    b.iter(|| re.match_slices(b"aaaaaaaaaaaaaaaaaaaa"));
+}
 
 // TODO(mleonhard) Add tests for star: (a*)(a*)(a*), (a*)(b*)(c*), etc.
 
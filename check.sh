@@ -94,7 +94,7 @@ echo "Running cargo test."
 echo "PWD=$(pwd)"
 time (
   set -x
-  $cargo test --verbose
+  $cargo test --verbose --all-features
   set +x
   echo -n "Finished cargo test."
 )
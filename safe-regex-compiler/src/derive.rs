@@ -0,0 +1,190 @@
+//! Reads the struct definitions that derive macros receive.
+//!
+//! This crate has no `syn` dependency, so it reads only the parts of a struct
+//! definition that the derive macros need.
+#![forbid(unsafe_code)]
+use crate::CompileError;
+use safe_proc_macro2::{Delimiter, Ident, Literal, Span, TokenStream, TokenTree};
+
+/// A struct definition.
+pub struct Struct {
+    pub name: Ident,
+    /// The literals in the struct's `#[regex(...)]` attributes.
+    pub regex_attrs: Vec<Literal>,
+    pub fields: Vec<Field>,
+}
+
+/// A field of a struct.
+pub struct Field {
+    /// The field's name, or `None` for a field of a tuple struct.
+    pub name: Option<Ident>,
+    pub ty: TokenStream,
+}
+
+/// Returns the attributes at the start of `trees` as bracketed groups and
+/// leaves `trees` after them.
+fn take_attrs(trees: &mut &[TokenTree]) -> Vec<TokenStream> {
+    let mut attrs = Vec::new();
+    while let [TokenTree::Punct(punct), TokenTree::Group(group), rest @ ..] = *trees {
+        if punct.as_char() != '#' || group.delimiter() != Delimiter::Bracket {
+            break;
+        }
+        attrs.push(group.stream());
+        *trees = rest;
+    }
+    attrs
+}
+
+/// Skips visibility like `pub` or `pub(crate)` at the start of `trees`.
+fn skip_visibility(trees: &mut &[TokenTree]) {
+    if let [TokenTree::Ident(ident), rest @ ..] = *trees {
+        if ident == "pub" {
+            *trees = rest;
+            if let [TokenTree::Group(group), rest @ ..] = *trees {
+                if group.delimiter() == Delimiter::Parenthesis {
+                    *trees = rest;
+                }
+            }
+        }
+    }
+}
+
+/// Splits `stream` at the commas that are not inside a type's `<>`.
+fn split_at_commas(stream: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut parts = vec![Vec::new()];
+    let mut depth = 0_usize;
+    let mut prev_char = None;
+    for tree in stream {
+        let this_char = match &tree {
+            TokenTree::Punct(punct) => Some(punct.as_char()),
+            _ => None,
+        };
+        match this_char {
+            Some(',') if depth == 0 => {
+                parts.push(Vec::new());
+                prev_char = this_char;
+                continue;
+            }
+            Some('<') => depth += 1,
+            // `->` in a function type is not the end of a type parameter.
+            Some('>') if prev_char != Some('-') => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        prev_char = this_char;
+        parts.last_mut().unwrap().push(tree);
+    }
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+/// Reads the fields in the `{...}` or `(...)` of a struct definition.
+fn parse_fields(stream: TokenStream, named: bool) -> Result<Vec<Field>, CompileError> {
+    let mut fields = Vec::new();
+    for part in split_at_commas(stream) {
+        let mut trees = &part[..];
+        take_attrs(&mut trees);
+        skip_visibility(&mut trees);
+        let name = if named {
+            match trees {
+                [TokenTree::Ident(ident), TokenTree::Punct(colon), rest @ ..]
+                    if colon.as_char() == ':' =>
+                {
+                    trees = rest;
+                    Some(ident.clone())
+                }
+                _ => {
+                    return Err(CompileError::new(
+                        "expected a field like `name: Type`",
+                        part[0].span(),
+                    ))
+                }
+            }
+        } else {
+            None
+        };
+        if trees.is_empty() {
+            return Err(CompileError::new("expected a type", part[0].span()));
+        }
+        fields.push(Field {
+            name,
+            ty: trees.iter().cloned().collect(),
+        });
+    }
+    Ok(fields)
+}
+
+/// Reads the struct definition in `stream`.
+///
+/// # Errors
+/// Returns an error when `stream` is not a struct without generic parameters.
+pub fn parse_struct(stream: TokenStream) -> Result<Struct, CompileError> {
+    const ERR: &str = "expected a struct without generic parameters";
+    let trees: Vec<TokenTree> = stream.into_iter().collect();
+    let mut trees = &trees[..];
+    let mut regex_attrs = Vec::new();
+    for attr in take_attrs(&mut trees) {
+        let attr: Vec<TokenTree> = attr.into_iter().collect();
+        if let [TokenTree::Ident(ident), rest @ ..] = &attr[..] {
+            if ident != "regex" {
+                continue;
+            }
+            match rest {
+                [TokenTree::Group(group)] if group.delimiter() == Delimiter::Parenthesis => {
+                    let mut inner = group.stream().into_iter();
+                    match (inner.next(), inner.next()) {
+                        (Some(TokenTree::Literal(literal)), None) => {
+                            regex_attrs.push(literal);
+                        }
+                        _ => {
+                            return Err(CompileError::new(
+                                "expected an attribute like `#[regex(br\"[0-9]+\")]`",
+                                group.span(),
+                            ))
+                        }
+                    }
+                }
+                _ => {
+                    return Err(CompileError::new(
+                        "expected an attribute like `#[regex(br\"[0-9]+\")]`",
+                        ident.span(),
+                    ))
+                }
+            }
+        }
+    }
+    skip_visibility(&mut trees);
+    let name = match trees {
+        [TokenTree::Ident(keyword), TokenTree::Ident(name), rest @ ..] if keyword == "struct" => {
+            trees = rest;
+            name.clone()
+        }
+        [tree, ..] => return Err(CompileError::new(ERR, tree.span())),
+        [] => return Err(CompileError::new(ERR, Span::call_site())),
+    };
+    if let [TokenTree::Punct(punct), ..] = trees {
+        if punct.as_char() == '<' {
+            return Err(CompileError::new(
+                "generic parameters are not supported",
+                punct.span(),
+            ));
+        }
+    }
+    let fields = match trees {
+        [TokenTree::Group(group)] if group.delimiter() == Delimiter::Brace => {
+            parse_fields(group.stream(), true)?
+        }
+        [TokenTree::Group(group), TokenTree::Punct(semicolon)]
+            if group.delimiter() == Delimiter::Parenthesis && semicolon.as_char() == ';' =>
+        {
+            parse_fields(group.stream(), false)?
+        }
+        [TokenTree::Punct(semicolon)] if semicolon.as_char() == ';' => Vec::new(),
+        [tree, ..] => return Err(CompileError::new(ERR, tree.span())),
+        [] => return Err(CompileError::new(ERR, name.span())),
+    };
+    Ok(Struct {
+        name,
+        regex_attrs,
+        fields,
+    })
+}
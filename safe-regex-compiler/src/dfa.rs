@@ -0,0 +1,478 @@
+//! Converts expressions into deterministic finite automata (DFAs).
+//!
+//! A DFA reads each byte with one table lookup, so it is much faster than the
+//! state machine that the generator emits for other expressions.
+//! [`build`](fn.build.html) uses subset construction on the states of a
+//! [`Program`](../program/struct.Program.html).  The search DFA tracks the
+//! order of the search program's threads, so it finds the same leftmost-first
+//! match.
+//! It gives up on expressions with assertions and on expressions that need
+//! too many DFA states.
+//!
+//! DFAs do not track capturing groups.  When an expression is one-pass, a
+//! [`OnePass`](struct.OnePass.html) DFA finds its captures.
+#![forbid(unsafe_code)]
+use crate::program::{Expr, Inst, Machine, Nfa, Program};
+use core::convert::TryFrom;
+use std::collections::HashMap;
+
+/// The most states that one DFA may have.  State numbers fit in a `u8`.
+pub const MAX_STATES: usize = 256;
+
+/// A DFA with a transition table.
+///
+/// State 0 is the dead state, which accepts nothing and never leaves.
+/// State 1 is the start state.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dfa {
+    /// The next state for each state and byte class, at
+    /// `state * num_classes + class`.
+    pub transitions: Vec<u8>,
+    /// True for states where a match ends.
+    pub accept: Vec<bool>,
+}
+
+/// The DFAs that implement a matcher.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dfas {
+    /// The class of each byte.  Bytes in the same class have the same
+    /// transitions.
+    pub classes: [u8; 256],
+    pub num_classes: usize,
+    /// Matches all of the data.
+    pub anchored: Dfa,
+    /// Reads from the search start and accepts at the positions where the
+    /// match that a search returns may end.  The last such position is the
+    /// end of the match.
+    pub search: Dfa,
+    /// Reads backward from the end of a match.  Accepts at the positions where
+    /// the match may start.  The first position, reading backward, is the last
+    /// position in the data, so the start of the match is the last one it
+    /// accepts.
+    pub reverse: Dfa,
+    /// Finds the captures of a match.  This is `None` for expressions without
+    /// capturing groups.
+    pub one_pass: Option<OnePass>,
+}
+
+/// Updates capture ranges when a one-pass DFA reads a byte.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Action {
+    /// The groups that start before the byte.  Their ranges become empty.
+    pub open: Vec<usize>,
+    /// The groups whose ranges end after the byte.
+    pub end: Vec<usize>,
+}
+
+/// A DFA for an expression that is one-pass.
+///
+/// An expression is one-pass when the matcher never has more than one thread.
+/// At every state, each byte moves to at most one state.  Then each
+/// transition updates the capture ranges in one fixed way, and the DFA
+/// records them directly.  For example, `([0-9]+)-([0-9]+)` is one-pass and
+/// `(a*)(a*)` is not.
+///
+/// The DFA matches all of the data.  It uses the byte classes of its
+/// [`Dfas`](struct.Dfas.html).
+#[derive(Clone, Debug, PartialEq)]
+pub struct OnePass {
+    /// The next state and the action for each state and byte class, at
+    /// `state * num_classes + class`.  State 0 is the dead state and state 1 is
+    /// the start state.
+    pub transitions: Vec<(u8, u8)>,
+    /// The actions that transitions refer to.  Action 0 does nothing.
+    pub actions: Vec<Action>,
+    /// For each state, the groups that start at the end of the data, or `None`
+    /// when no match ends at the state.
+    pub accept: Vec<Option<Vec<usize>>>,
+}
+
+/// The states of a machine, as a Glushkov automaton.
+///
+/// Position `num_states` is the start, before any byte.
+struct Positions {
+    /// The bytes that each state accepts.
+    bytes: Vec<[bool; 256]>,
+    /// The groups that end after each state reads a byte.
+    ends: Vec<Vec<usize>>,
+    /// The states that can come after each position.
+    follow: Vec<Vec<usize>>,
+    /// The groups that a thread opens when it moves from each position to
+    /// each state in `follow`.
+    opens: Vec<Vec<Vec<usize>>>,
+    /// True for the positions where a match can end.
+    last: Vec<bool>,
+    /// The groups that a thread opens when it ends a match at each position.
+    last_opens: Vec<Vec<usize>>,
+}
+impl Positions {
+    /// Returns `None` when the machine has assertions.
+    fn new(machine: &Machine) -> Option<Self> {
+        let num_states = machine.states.len();
+        let mut follow = Vec::with_capacity(num_states + 1);
+        let mut opens = Vec::with_capacity(num_states + 1);
+        let mut last = Vec::with_capacity(num_states + 1);
+        let mut last_opens = Vec::with_capacity(num_states + 1);
+        for position in 0..=num_states {
+            // Finds the exprs that hold a thread when only `position` has one,
+            // and the groups that the thread opened on its way to each expr.
+            let mut values: Vec<Option<Vec<usize>>> = vec![None; machine.exprs.len()];
+            for (n, expr) in machine.exprs.iter().enumerate() {
+                values[n] = match expr {
+                    Expr::Start if position == num_states => Some(Vec::new()),
+                    Expr::Prev(state) if position == *state => Some(Vec::new()),
+                    Expr::Start | Expr::Prev(_) => None,
+                    Expr::Assert(..) => return None,
+                    Expr::First(exprs) => exprs.iter().find_map(|expr| values[*expr].clone()),
+                    Expr::OpenGroup(expr, group) => values[*expr].clone().map(|mut groups| {
+                        if !groups.contains(group) {
+                            groups.push(*group);
+                        }
+                        groups
+                    }),
+                };
+            }
+            let (states, groups): (Vec<usize>, Vec<Vec<usize>>) = (0..num_states)
+                .filter_map(|state| Some((state, values[machine.states[state].expr].clone()?)))
+                .unzip();
+            follow.push(states);
+            opens.push(groups);
+            last.push(values[machine.accept].is_some());
+            last_opens.push(values[machine.accept].clone().unwrap_or_default());
+        }
+        Some(Self {
+            bytes: machine
+                .states
+                .iter()
+                .map(|state| machine.sets[state.set].table())
+                .collect(),
+            ends: machine
+                .states
+                .iter()
+                .map(|state| state.groups.clone())
+                .collect(),
+            follow,
+            opens,
+            last,
+            last_opens,
+        })
+    }
+
+    fn start(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns the sorted states that threads at `positions` move to when
+    /// they read a byte in `class`.
+    fn step(&self, positions: &[usize], class: u8, classes: &[u8; 256]) -> Vec<usize> {
+        let byte = classes.iter().position(|c| *c == class).unwrap();
+        let mut result: Vec<usize> = positions
+            .iter()
+            .flat_map(|position| self.follow[*position].iter().copied())
+            .filter(|state| self.bytes[*state][byte])
+            .collect();
+        result.sort_unstable();
+        result.dedup();
+        result
+    }
+
+    /// Returns true when a match can end at one of `positions`.
+    fn accepts(&self, positions: &[usize]) -> bool {
+        positions.iter().any(|position| self.last[*position])
+    }
+}
+
+/// Builds a one-pass DFA, or returns `None` when the expression is not
+/// one-pass or the DFA needs more than `MAX_STATES` states.
+///
+/// DFA state `position + 2` holds the thread at `position`.  The start
+/// position becomes state 1.
+fn build_one_pass(
+    positions: &Positions,
+    classes: &[u8; 256],
+    num_classes: usize,
+) -> Option<OnePass> {
+    let start = positions.start();
+    if start + 2 > MAX_STATES {
+        return None;
+    }
+    let state_number = |position: usize| {
+        #[allow(clippy::cast_possible_truncation)]
+        let number = if position == start { 1 } else { position + 2 } as u8;
+        number
+    };
+    let mut actions = vec![Action::default()];
+    let mut transitions = vec![(0_u8, 0_u8); num_classes];
+    let mut accept = vec![None];
+    let mut positions_by_state: Vec<usize> = vec![start];
+    positions_by_state.extend(0..start);
+    for position in positions_by_state {
+        for class in 0..num_classes {
+            let byte = classes
+                .iter()
+                .position(|c| usize::from(*c) == class)
+                .unwrap();
+            let mut next = positions.follow[position]
+                .iter()
+                .zip(positions.opens[position].iter())
+                .filter(|(state, _)| positions.bytes[**state][byte]);
+            let transition = match (next.next(), next.next()) {
+                (None, _) => (0, 0),
+                (Some((state, open)), None) => {
+                    let action = Action {
+                        open: open.clone(),
+                        end: positions.ends[*state].clone(),
+                    };
+                    let index = if let Some(index) = actions.iter().position(|a| *a == action) {
+                        index
+                    } else {
+                        actions.push(action);
+                        actions.len() - 1
+                    };
+                    let index = u8::try_from(index).ok()?;
+                    (state_number(*state), index)
+                }
+                (Some(_), Some(_)) => return None,
+            };
+            transitions.push(transition);
+        }
+        accept.push(if positions.last[position] {
+            Some(positions.last_opens[position].clone())
+        } else {
+            None
+        });
+    }
+    Some(OnePass {
+        transitions,
+        actions,
+        accept,
+    })
+}
+
+/// Groups bytes that every state treats the same way.
+fn byte_classes(positions: &Positions) -> ([u8; 256], usize) {
+    let mut classes = [0_u8; 256];
+    let mut signatures: HashMap<Vec<bool>, u8> = HashMap::new();
+    for (b, class) in classes.iter_mut().enumerate() {
+        let signature: Vec<bool> = positions.bytes.iter().map(|bytes| bytes[b]).collect();
+        let next_class = signatures.len();
+        #[allow(clippy::cast_possible_truncation)]
+        let next_class = next_class as u8;
+        *class = *signatures.entry(signature).or_insert(next_class);
+    }
+    (classes, signatures.len())
+}
+
+/// Runs subset construction.
+///
+/// `step` returns the next DFA state, or `None` for the dead state.
+/// Returns `None` when the DFA needs more than `MAX_STATES` states.
+fn construct<S: Clone + Eq + core::hash::Hash>(
+    start: S,
+    num_classes: usize,
+    accepts: impl Fn(&S) -> bool,
+    step: impl Fn(&S, u8) -> Option<S>,
+) -> Option<Dfa> {
+    let mut numbers: HashMap<S, u8> = HashMap::new();
+    numbers.insert(start.clone(), 1);
+    let mut states = vec![None, Some(start)];
+    let mut transitions = vec![0_u8; 2 * num_classes];
+    let mut accept = vec![false, false];
+    let mut n = 1;
+    while n < states.len() {
+        let state = states[n].clone().unwrap();
+        accept[n] = accepts(&state);
+        for class in 0..num_classes {
+            #[allow(clippy::cast_possible_truncation)]
+            let next = match step(&state, class as u8) {
+                None => 0,
+                Some(next) => {
+                    if let Some(number) = numbers.get(&next) {
+                        *number
+                    } else {
+                        if states.len() == MAX_STATES {
+                            return None;
+                        }
+                        let number = states.len() as u8;
+                        numbers.insert(next.clone(), number);
+                        states.push(Some(next));
+                        transitions.extend(core::iter::repeat_n(0, num_classes));
+                        accept.push(false);
+                        number
+                    }
+                }
+            };
+            transitions[n * num_classes + class] = next;
+        }
+        n += 1;
+    }
+    Some(Dfa {
+        transitions,
+        accept,
+    })
+}
+
+/// Appends the threads that a thread at instruction `pc` of `nfa` reaches
+/// without reading a byte to `threads`, in priority order.  Threads are at
+/// `Byte` and `Match` instructions.
+///
+/// `visited` marks the ids of the instructions that threads reached at this
+/// position.
+/// Each id keeps only its first thread.
+fn add_threads(nfa: &Nfa, pc: usize, visited: &mut [bool], threads: &mut Vec<usize>) {
+    let mut stack = vec![pc];
+    while let Some(mut pc) = stack.pop() {
+        while !visited[nfa.ids[pc]] {
+            visited[nfa.ids[pc]] = true;
+            match nfa.insts[pc] {
+                Inst::Byte(..) | Inst::Match => {
+                    threads.push(pc);
+                    break;
+                }
+                Inst::Split(first, second) => {
+                    stack.push(second);
+                    pc = first;
+                }
+                Inst::Save(_, next) => pc = next,
+                // `build` gives up on expressions with assertions.
+                Inst::Assert(..) => unreachable!(),
+            }
+        }
+    }
+}
+
+/// A configuration of the search program.
+///
+/// `threads` holds the instructions of the threads at a position, in
+/// priority order, after the search added the thread that starts there.
+/// Once the search finds a match, `found` is true and it stops starting
+/// threads.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct SearchState {
+    threads: Vec<usize>,
+    found: bool,
+}
+
+/// Builds DFAs for `program`.
+///
+/// Returns `None` when the program has assertions, when it has capturing
+/// groups and is not one-pass, or when a DFA needs more than
+/// [`MAX_STATES`](constant.MAX_STATES.html) states.
+///
+/// # Example
+/// ```
+/// use safe_regex_compiler::dfa::build;
+/// use safe_regex_compiler::parser::parse;
+/// use safe_regex_compiler::program::compile;
+/// let dfas = build(&compile(&parse(b"a+b").unwrap())).unwrap();
+/// assert_eq!(3, dfas.num_classes);
+/// assert_eq!(vec![false, false, false, false, true], dfas.anchored.accept);
+/// assert_eq!(None, dfas.one_pass);
+/// let dfas = build(&compile(&parse(b"([0-9]+)-([0-9]+)").unwrap())).unwrap();
+/// assert!(dfas.one_pass.is_some());
+/// assert!(build(&compile(&parse(b"(a*)(a*)").unwrap())).is_none());
+/// assert!(build(&compile(&parse(b"^a").unwrap())).is_none());
+/// ```
+#[must_use]
+pub fn build(program: &Program) -> Option<Dfas> {
+    let positions = Positions::new(&program.anchored)?;
+    let (classes, num_classes) = byte_classes(&positions);
+    let one_pass = if program.group_names.is_empty() {
+        None
+    } else {
+        Some(build_one_pass(&positions, &classes, num_classes)?)
+    };
+    let start = positions.start();
+    let non_empty = |positions: Vec<usize>| {
+        if positions.is_empty() {
+            None
+        } else {
+            Some(positions)
+        }
+    };
+    let anchored = construct(
+        vec![start],
+        num_classes,
+        |state| positions.accepts(state),
+        |state, class| non_empty(positions.step(state, class, &classes)),
+    )?;
+    // Runs the search program like `safe_regex::internal::search`.  When the
+    // first thread reaches a match, the search drops the threads after it.
+    let nfa = &program.search;
+    let mut start_threads = Vec::new();
+    add_threads(
+        nfa,
+        nfa.start,
+        &mut vec![false; nfa.insts.len()],
+        &mut start_threads,
+    );
+    let search = construct(
+        SearchState {
+            threads: start_threads,
+            found: false,
+        },
+        num_classes,
+        |state| state.threads.iter().any(|pc| nfa.insts[*pc] == Inst::Match),
+        |state, class| {
+            let byte = classes.iter().position(|c| *c == class).unwrap();
+            #[allow(clippy::cast_possible_truncation)]
+            let byte = byte as u8;
+            let mut visited = vec![false; nfa.insts.len()];
+            let mut threads = Vec::new();
+            let mut found = state.found;
+            for pc in &state.threads {
+                match nfa.insts[*pc] {
+                    Inst::Match => {
+                        found = true;
+                        break;
+                    }
+                    Inst::Byte(set, next) if nfa.sets[set].contains(byte) => {
+                        add_threads(nfa, next, &mut visited, &mut threads);
+                    }
+                    _ => {}
+                }
+            }
+            if !found {
+                add_threads(nfa, nfa.start, &mut visited, &mut threads);
+            }
+            if threads.is_empty() {
+                None
+            } else {
+                Some(SearchState { threads, found })
+            }
+        },
+    )?;
+    // Reading backward, a thread moves from a state to the states that can
+    // come before it.  `None` is the end of the match, before any byte.
+    let reverse = construct(
+        None,
+        num_classes,
+        |state: &Option<Vec<usize>>| match state {
+            None => positions.last[start],
+            Some(states) => states
+                .iter()
+                .any(|state| positions.follow[start].contains(state)),
+        },
+        |state, class| {
+            let byte = classes.iter().position(|c| *c == class).unwrap();
+            let previous: Vec<usize> = (0..start)
+                .filter(|position| positions.bytes[*position][byte])
+                .filter(|position| match state {
+                    None => positions.last[*position],
+                    Some(states) => states
+                        .iter()
+                        .any(|state| positions.follow[*position].contains(state)),
+                })
+                .collect();
+            non_empty(previous).map(Some)
+        },
+    )?;
+    Some(Dfas {
+        classes,
+        num_classes,
+        anchored,
+        search,
+        reverse,
+        one_pass,
+    })
+}
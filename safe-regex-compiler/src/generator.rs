@@ -3,24 +3,236 @@
 //!
 //! How-to develop proc macros: <https://github.com/dtolnay/proc-macro-workshop>
 #![forbid(unsafe_code)]
+use crate::dfa::{Dfa, Dfas, OnePass};
 use crate::parser::{ClassItem, FinalNode};
-use safe_proc_macro2::{Ident, TokenStream};
+use crate::prefilter::Prefilter;
+use crate::program::{Inst, Nfa};
+use safe_proc_macro2::{Ident, Literal, TokenStream};
 use safe_quote::{format_ident, quote};
 
-#[derive(Clone, PartialOrd, PartialEq)]
-pub enum Predicate {
-    Any,
-    Incl(Vec<ClassItem>),
-    Excl(Vec<ClassItem>),
+/// The set of bytes that a byte node matches, with one bit for each byte.
+///
+/// Classes, negated classes, `.`, and single bytes all become sets.  So nodes
+/// that match the same bytes have equal predicates, however the expression
+/// wrote them.
+#[derive(Clone, Copy, PartialOrd, PartialEq, Eq, Hash)]
+pub struct Predicate([u64; 4]);
+impl Predicate {
+    /// Matches every byte.
+    pub const ANY: Predicate = Predicate([u64::MAX; 4]);
+
+    /// Returns the set of the bytes in `items`.
+    ///
+    /// # Panics
+    /// Panics when `items` contains a `ClassItem::CharRange`.
+    #[must_use]
+    pub fn from_items(items: &[ClassItem]) -> Self {
+        let mut words = [0_u64; 4];
+        for item in items {
+            let (first, last) = match item {
+                ClassItem::Byte(b) => (*b, *b),
+                ClassItem::ByteRange(x, y) => (*x, *y),
+                // `parse_str` converts these into byte sequences.
+                ClassItem::CharRange(..) => unreachable!(),
+            };
+            for b in first..=last {
+                words[usize::from(b >> 6)] |= 1 << (b & 63);
+            }
+        }
+        Self(words)
+    }
+
+    /// Returns the set that contains only `b`.
+    #[must_use]
+    pub fn byte(b: u8) -> Self {
+        Self::from_items(&[ClassItem::Byte(b)])
+    }
+
+    /// Returns the set of the bytes that are not in this set.
+    #[must_use]
+    pub fn negated(self) -> Self {
+        Self(self.0.map(|word| !word))
+    }
+
+    #[must_use]
+    pub fn contains(&self, b: u8) -> bool {
+        self.0[usize::from(b >> 6)] & (1 << (b & 63)) != 0
+    }
+
+    /// Returns the byte in this set, when it has exactly one.
+    #[must_use]
+    pub fn single_byte(&self) -> Option<u8> {
+        if self.0.iter().map(|word| word.count_ones()).sum::<u32>() == 1 {
+            (0..=u8::MAX).find(|b| self.contains(*b))
+        } else {
+            None
+        }
+    }
+
+    /// Returns a table with `true` for each byte in this set.
+    #[must_use]
+    pub fn table(&self) -> [bool; 256] {
+        let mut table = [false; 256];
+        for (b, entry) in (0..=u8::MAX).zip(table.iter_mut()) {
+            *entry = self.contains(b);
+        }
+        table
+    }
 }
 impl core::fmt::Debug for Predicate {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> Result<(), core::fmt::Error> {
+        write!(f, "Predicate(")?;
+        let mut bytes = (0..=u8::MAX).filter(|b| self.contains(*b)).peekable();
+        while let Some(first) = bytes.next() {
+            let mut last = first;
+            while let Some(b) = bytes.next_if(|b| *b == last + 1) {
+                last = b;
+            }
+            if first == last {
+                write!(f, "{}", crate::escape_ascii([first]))?;
+            } else {
+                write!(
+                    f,
+                    "{}-{}",
+                    crate::escape_ascii([first]),
+                    crate::escape_ascii([last])
+                )?;
+            }
+        }
+        write!(f, ")")
+    }
+}
+#[cfg(test)]
+#[test]
+fn test_predicate() {
+    let a = Predicate::byte(b'a');
+    assert_eq!(
+        a,
+        Predicate::from_items(&[ClassItem::ByteRange(b'a', b'a')])
+    );
+    assert_eq!(
+        a,
+        Predicate::from_items(&[
+            ClassItem::ByteRange(0, b'`'),
+            ClassItem::ByteRange(b'b', 255)
+        ])
+        .negated()
+    );
+    assert_eq!(Some(b'a'), a.single_byte());
+    assert_eq!(None, Predicate::ANY.single_byte());
+    assert_eq!(Predicate::ANY, Predicate::from_items(&[]).negated());
+    let class = Predicate::from_items(&[
+        ClassItem::ByteRange(b'x', b'z'),
+        ClassItem::Byte(b'_'),
+        ClassItem::ByteRange(b'a', b'y'),
+    ]);
+    assert!(class.contains(b'_') && class.contains(b'q') && !class.contains(b'{'));
+    assert_eq!(
+        class,
+        Predicate::from_items(&[ClassItem::Byte(b'_'), ClassItem::ByteRange(b'a', b'z')])
+    );
+    assert_eq!("Predicate(_a-z)", format!("{:?}", class));
+    assert_eq!("Predicate(\\x00-\\xff)", format!("{:?}", Predicate::ANY));
+}
+
+/// The kind of machine that [`build`](fn.build.html) generates code for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Mode {
+    /// Matches all of `data`.
+    Anchored,
+    /// Matches input that arrives in chunks.  Instead of `data`, it has
+    /// `prev_byte`, the byte before position `n`, and `next_byte`, the byte at
+    /// `n`.  `next_byte` is `None` at the end of the input.
+    Stream,
+}
+
+/// A zero-width assertion about the position between two bytes.
+#[derive(Clone, Copy, Debug, PartialOrd, PartialEq)]
+pub enum Assertion {
+    StartText,
+    EndText,
+    StartLine,
+    EndLine,
+    WordBoundary,
+    NotWordBoundary,
+}
+impl Assertion {
+    /// Returns true when the assertion holds at position `n` in `data`.
+    ///
+    /// This is what the expressions from `condition` compute.
+    #[must_use]
+    pub fn holds(self, data: &[u8], n: usize) -> bool {
+        let is_word_byte = |c: u8| c.is_ascii_alphanumeric() || c == b'_';
+        let before = n.checked_sub(1).and_then(|i| data.get(i)).copied();
+        let after = data.get(n).copied();
+        match self {
+            Assertion::StartText => n == 0,
+            Assertion::EndText => n == data.len(),
+            Assertion::StartLine => matches!(before, None | Some(b'\n')),
+            Assertion::EndLine => matches!(after, None | Some(b'\n')),
+            Assertion::WordBoundary => {
+                before.is_some_and(is_word_byte) != after.is_some_and(is_word_byte)
+            }
+            Assertion::NotWordBoundary => {
+                before.is_some_and(is_word_byte) == after.is_some_and(is_word_byte)
+            }
+        }
+    }
+
+    /// Generates an expression that is true when the assertion holds at
+    /// position `n` in `data`.
+    fn condition(self, mode: Mode) -> TokenStream {
+        if mode == Mode::Stream {
+            return self.stream_condition();
+        }
+        match self {
+            Assertion::StartText => quote! { n == 0 },
+            Assertion::EndText => quote! { n == data.len() },
+            Assertion::StartLine => quote! { n == 0 || data[n - 1] == b'\n' },
+            Assertion::EndLine => quote! { n == data.len() || data[n] == b'\n' },
+            Assertion::WordBoundary => {
+                let (before, after) = Self::word_bytes_around();
+                quote! { #before != #after }
+            }
+            Assertion::NotWordBoundary => {
+                let (before, after) = Self::word_bytes_around();
+                quote! { #before == #after }
+            }
+        }
+    }
+
+    /// Generates an expression that is true when the assertion holds between
+    /// `prev_byte` and `next_byte`.
+    fn stream_condition(self) -> TokenStream {
+        let word_byte = |byte: TokenStream| {
+            quote! { matches!(#byte, Some(c) if c.is_ascii_alphanumeric() || c == b'_') }
+        };
         match self {
-            Predicate::Any => write!(f, "Empty"),
-            Predicate::Incl(items) => write!(f, "Incl{:?}", items),
-            Predicate::Excl(items) => write!(f, "Excl{:?}", items),
+            Assertion::StartText => quote! { n == 0 },
+            Assertion::EndText => quote! { next_byte.is_none() },
+            Assertion::StartLine => quote! { matches!(prev_byte, None | Some(b'\n')) },
+            Assertion::EndLine => quote! { matches!(next_byte, None | Some(b'\n')) },
+            Assertion::WordBoundary | Assertion::NotWordBoundary => {
+                let before = word_byte(quote! { prev_byte });
+                let after = word_byte(quote! { next_byte });
+                if self == Assertion::WordBoundary {
+                    quote! { #before != #after }
+                } else {
+                    quote! { #before == #after }
+                }
+            }
         }
     }
+
+    /// Generates two expressions.  The first is true when the byte before
+    /// position `n` is a word byte.  The second is true when the byte at `n` is
+    /// a word byte.  Word bytes are ASCII letters, digits, and `_`.
+    fn word_bytes_around() -> (TokenStream, TokenStream) {
+        (
+            quote! { (n > 0 && (data[n - 1].is_ascii_alphanumeric() || data[n - 1] == b'_')) },
+            quote! { (n < data.len() && (data[n].is_ascii_alphanumeric() || data[n] == b'_')) },
+        )
+    }
 }
 
 // TODO(mleonhard) Add more tree simplifications:
@@ -32,45 +244,50 @@ impl core::fmt::Debug for Predicate {
 // - Reorder Optional(x),x so the optional comes later
 // - Translate x{2,5} into "xx(x(x(x)?)?)?" rather than "xxx?x?x?"
 #[derive(Clone, PartialOrd, PartialEq)]
-enum OptimizedNode {
+pub(crate) enum OptimizedNode {
+    Empty,
     Byte(Predicate),
+    Assert(Assertion),
     Seq(Vec<OptimizedNode>),
     Alt(Vec<OptimizedNode>),
-    Optional(Box<OptimizedNode>),
-    Star(Box<OptimizedNode>),
-    Group(Box<OptimizedNode>),
+    Optional(Box<OptimizedNode>, bool),
+    Star(Box<OptimizedNode>, bool),
+    Group(Option<String>, Box<OptimizedNode>),
+    /// A byte repeated from `min` to `max` times: predicate, min, max, lazy.
+    Repeat(Predicate, usize, usize, bool),
 }
 impl OptimizedNode {
     pub fn non_capturing(&self) -> OptimizedNode {
         match self {
-            OptimizedNode::Byte(_) => self.clone(),
+            OptimizedNode::Empty
+            | OptimizedNode::Byte(_)
+            | OptimizedNode::Assert(_)
+            | OptimizedNode::Repeat(..) => self.clone(),
             OptimizedNode::Seq(nodes) => {
                 OptimizedNode::Seq(nodes.iter().map(OptimizedNode::non_capturing).collect())
             }
             OptimizedNode::Alt(nodes) => {
                 OptimizedNode::Alt(nodes.iter().map(OptimizedNode::non_capturing).collect())
             }
-            OptimizedNode::Optional(node) => {
-                OptimizedNode::Optional(Box::new(node.non_capturing()))
+            OptimizedNode::Optional(node, lazy) => {
+                OptimizedNode::Optional(Box::new(node.non_capturing()), *lazy)
+            }
+            OptimizedNode::Star(node, lazy) => {
+                OptimizedNode::Star(Box::new(node.non_capturing()), *lazy)
             }
-            OptimizedNode::Star(node) => OptimizedNode::Star(Box::new(node.non_capturing())),
-            OptimizedNode::Group(node) => node.non_capturing(),
+            OptimizedNode::Group(_, node) => node.non_capturing(),
         }
     }
 
     pub fn from_final_node(final_node: &FinalNode) -> Option<Self> {
         match final_node {
-            FinalNode::AnyByte => Some(OptimizedNode::Byte(Predicate::Any)),
-            FinalNode::Byte(b) => {
-                Some(OptimizedNode::Byte(Predicate::Incl(vec![ClassItem::Byte(
-                    *b,
-                )])))
-            }
+            FinalNode::AnyByte => Some(OptimizedNode::Byte(Predicate::ANY)),
+            FinalNode::Byte(b) => Some(OptimizedNode::Byte(Predicate::byte(*b))),
             FinalNode::Class(true, items) => {
-                Some(OptimizedNode::Byte(Predicate::Incl(items.clone())))
+                Some(OptimizedNode::Byte(Predicate::from_items(items)))
             }
             FinalNode::Class(false, items) => {
-                Some(OptimizedNode::Byte(Predicate::Excl(items.clone())))
+                Some(OptimizedNode::Byte(Predicate::from_items(items).negated()))
             }
             FinalNode::Seq(final_nodes) => {
                 let mut nodes: Vec<OptimizedNode> = final_nodes
@@ -88,9 +305,11 @@ impl OptimizedNode {
             FinalNode::Alt(final_nodes) => {
                 let mut nodes: Vec<OptimizedNode> = final_nodes
                     .iter()
-                    .filter_map(OptimizedNode::from_final_node)
+                    .map(|node| {
+                        OptimizedNode::from_final_node(node).unwrap_or(OptimizedNode::Empty)
+                    })
                     .collect();
-                if nodes.is_empty() {
+                if nodes.iter().all(|node| node == &OptimizedNode::Empty) {
                     None
                 } else if nodes.len() == 1 {
                     Some(nodes.pop().unwrap())
@@ -98,54 +317,114 @@ impl OptimizedNode {
                     Some(OptimizedNode::Alt(nodes))
                 }
             }
-            FinalNode::Repeat(inner_final_node, 0, None) => Some(OptimizedNode::Star(Box::new(
-                OptimizedNode::from_final_node(inner_final_node)?,
-            ))),
-            FinalNode::Repeat(inner_final_node, min, None) => {
+            FinalNode::Repeat(inner_final_node, 0, None, lazy) => Some(OptimizedNode::Star(
+                Box::new(OptimizedNode::from_final_node(inner_final_node)?),
+                *lazy,
+            )),
+            FinalNode::Repeat(inner_final_node, min, None, lazy) => {
                 let node = OptimizedNode::from_final_node(inner_final_node)?;
                 let non_capturing_node = node.non_capturing();
                 let mut src_nodes =
                     core::iter::once(node).chain(core::iter::repeat(non_capturing_node.clone()));
                 let mut nodes = Vec::with_capacity(min + 1);
                 nodes.extend(src_nodes.by_ref().take(*min));
-                nodes.push(OptimizedNode::Star(Box::new(non_capturing_node)));
+                nodes.push(OptimizedNode::Star(Box::new(non_capturing_node), *lazy));
                 Some(OptimizedNode::Seq(nodes))
             }
-            FinalNode::Repeat(_node, 0, Some(0)) => None,
-            FinalNode::Repeat(node, 1, Some(1)) => OptimizedNode::from_final_node(node),
-            FinalNode::Repeat(_node, min, Some(max)) if max < min => unreachable!(),
-            FinalNode::Repeat(inner_final_node, min, Some(max)) => {
+            FinalNode::Repeat(_node, 0, Some(0), _) => None,
+            FinalNode::Repeat(node, 1, Some(1), _) => OptimizedNode::from_final_node(node),
+            FinalNode::Repeat(_node, min, Some(max), _) if max < min => unreachable!(),
+            FinalNode::Repeat(inner_final_node, min, Some(max), lazy) => {
                 let node = OptimizedNode::from_final_node(inner_final_node)?;
+                if let OptimizedNode::Byte(predicate) = node {
+                    return Some(OptimizedNode::Repeat(predicate, *min, *max, *lazy));
+                }
                 let non_capturing_node = node.non_capturing();
                 let mut src_nodes =
                     core::iter::once(node).chain(core::iter::repeat(non_capturing_node));
                 let mut nodes = Vec::with_capacity(*max);
                 nodes.extend(src_nodes.by_ref().take(*min));
-                nodes.extend(
-                    src_nodes
-                        .map(|node| OptimizedNode::Optional(Box::new(node)))
-                        .take(max - min),
-                );
+                if *lazy && *min == 0 {
+                    // In `x??x??`, the second copy matches before the first,
+                    // which is the one that captures.  So this makes
+                    // `(?:xx??)??`, where the first copy matches first.
+                    let mut inner = vec![src_nodes.next().unwrap()];
+                    inner.extend(
+                        src_nodes
+                            .map(|node| OptimizedNode::Optional(Box::new(node), true))
+                            .take(max - 1),
+                    );
+                    let inner = if inner.len() == 1 {
+                        inner.pop().unwrap()
+                    } else {
+                        OptimizedNode::Seq(inner)
+                    };
+                    nodes.push(OptimizedNode::Optional(Box::new(inner), true));
+                } else {
+                    nodes.extend(
+                        src_nodes
+                            .map(|node| OptimizedNode::Optional(Box::new(node), *lazy))
+                            .take(max - min),
+                    );
+                }
                 Some(OptimizedNode::Seq(nodes))
             }
-            FinalNode::Group(inner_final_node) => Some(OptimizedNode::Group(Box::new(
-                OptimizedNode::from_final_node(inner_final_node).expect("found empty group"),
-            ))),
+            FinalNode::Group(inner_final_node) => Some(OptimizedNode::Group(
+                None,
+                Box::new(
+                    OptimizedNode::from_final_node(inner_final_node)
+                        .unwrap_or(OptimizedNode::Empty),
+                ),
+            )),
+            FinalNode::NamedGroup(name, inner_final_node) => Some(OptimizedNode::Group(
+                Some(name.clone()),
+                Box::new(
+                    OptimizedNode::from_final_node(inner_final_node)
+                        .unwrap_or(OptimizedNode::Empty),
+                ),
+            )),
             FinalNode::NonCapturingGroup(inner_final_node) => {
                 Some(OptimizedNode::from_final_node(inner_final_node)?)
             }
+            FinalNode::StartText => Some(OptimizedNode::Assert(Assertion::StartText)),
+            FinalNode::EndText => Some(OptimizedNode::Assert(Assertion::EndText)),
+            FinalNode::StartLine => Some(OptimizedNode::Assert(Assertion::StartLine)),
+            FinalNode::EndLine => Some(OptimizedNode::Assert(Assertion::EndLine)),
+            FinalNode::WordBoundary => Some(OptimizedNode::Assert(Assertion::WordBoundary)),
+            FinalNode::NotWordBoundary => Some(OptimizedNode::Assert(Assertion::NotWordBoundary)),
         }
     }
 }
 impl core::fmt::Debug for OptimizedNode {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> Result<(), core::fmt::Error> {
         match self {
+            OptimizedNode::Empty => write!(f, "OptimizedNode::Empty"),
             OptimizedNode::Byte(items) => write!(f, "OptimizedNode::Byte({:?})", items),
+            OptimizedNode::Assert(assertion) => write!(f, "OptimizedNode::Assert({:?})", assertion),
             OptimizedNode::Seq(nodes) => write!(f, "OptimizedNode::Seq{:?}", nodes),
             OptimizedNode::Alt(nodes) => write!(f, "OptimizedNode::Alt{:?}", nodes),
-            OptimizedNode::Optional(node) => write!(f, "OptimizedNode::Optional({:?})", node),
-            OptimizedNode::Star(node) => write!(f, "OptimizedNode::Star({:?})", node),
-            OptimizedNode::Group(node) => write!(f, "OptimizedNode::Group({:?})", node),
+            OptimizedNode::Optional(node, false) => {
+                write!(f, "OptimizedNode::Optional({:?})", node)
+            }
+            OptimizedNode::Optional(node, true) => {
+                write!(f, "OptimizedNode::LazyOptional({:?})", node)
+            }
+            OptimizedNode::Star(node, false) => write!(f, "OptimizedNode::Star({:?})", node),
+            OptimizedNode::Star(node, true) => write!(f, "OptimizedNode::LazyStar({:?})", node),
+            OptimizedNode::Group(None, node) => write!(f, "OptimizedNode::Group({:?})", node),
+            OptimizedNode::Group(Some(name), node) => {
+                write!(f, "OptimizedNode::Group({},{:?})", name, node)
+            }
+            OptimizedNode::Repeat(predicate, min, max, false) => {
+                write!(f, "OptimizedNode::Repeat({:?},{},{})", predicate, min, max)
+            }
+            OptimizedNode::Repeat(predicate, min, max, true) => {
+                write!(
+                    f,
+                    "OptimizedNode::LazyRepeat({:?},{},{})",
+                    predicate, min, max
+                )
+            }
         }
     }
 }
@@ -180,124 +459,352 @@ fn test_counter() {
     assert_eq!(3, counter.get());
 }
 
+/// Repetitions of one byte with at least this maximum get a counter instead of
+/// one state per byte.
+const MIN_COUNTED_REPEAT: usize = 16;
+
 fn byte_and_prev_var_names(n: usize) -> (Ident, Ident) {
     (format_ident!("b{}", n), format_ident!("prev_b{}", n))
 }
 
+/// The repetitions that a machine runs with a
+/// `safe_regex::internal::CountedRepeat` instead of byte states.
+///
+/// Each one has a `counterN` variable and a `prev_counterN` variable that holds
+/// the state of the thread that leaves the repetition before the byte.
+#[derive(Clone, Default)]
+struct Counters {
+    /// The `PENDING`, `WINDOW`, and `OLDEST` parameters of each counter.
+    sizes: Vec<(usize, usize, bool)>,
+}
+impl Counters {
+    fn names(&self) -> Vec<Ident> {
+        (0..self.sizes.len())
+            .map(|n| format_ident!("counter{}", n))
+            .collect()
+    }
+
+    /// Returns the type of each counter.  `data_type` is the type of the
+    /// threads' data.
+    fn types(&self, data_type: &TokenStream) -> Vec<TokenStream> {
+        self.sizes
+            .iter()
+            .map(|(pending, window, oldest)| {
+                let pending = Literal::usize_unsuffixed(*pending);
+                let window = Literal::usize_unsuffixed(*window);
+                quote! {
+                    safe_regex::internal::CountedRepeat<#data_type, #pending, #window, #oldest>
+                }
+            })
+            .collect()
+    }
+
+    fn declarations(&self, data_type: &TokenStream) -> Vec<TokenStream> {
+        self.types(data_type)
+            .into_iter()
+            .zip(self.names())
+            .map(|(counter_type, name)| {
+                quote! { let mut #name: #counter_type = safe_regex::internal::CountedRepeat::new(); }
+            })
+            .collect()
+    }
+
+    fn clone_statements(&self) -> Vec<TokenStream> {
+        (0..self.sizes.len())
+            .map(|n| {
+                let name = format_ident!("counter{}", n);
+                let prev_name = format_ident!("prev_counter{}", n);
+                quote! { let #prev_name = #name .exit(); }
+            })
+            .collect()
+    }
+}
+
 #[derive(Clone, PartialOrd, PartialEq)]
-enum TaggedNode {
+pub(crate) enum TaggedNode {
+    Empty,
     Byte(Predicate),
+    Assert(Assertion),
     Seq(Vec<TaggedNode>),
     Alt(Vec<TaggedNode>),
-    Optional(Box<TaggedNode>),
-    Star(Box<TaggedNode>),
+    Optional(Box<TaggedNode>, bool),
+    Star(Box<TaggedNode>, bool),
     Group(usize, Box<TaggedNode>),
+    /// A byte repeated from `min` to `max` times: predicate, min, max, lazy.
+    Repeat(Predicate, usize, usize, bool),
 }
 impl TaggedNode {
-    pub fn from_optimized(group_counter: &mut Counter, source: &OptimizedNode) -> Self {
+    /// Returns the nodes that match `predicate` from `min` to `max` times.
+    /// For example, `x{2,4}` becomes `xxx?x?`.
+    pub fn expand_repeat(predicate: &Predicate, min: usize, max: usize, lazy: bool) -> Self {
+        let byte = TaggedNode::Byte(*predicate);
+        let mut nodes = vec![byte.clone(); min];
+        nodes.extend(core::iter::repeat_n(
+            TaggedNode::Optional(Box::new(byte), lazy),
+            max - min,
+        ));
+        TaggedNode::Seq(nodes)
+    }
+
+    pub fn from_optimized(group_names: &mut Vec<Option<String>>, source: &OptimizedNode) -> Self {
         match source {
-            OptimizedNode::Byte(predicate) => TaggedNode::Byte(predicate.clone()),
+            OptimizedNode::Empty => TaggedNode::Empty,
+            OptimizedNode::Byte(predicate) => TaggedNode::Byte(*predicate),
+            OptimizedNode::Assert(assertion) => TaggedNode::Assert(*assertion),
             OptimizedNode::Seq(nodes) => TaggedNode::Seq(
                 nodes
                     .iter()
-                    .map(|node| TaggedNode::from_optimized(group_counter, node))
+                    .map(|node| TaggedNode::from_optimized(group_names, node))
                     .collect(),
             ),
             OptimizedNode::Alt(nodes) => TaggedNode::Alt(
                 nodes
                     .iter()
-                    .map(|node| TaggedNode::from_optimized(group_counter, node))
+                    .map(|node| TaggedNode::from_optimized(group_names, node))
                     .collect(),
             ),
-            OptimizedNode::Optional(node) => {
-                TaggedNode::Optional(Box::new(TaggedNode::from_optimized(group_counter, node)))
-            }
-            OptimizedNode::Star(node) => {
-                TaggedNode::Star(Box::new(TaggedNode::from_optimized(group_counter, node)))
-            }
-            OptimizedNode::Group(node) => {
-                let this_group = group_counter.get_and_increment();
+            OptimizedNode::Optional(node, lazy) => TaggedNode::Optional(
+                Box::new(TaggedNode::from_optimized(group_names, node)),
+                *lazy,
+            ),
+            OptimizedNode::Star(node, lazy) => TaggedNode::Star(
+                Box::new(TaggedNode::from_optimized(group_names, node)),
+                *lazy,
+            ),
+            OptimizedNode::Group(name, node) => {
+                let this_group = group_names.len();
+                group_names.push(name.clone());
                 TaggedNode::Group(
                     this_group,
-                    Box::new(TaggedNode::from_optimized(group_counter, node)),
+                    Box::new(TaggedNode::from_optimized(group_names, node)),
                 )
             }
+            OptimizedNode::Repeat(predicate, min, max, lazy) => {
+                if *max < MIN_COUNTED_REPEAT {
+                    TaggedNode::expand_repeat(predicate, *min, *max, *lazy)
+                } else {
+                    TaggedNode::Repeat(*predicate, *min, *max, *lazy)
+                }
+            }
+        }
+    }
+
+    /// Returns this node with its capturing groups removed.
+    pub fn non_capturing(&self) -> TaggedNode {
+        match self {
+            TaggedNode::Empty
+            | TaggedNode::Byte(_)
+            | TaggedNode::Assert(_)
+            | TaggedNode::Repeat(..) => self.clone(),
+            TaggedNode::Seq(nodes) => {
+                TaggedNode::Seq(nodes.iter().map(TaggedNode::non_capturing).collect())
+            }
+            TaggedNode::Alt(nodes) => {
+                TaggedNode::Alt(nodes.iter().map(TaggedNode::non_capturing).collect())
+            }
+            TaggedNode::Optional(node, lazy) => {
+                TaggedNode::Optional(Box::new(node.non_capturing()), *lazy)
+            }
+            TaggedNode::Star(node, lazy) => TaggedNode::Star(Box::new(node.non_capturing()), *lazy),
+            TaggedNode::Group(_, node) => node.non_capturing(),
+        }
+    }
+
+    /// Returns true when this node matches the empty string.
+    pub fn matches_empty(&self) -> bool {
+        match self {
+            TaggedNode::Empty
+            | TaggedNode::Assert(_)
+            | TaggedNode::Optional(..)
+            | TaggedNode::Star(..) => true,
+            TaggedNode::Byte(_) => false,
+            TaggedNode::Seq(nodes) => nodes.iter().all(TaggedNode::matches_empty),
+            TaggedNode::Alt(nodes) => nodes.iter().any(TaggedNode::matches_empty),
+            TaggedNode::Group(_, node) => node.matches_empty(),
+            TaggedNode::Repeat(_, min, _, _) => *min == 0,
+        }
+    }
+
+    /// Returns true when this node or any of its descendants is an assertion.
+    pub fn has_assertion(&self) -> bool {
+        match self {
+            TaggedNode::Empty | TaggedNode::Byte(_) | TaggedNode::Repeat(..) => false,
+            TaggedNode::Assert(_) => true,
+            TaggedNode::Seq(nodes) | TaggedNode::Alt(nodes) => {
+                nodes.iter().any(TaggedNode::has_assertion)
+            }
+            TaggedNode::Optional(node, _)
+            | TaggedNode::Star(node, _)
+            | TaggedNode::Group(_, node) => node.has_assertion(),
+        }
+    }
+
+    /// Returns the largest maximum of the repetitions in this node that use
+    /// counters, or 0 when there are none.
+    pub fn longest_repeat(&self) -> usize {
+        match self {
+            TaggedNode::Empty | TaggedNode::Byte(_) | TaggedNode::Assert(_) => 0,
+            TaggedNode::Seq(nodes) | TaggedNode::Alt(nodes) => nodes
+                .iter()
+                .map(TaggedNode::longest_repeat)
+                .max()
+                .unwrap_or(0),
+            TaggedNode::Optional(node, _)
+            | TaggedNode::Star(node, _)
+            | TaggedNode::Group(_, node) => node.longest_repeat(),
+            TaggedNode::Repeat(_, _, max, _) => *max,
         }
     }
 }
 impl core::fmt::Debug for TaggedNode {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> Result<(), core::fmt::Error> {
         match self {
+            TaggedNode::Empty => write!(f, "Empty"),
             TaggedNode::Byte(predicate) => write!(f, "Byte({:?})", predicate),
+            TaggedNode::Assert(assertion) => write!(f, "Assert({:?})", assertion),
             TaggedNode::Seq(nodes) => write!(f, "Seq({:?})", nodes),
             TaggedNode::Alt(nodes) => write!(f, "Alt({:?})", nodes),
-            TaggedNode::Optional(node) => write!(f, "Optional({:?})", node),
-            TaggedNode::Star(node) => write!(f, "Star({:?})", node),
+            TaggedNode::Optional(node, false) => write!(f, "Optional({:?})", node),
+            TaggedNode::Optional(node, true) => write!(f, "LazyOptional({:?})", node),
+            TaggedNode::Star(node, false) => write!(f, "Star({:?})", node),
+            TaggedNode::Star(node, true) => write!(f, "LazyStar({:?})", node),
             TaggedNode::Group(group_num, node) => {
                 write!(f, "Group({},{:?})", group_num, node)
             }
+            TaggedNode::Repeat(predicate, min, max, false) => {
+                write!(f, "Repeat({:?},{},{})", predicate, min, max)
+            }
+            TaggedNode::Repeat(predicate, min, max, true) => {
+                write!(f, "LazyRepeat({:?},{},{})", predicate, min, max)
+            }
         }
     }
 }
 
+/// Generates an expression that combines two machine states.
+///
+/// The expression prefers `a`, or `b` when `lazy` is true.
+fn merge(lazy: bool, a: &TokenStream, b: &TokenStream) -> TokenStream {
+    let (a, b) = if lazy { (b, a) } else { (a, b) };
+    quote! { #a .clone() .or_else(|| #b .clone()) }
+}
+
+/// The byte sets that a machine tests bytes against.
+///
+/// The generated code has one `static` table for each distinct set, so byte
+/// nodes that match the same bytes share a table.
+#[derive(Default)]
+struct ByteSets {
+    sets: Vec<Predicate>,
+}
+impl ByteSets {
+    /// Generates an expression that is true when the byte `b` matches
+    /// `predicate`.
+    fn filter(&mut self, predicate: &Predicate) -> TokenStream {
+        if *predicate == Predicate::ANY {
+            return quote! { true };
+        }
+        let n = if let Some(n) = self.sets.iter().position(|set| set == predicate) {
+            n
+        } else {
+            self.sets.push(*predicate);
+            self.sets.len() - 1
+        };
+        let name = format_ident!("BYTES{}", n);
+        quote! { #name[usize::from(*b)] }
+    }
+
+    fn declarations(&self) -> Vec<TokenStream> {
+        self.sets
+            .iter()
+            .enumerate()
+            .map(|(n, set)| {
+                let name = format_ident!("BYTES{}", n);
+                let table = set.table();
+                let table = table.iter();
+                quote! { static #name: [bool; 256] = [ #( #table ),* ]; }
+            })
+            .collect()
+    }
+}
+
+/// Generates a `.map` call that sets the ends of the `enclosing_groups` ranges
+/// in a state to `end`.  Generates nothing when there are no enclosing groups.
+fn end_groups(num_groups: usize, enclosing_groups: &[usize], end: &TokenStream) -> TokenStream {
+    if enclosing_groups.is_empty() {
+        return quote! {};
+    }
+    let mut range_names = Vec::new();
+    let mut range_values = Vec::new();
+    for r in 0..num_groups {
+        let range_name = format_ident!("r{}", r);
+        range_names.push(range_name.clone());
+        range_values.push(if enclosing_groups.contains(&r) {
+            quote! { #range_name .start .. #end }
+        } else {
+            quote! { #range_name }
+        });
+    }
+    let extra_comma = if num_groups > 1 {
+        quote! {}
+    } else {
+        quote! {,}
+    };
+    quote! {
+        .map(
+            |( #( #range_names ),* #extra_comma )| ( #( #range_values ),* #extra_comma )
+        )
+    }
+}
+
+/// Generates the statements that update the states of `node`'s bytes and
+/// returns an expression for the state after `node`.
+///
+/// When two threads reach the same state, the generated code keeps one of
+/// them.  `lazy` tells which one.  When it is false, the code keeps the thread
+/// that spent more time in the most recent repetition before the state.
+/// When it is true, the code keeps the thread that spent less time there.
+/// `bindings` gets statements that compute shared state expressions.
+/// The caller must put them at the start of each step, before using any of
+/// the expressions.
+/// `counters` gets the repetitions that use counters.  The caller must
+/// declare them with the byte states.
+/// `byte_sets` gets the sets that the statements test bytes against.
+/// The caller must declare their tables.
+///
+/// On entry, `lazy` holds the laziness of the last repetition before `node`.
+/// On return, it holds the laziness of the last repetition in or before `node`.
+#[allow(clippy::too_many_arguments)]
 #[allow(clippy::too_many_lines)]
 fn build(
+    mode: Mode,
     var_counter: &mut Counter,
+    counters: &mut Counters,
+    byte_sets: &mut ByteSets,
     num_groups: usize,
     enclosing_groups: &[usize],
     statements2_reversed: &mut Vec<TokenStream>,
+    bindings: &mut Vec<TokenStream>,
+    lazy: &mut bool,
     prev_state_expr: &TokenStream,
     node: &TaggedNode,
 ) -> TokenStream {
     crate::dprintln!("build {:?}", node);
     let result = match node {
+        TaggedNode::Empty => prev_state_expr.clone(),
+        TaggedNode::Assert(assertion) => {
+            let condition = assertion.condition(mode);
+            quote! { #prev_state_expr .clone() .filter(|_| #condition ) }
+        }
         TaggedNode::Byte(predicate) => {
             let var_num = var_counter.get_and_increment();
             let (var_name, prev_var_name) = byte_and_prev_var_names(var_num);
-            let filter = match predicate {
-                Predicate::Any => quote! {},
-                Predicate::Incl(items) => {
-                    let comparisons = items.iter().map(|p| match p {
-                        ClassItem::Byte(b) => quote! {*b == #b},
-                        ClassItem::ByteRange(x, y) => quote! {(#x ..= #y).contains(b)},
-                    });
-                    quote! { .filter(|_| { #( #comparisons )||* } )  }
-                }
-                Predicate::Excl(items) => {
-                    let comparisons = items.iter().map(|p| match p {
-                        ClassItem::Byte(b) => quote! {*b != #b},
-                        ClassItem::ByteRange(x, y) => quote! {!(#x ..= #y).contains(b)},
-                    });
-                    quote! { .filter(|_| { #( #comparisons )&&* } )  }
-                }
-            };
-            let update_groups = if enclosing_groups.is_empty() {
-                quote! {}
-            } else {
-                let mut range_names = Vec::new();
-                let mut range_values = Vec::new();
-                for r in 0..num_groups {
-                    let range_name = format_ident!("r{}", r);
-                    range_names.push(range_name.clone());
-                    range_values.push(if enclosing_groups.contains(&r) {
-                        quote! { #range_name .start .. n + 1}
-                    } else {
-                        quote! { #range_name }
-                    });
-                }
-                let extra_comma = if num_groups > 1 {
-                    quote! {}
-                } else {
-                    quote! {,}
-                };
-                quote! {
-                    .map(
-                        |( #( #range_names ),* #extra_comma )| ( #( #range_values ),* #extra_comma )
-                    )
-                }
-            };
+            let filter = byte_sets.filter(predicate);
+            let update_groups = end_groups(num_groups, enclosing_groups, &quote! { n + 1 });
+            // An `if` compiles faster than a `filter` closure.  This matters
+            // for Unicode classes, which become thousands of byte nodes.
             statements2_reversed.push(quote! {
-                #var_name = #prev_state_expr .clone() #filter #update_groups ;
+                #var_name = if #filter { #prev_state_expr .clone() } else { None } #update_groups ;
             });
             quote! { #prev_var_name }
         }
@@ -306,10 +813,15 @@ fn build(
             let mut last_state_expr = prev_state_expr.clone();
             for node in inner_nodes {
                 last_state_expr = build(
+                    mode,
                     var_counter,
+                    counters,
+                    byte_sets,
                     num_groups,
                     enclosing_groups,
                     statements2_reversed,
+                    bindings,
+                    lazy,
                     &last_state_expr,
                     node,
                 );
@@ -318,49 +830,91 @@ fn build(
         }
         TaggedNode::Alt(inner_nodes) => {
             assert!(!inner_nodes.is_empty());
+            // Every arm starts from the same state.  Compute it once per byte
+            // so the generated code does not repeat a long expression in every
+            // arm.
+            let prev_state_expr = if prev_state_expr.clone().into_iter().count() > 1 {
+                let name = format_ident!("alt{}", bindings.len());
+                bindings.push(quote! {
+                    #[allow(unused_variables)]
+                    let #name = #prev_state_expr .clone() ;
+                });
+                quote! { #name }
+            } else {
+                prev_state_expr.clone()
+            };
+            let prev_state_expr = &prev_state_expr;
             let mut arm_state_exprs: Vec<TokenStream> = Vec::new();
+            let mut any_arm_lazy = false;
             for node in inner_nodes {
+                let mut arm_lazy = *lazy;
                 arm_state_exprs.push(build(
+                    mode,
                     var_counter,
+                    counters,
+                    byte_sets,
                     num_groups,
                     enclosing_groups,
                     statements2_reversed,
+                    bindings,
+                    &mut arm_lazy,
                     prev_state_expr,
                     node,
                 ));
+                any_arm_lazy |= arm_lazy;
             }
+            *lazy = any_arm_lazy;
             quote! { None #( .or_else(|| #arm_state_exprs.clone()) )* }
         }
-        TaggedNode::Optional(inner) => {
+        TaggedNode::Optional(inner, inner_lazy) => {
             let node_state_expr = build(
+                mode,
                 var_counter,
+                counters,
+                byte_sets,
                 num_groups,
                 enclosing_groups,
                 statements2_reversed,
+                bindings,
+                &mut lazy.clone(),
                 prev_state_expr,
                 inner,
             );
-            quote! { #prev_state_expr .clone() .or_else(|| #node_state_expr .clone()) }
+            let expr = merge(*lazy, prev_state_expr, &node_state_expr);
+            *lazy = *inner_lazy;
+            expr
         }
         // See safe-regex/tests/machine::seq_in_star .
-        TaggedNode::Star(inner) => {
+        TaggedNode::Star(inner, inner_lazy) => {
             let first_expr = build(
+                mode,
                 &mut var_counter.clone(), // <-- discards
+                &mut counters.clone(),    // <-- discards
+                byte_sets,
                 num_groups,
                 enclosing_groups,
                 &mut Vec::new(), // <-- discards
+                bindings,
+                &mut lazy.clone(),
                 &quote! { #prev_state_expr },
                 inner,
             );
             let expr = build(
+                mode,
                 var_counter,
+                counters,
+                byte_sets,
                 num_groups,
                 enclosing_groups,
                 statements2_reversed,
-                &quote! { #prev_state_expr .clone() .or_else(|| #first_expr .clone()) },
+                bindings,
+                &mut lazy.clone(),
+                &merge(*lazy, prev_state_expr, &first_expr),
                 inner,
             );
-            quote! { #prev_state_expr .clone() .or_else(|| #expr .clone()) }
+            let expr = merge(*lazy, prev_state_expr, &expr);
+            *lazy = *inner_lazy;
+            expr
         }
         TaggedNode::Group(group_num, inner) => {
             let inner_enclosing_groups: Vec<usize> = enclosing_groups
@@ -392,51 +946,140 @@ fn build(
                 }
             };
             build(
+                mode,
                 var_counter,
+                counters,
+                byte_sets,
                 num_groups,
                 &inner_enclosing_groups,
                 statements2_reversed,
+                bindings,
+                lazy,
                 &inner_prev_state_expr,
                 inner,
             )
         }
+        // The threads in the repetition read the same bytes.  So one counter
+        // can hold all of them, when it can tell which thread the expanded
+        // repetition would keep.  The newest thread wins after a greedy
+        // repetition and the oldest wins after a lazy one.
+        TaggedNode::Repeat(predicate, min, max, repeat_lazy) => {
+            if min == max || *lazy == *repeat_lazy {
+                let counter_num = counters.sizes.len();
+                let name = format_ident!("counter{}", counter_num);
+                let prev_name = format_ident!("prev_counter{}", counter_num);
+                // Threads leave after at least one byte.  With `min` of 0,
+                // the thread that skips the repetition comes from
+                // `prev_state_expr`.
+                let pending = min.saturating_sub(1);
+                counters.sizes.push((pending, max - pending, *repeat_lazy));
+                let filter = byte_sets.filter(predicate);
+                statements2_reversed.push(quote! {
+                    #name .push(#filter, #prev_state_expr .clone());
+                });
+                let exit_expr = if enclosing_groups.is_empty() {
+                    quote! { #prev_name }
+                } else {
+                    let update_groups = end_groups(num_groups, enclosing_groups, &quote! { n });
+                    quote! { #prev_name .clone() #update_groups }
+                };
+                let expr = if *min == 0 {
+                    merge(*lazy, prev_state_expr, &exit_expr)
+                } else {
+                    exit_expr
+                };
+                if max > min {
+                    *lazy = *repeat_lazy;
+                }
+                expr
+            } else {
+                // In the expanded repetition, only the first optional byte
+                // merges threads with `lazy`.  The rest merge them with the
+                // repetition's laziness, so a counter can hold them.
+                let mut nodes = Vec::new();
+                if *min > 0 {
+                    nodes.push(TaggedNode::Repeat(*predicate, *min, *min, *repeat_lazy));
+                }
+                nodes.push(TaggedNode::Optional(
+                    Box::new(TaggedNode::Byte(*predicate)),
+                    *repeat_lazy,
+                ));
+                if max - min > 1 {
+                    nodes.push(TaggedNode::Repeat(
+                        *predicate,
+                        0,
+                        max - min - 1,
+                        *repeat_lazy,
+                    ));
+                }
+                build(
+                    mode,
+                    var_counter,
+                    counters,
+                    byte_sets,
+                    num_groups,
+                    enclosing_groups,
+                    statements2_reversed,
+                    bindings,
+                    lazy,
+                    prev_state_expr,
+                    &TaggedNode::Seq(nodes),
+                )
+            }
+        }
     };
     crate::dprintln!("build returning {:?}", result);
     result
 }
 
-/// Generates an enum that implements `parsed_re` and implements the
-/// [`safe_regex::internal::Machine`](https://docs.rs/safe-regex/latest/safe_regex/internal/trait.Machine.html)
-/// trait.
-#[must_use]
-#[allow(clippy::too_many_lines)]
-pub fn generate(final_node: &FinalNode) -> safe_proc_macro2::TokenStream {
-    let optimized_node = if let Some(node) = OptimizedNode::from_final_node(final_node) {
-        node
+/// Generates an expression that is true when all of the `var_names` states
+/// are `None` and all of the `counters` are empty.  A machine without byte
+/// states or counters matches only the empty string, so the expression is
+/// `true`.
+fn all_none_expr(var_names: &[Ident], counters: &Counters) -> TokenStream {
+    let counter_names = counters.names();
+    if var_names.is_empty() && counter_names.is_empty() {
+        quote! { true }
     } else {
-        return quote! {
-            safe_regex::Matcher0::new(|data: &[u8]| {
-                if data.is_empty() {
-                    Some(())
-                } else {
-                    None
-                }
-            })
-        };
-    };
-    let mut group_counter = Counter::new();
-    let tagged_node = TaggedNode::from_optimized(&mut group_counter, &optimized_node);
-    let num_groups = group_counter.get();
-    let matcher_type_name = format_ident!("Matcher{}", num_groups);
+        let conditions = var_names
+            .iter()
+            .map(|name| quote! { #name .is_none() })
+            .chain(
+                counter_names
+                    .iter()
+                    .map(|name| quote! { #name .is_empty() }),
+            );
+        quote! { #( #conditions )&&* }
+    }
+}
+
+/// Generates the statements that match `tagged_node` against all of `data`.
+///
+/// The statements use the tables of `byte_sets`.
+///
+/// The statements evaluate to
+/// `Option<(core::ops::Range<usize>, [Option<core::ops::Range<usize>>; N])>`.
+fn generate_anchored(
+    num_groups: usize,
+    tagged_node: &TaggedNode,
+    byte_sets: &mut ByteSets,
+) -> TokenStream {
     let mut statements2_reversed: Vec<TokenStream> = Vec::new();
+    let mut bindings: Vec<TokenStream> = Vec::new();
     let mut var_counter = Counter::new();
+    let mut counters = Counters::default();
     let accept_expr = build(
+        Mode::Anchored,
         &mut var_counter,
+        &mut counters,
+        byte_sets,
         num_groups,
         &Vec::new(),
         &mut statements2_reversed,
+        &mut bindings,
+        &mut false,
         &quote! { start },
-        &tagged_node,
+        tagged_node,
     );
     let mut var_names: Vec<Ident> = Vec::new();
     let mut var_clone_statements: Vec<TokenStream> = Vec::new();
@@ -447,73 +1090,1097 @@ pub fn generate(final_node: &FinalNode) -> safe_proc_macro2::TokenStream {
         });
         var_names.push(var_name);
     }
+    var_clone_statements.extend(counters.clone_statements());
+    let all_none = all_none_expr(&var_names, &counters);
     let statements2 = statements2_reversed.iter().rev();
-    let result = if num_groups == 0 {
+    if num_groups == 0 {
+        // Only assertions use the position.
+        let (n_statement, n_increment) = if tagged_node.has_assertion() {
+            (quote! { let mut n = 0; }, quote! { n += 1; })
+        } else {
+            (quote! {}, quote! {})
+        };
+        let counter_declarations = counters.declarations(&quote! { () });
         quote! {
-            safe_regex::#matcher_type_name::new(|data: &[u8]| {
-                let mut start = Some(());
-                #( let mut #var_names : Option<()> = None; )*
-                let mut data_iter = data.iter();
-                loop {
-                    #( #var_clone_statements )*
-                    if let Some(b) = data_iter.next() {
-                        #( #statements2 )*
-                        start = None;
-                        if #( #var_names .is_none() )&&* {
-                            return None;
-                        }
-                    } else {
-                        return #accept_expr ;
+            let mut start = Some(());
+            #( let mut #var_names : Option<()> = None; )*
+            #( #counter_declarations )*
+            let mut data_iter = data.iter();
+            #n_statement
+            loop {
+                #( #var_clone_statements )*
+                #( #bindings )*
+                if let Some(b) = data_iter.next() {
+                    #( #statements2 )*
+                    start = None;
+                    if #all_none {
+                        return None;
                     }
+                } else {
+                    return #accept_expr .map(|_| (0..data.len(), []));
                 }
-            })
+                #n_increment
+            }
         }
     } else {
-        let default_ranges = core::iter::repeat(quote! { usize::MAX..usize::MAX }).take(num_groups);
+        let default_ranges = core::iter::repeat_n(quote! { usize::MAX..usize::MAX }, num_groups);
         let extra_comma = if num_groups > 1 {
             quote! {}
         } else {
             quote! {,}
         };
-        let range_types = core::iter::repeat(quote! { core::ops::Range<usize> }).take(num_groups);
-        let range_type = quote! { Option<( #( #range_types ),* #extra_comma )> };
+        let range_types = core::iter::repeat_n(quote! { core::ops::Range<usize> }, num_groups);
+        let data_type = quote! { ( #( #range_types ),* #extra_comma ) };
+        let range_type = quote! { Option<#data_type> };
+        let counter_declarations = counters.declarations(&data_type);
         let range_names: Vec<Ident> = (0..num_groups).map(|r| format_ident!("r{}", r)).collect();
         quote! {
-            safe_regex::#matcher_type_name::new(|data: &[u8]| {
-                assert!(data.len() < usize::MAX - 2);
-                let mut start = Some(( #( #default_ranges ),* #extra_comma ));
-                #( let mut #var_names : #range_type = None; )*
-                let mut accept : #range_type = None;
-                let mut data_iter = data.iter();
-                let mut n = 0;
-                loop {
-                    #( #var_clone_statements )*
-                    accept = #accept_expr .clone() ;
-                    if let Some(b) = data_iter.next() {
-                        #( #statements2 )*
-                        start = None;
-                        if #( #var_names .is_none() )&&* {
-                            return None;
-                        }
+            assert!(data.len() < usize::MAX - 2);
+            let mut start = Some(( #( #default_ranges ),* #extra_comma ));
+            #( let mut #var_names : #range_type = None; )*
+            #( #counter_declarations )*
+            let mut accept : #range_type = None;
+            let mut data_iter = data.iter();
+            let mut n = 0;
+            loop {
+                #( #var_clone_statements )*
+                #( #bindings )*
+                accept = #accept_expr .clone() ;
+                if let Some(b) = data_iter.next() {
+                    #( #statements2 )*
+                    start = None;
+                    if #all_none {
+                        return None;
+                    }
+                } else {
+                    break;
+                }
+                n += 1;
+            }
+            accept .map(|( #( #range_names ),* #extra_comma )| {
+                (
+                    0..data.len(),
+                    [
+                        #(
+                            if #range_names.start == usize::MAX || #range_names.end == usize::MAX {
+                                None
+                            } else {
+                                Some(#range_names)
+                            }
+                        ),*
+                    ]
+                )
+            })
+        }
+    }
+}
+
+/// Generates a `search` function that finds the leftmost-first match of `nfa`
+/// in `data[search_start..]`.
+///
+/// The function puts the program's instructions in a table and runs it with
+/// `safe_regex::internal::search`.  Its memory is an array on the stack, of
+/// [`Nfa::memory_size`](../program/struct.Nfa.html#method.memory_size) bytes.
+/// The function is never inlined, so whole-data matches do not reserve the
+/// array.
+///
+/// The function returns
+/// `Option<(core::ops::Range<usize>, [Option<core::ops::Range<usize>>; N])>`.
+fn generate_search(nfa: &Nfa) -> TokenStream {
+    let insts = nfa.insts.iter().map(|inst| match inst {
+        Inst::Byte(set, next) => quote! { safe_regex::internal::Inst::Byte(#set, #next) },
+        Inst::Split(first, second) => {
+            quote! { safe_regex::internal::Inst::Split(#first, #second) }
+        }
+        Inst::Save(slot, next) => quote! { safe_regex::internal::Inst::Save(#slot, #next) },
+        Inst::Assert(assertion, next) => {
+            let assertion = format_ident!("{}", format!("{:?}", assertion));
+            quote! {
+                safe_regex::internal::Inst::Assert(
+                    safe_regex::internal::Assertion::#assertion,
+                    #next
+                )
+            }
+        }
+        Inst::Match => quote! { safe_regex::internal::Inst::Match },
+    });
+    let num_insts = nfa.insts.len();
+    let ids = nfa.ids.iter();
+    let start = nfa.start;
+    let sets = nfa.sets.iter().map(|set| {
+        let table = set.table();
+        let table = table.iter();
+        quote! { [ #( #table ),* ] }
+    });
+    let num_sets = nfa.sets.len();
+    let num_slots = nfa.num_slots;
+    let match_start = num_slots - 2;
+    let match_end = num_slots - 1;
+    let groups = (0..match_start).step_by(2).map(|slot| {
+        let end = slot + 1;
+        quote! {
+            if slots[#slot] == usize::MAX || slots[#end] == usize::MAX {
+                None
+            } else {
+                Some(slots[#slot]..slots[#end])
+            }
+        }
+    });
+    let num_groups = match_start / 2;
+    quote! {
+        #[inline(never)]
+        fn search(
+            data: &[u8],
+            search_start: usize,
+        ) -> Option<(core::ops::Range<usize>, [Option<core::ops::Range<usize>>; #num_groups])> {
+            static INSTS: [safe_regex::internal::Inst; #num_insts] = [ #( #insts ),* ];
+            static IDS: [usize; #num_insts] = [ #( #ids ),* ];
+            static SETS: [[bool; 256]; #num_sets] = [ #( #sets ),* ];
+            let nfa = safe_regex::internal::Nfa {
+                insts: &INSTS,
+                ids: &IDS,
+                sets: &SETS,
+                start: #start,
+            };
+            let mut memory = [0_usize; safe_regex::internal::search_memory_len(#num_insts, #num_slots)];
+            let mut slots = [usize::MAX; #num_slots];
+            if !safe_regex::internal::search(
+                &nfa,
+                data,
+                search_start,
+                &mut memory,
+                &mut slots,
+            ) {
+                return None;
+            }
+            Some((slots[#match_start]..slots[#match_end], [ #( #groups ),* ]))
+        }
+    }
+}
+
+/// Generates a `one_pass` closure that matches all of its `data` argument and
+/// returns the captures.  Its `offset` argument is the position of `data` in
+/// the matcher's data.  The closure uses the `CLASSES` table from
+/// [`generate_dfa`](fn.generate_dfa.html).
+///
+/// Like [`generate_dfa`](fn.generate_dfa.html), the table holds the offsets of
+/// the next states.
+fn generate_one_pass(one_pass: &OnePass, num_classes: usize, num_groups: usize) -> TokenStream {
+    let len = one_pass.transitions.len();
+    #[allow(clippy::cast_possible_truncation)]
+    let transitions = one_pass
+        .transitions
+        .iter()
+        .map(|(state, _)| (usize::from(*state) * num_classes) as u16);
+    let actions = one_pass.transitions.iter().map(|(_, action)| action);
+    let range_names: Vec<Ident> = (0..num_groups).map(|r| format_ident!("r{}", r)).collect();
+    let default_ranges = core::iter::repeat_n(quote! { usize::MAX..usize::MAX }, num_groups);
+    // Action 0 does nothing.
+    let loop_statement = if one_pass.actions.len() == 1 {
+        quote! {
+            for b in data {
+                state = usize::from(ONE_PASS[state + usize::from(CLASSES[usize::from(*b)])]);
+                if state == 0 {
+                    return None;
+                }
+            }
+        }
+    } else {
+        let action_arms: Vec<(u8, TokenStream)> = one_pass
+            .actions
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(n, action)| {
+                #[allow(clippy::cast_possible_truncation)]
+                let n = n as u8;
+                let statements = action.open.iter().map(|group| {
+                    if action.end.contains(group) {
+                        quote! { ranges[#group] = n..n + 1; }
+                    } else {
+                        quote! { ranges[#group] = n..n; }
+                    }
+                });
+                let end = action
+                    .end
+                    .iter()
+                    .filter(|group| !action.open.contains(group));
+                let body = quote! {
+                    #( #statements )*
+                    #( ranges[#end].end = n + 1; )*
+                };
+                (n, body)
+            })
+            .collect();
+        let action_statement = if let [(n, body)] = action_arms.as_slice() {
+            quote! {
+                if ONE_PASS_ACTIONS[index] == #n {
+                    #body
+                }
+            }
+        } else {
+            let (numbers, bodies): (Vec<u8>, Vec<TokenStream>) = action_arms.into_iter().unzip();
+            quote! {
+                match ONE_PASS_ACTIONS[index] {
+                    #( #numbers => { #bodies } )*
+                    _ => {}
+                }
+            }
+        };
+        quote! {
+            for (n, b) in data.iter().enumerate() {
+                let index = state + usize::from(CLASSES[usize::from(*b)]);
+                state = usize::from(ONE_PASS[index]);
+                if state == 0 {
+                    return None;
+                }
+                let n = n + offset;
+                #action_statement
+            }
+        }
+    };
+    // Groups the accepting states by the groups that they open.
+    let mut accept_arms: Vec<(Vec<usize>, Vec<usize>)> = Vec::new();
+    for (state, open) in one_pass.accept.iter().enumerate() {
+        if let Some(open) = open {
+            let offset = state * num_classes;
+            if let Some(arm) = accept_arms.iter_mut().find(|(o, _)| o == open) {
+                arm.1.push(offset);
+            } else {
+                accept_arms.push((open.clone(), vec![offset]));
+            }
+        }
+    }
+    let accept_statement = if accept_arms.is_empty() {
+        quote! { return None; }
+    } else {
+        let n_statement = if accept_arms.iter().all(|(open, _)| open.is_empty()) {
+            quote! {}
+        } else {
+            quote! { let n = data.len() + offset; }
+        };
+        let (states, bodies): (Vec<TokenStream>, Vec<TokenStream>) = accept_arms
+            .iter()
+            .map(|(open, states)| {
+                let open = open.iter();
+                let states = states.iter();
+                (
+                    quote! { #( #states )|* },
+                    quote! { #( ranges[#open] = n..n; )* },
+                )
+            })
+            .unzip();
+        if let ([states], [body]) = (states.as_slice(), bodies.as_slice()) {
+            quote! {
+                #n_statement
+                if let #states = state {
+                    #body
+                } else {
+                    return None;
+                }
+            }
+        } else {
+            quote! {
+                #n_statement
+                match state {
+                    #( #states => { #bodies } )*
+                    _ => return None,
+                }
+            }
+        }
+    };
+    quote! {
+        let one_pass = |data: &[u8], offset: usize|
+            -> Option<[Option<core::ops::Range<usize>>; #num_groups]>
+        {
+            const ONE_PASS: [u16; #len] = [ #( #transitions ),* ];
+            const ONE_PASS_ACTIONS: [u8; #len] = [ #( #actions ),* ];
+            let mut ranges = [ #( #default_ranges ),* ];
+            let mut state = #num_classes;
+            #loop_statement
+            #accept_statement
+            let [ #( #range_names ),* ] = ranges;
+            Some([
+                #(
+                    if #range_names.start == usize::MAX || #range_names.end == usize::MAX {
+                        None
                     } else {
+                        Some(#range_names)
+                    }
+                ),*
+            ])
+        };
+    }
+}
+
+/// Generates the statements of a matcher that uses `dfas`.
+///
+/// A search reads forward with the search DFA to find the end of the match.
+/// Then it reads backward from there with the reverse DFA to find the start.
+/// When the expression has capturing groups, the one-pass DFA reads the match
+/// again to find the captures.
+///
+/// The statements evaluate to
+/// `Option<(core::ops::Range<usize>, [Option<core::ops::Range<usize>>; N])>`.
+fn generate_dfa(dfas: &Dfas, num_groups: usize) -> TokenStream {
+    let classes = dfas.classes.iter();
+    let num_classes = dfas.num_classes;
+    // Each entry holds the next state's offset in the table, not its number.
+    // This keeps the multiplication out of the loop.
+    let table = |dfa: &Dfa| {
+        #[allow(clippy::cast_possible_truncation)]
+        let transitions = dfa
+            .transitions
+            .iter()
+            .map(|state| (usize::from(*state) * num_classes) as u16);
+        let len = dfa.transitions.len();
+        let accept = dfa.accept.iter();
+        let num_states = dfa.accept.len();
+        (
+            quote! { [u16; #len] = [ #( #transitions ),* ] },
+            quote! { [bool; #num_states] = [ #( #accept ),* ] },
+        )
+    };
+    let (anchored, anchored_accept) = table(&dfas.anchored);
+    let (search, search_accept) = table(&dfas.search);
+    let (reverse, reverse_accept) = table(&dfas.reverse);
+    let state_number = if num_classes == 1 {
+        quote! { state }
+    } else {
+        quote! { state / #num_classes }
+    };
+    let (one_pass, search_result, anchored_statements) = if let Some(one_pass) = &dfas.one_pass {
+        (
+            generate_one_pass(one_pass, num_classes, num_groups),
+            quote! { one_pass(&data[start..end], start).map(|groups| (start..end, groups)) },
+            quote! { one_pass(data, 0).map(|groups| (0..data.len(), groups)) },
+        )
+    } else {
+        (
+            quote! {},
+            quote! { Some((start..end, [])) },
+            quote! {
+                const ANCHORED: #anchored;
+                const ANCHORED_ACCEPT: #anchored_accept;
+                let mut state = #num_classes;
+                for b in data {
+                    state = next(&ANCHORED, state, *b);
+                    if state == 0 {
+                        return None;
+                    }
+                }
+                if ANCHORED_ACCEPT[#state_number] {
+                    Some((0..data.len(), []))
+                } else {
+                    None
+                }
+            },
+        )
+    };
+    quote! {
+        const CLASSES: [u8; 256] = [ #( #classes ),* ];
+        #one_pass
+        let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+            usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+        };
+        if let Some(search_start) = search_start {
+            const SEARCH: #search;
+            const SEARCH_ACCEPT: #search_accept;
+            const REVERSE: #reverse;
+            const REVERSE_ACCEPT: #reverse_accept;
+            let mut state = #num_classes;
+            let mut end = None;
+            let mut n = search_start;
+            let mut data_iter = data.get(search_start..)?.iter();
+            loop {
+                if SEARCH_ACCEPT[#state_number] {
+                    end = Some(n);
+                }
+                if let Some(b) = data_iter.next() {
+                    state = next(&SEARCH, state, *b);
+                    if state == 0 {
                         break;
                     }
-                    n = n + 1;
+                } else {
+                    break;
                 }
-                accept .map(|( #( #range_names ),* #extra_comma )| {
+                n += 1;
+            }
+            let end = end?;
+            let mut start = end;
+            let mut state = #num_classes;
+            for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                state = next(&REVERSE, state, *b);
+                if state == 0 {
+                    break;
+                }
+                if REVERSE_ACCEPT[#state_number] {
+                    start = n;
+                }
+            }
+            #search_result
+        } else {
+            #anchored_statements
+        }
+    }
+}
+
+/// Generates statements that return `None` early when `data` cannot match.
+///
+/// When the search start is `Some`, the statements move it forward to the
+/// first byte that can start a match.
+fn generate_prefilter(prefilter: &Prefilter) -> TokenStream {
+    let literal_statement = if prefilter.literal.is_empty() {
+        quote! {}
+    } else {
+        let literal = Literal::byte_string(&prefilter.literal);
+        quote! {
+            safe_regex::internal::find_literal(
+                #literal,
+                data.get(search_start.unwrap_or(0)..)?
+            )?;
+        }
+    };
+    let skip_statement = if let Some(first_bytes) = &prefilter.first_bytes {
+        let needles: Vec<u8> = (0..=u8::MAX)
+            .filter(|b| first_bytes[usize::from(*b)])
+            .collect();
+        let find = if needles.len() <= 3 {
+            let needles = Literal::byte_string(&needles);
+            quote! { safe_regex::internal::find_any_byte(#needles, rest) }
+        } else {
+            let first_bytes = first_bytes.iter();
+            quote! {
+                {
+                    const FIRST_BYTES: [bool; 256] = [ #( #first_bytes ),* ];
+                    safe_regex::internal::find_in_set(&FIRST_BYTES, rest)
+                }
+            }
+        };
+        quote! {
+            let search_start = match search_start {
+                Some(search_start) => {
+                    let rest = data.get(search_start..)?;
+                    Some(search_start + #find?)
+                }
+                None => None,
+            };
+        }
+    } else {
+        quote! {}
+    };
+    quote! {
+        #literal_statement
+        #skip_statement
+    }
+}
+
+/// Generates an expression that wraps `matcher` in a struct with accessors for
+/// the named groups.
+///
+/// The struct dereferences to the `Matcher` so it provides all of its methods.
+/// It replaces `match_ranges` and `match_slices` with versions that return
+/// structs with one method per named group.
+///
+/// When `utf8` is true, `matcher` is a `StrMatcher` and the methods take and
+/// return `str`.
+#[allow(clippy::too_many_lines)]
+fn generate_named(
+    group_names: &[Option<String>],
+    matcher: &TokenStream,
+    utf8: bool,
+) -> TokenStream {
+    let num_groups = group_names.len();
+    let (matcher_type, data_type) = if utf8 {
+        (quote! { safe_regex::StrMatcher }, quote! { str })
+    } else {
+        (quote! { safe_regex::Matcher }, quote! { [u8] })
+    };
+    let is_match_impl = if utf8 {
+        quote! {}
+    } else {
+        quote! {
+            impl<M: safe_regex::IsMatch> safe_regex::IsMatch for NamedMatcher<M> {
+                fn is_match(&self, data: &[u8]) -> bool {
+                    self.matcher.is_match(data)
+                }
+            }
+        }
+    };
+    let matcher_fn = quote! {
+        Fn(&[u8], Option<usize>) -> Option<(
+            core::ops::Range<usize>,
+            [Option<core::ops::Range<usize>>; #num_groups]
+        )>
+    };
+    let (accessor_names, accessor_indices): (Vec<Ident>, Vec<usize>) = group_names
+        .iter()
+        .enumerate()
+        .filter_map(|(n, name)| Some((format_ident!("{}", name.as_ref()?), n)))
+        .unzip();
+    quote! {
+        {
+            struct NamedMatcher<M> {
+                matcher: M,
+            }
+            impl<M> core::ops::Deref for NamedMatcher<M> {
+                type Target = M;
+                fn deref(&self) -> &M {
+                    &self.matcher
+                }
+            }
+            #is_match_impl
+            impl<M: safe_regex::Regex> safe_regex::Regex for NamedMatcher<M> {
+                fn pattern(&self) -> &str {
+                    self.matcher.pattern()
+                }
+                fn group_count(&self) -> usize {
+                    self.matcher.group_count()
+                }
+                fn match_ranges_into(
+                    &self,
+                    data: &[u8],
+                    ranges: &mut [core::ops::Range<usize>],
+                ) -> bool {
+                    self.matcher.match_ranges_into(data, ranges)
+                }
+            }
+            #[allow(dead_code)]
+            impl<F: #matcher_fn> NamedMatcher<#matcher_type<F, #num_groups>> {
+                #[must_use]
+                pub fn match_ranges(&self, data: &#data_type) -> Option<NamedRanges> {
+                    Some(NamedRanges(self.matcher.match_ranges(data)?))
+                }
+                #[must_use]
+                pub fn match_slices<'d>(&self, data: &'d #data_type) -> Option<NamedSlices<'d>> {
+                    Some(NamedSlices(self.matcher.match_slices(data)?))
+                }
+            }
+            #[derive(Clone, Debug, PartialEq, Eq)]
+            struct NamedRanges([core::ops::Range<usize>; #num_groups]);
+            #[allow(dead_code)]
+            impl NamedRanges {
+                #(
+                    #[must_use]
+                    pub fn #accessor_names(&self) -> core::ops::Range<usize> {
+                        self.0[#accessor_indices].clone()
+                    }
+                )*
+            }
+            #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+            struct NamedSlices<'d>([&'d #data_type; #num_groups]);
+            #[allow(dead_code)]
+            impl<'d> NamedSlices<'d> {
+                #(
+                    #[must_use]
+                    pub fn #accessor_names(&self) -> &'d #data_type {
+                        self.0[#accessor_indices]
+                    }
+                )*
+            }
+            NamedMatcher { matcher: #matcher }
+        }
+    }
+}
+
+/// Generates an expression that implements `parsed_re` and evaluates to a
+/// [`safe_regex::Matcher`](https://docs.rs/safe-regex/latest/safe_regex/struct.Matcher.html)
+/// struct.
+///
+/// `pattern` is the source of the expression.
+/// The matcher returns it from `Regex::pattern`.
+///
+/// The matcher's closure takes the data and an optional search start.
+/// When the search start is `None`, it matches the whole data.
+/// Otherwise, it searches for the leftmost-first match at or after the
+/// search start, like the `regex` crate.
+#[must_use]
+pub fn generate(final_node: &FinalNode, pattern: &str) -> safe_proc_macro2::TokenStream {
+    generate_impl(final_node, pattern, false, true)
+}
+
+/// Generates an expression that implements `parsed_re` and evaluates to a
+/// [`safe_regex::StrMatcher`](https://docs.rs/safe-regex/latest/safe_regex/struct.StrMatcher.html)
+/// struct.
+///
+/// `final_node` must come from
+/// [`parse_str`](../parser/fn.parse_str.html),
+/// so the matcher matches whole UTF-8 code points.
+#[must_use]
+pub fn generate_str(final_node: &FinalNode, pattern: &str) -> safe_proc_macro2::TokenStream {
+    generate_impl(final_node, pattern, true, true)
+}
+
+/// Like [`generate`](fn.generate.html), but the matcher always returns arrays
+/// of captures, even when the expression has named groups.
+///
+/// When `utf8` is true, generates a `StrMatcher` like
+/// [`generate_str`](fn.generate_str.html).
+#[must_use]
+pub fn generate_unnamed(
+    final_node: &FinalNode,
+    pattern: &str,
+    utf8: bool,
+) -> safe_proc_macro2::TokenStream {
+    generate_impl(final_node, pattern, utf8, false)
+}
+
+/// Returns the number of capturing groups in `final_node`.
+///
+/// This counts the groups that the generated matchers return,
+/// so groups in repetitions like `(a){2}` count once.
+#[must_use]
+pub fn count_groups(final_node: &FinalNode) -> usize {
+    let mut group_names: Vec<Option<String>> = Vec::new();
+    if let Some(optimized_node) = OptimizedNode::from_final_node(final_node) {
+        TaggedNode::from_optimized(&mut group_names, &optimized_node);
+    }
+    group_names.len()
+}
+
+fn generate_impl(
+    final_node: &FinalNode,
+    pattern: &str,
+    utf8: bool,
+    named: bool,
+) -> safe_proc_macro2::TokenStream {
+    let matcher_type = if utf8 {
+        quote! { safe_regex::StrMatcher }
+    } else {
+        quote! { safe_regex::Matcher }
+    };
+    let optimized_node = if let Some(node) = OptimizedNode::from_final_node(final_node) {
+        node
+    } else {
+        return quote! {
+            #matcher_type::new(#pattern, |data: &[u8], search_start: Option<usize>| {
+                if let Some(search_start) = search_start {
+                    if search_start <= data.len() {
+                        Some((search_start..search_start, []))
+                    } else {
+                        None
+                    }
+                } else if data.is_empty() {
+                    Some((0..0, []))
+                } else {
+                    None
+                }
+            })
+        };
+    };
+    let mut group_names: Vec<Option<String>> = Vec::new();
+    let tagged_node = TaggedNode::from_optimized(&mut group_names, &optimized_node);
+    let num_groups = group_names.len();
+    let prefilter = generate_prefilter(&Prefilter::from_optimized(&optimized_node));
+    // A DFA needs a state for every count of a repetition.  Skip the slow
+    // attempt when it cannot fit.
+    let program = crate::program::compile(final_node);
+    let dfas = if tagged_node.longest_repeat() < crate::dfa::MAX_STATES {
+        crate::dfa::build(&program)
+    } else {
+        None
+    };
+    let matcher = if let Some(dfas) = dfas {
+        let statements = generate_dfa(&dfas, num_groups);
+        quote! {
+            #matcher_type::new(#pattern, |data: &[u8], search_start: Option<usize>| {
+                #prefilter
+                #statements
+            })
+        }
+    } else {
+        // The search keeps threads for every copy of a repeated element on the
+        // stack.
+        let memory_size = program.search.memory_size();
+        if memory_size > crate::program::MAX_SEARCH_MEMORY {
+            let message = format!(
+                "expression is too large: its search uses {} bytes of stack, more than {}",
+                memory_size,
+                crate::program::MAX_SEARCH_MEMORY
+            );
+            return quote! { compile_error!(#message) };
+        }
+        let mut byte_sets = ByteSets::default();
+        let search_fn = generate_search(&program.search);
+        let anchored_statements = generate_anchored(num_groups, &tagged_node, &mut byte_sets);
+        let byte_set_declarations = byte_sets.declarations();
+        quote! {
+            #matcher_type::new(#pattern, |data: &[u8], search_start: Option<usize>| {
+                #search_fn
+                #prefilter
+                #( #byte_set_declarations )*
+                if let Some(search_start) = search_start {
+                    search(data, search_start)
+                } else {
+                    #anchored_statements
+                }
+            })
+        }
+    };
+    let result = if named && group_names.iter().any(Option::is_some) {
+        generate_named(&group_names, &matcher, utf8)
+    } else {
+        matcher
+    };
+    crate::dprintln!("result={}", result);
+    result
+}
+
+/// Generates an expression that implements `final_node` and evaluates to a
+/// [`safe_regex::StreamMatcher`](https://docs.rs/safe-regex/latest/safe_regex/struct.StreamMatcher.html)
+/// struct.
+///
+/// The expression defines a `State` struct that holds the machine states
+/// between chunks.  Its size does not depend on the input.
+/// Capture ranges are `u64` positions counted from the first pushed byte.
+#[must_use]
+#[allow(clippy::too_many_lines)]
+pub fn generate_stream(final_node: &FinalNode) -> safe_proc_macro2::TokenStream {
+    let optimized_node = OptimizedNode::from_final_node(final_node).unwrap_or(OptimizedNode::Empty);
+    let mut group_names: Vec<Option<String>> = Vec::new();
+    let tagged_node = TaggedNode::from_optimized(&mut group_names, &optimized_node);
+    let num_groups = group_names.len();
+    let mut statements2_reversed: Vec<TokenStream> = Vec::new();
+    let mut bindings: Vec<TokenStream> = Vec::new();
+    let mut var_counter = Counter::new();
+    let mut counters = Counters::default();
+    let mut byte_sets = ByteSets::default();
+    let accept_expr = build(
+        Mode::Stream,
+        &mut var_counter,
+        &mut counters,
+        &mut byte_sets,
+        num_groups,
+        &Vec::new(),
+        &mut statements2_reversed,
+        &mut bindings,
+        &mut false,
+        &quote! { start },
+        &tagged_node,
+    );
+    let mut var_names: Vec<Ident> = Vec::new();
+    let mut prev_var_names: Vec<Ident> = Vec::new();
+    for n in 0..var_counter.get() {
+        let (var_name, prev_var_name) = byte_and_prev_var_names(n);
+        var_names.push(var_name);
+        prev_var_names.push(prev_var_name);
+    }
+    let all_none = all_none_expr(&var_names, &counters);
+    let counter_names = counters.names();
+    let counter_clone_statements = counters.clone_statements();
+    let byte_set_declarations = byte_sets.declarations();
+    let statements2 = statements2_reversed.iter().rev();
+    let (data_type, start_value, result_expr) = if num_groups == 0 {
+        (
+            quote! { () },
+            quote! { Some(()) },
+            quote! { #accept_expr .map(|_| []) },
+        )
+    } else {
+        let extra_comma = if num_groups > 1 {
+            quote! {}
+        } else {
+            quote! {,}
+        };
+        let range_types = core::iter::repeat_n(quote! { core::ops::Range<u64> }, num_groups);
+        let default_ranges = core::iter::repeat_n(quote! { u64::MAX..u64::MAX }, num_groups);
+        let range_names: Vec<Ident> = (0..num_groups).map(|r| format_ident!("r{}", r)).collect();
+        (
+            quote! { ( #( #range_types ),* #extra_comma ) },
+            quote! { Some(( #( #default_ranges ),* #extra_comma )) },
+            quote! {
+                #accept_expr .map(|( #( #range_names ),* #extra_comma )| {
                     [
                         #(
-                            if #range_names.start == usize::MAX || #range_names.end == usize::MAX || #range_names.is_empty() {
-                                0..0usize
+                            if #range_names.start == u64::MAX || #range_names.end == u64::MAX {
+                                None
                             } else {
-                                #range_names
-                            },
-                        )*
+                                Some(#range_names)
+                            }
+                        ),*
                     ]
                 })
-            })
+            },
+        )
+    };
+    let state_type = quote! { Option<#data_type> };
+    let counter_types = counters.types(&data_type);
+    let result = quote! {
+        {
+            #[derive(Clone)]
+            struct State {
+                start: #state_type,
+                #( #var_names : #state_type, )*
+                #( #counter_names : #counter_types, )*
+                n: u64,
+                prev_byte: Option<u8>,
+            }
+            impl safe_regex::internal::StreamState<#num_groups> for State {
+                fn new() -> Self {
+                    Self {
+                        start: #start_value,
+                        #( #var_names : None, )*
+                        #( #counter_names : safe_regex::internal::CountedRepeat::new(), )*
+                        n: 0,
+                        prev_byte: None,
+                    }
+                }
+
+                #[allow(unused_variables, unused_assignments)]
+                fn push(&mut self, chunk: &[u8]) {
+                    #( #byte_set_declarations )*
+                    let mut start = self.start.take();
+                    #( let mut #var_names = self.#var_names.take(); )*
+                    #( let mut #counter_names = core::mem::take(&mut self.#counter_names); )*
+                    if start.is_none() && #all_none {
+                        return;
+                    }
+                    let mut n = self.n;
+                    let mut prev_byte = self.prev_byte;
+                    for b in chunk {
+                        let next_byte = Some(*b);
+                        #( let #prev_var_names = #var_names .clone() ; )*
+                        #( #counter_clone_statements )*
+                        #( #bindings )*
+                        #( #statements2 )*
+                        start = None;
+                        prev_byte = next_byte;
+                        n += 1;
+                        if #all_none {
+                            break;
+                        }
+                    }
+                    self.start = start;
+                    #( self.#var_names = #var_names; )*
+                    #( self.#counter_names = #counter_names; )*
+                    self.n = n;
+                    self.prev_byte = prev_byte;
+                }
+
+                #[allow(unused_variables)]
+                fn finish(&self) -> Option<[Option<core::ops::Range<u64>>; #num_groups]> {
+                    let start = self.start.clone();
+                    #( let #prev_var_names = self.#var_names.clone(); )*
+                    #( let #counter_names = &self.#counter_names; )*
+                    #( #counter_clone_statements )*
+                    let n = self.n;
+                    let prev_byte = self.prev_byte;
+                    let next_byte: Option<u8> = None;
+                    #( #bindings )*
+                    #result_expr
+                }
+            }
+            safe_regex::StreamMatcher::new(<State as safe_regex::internal::StreamState<#num_groups>>::new())
         }
     };
     crate::dprintln!("result={}", result);
     result
 }
+
+/// Machines for several expressions that share the `start` state and step
+/// over the data together.
+///
+/// Each expression gets its own byte states.
+/// Capturing groups are ignored.
+struct ParallelMachines {
+    accept_exprs: Vec<TokenStream>,
+    var_names: Vec<Ident>,
+    counters: Counters,
+    byte_sets: ByteSets,
+    var_clone_statements: Vec<TokenStream>,
+    bindings: Vec<TokenStream>,
+    statements2: Vec<TokenStream>,
+    has_assertion: bool,
+}
+impl ParallelMachines {
+    fn new<'a>(final_nodes: impl Iterator<Item = &'a FinalNode>) -> Self {
+        let mut statements2_reversed: Vec<TokenStream> = Vec::new();
+        let mut bindings: Vec<TokenStream> = Vec::new();
+        let mut var_counter = Counter::new();
+        let mut counters = Counters::default();
+        let mut byte_sets = ByteSets::default();
+        let mut has_assertion = false;
+        let mut accept_exprs: Vec<TokenStream> = Vec::new();
+        for final_node in final_nodes {
+            let optimized_node = OptimizedNode::from_final_node(final_node)
+                .unwrap_or(OptimizedNode::Empty)
+                .non_capturing();
+            let tagged_node = TaggedNode::from_optimized(&mut Vec::new(), &optimized_node);
+            has_assertion |= tagged_node.has_assertion();
+            accept_exprs.push(build(
+                Mode::Anchored,
+                &mut var_counter,
+                &mut counters,
+                &mut byte_sets,
+                0,
+                &Vec::new(),
+                &mut statements2_reversed,
+                &mut bindings,
+                &mut false,
+                &quote! { start },
+                &tagged_node,
+            ));
+        }
+        let mut var_names: Vec<Ident> = Vec::new();
+        let mut var_clone_statements: Vec<TokenStream> = Vec::new();
+        for n in 0..var_counter.get() {
+            let (var_name, prev_var_name) = byte_and_prev_var_names(n);
+            var_clone_statements.push(quote! {
+                let #prev_var_name = #var_name .clone() ;
+            });
+            var_names.push(var_name);
+        }
+        var_clone_statements.extend(counters.clone_statements());
+        statements2_reversed.reverse();
+        Self {
+            accept_exprs,
+            var_names,
+            counters,
+            byte_sets,
+            var_clone_statements,
+            bindings,
+            statements2: statements2_reversed,
+            has_assertion,
+        }
+    }
+}
+
+/// Generates an expression that implements all of `final_nodes` in one
+/// machine and evaluates to a
+/// [`safe_regex::RegexSet`](https://docs.rs/safe-regex/latest/safe_regex/struct.RegexSet.html)
+/// struct.
+///
+/// The machine makes one pass over the data and matches every expression
+/// against all of it.
+/// Capturing groups do not matter for a set, so the machine ignores them.
+#[must_use]
+pub fn generate_set(final_nodes: &[FinalNode]) -> safe_proc_macro2::TokenStream {
+    let num_patterns = final_nodes.len();
+    let ParallelMachines {
+        accept_exprs,
+        var_names,
+        counters,
+        byte_sets,
+        var_clone_statements,
+        bindings,
+        statements2,
+        has_assertion,
+    } = ParallelMachines::new(final_nodes.iter());
+    let all_none = all_none_expr(&var_names, &counters);
+    let counter_declarations = counters.declarations(&quote! { () });
+    let byte_set_declarations = byte_sets.declarations();
+    // Only assertions use the position.
+    let (n_statement, n_increment) = if has_assertion {
+        (quote! { let mut n = 0; }, quote! { n += 1; })
+    } else {
+        (quote! {}, quote! {})
+    };
+    let result = quote! {
+        safe_regex::RegexSet::new(|data: &[u8]| -> [bool; #num_patterns] {
+            #( #byte_set_declarations )*
+            let mut start = Some(());
+            #( let mut #var_names : Option<()> = None; )*
+            #( #counter_declarations )*
+            let mut data_iter = data.iter();
+            #n_statement
+            loop {
+                #( #var_clone_statements )*
+                #( #bindings )*
+                if let Some(b) = data_iter.next() {
+                    #( #statements2 )*
+                    start = None;
+                    if #all_none {
+                        return [false; #num_patterns];
+                    }
+                } else {
+                    return [ #( #accept_exprs .is_some() ),* ];
+                }
+                #n_increment
+            }
+        })
+    };
+    crate::dprintln!("result={}", result);
+    result
+}
+
+/// Generates an expression that implements a lexer with `rules` and evaluates
+/// to a
+/// [`safe_regex::Lexer`](https://docs.rs/safe-regex/latest/safe_regex/struct.Lexer.html)
+/// struct.
+///
+/// Each rule is an expression that makes a token and the expression that the
+/// token's bytes must match.
+/// The lexer's closure takes the data and the position of the next token.
+/// It steps the machines of all rules together until none of them can match
+/// more bytes, and returns the token of the longest non-empty match and its
+/// end.  When several rules match the longest bytes, the first one wins.
+#[must_use]
+pub fn generate_lexer(rules: &[(TokenStream, FinalNode)]) -> safe_proc_macro2::TokenStream {
+    let ParallelMachines {
+        accept_exprs,
+        var_names,
+        counters,
+        byte_sets,
+        var_clone_statements,
+        bindings,
+        statements2,
+        has_assertion: _,
+    } = ParallelMachines::new(rules.iter().map(|(_, node)| node));
+    let all_none = all_none_expr(&var_names, &counters);
+    let counter_declarations = counters.declarations(&quote! { () });
+    let byte_set_declarations = byte_sets.declarations();
+    // Earlier rules win, so check them first.
+    let update_longest = accept_exprs.iter().enumerate().rev().fold(
+        quote! {},
+        |else_branch, (rule, accept_expr)| {
+            let else_branch = if else_branch.is_empty() {
+                else_branch
+            } else {
+                quote! { else #else_branch }
+            };
+            quote! {
+                if #accept_expr .is_some() {
+                    longest = Some((#rule, n));
+                } #else_branch
+            }
+        },
+    );
+    let rule_indices = 0..rules.len();
+    let tokens = rules.iter().map(|(token, _)| token);
+    let result = quote! {
+        safe_regex::Lexer::new(|data: &[u8], token_start: usize| {
+            #( #byte_set_declarations )*
+            let mut start = Some(());
+            #( let mut #var_names : Option<()> = None; )*
+            #( #counter_declarations )*
+            let mut data_iter = data.get(token_start..)?.iter();
+            let mut n = token_start;
+            let mut longest: Option<(usize, usize)> = None;
+            loop {
+                #( #var_clone_statements )*
+                #( #bindings )*
+                if n > token_start {
+                    #update_longest
+                }
+                if let Some(b) = data_iter.next() {
+                    #( #statements2 )*
+                    start = None;
+                    if #all_none {
+                        break;
+                    }
+                } else {
+                    break;
+                }
+                n += 1;
+            }
+            let (rule, end) = longest?;
+            let token = match rule {
+                #( #rule_indices => #tokens, )*
+                _ => unreachable!(),
+            };
+            Some((token, end))
+        })
+    };
+    crate::dprintln!("result={}", result);
+    result
+}
@@ -17,11 +17,23 @@
 //! 1. Edit `Cargo.toml` and bump version number.
 //! 1. Run `../release.sh`
 #![forbid(unsafe_code)]
-use crate::generator::generate;
-use safe_proc_macro2::{TokenStream, TokenTree};
+use crate::derive::{parse_struct, Struct};
+use crate::generator::{
+    count_groups, generate, generate_lexer, generate_set, generate_str, generate_stream,
+    generate_unnamed,
+};
+use crate::parser::FinalNode;
+use safe_proc_macro2::{Ident, Literal, Spacing, Span, TokenStream, TokenTree};
+use safe_quote::{format_ident, quote, quote_spanned};
 
+mod derive;
+pub mod dfa;
 pub mod generator;
 pub mod parser;
+pub mod prefilter;
+pub mod program;
+pub mod unicode;
+pub mod utf8;
 
 #[macro_export]
 macro_rules! dprintln {
@@ -41,11 +53,357 @@ pub fn escape_ascii(input: impl AsRef<[u8]>) -> String {
     result
 }
 
+const RAW_STRING_ERR: &str = "expected a raw string, like r\"abc\" or br\"abc\"";
+
+/// A problem with the input of a macro, and where it is.
+#[derive(Clone, Debug)]
+pub struct CompileError {
+    pub message: String,
+    pub span: Span,
+}
+impl CompileError {
+    #[must_use]
+    pub fn new(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            message: message.into(),
+            span,
+        }
+    }
+
+    /// Returns a `compile_error!` invocation that makes the compiler show
+    /// `message` and underline `span`.
+    #[must_use]
+    pub fn to_compile_error(&self) -> TokenStream {
+        let message = &self.message;
+        quote_spanned!(self.span=> compile_error!(#message))
+    }
+}
+
 /// Implements the `regex!` macro.
 ///
 /// # Errors
-/// Returns `Err(String)` with a human-readable description of the problem.
-pub fn impl_regex(stream: TokenStream) -> Result<TokenStream, String> {
+/// Returns a [`CompileError`](struct.CompileError.html) with a human-readable
+/// description of the problem.
+/// When the expression has a syntax error, the error's span covers the bytes
+/// with the problem, like the `(` in `br"ab(c"`.
+/// Only nightly compilers support such narrow spans.
+/// Stable compilers get the span of the whole literal and a message that
+/// shows the problem underlined with `^` characters.
+pub fn impl_regex(stream: TokenStream) -> Result<TokenStream, CompileError> {
+    let (node, utf8, pattern) = parse_literal(stream)?;
+    if utf8 {
+        Ok(generate_str(&node, &pattern))
+    } else {
+        Ok(generate(&node, &pattern))
+    }
+}
+
+/// Implements the `regex_stream!` macro.
+///
+/// # Errors
+/// Returns a [`CompileError`](struct.CompileError.html) like
+/// [`impl_regex`](fn.impl_regex.html) does.
+pub fn impl_regex_stream(stream: TokenStream) -> Result<TokenStream, CompileError> {
+    let (node, _utf8, _pattern) = parse_literal(stream)?;
+    Ok(generate_stream(&node))
+}
+
+/// Implements the `regex_set!` macro.
+///
+/// # Errors
+/// Returns a [`CompileError`](struct.CompileError.html) like
+/// [`impl_regex`](fn.impl_regex.html) does.
+pub fn impl_regex_set(stream: TokenStream) -> Result<TokenStream, CompileError> {
+    const ERR: &str = "expected raw strings separated by commas, like br\"a\", br\"b\"";
+    let mut nodes = Vec::new();
+    let mut stream_iter = stream.into_iter();
+    loop {
+        match stream_iter.next() {
+            Some(TokenTree::Literal(literal)) => nodes.push(parse_regex_literal(&literal)?.0),
+            Some(tree) => return Err(CompileError::new(ERR, tree.span())),
+            None if nodes.is_empty() => return Err(CompileError::new(ERR, Span::call_site())),
+            None => break,
+        }
+        match stream_iter.next() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == ',' => {}
+            Some(tree) => return Err(CompileError::new(ERR, tree.span())),
+            None => break,
+        }
+    }
+    Ok(generate_set(&nodes))
+}
+
+/// Implements the `lexer!` macro.
+///
+/// # Errors
+/// Returns a [`CompileError`](struct.CompileError.html) like
+/// [`impl_regex`](fn.impl_regex.html) does.
+pub fn impl_lexer(stream: TokenStream) -> Result<TokenStream, CompileError> {
+    const ERR: &str = "expected rules separated by commas, like Token::Word => br\"[a-z]+\"";
+    let mut rules = Vec::new();
+    let mut stream_iter = stream.into_iter().peekable();
+    while stream_iter.peek().is_some() {
+        // The token expression is everything up to `=>`.
+        let mut token = TokenStream::new();
+        loop {
+            match stream_iter.next() {
+                Some(TokenTree::Punct(punct))
+                    if punct.as_char() == '=' && punct.spacing() == Spacing::Joint =>
+                {
+                    match stream_iter.next() {
+                        Some(TokenTree::Punct(punct)) if punct.as_char() == '>' => break,
+                        Some(tree) => return Err(CompileError::new(ERR, tree.span())),
+                        None => return Err(CompileError::new(ERR, punct.span())),
+                    }
+                }
+                Some(TokenTree::Punct(punct)) if punct.as_char() == ',' => {
+                    return Err(CompileError::new(ERR, punct.span()))
+                }
+                Some(tree) => token.extend(core::iter::once(tree)),
+                None => return Err(CompileError::new(ERR, Span::call_site())),
+            }
+        }
+        if token.is_empty() {
+            return Err(CompileError::new(ERR, Span::call_site()));
+        }
+        let node = match stream_iter.next() {
+            Some(TokenTree::Literal(literal)) => parse_regex_literal(&literal)?.0,
+            Some(tree) => return Err(CompileError::new(ERR, tree.span())),
+            None => return Err(CompileError::new(ERR, Span::call_site())),
+        };
+        rules.push((token, node));
+        match stream_iter.next() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == ',' => {}
+            Some(tree) => return Err(CompileError::new(ERR, tree.span())),
+            None => break,
+        }
+    }
+    if rules.is_empty() {
+        return Err(CompileError::new(ERR, Span::call_site()));
+    }
+    Ok(generate_lexer(&rules))
+}
+
+/// Implements the `define_regex!` macro.
+///
+/// The input is one or more definitions like
+/// `/// Docs \n pub(crate) Name = br"...";`.
+/// Each one becomes a zero-sized struct that dereferences to a `Matcher` in a
+/// `static`.
+/// The struct gets the attributes and visibility in front of its name.
+///
+/// # Errors
+/// Returns a [`CompileError`](struct.CompileError.html) like
+/// [`impl_regex`](fn.impl_regex.html) does.
+pub fn impl_define_regex(stream: TokenStream) -> Result<TokenStream, CompileError> {
+    const ERR: &str = "expected definitions like `pub Name = br\"[0-9]+\";`";
+    let mut result = TokenStream::new();
+    let mut stream_iter = stream.into_iter().peekable();
+    while stream_iter.peek().is_some() {
+        // Attributes, visibility, and the name come before `=`.
+        let mut prefix: Vec<TokenTree> = Vec::new();
+        loop {
+            match stream_iter.next() {
+                Some(TokenTree::Punct(punct)) if punct.as_char() == '=' => break,
+                Some(tree) => prefix.push(tree),
+                None => return Err(CompileError::new(ERR, Span::call_site())),
+            }
+        }
+        let name = match prefix.pop() {
+            Some(TokenTree::Ident(name)) => name,
+            Some(tree) => return Err(CompileError::new(ERR, tree.span())),
+            None => return Err(CompileError::new(ERR, Span::call_site())),
+        };
+        let literal = match stream_iter.next() {
+            Some(TokenTree::Literal(literal)) => literal,
+            Some(tree) => return Err(CompileError::new(ERR, tree.span())),
+            None => return Err(CompileError::new(ERR, Span::call_site())),
+        };
+        let (node, utf8, pattern) = parse_regex_literal(&literal)?;
+        let num_groups = count_groups(&node);
+        let matcher = generate_unnamed(&node, &pattern, utf8);
+        let matcher_type = if utf8 {
+            quote! { safe_regex::StrMatcher<safe_regex::internal::MatcherFn<#num_groups>, #num_groups> }
+        } else {
+            quote! { safe_regex::Matcher<safe_regex::internal::MatcherFn<#num_groups>, #num_groups> }
+        };
+        let is_match_impl = if utf8 {
+            quote! {}
+        } else {
+            quote! {
+                impl safe_regex::IsMatch for #name {
+                    fn is_match(&self, data: &[u8]) -> bool {
+                        core::ops::Deref::deref(self).is_match(data)
+                    }
+                }
+            }
+        };
+        let prefix: TokenStream = prefix.into_iter().collect();
+        result.extend(quote! {
+            #prefix struct #name;
+            impl #name {
+                #[must_use]
+                #[allow(dead_code)]
+                pub const fn new() -> Self {
+                    Self
+                }
+            }
+            impl core::ops::Deref for #name {
+                type Target = #matcher_type;
+                fn deref(&self) -> &Self::Target {
+                    static MATCHER: #matcher_type = #matcher;
+                    &MATCHER
+                }
+            }
+            #is_match_impl
+            impl safe_regex::Regex for #name {
+                fn pattern(&self) -> &str {
+                    core::ops::Deref::deref(self).pattern()
+                }
+                fn group_count(&self) -> usize {
+                    #num_groups
+                }
+                fn match_ranges_into(
+                    &self,
+                    data: &[u8],
+                    ranges: &mut [core::ops::Range<usize>],
+                ) -> bool {
+                    core::ops::Deref::deref(self).match_ranges_into(data, ranges)
+                }
+            }
+        });
+        match stream_iter.next() {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == ';' => {}
+            Some(tree) => return Err(CompileError::new(ERR, tree.span())),
+            None => break,
+        }
+    }
+    if result.is_empty() {
+        return Err(CompileError::new(ERR, Span::call_site()));
+    }
+    Ok(result)
+}
+
+/// Implements the `FromRegex` derive macro.
+///
+/// Generates a `from_bytes` function that matches the data against the
+/// expression in the struct's `#[regex(br"...")]` attribute and converts each
+/// capturing group into the field at the same position.
+/// A `#[regex(r"...")]` attribute generates a `FromStr` implementation
+/// instead.
+///
+/// # Errors
+/// Returns a [`CompileError`](struct.CompileError.html) when the input is not
+/// a struct with one `#[regex(...)]` attribute, when the expression has a
+/// syntax error, or when the expression's number of capturing groups differs
+/// from the struct's number of fields.
+pub fn impl_from_regex(stream: TokenStream) -> Result<TokenStream, CompileError> {
+    let Struct {
+        name,
+        regex_attrs,
+        fields,
+    } = parse_struct(stream)?;
+    let literal = match &regex_attrs[..] {
+        [literal] => literal,
+        [] => {
+            return Err(CompileError::new(
+                "expected an attribute like `#[regex(br\"[0-9]+\")]`",
+                name.span(),
+            ))
+        }
+        [_, second, ..] => {
+            return Err(CompileError::new(
+                "expected only one `#[regex(...)]` attribute",
+                second.span(),
+            ))
+        }
+    };
+    let (node, utf8, pattern) = parse_regex_literal(literal)?;
+    let num_groups = count_groups(&node);
+    if num_groups != fields.len() {
+        return Err(CompileError::new(
+            format!(
+                "the expression has {} capturing groups but `{}` has {} fields",
+                num_groups,
+                name,
+                fields.len()
+            ),
+            literal.span(),
+        ));
+    }
+    let matcher = generate_unnamed(&node, &pattern, utf8);
+    let capture_names: Vec<Ident> = (0..num_groups).map(|n| format_ident!("c{}", n)).collect();
+    let values = fields
+        .iter()
+        .zip(&capture_names)
+        .enumerate()
+        .map(|(n, (field, capture))| {
+            let ty = &field.ty;
+            let capture = if utf8 {
+                quote! { #capture.as_bytes() }
+            } else {
+                quote! { #capture }
+            };
+            let field_name = field
+                .name
+                .as_ref()
+                .map_or_else(|| n.to_string(), ToString::to_string);
+            quote! {
+                (&safe_regex::internal::FieldConverter::<#ty>::new())
+                    .convert(#capture)
+                    .ok_or(safe_regex::FromRegexError::InvalidField(#field_name))?
+            }
+        });
+    let value = match fields.first() {
+        None => quote! { Self },
+        Some(first) if first.name.is_some() => {
+            let field_names = fields.iter().map(|field| &field.name);
+            quote! { Self { #( #field_names : #values ),* } }
+        }
+        Some(_) => quote! { Self( #( #values ),* ) },
+    };
+    let body = quote! {
+        #[allow(unused_imports)]
+        use safe_regex::internal::{ConvertViaFromStr as _, ConvertViaTryFrom as _};
+        let matcher = #matcher;
+        let [ #( #capture_names ),* ] = matcher
+            .match_slices(data)
+            .ok_or(safe_regex::FromRegexError::NoMatch)?;
+        Ok(#value)
+    };
+    if utf8 {
+        Ok(quote! {
+            impl core::str::FromStr for #name {
+                type Err = safe_regex::FromRegexError;
+
+                fn from_str(data: &str) -> Result<Self, Self::Err> {
+                    #body
+                }
+            }
+        })
+    } else {
+        Ok(quote! {
+            impl #name {
+                /// Matches all of `data` against the struct's regular expression
+                /// and converts the captures into the struct's fields.
+                ///
+                /// # Errors
+                /// Returns an error when the expression does not match `data`
+                /// or when a capture cannot convert into its field.
+                pub fn from_bytes(data: &[u8]) -> Result<Self, safe_regex::FromRegexError> {
+                    #body
+                }
+            }
+        })
+    }
+}
+
+/// Parses the expression in the raw string literal in `stream`.
+///
+/// Returns the parsed expression, `true` when the literal is a string
+/// `r"..."` instead of a byte string `br"..."`, and the source of the
+/// expression.
+fn parse_literal(stream: TokenStream) -> Result<(FinalNode, bool, String), CompileError> {
     // Ident { sym: regex }
     // Punct { char: '!', spacing: Alone }
     // Group {
@@ -54,9 +412,8 @@ pub fn impl_regex(stream: TokenStream) -> Result<TokenStream, String> {
     //     Literal { lit: br"a" }
     //   ]
     // }
-    const ERR: &str = "expected a raw byte string, like br\"abc\"";
     dprintln!(
-        "impl_regex {:?}",
+        "parse_literal {:?}",
         stream
             .clone()
             .into_iter()
@@ -66,28 +423,56 @@ pub fn impl_regex(stream: TokenStream) -> Result<TokenStream, String> {
     let mut stream_iter = stream.into_iter();
     let literal = match stream_iter.next() {
         Some(TokenTree::Literal(literal)) => literal,
-        _ => return Err(ERR.to_string()),
+        Some(tree) => return Err(CompileError::new(RAW_STRING_ERR, tree.span())),
+        None => return Err(CompileError::new(RAW_STRING_ERR, Span::call_site())),
     };
-    if stream_iter.next().is_some() {
-        return Err(ERR.to_string());
+    if let Some(tree) = stream_iter.next() {
+        return Err(CompileError::new(RAW_STRING_ERR, tree.span()));
     }
+    parse_regex_literal(&literal)
+}
 
+/// Parses the expression in `literal`.
+///
+/// Returns the parsed expression, `true` when the literal is a string
+/// `r"..."` instead of a byte string `br"..."`, and the source of the
+/// expression.
+fn parse_regex_literal(literal: &Literal) -> Result<(FinalNode, bool, String), CompileError> {
     // The compiler already parsed the literal, but does not expose its fields.
     // So we convert the literal to a string and parse it ourselves.
     // https://stackoverflow.com/questions/61169932/how-do-i-get-the-value-and-type-of-a-literal-in-a-rust-proc-macro
     let literal_string = literal.to_string();
     // println!("compiling safe_regex::regex!({})", literal_string);
-    let raw_byte_string = literal_string
-        .strip_prefix("br")
-        .ok_or_else(|| ERR.to_string())?
+    // A raw byte string `br"..."` makes a matcher for byte slices.
+    // A raw string `r"..."` makes a matcher for strings.
+    let (raw_string, utf8) = if let Some(rest) = literal_string.strip_prefix("br") {
+        (rest, false)
+    } else if let Some(rest) = literal_string.strip_prefix('r') {
+        (rest, true)
+    } else {
+        return Err(CompileError::new(RAW_STRING_ERR, literal.span()));
+    };
+    let raw_string = raw_string
         // Compiler guarantees that strings are closed.
         .trim_start_matches('#')
-        .trim_start_matches('"')
-        .trim_end_matches('#')
-        .trim_end_matches('"');
-    // The compiler guarantees that a literal byte string contains only ASCII.
-    // > regex!(br"€"); // error: raw byte string must be ASCII
-    // Therefore, we can slice the string at any byte offset.
-    let final_node = crate::parser::parse(raw_byte_string.as_bytes())?;
-    Ok(generate(&final_node))
+        .trim_start_matches('"');
+    // Where the expression starts in the source code of the literal.
+    let offset = literal_string.len() - raw_string.len();
+    let raw_string = raw_string.trim_end_matches('#').trim_end_matches('"');
+    let result = if utf8 {
+        crate::parser::parse_str(raw_string)
+    } else {
+        // The compiler guarantees that a literal byte string contains only ASCII.
+        // > regex!(br"€"); // error: raw byte string must be ASCII
+        // Therefore, we can slice the string at any byte offset.
+        crate::parser::parse(raw_string.as_bytes())
+    };
+    let node = result.map_err(|e| {
+        let span = e.span.start + offset..e.span.end + offset;
+        match literal.subspan(span) {
+            Some(span) => CompileError::new(e.kind.to_string(), span),
+            None => CompileError::new(e.to_string(), literal.span()),
+        }
+    })?;
+    Ok((node, utf8, raw_string.to_string()))
 }
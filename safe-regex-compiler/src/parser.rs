@@ -26,6 +26,216 @@
 //!   - No documentation
 #![forbid(unsafe_code)]
 use crate::escape_ascii;
+use core::convert::TryFrom;
+use core::ops::Range;
+
+/// The kinds of problems that [`parse`](fn.parse.html) finds.
+///
+/// `Display` shows a message like ``missing closing `)` ``.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// `\`, `\x`, `\x4`, or `\p` at the end of the expression.
+    IncompleteEscape(String),
+    /// `\q` or `\xZZ`
+    InvalidEscape(String),
+    /// `\p{Greek`
+    UnclosedPropertyName(String),
+    /// `\p1`
+    MissingPropertyName(String),
+    /// `\p{Klingon}`
+    UnknownProperty(String),
+    /// `[a`
+    UnclosedClass,
+    /// `[a-`
+    UnclosedRange(String),
+    /// `[a-\d]`
+    ClassInRange(String),
+    /// `[a-b-c]`
+    RangeInRange(String),
+    /// `[\b]`
+    AssertionInClass,
+    /// `[[:bogus:]]`
+    UnknownPosixClass(String),
+    /// `[[:alpha]]`
+    UnclosedPosixClass(String),
+    /// `(a`
+    UnclosedGroup,
+    /// `(?Q)`
+    InvalidExtendedGroup(String),
+    /// `(?P<name`
+    UnclosedGroupName,
+    /// `(?P<>a)`
+    MissingGroupName,
+    /// `(?P<1a>a)`
+    GroupNameStartsWithDigit(String),
+    /// `(?P<fn>a)`
+    GroupNameIsKeyword(String),
+    /// `(?P<a-b>a)`
+    InvalidGroupNameByte(u8),
+    /// `(?P<a>a)(?P<a>b)`
+    DuplicateGroupName(String),
+    /// `(?m!)`
+    InvalidFlagsByte(u8),
+    /// `(?m-)`
+    MissingFlag(String),
+    /// `(?x)`
+    UnknownFlag(char),
+    /// `|a`
+    MissingElementBeforeBar,
+    /// `a|`
+    MissingElementAfterBar,
+    /// `*a`
+    MissingRepeatElement(String),
+    /// `a{2`
+    UnclosedRepeat(String),
+    /// `a{x}`
+    InvalidRepeatValue(String, core::num::ParseIntError),
+    /// `a{2,1}`
+    RepeatMaxBelowMin(String),
+    /// `a{1000000}`, an expression that compiles to more than
+    /// [`MAX_SIZE`](../program/constant.MAX_SIZE.html) instructions.
+    TooLarge(usize),
+}
+impl core::fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> Result<(), core::fmt::Error> {
+        use ParseErrorKind::{
+            AssertionInClass, ClassInRange, DuplicateGroupName, GroupNameIsKeyword,
+            GroupNameStartsWithDigit, IncompleteEscape, InvalidEscape, InvalidExtendedGroup,
+            InvalidFlagsByte, InvalidGroupNameByte, InvalidRepeatValue, MissingElementAfterBar,
+            MissingElementBeforeBar, MissingFlag, MissingGroupName, MissingPropertyName,
+            MissingRepeatElement, RangeInRange, RepeatMaxBelowMin, TooLarge, UnclosedClass,
+            UnclosedGroup, UnclosedGroupName, UnclosedPosixClass, UnclosedPropertyName,
+            UnclosedRange, UnclosedRepeat, UnknownFlag, UnknownPosixClass, UnknownProperty,
+        };
+        match self {
+            IncompleteEscape(s) => write!(f, "incomplete escape sequence: `{}`", s),
+            InvalidEscape(s) => write!(f, "invalid escape sequence `{}`", s),
+            UnclosedPropertyName(s) => write!(f, "missing closing `}}` of property name: `{}`", s),
+            MissingPropertyName(s) => write!(
+                f,
+                "expected property name after `{}`: `{}`",
+                s.get(..2).unwrap_or_default(),
+                s
+            ),
+            UnknownProperty(name) => write!(f, "unknown Unicode property: `{}`", name),
+            UnclosedClass => write!(f, "missing closing `]`"),
+            UnclosedRange(s) => write!(f, "missing byte to close range: `{}-`", s),
+            ClassInRange(s) => write!(f, "expected byte after '-' symbol, not class: `{}-`", s),
+            RangeInRange(s) => write!(f, "expected byte before '-' symbol, not range: `{}-`", s),
+            AssertionInClass => write!(f, "assertions are not allowed in classes"),
+            UnknownPosixClass(name) => write!(f, "unknown POSIX class: `[:{}]`", name),
+            UnclosedPosixClass(name) => {
+                write!(f, "missing `:]` after POSIX class: `[:{}]`", name)
+            }
+            UnclosedGroup => write!(f, "missing closing `)`"),
+            InvalidExtendedGroup(s) => write!(f, "unexpected symbol after `{}`", s),
+            UnclosedGroupName => write!(f, "missing closing `>` of group name"),
+            MissingGroupName => write!(f, "missing group name"),
+            GroupNameStartsWithDigit(name) => {
+                write!(f, "group name starts with a digit: `{}`", name)
+            }
+            GroupNameIsKeyword(name) => write!(f, "group name is a Rust keyword: `{}`", name),
+            InvalidGroupNameByte(b) => {
+                write!(f, "invalid byte in group name: `{}`", escape_ascii([*b]))
+            }
+            DuplicateGroupName(name) => write!(f, "duplicate group name: `{}`", name),
+            InvalidFlagsByte(b) => {
+                write!(f, "unexpected symbol in flags: `{}`", escape_ascii([*b]))
+            }
+            MissingFlag(s) => write!(f, "missing flag after `-`: `(?{}`", s),
+            UnknownFlag(c) => write!(f, "unknown flag: `{}`", c),
+            MissingElementBeforeBar => write!(f, "missing element before bar `|`"),
+            MissingElementAfterBar => write!(f, "missing element after bar `|`"),
+            MissingRepeatElement(s) => {
+                write!(f, "missing element before repeat element: `{}`", s)
+            }
+            UnclosedRepeat(s) => write!(f, "missing closing `}}` symbol: `{}`", s),
+            InvalidRepeatValue(s, e) => write!(f, "invalid repetition value `{}`: {}", s, e),
+            RepeatMaxBelowMin(s) => write!(
+                f,
+                "repeating element has max that is smaller than min: `{}`",
+                s
+            ),
+            TooLarge(size) => write!(
+                f,
+                "expression is too large: it compiles to {} instructions, more than {}",
+                size,
+                crate::program::MAX_SIZE
+            ),
+        }
+    }
+}
+
+/// A problem with a regular expression.
+///
+/// `span` is the range of bytes in the expression with the problem.
+/// Example: for `ab(c`, `kind` is
+/// [`UnclosedGroup`](enum.ParseErrorKind.html#variant.UnclosedGroup)
+/// and `span` is `2..3`, the `(`.
+///
+/// `Display` shows the message and the expression with the span underlined:
+/// ```text
+/// missing closing `)`
+/// ab(c
+///   ^
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub span: Range<usize>,
+    pattern: Vec<u8>,
+}
+impl ParseError {
+    #[must_use]
+    pub fn new(kind: ParseErrorKind, span: Range<usize>, pattern: &[u8]) -> Self {
+        Self {
+            kind,
+            span,
+            pattern: pattern.to_vec(),
+        }
+    }
+
+    /// Returns the expression that has the problem.
+    #[must_use]
+    pub fn pattern(&self) -> &[u8] {
+        &self.pattern
+    }
+}
+impl core::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> Result<(), core::fmt::Error> {
+        let end = self.span.end.min(self.pattern.len());
+        let start = self.span.start.min(end);
+        let before = printable(&self.pattern[..start]);
+        let span = printable(&self.pattern[start..end]);
+        let after = printable(&self.pattern[end..]);
+        writeln!(f, "{}", self.kind)?;
+        writeln!(f, "{}{}{}", before, span, after)?;
+        write!(
+            f,
+            "{}{}",
+            " ".repeat(before.chars().count()),
+            "^".repeat(span.chars().count().max(1))
+        )
+    }
+}
+impl std::error::Error for ParseError {}
+
+/// Converts part of an expression into text that fits on one line.
+fn printable(bytes: &[u8]) -> String {
+    match core::str::from_utf8(bytes) {
+        Ok(s) => s
+            .chars()
+            .map(|c| {
+                if c.is_control() {
+                    c.escape_default().to_string()
+                } else {
+                    c.to_string()
+                }
+            })
+            .collect(),
+        Err(_) => escape_ascii(bytes),
+    }
+}
 
 /// An AST node used during parsing.
 #[derive(Clone, Debug, PartialOrd, PartialEq)]
@@ -62,6 +272,10 @@ impl Node {
 pub enum ClassItem {
     Byte(u8),
     ByteRange(u8, u8),
+    /// A range of non-ASCII `char`s.
+    /// Only [`parse_str`](fn.parse_str.html) uses this,
+    /// and it converts classes into byte sequences before returning.
+    CharRange(char, char),
 }
 impl core::fmt::Debug for ClassItem {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> Result<(), core::fmt::Error> {
@@ -75,6 +289,9 @@ impl core::fmt::Debug for ClassItem {
                     escape_ascii([*b])
                 )
             }
+            ClassItem::CharRange(a, b) => {
+                write!(f, "CharRange({}-{})", a.escape_debug(), b.escape_debug())
+            }
         }
     }
 }
@@ -85,18 +302,27 @@ pub enum NonFinalNode {
     Escape,
     HexEscape0,
     HexEscape1(u8),
+    PropertyEscape0(/* negated */ bool),
+    PropertyEscape1(/* negated */ bool, String),
     OpenClass0,
     OpenClassNeg,
     OpenClass(/* inclusive */ bool, Vec<ClassItem>),
+    OpenClassBracket,
+    OpenPosixClass(String),
     OpenByteRange(u8),
+    OpenCharRange(char),
     ByteRange(u8, u8),
     OpenGroup,
     OpenExtendedGroup,
+    OpenExtendedGroupP,
+    OpenGroupName(String),
+    OpenNamedGroup(String),
+    OpenFlags(String),
     OpenNonCapturingGroup,
     OpenAlt(Vec<FinalNode>),
     RepeatMin(String),
     RepeatMax(String, String),
-    RepeatToken(String, usize, Option<usize>),
+    RepeatToken(String, usize, Option<usize>, bool),
 }
 impl core::fmt::Debug for NonFinalNode {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> Result<(), core::fmt::Error> {
@@ -104,6 +330,15 @@ impl core::fmt::Debug for NonFinalNode {
             NonFinalNode::Escape => write!(f, "Escape"),
             NonFinalNode::HexEscape0 => write!(f, "HexEscape0"),
             NonFinalNode::HexEscape1(b) => write!(f, "HexEscape1({})", escape_ascii([*b])),
+            NonFinalNode::PropertyEscape0(negated) => {
+                write!(f, "PropertyEscape0({})", if *negated { 'P' } else { 'p' })
+            }
+            NonFinalNode::PropertyEscape1(negated, name) => write!(
+                f,
+                "PropertyEscape1({},{})",
+                if *negated { 'P' } else { 'p' },
+                name
+            ),
             NonFinalNode::OpenClass0 => write!(f, "OpenClass0"),
             NonFinalNode::OpenClassNeg => write!(f, "OpenClassNeg"),
             NonFinalNode::OpenClass(true, items) => {
@@ -112,7 +347,10 @@ impl core::fmt::Debug for NonFinalNode {
             NonFinalNode::OpenClass(false, items) => {
                 write!(f, "OpenClass^{:?}", items)
             }
+            NonFinalNode::OpenClassBracket => write!(f, "OpenClassBracket"),
+            NonFinalNode::OpenPosixClass(name) => write!(f, "OpenPosixClass({})", name),
             NonFinalNode::OpenByteRange(b) => write!(f, "OpenByteRange({})", escape_ascii([*b])),
+            NonFinalNode::OpenCharRange(c) => write!(f, "OpenCharRange({})", c.escape_debug()),
             NonFinalNode::ByteRange(a, b) => write!(
                 f,
                 "ByteRange({}-{})",
@@ -121,46 +359,63 @@ impl core::fmt::Debug for NonFinalNode {
             ),
             NonFinalNode::OpenGroup => write!(f, "OpenGroup"),
             NonFinalNode::OpenExtendedGroup => write!(f, "OpenExtendedGroup"),
+            NonFinalNode::OpenExtendedGroupP => write!(f, "OpenExtendedGroupP"),
+            NonFinalNode::OpenGroupName(name) => write!(f, "OpenGroupName({})", name),
+            NonFinalNode::OpenNamedGroup(name) => write!(f, "OpenNamedGroup({})", name),
+            NonFinalNode::OpenFlags(flags) => write!(f, "OpenFlags({})", flags),
             NonFinalNode::OpenNonCapturingGroup => write!(f, "OpenNonCapturingGroup"),
             NonFinalNode::OpenAlt(nodes) => write!(f, "OpenAlt{:?}", nodes),
             NonFinalNode::RepeatMin(min) => write!(f, "RepeatMin({})", min),
             NonFinalNode::RepeatMax(min, max) => write!(f, "RepeatMax({},{})", min, max),
-            NonFinalNode::RepeatToken(printable, min, opt_max) => {
+            NonFinalNode::RepeatToken(printable, min, opt_max, false) => {
                 write!(f, "RepeatToken({:?},{},{:?})", printable, min, opt_max)
             }
+            NonFinalNode::RepeatToken(printable, min, opt_max, true) => {
+                write!(f, "LazyRepeatToken({:?},{},{:?})", printable, min, opt_max)
+            }
         }
     }
 }
 impl NonFinalNode {
     /// Parsing can fail when a `NonFinalNode` is not converted into a
-    /// `FinalNode`.  This function returns an explanation to show to the user.
+    /// `FinalNode`.  This function returns the problem to show to the user.
     #[must_use]
-    pub fn reason(&self) -> String {
+    pub fn error_kind(&self) -> ParseErrorKind {
+        let p = |negated: bool| if negated { 'P' } else { 'p' };
         match self {
-            NonFinalNode::Escape => "incomplete escape sequence: `\\`".to_string(),
-            NonFinalNode::HexEscape0 => "incomplete escape sequence: `\\x`".to_string(),
+            NonFinalNode::Escape => ParseErrorKind::IncompleteEscape("\\".to_string()),
+            NonFinalNode::HexEscape0 => ParseErrorKind::IncompleteEscape("\\x".to_string()),
             NonFinalNode::HexEscape1(d) => {
-                format!("incomplete escape sequence: `\\x{}`", escape_ascii([*d]))
+                ParseErrorKind::IncompleteEscape(format!("\\x{}", escape_ascii([*d])))
+            }
+            NonFinalNode::PropertyEscape0(negated) => {
+                ParseErrorKind::IncompleteEscape(format!("\\{}", p(*negated)))
+            }
+            NonFinalNode::PropertyEscape1(negated, name) => {
+                ParseErrorKind::UnclosedPropertyName(format!("\\{}{{{}", p(*negated), name))
             }
             NonFinalNode::OpenClass0
             | NonFinalNode::OpenClassNeg
             | NonFinalNode::OpenClass(..)
-            | NonFinalNode::ByteRange(..) => "missing closing `]`".to_string(),
-            NonFinalNode::OpenByteRange(b) => {
-                format!("missing byte to close range: `{}-`", escape_ascii([*b]))
-            }
+            | NonFinalNode::OpenClassBracket
+            | NonFinalNode::OpenPosixClass(_)
+            | NonFinalNode::ByteRange(..) => ParseErrorKind::UnclosedClass,
+            NonFinalNode::OpenByteRange(b) => ParseErrorKind::UnclosedRange(escape_ascii([*b])),
+            NonFinalNode::OpenCharRange(c) => ParseErrorKind::UnclosedRange(c.to_string()),
             NonFinalNode::OpenGroup
             | NonFinalNode::OpenExtendedGroup
-            | NonFinalNode::OpenNonCapturingGroup => "missing closing `)`".to_string(),
-            NonFinalNode::OpenAlt(_) => "missing element after bar `|`".to_string(),
-            NonFinalNode::RepeatMin(min) => {
-                format!("missing closing `}}` symbol: `{{{}`", min)
-            }
+            | NonFinalNode::OpenExtendedGroupP
+            | NonFinalNode::OpenNamedGroup(_)
+            | NonFinalNode::OpenFlags(_)
+            | NonFinalNode::OpenNonCapturingGroup => ParseErrorKind::UnclosedGroup,
+            NonFinalNode::OpenGroupName(_) => ParseErrorKind::UnclosedGroupName,
+            NonFinalNode::OpenAlt(_) => ParseErrorKind::MissingElementAfterBar,
+            NonFinalNode::RepeatMin(min) => ParseErrorKind::UnclosedRepeat(format!("{{{}", min)),
             NonFinalNode::RepeatMax(min, max) => {
-                format!("missing closing `}}` symbol: `{{{},{}`", min, max)
+                ParseErrorKind::UnclosedRepeat(format!("{{{},{}", min, max))
             }
-            NonFinalNode::RepeatToken(printable, _, _) => {
-                format!("missing element before repeat element: `{}`", printable)
+            NonFinalNode::RepeatToken(printable, ..) => {
+                ParseErrorKind::MissingRepeatElement(printable.clone())
             }
         }
     }
@@ -187,6 +442,39 @@ impl NonFinalNode {
         }
     }
 
+    /// Returns the contents of this `NonFinalNode::OpenGroupName(..)`.
+    /// Panics if this is a different enum variant.
+    #[allow(clippy::must_use_candidate)]
+    #[allow(clippy::missing_panics_doc)]
+    pub fn unwrap_open_group_name(self) -> String {
+        match self {
+            NonFinalNode::OpenGroupName(name) => name,
+            other => panic!("unwrap_open_group_name() called on value: {:?}", other),
+        }
+    }
+
+    /// Returns the contents of this `NonFinalNode::OpenNamedGroup(..)`.
+    /// Panics if this is a different enum variant.
+    #[allow(clippy::must_use_candidate)]
+    #[allow(clippy::missing_panics_doc)]
+    pub fn unwrap_open_named_group(self) -> String {
+        match self {
+            NonFinalNode::OpenNamedGroup(name) => name,
+            other => panic!("unwrap_open_named_group() called on value: {:?}", other),
+        }
+    }
+
+    /// Returns the contents of this `NonFinalNode::OpenFlags(..)`.
+    /// Panics if this is a different enum variant.
+    #[allow(clippy::must_use_candidate)]
+    #[allow(clippy::missing_panics_doc)]
+    pub fn unwrap_open_flags(self) -> String {
+        match self {
+            NonFinalNode::OpenFlags(flags) => flags,
+            other => panic!("unwrap_open_flags() called on value: {:?}", other),
+        }
+    }
+
     /// Returns the contents of this `NonFinalNode::RepeatMin(..)`.
     /// Panics if this is a different enum variant.
     #[allow(clippy::must_use_candidate)]
@@ -222,10 +510,20 @@ impl NonFinalNode {
 /// - [`Byte`](#variant.Byte)
 /// - [`AnyByte`](#variant.AnyByte)
 ///
+/// Other leaves are zero-width assertions which match positions between bytes:
+/// - [`StartText`](#variant.StartText)
+/// - [`EndText`](#variant.EndText)
+/// - [`StartLine`](#variant.StartLine)
+/// - [`EndLine`](#variant.EndLine)
+/// - [`WordBoundary`](#variant.WordBoundary)
+/// - [`NotWordBoundary`](#variant.NotWordBoundary)
+///
 /// All other variants are edges of the AST:
 /// - [`Seq`](#variant.Seq)
 /// - [`Class`](#variant.Class)
 /// - [`Group`](#variant.Group)
+/// - [`NamedGroup`](#variant.NamedGroup)
+/// - [`NonCapturingGroup`](#variant.NonCapturingGroup)
 /// - [`Alt`](#variant.Alt)
 /// - [`Repeat`](#variant.Repeat)
 #[derive(Clone, PartialOrd, PartialEq)]
@@ -296,6 +594,9 @@ pub enum FinalNode {
     ///
     /// A character class.  Matches any byte in the class.
     ///
+    /// The parser converts shorthand classes like `\d` and POSIX classes like
+    /// `[[:alpha:]]` into `Class` nodes.
+    ///
     /// See [`ClassItem`](enum.ClassItem.html)
     ///
     /// # Examples
@@ -323,6 +624,20 @@ pub enum FinalNode {
     ///     ])),
     ///     parse(br"[0-9]"),
     /// );
+    /// assert_eq!(
+    ///     Ok(FinalNode::Class(false, vec![
+    ///         ClassItem::ByteRange(b'0', b'9'),
+    ///     ])),
+    ///     parse(br"\D"),
+    /// );
+    /// assert_eq!(
+    ///     Ok(FinalNode::Class(true, vec![
+    ///         ClassItem::ByteRange(b'A', b'Z'),
+    ///         ClassItem::ByteRange(b'a', b'z'),
+    ///         ClassItem::Byte(b'_'),
+    ///     ])),
+    ///     parse(br"[[:alpha:]_]"),
+    /// );
     /// ```
     Class(/* inclusive */ bool, Vec<ClassItem>),
 
@@ -358,12 +673,46 @@ pub enum FinalNode {
     ///       ))),
     ///       0,    // min
     ///       None, // max
+    ///       false,// lazy
     ///    )),
     ///    parse(br"(ab)*"),
     /// );
     /// ```
     Group(Box<FinalNode>),
 
+    /// `NamedGroup(String, Box<FinalNode>)`
+    ///
+    /// A capturing group of nodes with a name.
+    /// Regular expression authors write it as `(?P<name>ab)` or `(?<name>ab)`.
+    /// The name must be a Rust identifier and not a keyword.
+    ///
+    /// # Examples
+    /// ```
+    /// use safe_regex_compiler::parser::parse;
+    /// use safe_regex_compiler::parser::FinalNode;
+    /// assert_eq!(
+    ///    Ok(FinalNode::Seq(vec![
+    ///       FinalNode::Byte(b'a'),
+    ///       FinalNode::NamedGroup(
+    ///          "year".to_string(),
+    ///          Box::new(FinalNode::Alt(vec![
+    ///             FinalNode::Byte(b'b'),
+    ///             FinalNode::Byte(b'c'),
+    ///          ])),
+    ///       ),
+    ///    ])),
+    ///    parse(br"a(?P<year>b|c)"),
+    /// );
+    /// assert_eq!(
+    ///    Ok(FinalNode::NamedGroup(
+    ///       "x".to_string(),
+    ///       Box::new(FinalNode::Byte(b'a')),
+    ///    )),
+    ///    parse(br"(?<x>a)"),
+    /// );
+    /// ```
+    NamedGroup(String, Box<FinalNode>),
+
     /// `NonCapturingGroup(Box<FinalNode>)`
     ///
     /// A non-capturing group of nodes.
@@ -395,6 +744,7 @@ pub enum FinalNode {
     ///       ))),
     ///       0,    // min
     ///       None, // max
+    ///       false,// lazy
     ///    )),
     ///    parse(br"(?:ab)*"),
     /// );
@@ -430,10 +780,12 @@ pub enum FinalNode {
     /// ```
     Alt(Vec<FinalNode>),
 
-    /// `Repeat(Box<FinalNode>, min: usize, max: Option<usize>)`
+    /// `Repeat(Box<FinalNode>, min: usize, max: Option<usize>, lazy: bool)`
     ///
     /// A repetition of a node.  It contains the minimum number of repetitions
     /// and an optional inclusive maximum number.
+    /// It is lazy when the regular expression has `?` after the repeat element.
+    /// A lazy repetition prefers to match fewer times.
     ///
     /// # Examples
     /// ```
@@ -444,6 +796,7 @@ pub enum FinalNode {
     ///         Box::new(FinalNode::Byte(b'a')),
     ///         0,       // min
     ///         Some(1), // max
+    ///         false,   // lazy
     ///     )),
     ///     parse(br"a?"),
     /// );
@@ -452,6 +805,7 @@ pub enum FinalNode {
     ///         Box::new(FinalNode::Byte(b'a')),
     ///         0,    // min
     ///         None, // max
+    ///         false,// lazy
     ///     )),
     ///     parse(br"a*"),
     /// );
@@ -460,6 +814,7 @@ pub enum FinalNode {
     ///         Box::new(FinalNode::Byte(b'a')),
     ///         1,    // min
     ///         None, // max
+    ///         false,// lazy
     ///     )),
     ///     parse(br"a+"),
     /// );
@@ -468,6 +823,7 @@ pub enum FinalNode {
     ///         Box::new(FinalNode::Byte(b'a')),
     ///         5,       // min
     ///         Some(5), // max
+    ///         false,   // lazy
     ///     )),
     ///     parse(br"a{5}"),
     /// );
@@ -476,6 +832,7 @@ pub enum FinalNode {
     ///         Box::new(FinalNode::Byte(b'a')),
     ///         5,    // min
     ///         None, // max
+    ///         false,// lazy
     ///     )),
     ///     parse(br"a{5,}"),
     /// );
@@ -484,6 +841,7 @@ pub enum FinalNode {
     ///         Box::new(FinalNode::Byte(b'a')),
     ///         0,       // min
     ///         Some(7), // max
+    ///         false,   // lazy
     ///     )),
     ///     parse(br"a{,7}"),
     /// );
@@ -492,6 +850,7 @@ pub enum FinalNode {
     ///         Box::new(FinalNode::Byte(b'a')),
     ///         5,       // min
     ///         Some(7), // max
+    ///         false,   // lazy
     ///     )),
     ///     parse(br"a{5,7}"),
     /// );
@@ -500,11 +859,121 @@ pub enum FinalNode {
     ///         Box::new(FinalNode::Byte(b'a')),
     ///         0,    // min
     ///         None, // max
+    ///         false,// lazy
     ///     )),
     ///     parse(br"a{,}"),
     /// );
+    /// assert_eq!(
+    ///     Ok(FinalNode::Repeat(
+    ///         Box::new(FinalNode::Byte(b'a')),
+    ///         0,    // min
+    ///         None, // max
+    ///         true, // lazy
+    ///     )),
+    ///     parse(br"a*?"),
+    /// );
+    /// assert_eq!(
+    ///     Ok(FinalNode::Repeat(
+    ///         Box::new(FinalNode::Byte(b'a')),
+    ///         2,       // min
+    ///         Some(3), // max
+    ///         true,    // lazy
+    ///     )),
+    ///     parse(br"a{2,3}?"),
+    /// );
+    /// ```
+    Repeat(Box<FinalNode>, usize, Option<usize>, bool),
+
+    /// `StartText`
+    ///
+    /// A zero-width assertion that matches at the start of the input.
+    /// The parser produces it for `\A`, and for `^` when multi-line mode is off.
+    ///
+    /// # Examples
+    /// ```
+    /// use safe_regex_compiler::parser::parse;
+    /// use safe_regex_compiler::parser::FinalNode;
+    /// assert_eq!(Ok(FinalNode::StartText), parse(br"^"));
+    /// assert_eq!(Ok(FinalNode::StartText), parse(br"\A"));
+    /// ```
+    StartText,
+
+    /// `EndText`
+    ///
+    /// A zero-width assertion that matches at the end of the input.
+    /// The parser produces it for `\z`, and for `$` when multi-line mode is off.
+    ///
+    /// # Examples
+    /// ```
+    /// use safe_regex_compiler::parser::parse;
+    /// use safe_regex_compiler::parser::FinalNode;
+    /// assert_eq!(Ok(FinalNode::EndText), parse(br"$"));
+    /// assert_eq!(Ok(FinalNode::EndText), parse(br"\z"));
+    /// ```
+    EndText,
+
+    /// `StartLine`
+    ///
+    /// A zero-width assertion that matches at the start of the input and
+    /// after every `\n` byte.
+    /// The parser produces it for `^` when multi-line mode is on.
+    ///
+    /// # Examples
+    /// ```
+    /// use safe_regex_compiler::parser::parse;
+    /// use safe_regex_compiler::parser::FinalNode;
+    /// assert_eq!(Ok(FinalNode::StartLine), parse(br"(?m)^"));
+    /// assert_eq!(
+    ///     Ok(FinalNode::Seq(vec![
+    ///         FinalNode::NonCapturingGroup(Box::new(FinalNode::StartLine)),
+    ///         FinalNode::StartText,
+    ///     ])),
+    ///     parse(br"(?m:^)^"),
+    /// );
+    /// ```
+    StartLine,
+
+    /// `EndLine`
+    ///
+    /// A zero-width assertion that matches at the end of the input and
+    /// before every `\n` byte.
+    /// The parser produces it for `$` when multi-line mode is on.
+    ///
+    /// # Examples
     /// ```
-    Repeat(Box<FinalNode>, usize, Option<usize>),
+    /// use safe_regex_compiler::parser::parse;
+    /// use safe_regex_compiler::parser::FinalNode;
+    /// assert_eq!(Ok(FinalNode::EndLine), parse(br"(?m)$"));
+    /// assert_eq!(Ok(FinalNode::EndText), parse(br"(?m)(?-m)$"));
+    /// ```
+    EndLine,
+
+    /// `WordBoundary`
+    ///
+    /// A zero-width assertion that matches between a word byte and a non-word
+    /// byte, or between a word byte and the start or end of the input.
+    /// Word bytes are ASCII letters, digits, and `_`.
+    ///
+    /// # Examples
+    /// ```
+    /// use safe_regex_compiler::parser::parse;
+    /// use safe_regex_compiler::parser::FinalNode;
+    /// assert_eq!(Ok(FinalNode::WordBoundary), parse(br"\b"));
+    /// ```
+    WordBoundary,
+
+    /// `NotWordBoundary`
+    ///
+    /// A zero-width assertion that matches everywhere
+    /// [`WordBoundary`](#variant.WordBoundary) does not.
+    ///
+    /// # Examples
+    /// ```
+    /// use safe_regex_compiler::parser::parse;
+    /// use safe_regex_compiler::parser::FinalNode;
+    /// assert_eq!(Ok(FinalNode::NotWordBoundary), parse(br"\B"));
+    /// ```
+    NotWordBoundary,
 }
 impl FinalNode {
     /// Assumes this is a `FinalNode::Alt(_)` and returns its contents.
@@ -527,12 +996,370 @@ impl core::fmt::Debug for FinalNode {
             FinalNode::Class(true, items) => write!(f, "Class{:?}", items),
             FinalNode::Class(false, items) => write!(f, "Class^{:?}", items),
             FinalNode::Group(nodes) => write!(f, "Group({:?})", nodes),
+            FinalNode::NamedGroup(name, nodes) => write!(f, "NamedGroup({},{:?})", name, nodes),
             FinalNode::NonCapturingGroup(nodes) => write!(f, "NonCapturingGroup({:?})", nodes),
             FinalNode::Alt(nodes) => write!(f, "Alt{:?}", nodes),
-            FinalNode::Repeat(node, min, opt_max) => {
+            FinalNode::Repeat(node, min, opt_max, false) => {
                 write!(f, "Repeat({:?},{}-{:?})", node, min, opt_max)
             }
+            FinalNode::Repeat(node, min, opt_max, true) => {
+                write!(f, "LazyRepeat({:?},{}-{:?})", node, min, opt_max)
+            }
+            FinalNode::StartText => write!(f, "StartText"),
+            FinalNode::EndText => write!(f, "EndText"),
+            FinalNode::StartLine => write!(f, "StartLine"),
+            FinalNode::EndLine => write!(f, "EndLine"),
+            FinalNode::WordBoundary => write!(f, "WordBoundary"),
+            FinalNode::NotWordBoundary => write!(f, "NotWordBoundary"),
+        }
+    }
+}
+
+/// Returns the bytes of the POSIX character class with `name`,
+/// like `alpha` in `[[:alpha:]]`.
+///
+/// The shorthand classes use these too:
+/// `\d` is `digit`, `\w` is `word`, and `\s` is `space`.
+fn posix_class(name: &str) -> Option<Vec<ClassItem>> {
+    use ClassItem::{Byte, ByteRange};
+    Some(match name {
+        "alnum" => vec![
+            ByteRange(b'0', b'9'),
+            ByteRange(b'A', b'Z'),
+            ByteRange(b'a', b'z'),
+        ],
+        "alpha" => vec![ByteRange(b'A', b'Z'), ByteRange(b'a', b'z')],
+        "ascii" => vec![ByteRange(0, 0x7F)],
+        "blank" => vec![Byte(b'\t'), Byte(b' ')],
+        "cntrl" => vec![ByteRange(0, 0x1F), Byte(0x7F)],
+        "digit" => vec![ByteRange(b'0', b'9')],
+        "graph" => vec![ByteRange(b'!', b'~')],
+        "lower" => vec![ByteRange(b'a', b'z')],
+        "print" => vec![ByteRange(b' ', b'~')],
+        "punct" => vec![
+            ByteRange(b'!', b'/'),
+            ByteRange(b':', b'@'),
+            ByteRange(b'[', b'`'),
+            ByteRange(b'{', b'~'),
+        ],
+        "space" => vec![ByteRange(b'\t', b'\r'), Byte(b' ')],
+        "upper" => vec![ByteRange(b'A', b'Z')],
+        "word" => vec![
+            ByteRange(b'0', b'9'),
+            ByteRange(b'A', b'Z'),
+            Byte(b'_'),
+            ByteRange(b'a', b'z'),
+        ],
+        "xdigit" => vec![
+            ByteRange(b'0', b'9'),
+            ByteRange(b'A', b'F'),
+            ByteRange(b'a', b'f'),
+        ],
+        _ => return None,
+    })
+}
+
+/// Returns class items that match every byte not matched by `items`.
+fn complement(items: &[ClassItem]) -> Vec<ClassItem> {
+    let mut matched = [false; 256];
+    for item in items {
+        let (a, b) = match item {
+            ClassItem::Byte(b) => (*b, *b),
+            ClassItem::ByteRange(a, b) => (*a, *b),
+            ClassItem::CharRange(..) => continue,
+        };
+        for n in a..=b {
+            matched[usize::from(n)] = true;
+        }
+    }
+    let item = |a: u8, b: u8| {
+        if a == b {
+            ClassItem::Byte(a)
+        } else {
+            ClassItem::ByteRange(a, b)
+        }
+    };
+    let mut result = Vec::new();
+    let mut start: Option<u8> = None;
+    for n in 0..=255_u8 {
+        match (matched[usize::from(n)], start) {
+            (false, None) => start = Some(n),
+            (true, Some(a)) => {
+                result.push(item(a, n - 1));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(a) = start {
+        result.push(item(a, 255));
+    }
+    result
+}
+
+/// Returns `items` plus class items for the other case of every ASCII letter
+/// matched by `items`.
+fn fold_case(items: &[ClassItem]) -> Vec<ClassItem> {
+    let item = |a: u8, b: u8| {
+        if a == b {
+            ClassItem::Byte(a)
+        } else {
+            ClassItem::ByteRange(a, b)
+        }
+    };
+    let mut result = items.to_vec();
+    for class_item in items {
+        let (a, b) = match class_item {
+            ClassItem::Byte(b) => (*b, *b),
+            ClassItem::ByteRange(a, b) => (*a, *b),
+            ClassItem::CharRange(..) => continue,
+        };
+        for (lo, hi) in [(b'A', b'Z'), (b'a', b'z')].iter().copied() {
+            if a <= hi && lo <= b {
+                let (x, y) = (a.max(lo), b.min(hi));
+                let folded = item(x ^ 0x20, y ^ 0x20);
+                if !result.contains(&folded) {
+                    result.push(folded);
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Returns the sorted ranges of code points matched by `items`.
+fn char_ranges(items: &[ClassItem]) -> Vec<(u32, u32)> {
+    crate::utf8::normalize(
+        items
+            .iter()
+            .map(|item| match item {
+                ClassItem::Byte(b) => (u32::from(*b), u32::from(*b)),
+                ClassItem::ByteRange(a, b) => (u32::from(*a), u32::from(*b)),
+                ClassItem::CharRange(a, b) => (u32::from(*a), u32::from(*b)),
+            })
+            .collect(),
+    )
+}
+
+/// Returns class items that match the code points in `ranges`.
+fn char_class_items(ranges: &[(u32, u32)]) -> Vec<ClassItem> {
+    let mut result = Vec::new();
+    for (a, b) in ranges.iter().copied() {
+        if a <= 0x7F {
+            let x = u8::try_from(a).unwrap();
+            let y = u8::try_from(b.min(0x7F)).unwrap();
+            result.push(if x == y {
+                ClassItem::Byte(x)
+            } else {
+                ClassItem::ByteRange(x, y)
+            });
+        }
+        // Surrogates are not `char`s.
+        let surrogates = 0xD800..=0xDFFF;
+        let x = a.max(0x80);
+        let x = if surrogates.contains(&x) { 0xE000 } else { x };
+        let y = if surrogates.contains(&b) { 0xD7FF } else { b };
+        if x <= y {
+            if let (Some(x), Some(y)) = (char::from_u32(x), char::from_u32(y)) {
+                result.push(ClassItem::CharRange(x, y));
+            }
+        }
+    }
+    result
+}
+
+/// Returns `true` if `items` has non-ASCII `char`s, like `\p{Greek}`.
+/// Such a class matches whole UTF-8 code points, even in byte mode.
+fn has_chars(items: &[ClassItem]) -> bool {
+    items
+        .iter()
+        .any(|item| matches!(item, ClassItem::CharRange(..)))
+}
+
+/// Returns a class node that matches the code points in `ranges`,
+/// or the code points not in `ranges` when `negated` is true.
+fn char_class(ranges: &[(u32, u32)], negated: bool) -> FinalNode {
+    if negated {
+        FinalNode::Class(true, char_class_items(&crate::utf8::complement(ranges)))
+    } else {
+        FinalNode::Class(true, char_class_items(ranges))
+    }
+}
+
+/// Returns a class node for Unicode property `name`, like `Greek` in
+/// `\p{Greek}`.
+fn property_class(name: &str, negated: bool) -> Result<FinalNode, ParseErrorKind> {
+    let ranges = crate::unicode::property(name)
+        .ok_or_else(|| ParseErrorKind::UnknownProperty(name.to_string()))?;
+    Ok(char_class(ranges, negated))
+}
+
+/// Returns class items that match everything not matched by `items`.
+/// In UTF-8 mode, that is every `char`.  Otherwise, it is every byte.
+fn complement_class(items: &[ClassItem], utf8: bool) -> Vec<ClassItem> {
+    if utf8 || has_chars(items) {
+        char_class_items(&crate::utf8::complement(&char_ranges(items)))
+    } else {
+        complement(items)
+    }
+}
+
+/// Returns the `char` if `items` has only a single `char`, like `[é]`.
+fn single_char(items: &[ClassItem]) -> Option<char> {
+    match items {
+        [ClassItem::CharRange(a, b)] if a == b => Some(*a),
+        _ => None,
+    }
+}
+
+/// Returns a node that matches any of `sequences` of byte ranges.
+///
+/// Sequences with the same first byte range share one node for it,
+/// and first bytes followed by the same sub-sequences share one class.
+/// So `[à-é]` becomes `\xC3[\xA0-\xA9]`
+/// and `[\x{100}-\x{2FF}]` becomes `[\xC4-\xCB][\x80-\xBF]`.
+/// The sequences must be sorted and disjoint, like the output of
+/// [`utf8::sequences`](../utf8/fn.sequences.html).
+fn byte_sequences_node(sequences: &[&[(u8, u8)]]) -> FinalNode {
+    // Each alternative is a set of first bytes and the node that follows them.
+    let mut alternatives: Vec<(Vec<ClassItem>, Option<FinalNode>)> = Vec::new();
+    let mut n = 0;
+    while n < sequences.len() {
+        let (a, b) = sequences[n][0];
+        let group_len = sequences[n..]
+            .iter()
+            .take_while(|sequence| sequence[0] == (a, b))
+            .count();
+        let tails: Vec<&[(u8, u8)]> = sequences[n..n + group_len]
+            .iter()
+            .map(|sequence| &sequence[1..])
+            .filter(|tail| !tail.is_empty())
+            .collect();
+        n += group_len;
+        let head = if a == b {
+            ClassItem::Byte(a)
+        } else {
+            ClassItem::ByteRange(a, b)
+        };
+        let tail = if tails.is_empty() {
+            None
+        } else {
+            Some(byte_sequences_node(&tails))
+        };
+        if let Some((heads, _)) = alternatives.iter_mut().find(|(_, t)| *t == tail) {
+            heads.push(head);
+        } else {
+            alternatives.push((vec![head], tail));
+        }
+    }
+    let mut nodes: Vec<FinalNode> = alternatives
+        .into_iter()
+        .map(|(heads, tail)| {
+            let head = match heads[..] {
+                [ClassItem::Byte(b)] => FinalNode::Byte(b),
+                _ => FinalNode::Class(true, heads),
+            };
+            match tail {
+                None => head,
+                Some(FinalNode::Seq(tail_nodes)) => {
+                    FinalNode::Seq(core::iter::once(head).chain(tail_nodes).collect())
+                }
+                Some(node @ FinalNode::Alt(_)) => {
+                    FinalNode::Seq(vec![head, FinalNode::NonCapturingGroup(Box::new(node))])
+                }
+                Some(node) => FinalNode::Seq(vec![head, node]),
+            }
+        })
+        .collect();
+    if nodes.len() == 1 {
+        nodes.pop().unwrap()
+    } else {
+        FinalNode::Alt(nodes)
+    }
+}
+
+/// Returns a node that matches the UTF-8 encoding of any code point in
+/// `ranges`.
+fn utf8_node(ranges: &[(u32, u32)]) -> FinalNode {
+    let sequences: Vec<Vec<(u8, u8)>> = ranges
+        .iter()
+        .flat_map(|(a, b)| crate::utf8::sequences(*a, *b))
+        .collect();
+    if sequences.is_empty() {
+        return FinalNode::Class(true, Vec::new());
+    }
+    let sequences: Vec<&[(u8, u8)]> = sequences.iter().map(Vec::as_slice).collect();
+    // Wrap the node so it acts as a single element, like `(?:ab)`.
+    match byte_sequences_node(&sequences) {
+        node @ FinalNode::Seq(_) | node @ FinalNode::Alt(_) => {
+            FinalNode::NonCapturingGroup(Box::new(node))
         }
+        node => node,
+    }
+}
+
+/// Words that cannot be group names because the generated code uses group
+/// names as Rust method names.
+const RUST_KEYWORDS: &[&str] = &[
+    "_", "Self", "abstract", "as", "async", "await", "become", "box", "break", "const", "continue",
+    "crate", "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if",
+    "impl", "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub",
+    "ref", "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Flags that change how the parser interprets parts of the expression.
+/// Regular expression authors set them with `(?im)` and `(?im:ab)`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Flags {
+    /// `i` makes ASCII letters match both upper and lower case.
+    case_insensitive: bool,
+    /// `m` makes `^` and `$` match at the start and end of lines.
+    multiline: bool,
+    /// `u` makes `\w` and `\W` match Unicode word characters.
+    unicode: bool,
+    /// [`parse_str`](fn.parse_str.html) sets this.
+    /// It makes `.` and classes match whole UTF-8 code points.
+    utf8: bool,
+}
+
+/// Sets or clears the flags in `flag_chars`, like `m` or `-m`.
+fn apply_flags(flags: &mut Flags, flag_chars: &str) -> Result<(), ParseErrorKind> {
+    if flag_chars.ends_with('-') {
+        return Err(ParseErrorKind::MissingFlag(flag_chars.to_string()));
+    }
+    let mut enable = true;
+    for c in flag_chars.chars() {
+        match c {
+            '-' if enable => enable = false,
+            'i' => flags.case_insensitive = enable,
+            'm' => flags.multiline = enable,
+            'u' => flags.unicode = enable,
+            _ => return Err(ParseErrorKind::UnknownFlag(c)),
+        }
+    }
+    Ok(())
+}
+
+/// Returns the smallest range that contains `a` and `b`.
+fn join(a: &Range<usize>, b: &Range<usize>) -> Range<usize> {
+    a.start.min(b.start)..a.end.max(b.end)
+}
+
+/// The positions in the expression of the nodes and byte that a parser rule
+/// works on.  Rules use them to tell where a problem is.
+struct Spans {
+    prev: Range<usize>,
+    last: Range<usize>,
+    byte: Range<usize>,
+}
+impl Spans {
+    fn prev_and_last(&self) -> Range<usize> {
+        join(&self.prev, &self.last)
+    }
+
+    fn last_and_byte(&self) -> Range<usize> {
+        join(&self.last, &self.byte)
     }
 }
 
@@ -544,7 +1371,12 @@ impl core::fmt::Debug for FinalNode {
 /// - Match against the second-from-top node on the stack, `prev`
 /// - Consume the node by calling `prev.take()`
 /// - Return a new node to put on the stack above `prev` and `last`
-/// - Return an error string with an explanation for the regex author
+/// - Return an error with an explanation for the regex author and the part of
+///   `spans` with the problem
+///
+/// `flags` has an entry for each open group, plus one for the whole
+/// expression.  Rules that open a group push an entry and rules that close a
+/// group pop it.
 ///
 /// There are two kinds of parser rule:
 /// - A byte consumer rule consumes a byte of input and creates
@@ -560,13 +1392,20 @@ fn apply_rule_once(
     mut prev: &mut Option<Node>,
     mut last: &mut Option<Node>,
     byte: &mut Option<u8>,
-) -> Result<Option<Node>, String> {
-    use FinalNode::{Alt, AnyByte, Byte, Class, Group, NonCapturingGroup, Repeat, Seq};
+    flags: &mut Vec<Flags>,
+    spans: &Spans,
+) -> Result<Option<Node>, (ParseErrorKind, Range<usize>)> {
+    use FinalNode::{
+        Alt, AnyByte, Byte, Class, EndLine, EndText, Group, NamedGroup, NonCapturingGroup,
+        NotWordBoundary, Repeat, Seq, StartLine, StartText, WordBoundary,
+    };
     use Node::{Final, NonFinal};
     use NonFinalNode::{
-        ByteRange, Escape, HexEscape0, HexEscape1, OpenAlt, OpenByteRange, OpenClass, OpenClass0,
-        OpenClassNeg, OpenExtendedGroup, OpenGroup, OpenNonCapturingGroup, RepeatMax, RepeatMin,
-        RepeatToken,
+        ByteRange, Escape, HexEscape0, HexEscape1, OpenAlt, OpenByteRange, OpenCharRange,
+        OpenClass, OpenClass0, OpenClassBracket, OpenClassNeg, OpenExtendedGroup,
+        OpenExtendedGroupP, OpenFlags, OpenGroup, OpenGroupName, OpenNamedGroup,
+        OpenNonCapturingGroup, OpenPosixClass, PropertyEscape0, PropertyEscape1, RepeatMax,
+        RepeatMin, RepeatToken,
     };
     #[allow(clippy::match_same_arms, clippy::unnested_or_patterns)]
     match (&mut prev, &mut last, byte.map(|b| b)) {
@@ -602,35 +1441,108 @@ fn apply_rule_once(
             items.push(item);
             Ok(None)
         }
+        (Some(NonFinal(OpenClass0)), Some(Final(Class(..))), _)
+        | (Some(NonFinal(OpenClassNeg)), Some(Final(Class(..))), _) => {
+            let incl = prev.take() == Some(NonFinal(OpenClass0));
+            let items = match last.take() {
+                Some(Final(Class(true, items))) => items,
+                Some(Final(Class(false, items))) => {
+                    complement_class(&items, flags.last().unwrap().utf8)
+                }
+                _ => unreachable!(),
+            };
+            Ok(Some(NonFinal(OpenClass(incl, items))))
+        }
+        (Some(NonFinal(OpenClass(_, items))), Some(Final(Class(..))), _) => {
+            match last.take() {
+                Some(Final(Class(true, new_items))) => items.extend(new_items),
+                Some(Final(Class(false, new_items))) => {
+                    items.extend(complement_class(&new_items, flags.last().unwrap().utf8));
+                }
+                _ => unreachable!(),
+            }
+            Ok(None)
+        }
+        // Ranges with non-ASCII chars `[a-é]` `[à-ÿ]`
+        (Some(NonFinal(OpenByteRange(a))), Some(Final(Class(true, items))), _)
+            if single_char(items).is_some() =>
+        {
+            let item = ClassItem::CharRange(char::from(*a), single_char(items).unwrap());
+            prev.take();
+            last.take();
+            Ok(Some(Final(Class(true, vec![item]))))
+        }
+        (Some(NonFinal(OpenCharRange(a))), Some(Final(Byte(b))), _) => {
+            let item = ClassItem::CharRange(*a, char::from(*b));
+            prev.take();
+            last.take();
+            Ok(Some(Final(Class(true, vec![item]))))
+        }
+        (Some(NonFinal(OpenCharRange(a))), Some(Final(Class(true, items))), _)
+            if single_char(items).is_some() =>
+        {
+            let item = ClassItem::CharRange(*a, single_char(items).unwrap());
+            prev.take();
+            last.take();
+            Ok(Some(Final(Class(true, vec![item]))))
+        }
+        (Some(NonFinal(OpenByteRange(a))), Some(Final(Class(..))), _) => Err((
+            ParseErrorKind::ClassInRange(escape_ascii([*a])),
+            spans.prev_and_last(),
+        )),
+        (Some(NonFinal(OpenCharRange(a))), Some(Final(Class(..))), _) => Err((
+            ParseErrorKind::ClassInRange(a.to_string()),
+            spans.prev_and_last(),
+        )),
+        (Some(NonFinal(OpenClass0)), Some(Final(_)), _)
+        | (Some(NonFinal(OpenClassNeg)), Some(Final(_)), _)
+        | (Some(NonFinal(OpenClass(..))), Some(Final(_)), _)
+        | (Some(NonFinal(OpenByteRange(_))), Some(Final(_)), _)
+        | (Some(NonFinal(OpenCharRange(_))), Some(Final(_)), _) => {
+            Err((ParseErrorKind::AssertionInClass, spans.last.clone()))
+        }
 
         // Combine repeat tokens
-        (None, Some(NonFinal(RepeatToken(printable, _, _))), _)
-        | (Some(NonFinal(_)), Some(NonFinal(RepeatToken(printable, _, _))), _) => Err(format!(
-            "missing element before repeat element: `{}`",
-            printable
+        // Lazy repeat `*?` `+?` `??` `{n,m}?`
+        (_, Some(NonFinal(RepeatToken(printable, _, _, lazy @ false))), Some(b'?')) => {
+            byte.take();
+            printable.push('?');
+            *lazy = true;
+            Ok(None)
+        }
+        (None, Some(NonFinal(RepeatToken(printable, ..))), _)
+        | (Some(NonFinal(_)), Some(NonFinal(RepeatToken(printable, ..))), _) => Err((
+            ParseErrorKind::MissingRepeatElement(printable.clone()),
+            spans.last.clone(),
         )),
-        (Some(Final(Seq(items))), Some(NonFinal(RepeatToken(_, min, opt_max))), _) => {
+        (Some(Final(Seq(items))), Some(NonFinal(RepeatToken(_, min, opt_max, lazy))), _) => {
             let min_copy = *min;
             let opt_max_copy = *opt_max;
+            let lazy_copy = *lazy;
             last.take();
             let last_item = items.pop().unwrap();
-            items.push(Repeat(Box::new(last_item), min_copy, opt_max_copy));
+            items.push(Repeat(
+                Box::new(last_item),
+                min_copy,
+                opt_max_copy,
+                lazy_copy,
+            ));
             Ok(None)
         }
-        (Some(Final(_)), Some(NonFinal(RepeatToken(_, min, opt_max))), _) => {
+        (Some(Final(_)), Some(NonFinal(RepeatToken(_, min, opt_max, lazy))), _) => {
             let inner = prev.take().unwrap().unwrap_final();
-            let node = Final(Repeat(Box::new(inner), *min, *opt_max));
+            let node = Final(Repeat(Box::new(inner), *min, *opt_max, *lazy));
             last.take();
             Ok(Some(node))
         }
 
         // Combine group tokens
-        (Some(NonFinal(OpenGroup)), Some(Final(_)), None) => Err(OpenGroup.reason()),
-        (Some(NonFinal(OpenExtendedGroup)), Some(Final(_)), None) => {
-            Err(OpenExtendedGroup.reason())
-        }
-        (Some(NonFinal(OpenNonCapturingGroup)), Some(Final(_)), None) => {
-            Err(OpenNonCapturingGroup.reason())
+        (Some(NonFinal(OpenGroup)), Some(Final(_)), None)
+        | (Some(NonFinal(OpenExtendedGroup)), Some(Final(_)), None)
+        | (Some(NonFinal(OpenNonCapturingGroup)), Some(Final(_)), None)
+        | (Some(NonFinal(OpenExtendedGroupP)), Some(Final(_)), None)
+        | (Some(NonFinal(OpenNamedGroup(_))), Some(Final(_)), None) => {
+            Err((ParseErrorKind::UnclosedGroup, spans.prev.clone()))
         }
         // Combine Seq tokens
         (Some(Final(Seq(nodes))), Some(Final(_)), _) => {
@@ -673,6 +1585,114 @@ fn apply_rule_once(
             ]))))
         }
 
+        // Group name `(?P<name>` and `(?<name>`
+        // These must come before all of the generic `(_, _, b'X')` rules below.
+        (_, Some(NonFinal(OpenGroupName(_))), Some(b'>')) => {
+            byte.take();
+            let name = last
+                .take()
+                .unwrap()
+                .unwrap_non_final()
+                .unwrap_open_group_name();
+            if name.is_empty() {
+                Err((ParseErrorKind::MissingGroupName, spans.last_and_byte()))
+            } else if name.as_bytes()[0].is_ascii_digit() {
+                Err((
+                    ParseErrorKind::GroupNameStartsWithDigit(name),
+                    spans.last_and_byte(),
+                ))
+            } else if RUST_KEYWORDS.contains(&name.as_str()) {
+                Err((
+                    ParseErrorKind::GroupNameIsKeyword(name),
+                    spans.last_and_byte(),
+                ))
+            } else {
+                Ok(Some(NonFinal(OpenNamedGroup(name))))
+            }
+        }
+        (_, Some(NonFinal(OpenGroupName(name))), Some(b))
+            if b.is_ascii_alphanumeric() || b == b'_' =>
+        {
+            byte.take();
+            name.push(char::from(b));
+            Ok(None)
+        }
+        (_, Some(NonFinal(OpenGroupName(_))), Some(b)) => {
+            Err((ParseErrorKind::InvalidGroupNameByte(b), spans.byte.clone()))
+        }
+
+        // Flags `(?m)` `(?-m)` `(?m:ab)`
+        // These must come before all of the generic `(_, _, b'X')` rules below.
+        (_, Some(NonFinal(OpenFlags(_))), Some(b')')) => {
+            byte.take();
+            let flag_chars = last.take().unwrap().unwrap_non_final().unwrap_open_flags();
+            flags.pop();
+            apply_flags(flags.last_mut().unwrap(), &flag_chars)
+                .map_err(|kind| (kind, spans.last_and_byte()))?;
+            Ok(None)
+        }
+        (_, Some(NonFinal(OpenFlags(_))), Some(b':')) => {
+            byte.take();
+            let flag_chars = last.take().unwrap().unwrap_non_final().unwrap_open_flags();
+            apply_flags(flags.last_mut().unwrap(), &flag_chars)
+                .map_err(|kind| (kind, spans.last_and_byte()))?;
+            Ok(Some(NonFinal(OpenNonCapturingGroup)))
+        }
+        (_, Some(NonFinal(OpenFlags(flag_chars))), Some(b))
+            if b == b'-' || b.is_ascii_alphabetic() =>
+        {
+            byte.take();
+            flag_chars.push(char::from(b));
+            Ok(None)
+        }
+        (_, Some(NonFinal(OpenFlags(_))), Some(b)) => {
+            Err((ParseErrorKind::InvalidFlagsByte(b), spans.byte.clone()))
+        }
+
+        // POSIX class `[[:alpha:]]`, `[[:^alpha:]]`
+        // These must come before all of the generic `(_, _, b'X')` rules below.
+        (_, Some(NonFinal(OpenClassBracket)), Some(b':')) => {
+            last.take();
+            byte.take();
+            Ok(Some(NonFinal(OpenPosixClass(String::new()))))
+        }
+        (_, Some(NonFinal(OpenClassBracket)), Some(_)) => {
+            // Leave the byte for the next rule.
+            last.take();
+            Ok(Some(Final(Byte(b'['))))
+        }
+        (_, Some(NonFinal(OpenPosixClass(name))), Some(b']')) => {
+            byte.take();
+            let node = match name.strip_suffix(':') {
+                Some(class_name) => {
+                    let (incl, class_name) = match class_name.strip_prefix('^') {
+                        Some(class_name) => (false, class_name),
+                        None => (true, class_name),
+                    };
+                    let items = posix_class(class_name).ok_or_else(|| {
+                        (
+                            ParseErrorKind::UnknownPosixClass(name.clone()),
+                            spans.last_and_byte(),
+                        )
+                    })?;
+                    Final(Class(incl, items))
+                }
+                None => {
+                    return Err((
+                        ParseErrorKind::UnclosedPosixClass(name.clone()),
+                        spans.last_and_byte(),
+                    ))
+                }
+            };
+            last.take();
+            Ok(Some(node))
+        }
+        (_, Some(NonFinal(OpenPosixClass(name))), Some(b)) => {
+            byte.take();
+            name.push(char::from(b));
+            Ok(None)
+        }
+
         // Escape `\n`
         (_, Some(NonFinal(Escape)), Some(b'\\')) => {
             last.take();
@@ -710,15 +1730,101 @@ fn apply_rule_once(
             Ok(Some(node))
         }
 
+        // Shorthand classes `\d` `\D` `\w` `\W` `\s` `\S`
+        (_, Some(NonFinal(Escape)), Some(b @ b'w'))
+        | (_, Some(NonFinal(Escape)), Some(b @ b'W'))
+            if flags.last().unwrap().unicode =>
+        {
+            last.take();
+            byte.take();
+            Ok(Some(Final(char_class(crate::unicode::word(), b == b'W'))))
+        }
+        (_, Some(NonFinal(Escape)), Some(b)) if b"dDwWsS".contains(&b) => {
+            last.take();
+            byte.take();
+            let items = match b.to_ascii_lowercase() {
+                b'd' => posix_class("digit"),
+                b'w' => posix_class("word"),
+                _ => posix_class("space"),
+            }
+            .unwrap();
+            Ok(Some(Final(Class(b.is_ascii_lowercase(), items))))
+        }
+
+        // Assertions `\A` `\z` `\b` `\B`
+        (_, Some(NonFinal(Escape)), Some(b'A')) => {
+            last.take();
+            byte.take();
+            Ok(Some(Final(StartText)))
+        }
+        (_, Some(NonFinal(Escape)), Some(b'z')) => {
+            last.take();
+            byte.take();
+            Ok(Some(Final(EndText)))
+        }
+        (_, Some(NonFinal(Escape)), Some(b'b')) => {
+            last.take();
+            byte.take();
+            Ok(Some(Final(WordBoundary)))
+        }
+        (_, Some(NonFinal(Escape)), Some(b'B')) => {
+            last.take();
+            byte.take();
+            Ok(Some(Final(NotWordBoundary)))
+        }
+
+        // Unicode properties `\pL` `\p{Greek}` `\P{Nd}`
+        (_, Some(NonFinal(Escape)), Some(b @ b'p'))
+        | (_, Some(NonFinal(Escape)), Some(b @ b'P')) => {
+            last.take();
+            byte.take();
+            Ok(Some(NonFinal(PropertyEscape0(b == b'P'))))
+        }
+        (_, Some(NonFinal(PropertyEscape0(negated))), Some(b'{')) => {
+            let negated = *negated;
+            last.take();
+            byte.take();
+            Ok(Some(NonFinal(PropertyEscape1(negated, String::new()))))
+        }
+        (_, Some(NonFinal(PropertyEscape0(negated))), Some(b)) if b.is_ascii_alphabetic() => {
+            let negated = *negated;
+            last.take();
+            byte.take();
+            let node = property_class(&char::from(b).to_string(), negated)
+                .map_err(|kind| (kind, spans.last_and_byte()))?;
+            Ok(Some(Final(node)))
+        }
+        (_, Some(NonFinal(PropertyEscape0(negated))), Some(b)) => Err((
+            ParseErrorKind::MissingPropertyName(format!(
+                "\\{}{}",
+                if *negated { 'P' } else { 'p' },
+                escape_ascii([b])
+            )),
+            spans.last_and_byte(),
+        )),
+        (_, Some(NonFinal(PropertyEscape1(negated, name))), Some(b'}')) => {
+            let node =
+                property_class(name, *negated).map_err(|kind| (kind, spans.last_and_byte()))?;
+            last.take();
+            byte.take();
+            Ok(Some(Final(node)))
+        }
+        (_, Some(NonFinal(PropertyEscape1(_, ref mut name))), Some(b)) => {
+            name.push(char::from(b));
+            byte.take();
+            Ok(None)
+        }
+
         // Hex escape `\x20`
         (_, Some(NonFinal(Escape)), Some(b'x')) => {
             last.take();
             byte.take();
             Ok(Some(NonFinal(HexEscape0)))
         }
-        (_, Some(NonFinal(Escape)), Some(d)) => {
-            Err(format!("invalid escape sequence `\\{}`", escape_ascii([d])))
-        }
+        (_, Some(NonFinal(Escape)), Some(d)) => Err((
+            ParseErrorKind::InvalidEscape(format!("\\{}", escape_ascii([d]))),
+            spans.last_and_byte(),
+        )),
         (_, Some(NonFinal(HexEscape0)), Some(d)) => {
             last.take();
             byte.take();
@@ -733,9 +1839,9 @@ fn apply_rule_once(
             byte.take();
             Ok(Some(Final(Byte(b))))
         }
-        (_, Some(NonFinal(HexEscape1(d1))), Some(d0)) => Err(format!(
-            "invalid escape sequence `\\x{}`",
-            escape_ascii([*d1, d0])
+        (_, Some(NonFinal(HexEscape1(d1))), Some(d0)) => Err((
+            ParseErrorKind::InvalidEscape(format!("\\x{}", escape_ascii([*d1, d0]))),
+            spans.last_and_byte(),
         )),
 
         // Class `[ab0-9]`, `[^-ab0-9]`
@@ -743,7 +1849,7 @@ fn apply_rule_once(
         | (_, Some(NonFinal(OpenClassNeg)), Some(b'['))
         | (_, Some(NonFinal(OpenClass(..))), Some(b'[')) => {
             byte.take();
-            Ok(Some(Final(Byte(b'['))))
+            Ok(Some(NonFinal(OpenClassBracket)))
         }
         (_, _, Some(b'[')) => {
             byte.take();
@@ -759,11 +1865,21 @@ fn apply_rule_once(
             match items.pop().unwrap() {
                 // "[a-"
                 ClassItem::Byte(b) => Ok(Some(NonFinal(OpenByteRange(b)))),
+                // "[é-"
+                ClassItem::CharRange(a, b) if a == b => Ok(Some(NonFinal(OpenCharRange(a)))),
                 // "[a-b-"
-                ClassItem::ByteRange(a, b) => Err(format!(
-                    "expected byte before '-' symbol, not range: `{}-{}-`",
-                    escape_ascii([a]),
-                    escape_ascii([b])
+                ClassItem::ByteRange(a, b) => Err((
+                    ParseErrorKind::RangeInRange(format!(
+                        "{}-{}",
+                        escape_ascii([a]),
+                        escape_ascii([b])
+                    )),
+                    spans.byte.clone(),
+                )),
+                // "[à-é-"
+                ClassItem::CharRange(a, b) => Err((
+                    ParseErrorKind::RangeInRange(format!("{}-{}", a, b)),
+                    spans.byte.clone(),
                 )),
             }
         }
@@ -783,7 +1899,7 @@ fn apply_rule_once(
             Ok(Some(Final(Class(incl, items))))
         }
         (Some(NonFinal(OpenClass(..))), Some(NonFinal(non_final)), Some(b']')) => {
-            Err(non_final.reason())
+            Err((non_final.error_kind(), spans.last.clone()))
         }
 
         // Bytes inside classes.
@@ -800,11 +1916,13 @@ fn apply_rule_once(
         // Group `(ab)` and NonCapturingGroup `(?:ab)`
         (_, _, Some(b'(')) => {
             byte.take();
+            flags.push(*flags.last().unwrap());
             Ok(Some(NonFinal(OpenGroup)))
         }
         (_, Some(NonFinal(OpenGroup)), Some(b')')) => {
             last.take();
             byte.take();
+            flags.pop();
             Ok(Some(Final(Group(Box::new(Seq(vec![]))))))
         }
         (_, Some(NonFinal(OpenGroup)), Some(b'?')) => {
@@ -817,45 +1935,91 @@ fn apply_rule_once(
             byte.take();
             Ok(Some(NonFinal(OpenNonCapturingGroup)))
         }
-        (_, Some(NonFinal(OpenExtendedGroup)), Some(_)) => {
-            Err("unexpected symbol after `(?`".to_string())
+        (_, Some(NonFinal(OpenExtendedGroup)), Some(b'P')) => {
+            last.take();
+            byte.take();
+            Ok(Some(NonFinal(OpenExtendedGroupP)))
+        }
+        (_, Some(NonFinal(OpenExtendedGroup)), Some(b'<'))
+        | (_, Some(NonFinal(OpenExtendedGroupP)), Some(b'<')) => {
+            last.take();
+            byte.take();
+            Ok(Some(NonFinal(OpenGroupName(String::new()))))
+        }
+        (_, Some(NonFinal(OpenExtendedGroup)), Some(b)) if b == b'-' || b.is_ascii_lowercase() => {
+            // Leave the byte for the flags rules.
+            last.take();
+            Ok(Some(NonFinal(OpenFlags(String::new()))))
+        }
+        (_, Some(NonFinal(OpenExtendedGroup)), Some(_)) => Err((
+            ParseErrorKind::InvalidExtendedGroup("(?".to_string()),
+            spans.last_and_byte(),
+        )),
+        (_, Some(NonFinal(OpenExtendedGroupP)), Some(_)) => Err((
+            ParseErrorKind::InvalidExtendedGroup("(?P".to_string()),
+            spans.last_and_byte(),
+        )),
+        (_, Some(NonFinal(OpenNamedGroup(name))), Some(b')')) => {
+            let node = Final(NamedGroup(name.clone(), Box::new(Seq(vec![]))));
+            last.take();
+            byte.take();
+            flags.pop();
+            Ok(Some(node))
         }
         (_, Some(NonFinal(OpenNonCapturingGroup)), Some(b')')) => {
             last.take();
             byte.take();
+            flags.pop();
             Ok(Some(Final(NonCapturingGroup(Box::new(Seq(vec![]))))))
         }
-        (Some(NonFinal(OpenGroup)), Some(NonFinal(non_final)), Some(b')')) => {
-            Err(non_final.reason())
-        }
-        (Some(NonFinal(OpenNonCapturingGroup)), Some(NonFinal(non_final)), Some(b')')) => {
-            Err(non_final.reason())
+        (Some(NonFinal(OpenGroup)), Some(NonFinal(non_final)), Some(b')'))
+        | (Some(NonFinal(OpenNonCapturingGroup)), Some(NonFinal(non_final)), Some(b')'))
+        | (Some(NonFinal(OpenNamedGroup(_))), Some(NonFinal(non_final)), Some(b')')) => {
+            Err((non_final.error_kind(), spans.last.clone()))
         }
         (Some(NonFinal(OpenGroup)), Some(Final(_)), Some(b')')) => {
             byte.take();
             let node = last.take().unwrap().unwrap_final();
             prev.take();
+            flags.pop();
             Ok(Some(Final(Group(Box::new(node)))))
         }
         (Some(NonFinal(OpenNonCapturingGroup)), Some(Final(_)), Some(b')')) => {
             byte.take();
             let node = last.take().unwrap().unwrap_final();
             prev.take();
+            flags.pop();
             Ok(Some(Final(NonCapturingGroup(Box::new(node)))))
         }
+        (Some(NonFinal(OpenNamedGroup(_))), Some(Final(_)), Some(b')')) => {
+            byte.take();
+            let node = last.take().unwrap().unwrap_final();
+            let name = prev
+                .take()
+                .unwrap()
+                .unwrap_non_final()
+                .unwrap_open_named_group();
+            flags.pop();
+            Ok(Some(Final(NamedGroup(name, Box::new(node)))))
+        }
 
         // Repeat, postfix operators `?` `+` `*` `{n}` `{n,}` `{,m}` `{n,m}`
         (_, _, Some(b'?')) => {
             byte.take();
-            Ok(Some(NonFinal(RepeatToken("?".to_string(), 0, Some(1)))))
+            Ok(Some(NonFinal(RepeatToken(
+                "?".to_string(),
+                0,
+                Some(1),
+                false,
+            ))))
         }
         (_, _, Some(b'*')) => {
             byte.take();
-            Ok(Some(NonFinal(RepeatToken("*".to_string(), 0, None))))
+            Ok(Some(NonFinal(RepeatToken("*".to_string(), 0, None, false))))
         }
         (_, _, Some(b'+')) => {
             byte.take();
-            Ok(Some(NonFinal(RepeatToken("+".to_string(), 1, None))))
+            Ok(Some(NonFinal(RepeatToken("+".to_string(), 1, None, false))))
         }
         (_, _, Some(b'{')) => {
             byte.take();
@@ -869,13 +2033,17 @@ fn apply_rule_once(
         (_, Some(NonFinal(RepeatMin(_))), Some(b'}')) => {
             byte.take();
             let min = last.take().unwrap().unwrap_non_final().unwrap_repeat_min();
-            let min_usize = min
-                .parse::<usize>()
-                .map_err(|e| format!("invalid repetition value `{{{}}}`: {}", min, e))?;
+            let min_usize = min.parse::<usize>().map_err(|e| {
+                (
+                    ParseErrorKind::InvalidRepeatValue(format!("{{{}}}", min), e),
+                    spans.last_and_byte(),
+                )
+            })?;
             Ok(Some(NonFinal(RepeatToken(
                 format!("{{{}}}", min),
                 min_usize,
                 Some(min_usize),
+                false,
             ))))
         }
         (_, Some(NonFinal(RepeatMin(min))), Some(b)) => {
@@ -886,30 +2054,35 @@ fn apply_rule_once(
         (_, Some(NonFinal(RepeatMax(..))), Some(b'}')) => {
             byte.take();
             let (min, max) = last.take().unwrap().unwrap_non_final().unwrap_repeat_max();
+            let printable = format!("{{{},{}}}", min, max);
+            let invalid_value = |e| {
+                (
+                    ParseErrorKind::InvalidRepeatValue(printable.clone(), e),
+                    spans.last_and_byte(),
+                )
+            };
             let min_usize = if min.is_empty() {
                 0
             } else {
-                min.parse::<usize>()
-                    .map_err(|e| format!("invalid repetition value `{{{},{}}}`: {}", min, max, e))?
+                min.parse::<usize>().map_err(invalid_value)?
             };
             let max_opt_usize = if max.is_empty() {
                 None
             } else {
-                let max_usize = max.parse::<usize>().map_err(|e| {
-                    format!("invalid repetition value `{{{},{}}}`: {}", min, max, e)
-                })?;
+                let max_usize = max.parse::<usize>().map_err(invalid_value)?;
                 if max_usize < min_usize {
-                    return Err(format!(
-                        "repeating element has max that is smaller than min: `{{{},{}}}`",
-                        min, max
+                    return Err((
+                        ParseErrorKind::RepeatMaxBelowMin(printable),
+                        spans.last_and_byte(),
                     ));
                 }
                 Some(max_usize)
             };
             Ok(Some(NonFinal(RepeatToken(
-                format!("{{{},{}}}", min, max),
+                printable,
                 min_usize,
                 max_opt_usize,
+                false,
             ))))
         }
         (_, Some(NonFinal(RepeatMax(_, ref mut max))), Some(b)) => {
@@ -918,6 +2091,24 @@ fn apply_rule_once(
             Ok(None)
         }
 
+        // Assertions `^` `$`
+        (_, _, Some(b'^')) => {
+            byte.take();
+            if flags.last().unwrap().multiline {
+                Ok(Some(Final(StartLine)))
+            } else {
+                Ok(Some(Final(StartText)))
+            }
+        }
+        (_, _, Some(b'$')) => {
+            byte.take();
+            if flags.last().unwrap().multiline {
+                Ok(Some(Final(EndLine)))
+            } else {
+                Ok(Some(Final(EndText)))
+            }
+        }
+
         // Any byte `.`
         (_, _, Some(b'.')) => {
             byte.take();
@@ -935,7 +2126,7 @@ fn apply_rule_once(
             let node = last.take().unwrap().unwrap_final();
             Ok(Some(NonFinal(OpenAlt(vec![node]))))
         }
-        (_, None, Some(b'|')) => Err("missing element before bar `|`".to_string()),
+        (_, None, Some(b'|')) => Err((ParseErrorKind::MissingElementBeforeBar, spans.byte.clone())),
 
         // Other bytes
         (_, _, Some(b)) => {
@@ -944,21 +2135,26 @@ fn apply_rule_once(
         }
 
         // No more bytes.
-        (_, Some(NonFinal(node)), None) => Err(node.reason()),
+        (_, Some(NonFinal(node)), None) => Err((node.error_kind(), spans.last.clone())),
+        // An escape, class, or group that interrupts `{...}` or an escape,
+        // like `a{1\d` or `\x\.`.
+        (
+            Some(NonFinal(
+                node @ (RepeatMin(..) | RepeatMax(..) | Escape | HexEscape0 | HexEscape1(_)
+                | PropertyEscape0(_) | PropertyEscape1(..)),
+            )),
+            Some(Final(_)),
+            _,
+        ) => Err((node.error_kind(), spans.prev.clone())),
         (None, None, None) => unreachable!(),
         (None, Some(Final(_)), None) => unreachable!(),
 
         // These cases should be unreachable.
-        (Some(NonFinal(Escape)), Some(Final(_)), _) => unreachable!(),
-        (Some(NonFinal(HexEscape0)), Some(Final(_)), _) => unreachable!(),
-        (Some(NonFinal(HexEscape1(_))), Some(Final(_)), _) => unreachable!(),
-        (Some(NonFinal(RepeatMin(..))), Some(Final(_)), _) => unreachable!(),
-        (Some(NonFinal(RepeatMax(..))), Some(Final(_)), _) => unreachable!(),
-        (Some(NonFinal(OpenClass0)), Some(Final(_)), _) => unreachable!(),
-        (Some(NonFinal(OpenClassNeg)), Some(Final(_)), _) => unreachable!(),
-        (Some(NonFinal(OpenClass(..))), Some(Final(_)), _) => unreachable!(),
+        (Some(NonFinal(OpenGroupName(_))), Some(Final(_)), _) => unreachable!(),
+        (Some(NonFinal(OpenFlags(_))), Some(Final(_)), _) => unreachable!(),
+        (Some(NonFinal(OpenClassBracket)), Some(Final(_)), _) => unreachable!(),
+        (Some(NonFinal(OpenPosixClass(_))), Some(Final(_)), _) => unreachable!(),
         (Some(NonFinal(OpenAlt(_))), Some(Final(_)), _) => unreachable!(),
-        (Some(NonFinal(OpenByteRange(_))), Some(Final(_)), _) => unreachable!(),
         (Some(NonFinal(ByteRange(..))), Some(Final(_)), _) => unreachable!(),
         (Some(NonFinal(RepeatToken(..))), Some(Final(_)), _) => unreachable!(),
         (Some(Final(_)), Some(Final(_)), _) => unreachable!(),
@@ -972,12 +2168,13 @@ fn apply_rule_once(
 /// (AST) of the expression.
 ///
 /// # Errors
-/// On error, returns a string explaining the problem.
+/// On error, returns a [`ParseError`](struct.ParseError.html) with the kind
+/// of problem and where it is.
 ///
 /// # Examples
 /// ```
 /// use safe_regex_compiler::parser::parse;
-/// use safe_regex_compiler::parser::FinalNode;
+/// use safe_regex_compiler::parser::{FinalNode, ParseErrorKind};
 /// assert_eq!(
 ///     Ok(FinalNode::Byte(b'a')), parse(br"a")
 /// );
@@ -989,19 +2186,65 @@ fn apply_rule_once(
 ///     ])),
 ///     parse(br"a|b|c"),
 /// );
+/// let err = parse(br"ab(c").unwrap_err();
+/// assert_eq!(ParseErrorKind::UnclosedGroup, err.kind);
+/// assert_eq!(2..3, err.span);
+/// assert_eq!("missing closing `)`\nab(c\n  ^", err.to_string());
+/// ```
+/// See [`FinalNode`](enum.FinalNode.html) variants for more examples.
+pub fn parse(regex: &[u8]) -> Result<FinalNode, ParseError> {
+    parse_impl(regex, false)
+}
+
+/// Parses `regex` as a regular expression that matches UTF-8 strings.
+///
+/// The expression may contain non-ASCII `char`s.
+/// Escapes like `\xE9` mean the `char` with that code point.
+/// Every element of the expression matches whole UTF-8 code points:
+/// - A non-ASCII `char` becomes its sequence of UTF-8 bytes.
+/// - `.` and classes that can match non-ASCII `char`s,
+///   like `[^a]` and `[à-ÿ]`,
+///   become alternations of UTF-8 byte sequences.
+///
+/// The returned AST contains only bytes, so
+/// [`generate`](../generator/fn.generate.html) can compile it.
+///
+/// # Errors
+/// On error, returns a [`ParseError`](struct.ParseError.html).
+/// Its span is a range of bytes in the UTF-8 encoding of `regex`.
+///
+/// # Examples
+/// ```
+/// use safe_regex_compiler::parser::parse_str;
+/// use safe_regex_compiler::parser::FinalNode;
 /// assert_eq!(
-///     Err("missing closing `)`".to_string()),
-///     parse(br"(a"),
+///     Ok(FinalNode::NonCapturingGroup(Box::new(FinalNode::Seq(vec![
+///         FinalNode::Byte(0xC3),
+///         FinalNode::Byte(0xA9),
+///     ])))),
+///     parse_str("é"),
 /// );
 /// ```
-/// See [`FinalNode`](enum.FinalNode.html) variants for more examples.
-#[allow(clippy::missing_panics_doc)]
-pub fn parse(regex: &[u8]) -> Result<FinalNode, String> {
+pub fn parse_str(regex: &str) -> Result<FinalNode, ParseError> {
+    parse_impl(regex.as_bytes(), true)
+}
+
+#[allow(clippy::too_many_lines)]
+fn parse_impl(regex: &[u8], utf8: bool) -> Result<FinalNode, ParseError> {
     if regex.is_empty() {
         return Ok(FinalNode::Seq(Vec::new()));
     }
+    let error = |kind, span| ParseError::new(kind, span, regex);
     let mut data_iter = regex.iter().copied().peekable();
     let mut stack: Vec<Node> = Vec::new();
+    // The bytes of `regex` that made each node in `stack`.
+    let mut stack_spans: Vec<Range<usize>> = Vec::new();
+    let mut pos = 0;
+    let mut group_names: Vec<String> = Vec::new();
+    let mut flags = vec![Flags {
+        utf8,
+        ..Flags::default()
+    }];
     while data_iter.peek().is_some() || stack.len() > 1 {
         crate::dprintln!(
             "process {:?} next={:?}",
@@ -1009,34 +2252,171 @@ pub fn parse(regex: &[u8]) -> Result<FinalNode, String> {
             data_iter.peek().map(|b| escape_ascii([*b]))
         );
         let mut byte = data_iter.peek().copied();
-        let byte_was_some = byte.is_some();
+        let input_byte = byte;
+        // A byte from the input may start a multi-byte `char`.
+        let byte_len = match byte {
+            Some(b) if utf8 && b >= 0xC0 => b.leading_ones() as usize,
+            _ => 1,
+        };
         // Pull the top two items from the stack, so we can work with them and
         // keep the borrow checker happy.
         let mut last = stack.pop();
         let mut prev = stack.pop();
+        let last_span = stack_spans.pop();
+        let prev_span = stack_spans.pop();
+        let spans = Spans {
+            prev: prev_span.clone().unwrap_or(pos..pos),
+            last: last_span.clone().unwrap_or(pos..pos),
+            byte: pos..pos + byte_len,
+        };
         // Anything put in here becomes the new top of the stack in the next loop.
-        let mut to_push = apply_rule_once(&mut prev, &mut last, &mut byte)?;
+        let mut to_push = apply_rule_once(&mut prev, &mut last, &mut byte, &mut flags, &spans)
+            .map_err(|(kind, span)| error(kind, span))?;
+        // The bytes that the rule consumed, directly or in nodes.
+        let mut consumed: Option<Range<usize>> = None;
+        let mut consume = |span: &Range<usize>| {
+            consumed = Some(consumed.as_ref().map_or(span.clone(), |c| join(c, span)));
+        };
+        if prev.is_none() {
+            prev_span.iter().for_each(&mut consume);
+        }
+        if last.is_none() {
+            last_span.iter().for_each(&mut consume);
+        }
+        if input_byte.is_some() && byte.is_none() {
+            consume(&spans.byte);
+        }
+        let to_push_span = consumed.clone().unwrap_or(pos..pos);
+        if let Some(Node::NonFinal(NonFinalNode::OpenNamedGroup(name))) = &to_push {
+            if group_names.contains(name) {
+                return Err(error(
+                    ParseErrorKind::DuplicateGroupName(name.clone()),
+                    to_push_span,
+                ));
+            }
+            group_names.push(name.clone());
+        }
+        // Case-insensitive mode turns bytes and classes into classes that
+        // match both cases.  Items inside a class wait until the class closes.
+        let in_class = matches!(
+            last.as_ref().or(prev.as_ref()).or(stack.last()),
+            Some(Node::NonFinal(
+                NonFinalNode::OpenClass0
+                    | NonFinalNode::OpenClassNeg
+                    | NonFinalNode::OpenClass(..)
+                    | NonFinalNode::OpenByteRange(_)
+                    | NonFinalNode::OpenCharRange(_)
+            ))
+        );
+        // UTF-8 mode turns non-ASCII bytes into `char`s.
+        // A byte from the input is the first byte of a multi-byte `char`.
+        // A byte from an escape like `\xE9` is a code point.
+        let mut char_continuation_bytes = 0;
+        if let (true, Some(Node::Final(FinalNode::Byte(b)))) = (utf8, &to_push) {
+            let b = *b;
+            if b >= 0x80 {
+                let c = if byte.is_none() && input_byte == Some(b) {
+                    let len = b.leading_ones() as usize;
+                    let encoded: Vec<u8> = data_iter.clone().take(len).collect();
+                    char_continuation_bytes = len - 1;
+                    // `regex` came from a `&str` so it is valid UTF-8.
+                    core::str::from_utf8(&encoded)
+                        .unwrap()
+                        .chars()
+                        .next()
+                        .unwrap()
+                } else {
+                    char::from(b)
+                };
+                to_push = Some(Node::Final(if in_class {
+                    FinalNode::Class(true, vec![ClassItem::CharRange(c, c)])
+                } else {
+                    let n = u32::from(c);
+                    utf8_node(&[(n, n)])
+                }));
+            }
+        }
+        if flags.last().unwrap().case_insensitive && !in_class {
+            match &mut to_push {
+                Some(Node::Final(FinalNode::Byte(b))) if b.is_ascii_alphabetic() => {
+                    let items = fold_case(&[ClassItem::Byte(*b)]);
+                    to_push = Some(Node::Final(FinalNode::Class(true, items)));
+                }
+                Some(Node::Final(FinalNode::Class(_, items))) => *items = fold_case(items),
+                _ => {}
+            }
+        }
+        // UTF-8 mode turns `.` and classes that match non-ASCII `char`s
+        // into UTF-8 byte sequences.
+        // Classes with non-ASCII `char`s, like `\p{Greek}`, become UTF-8 byte
+        // sequences in byte mode, too.
+        if !in_class {
+            match &to_push {
+                Some(Node::Final(FinalNode::AnyByte)) if utf8 => {
+                    let node = utf8_node(&[(0, crate::utf8::MAX_CODE_POINT)]);
+                    to_push = Some(Node::Final(node));
+                }
+                Some(Node::Final(FinalNode::Class(incl, items))) if utf8 || has_chars(items) => {
+                    let ranges = char_ranges(items);
+                    let ranges = if *incl {
+                        ranges
+                    } else {
+                        crate::utf8::complement(&ranges)
+                    };
+                    if !*incl || ranges.iter().any(|(_, b)| *b > 0x7F) {
+                        to_push = Some(Node::Final(utf8_node(&ranges)));
+                    }
+                }
+                _ => {}
+            }
+        }
         // Put items back in `stack`.
+        // When a rule changes a node in place, the node grows to cover
+        // what the rule consumed.
+        let mut prev_span = prev_span.filter(|_| prev.is_some());
+        let mut last_span = last_span.filter(|_| last.is_some());
+        if let (None, Some(consumed)) = (&to_push, &consumed) {
+            if let Some(span) = last_span.as_mut().or(prev_span.as_mut()) {
+                *span = join(span, consumed);
+            }
+        }
         if let Some(node) = prev.take() {
             stack.push(node);
+            stack_spans.push(prev_span.unwrap());
         }
         if let Some(node) = last.take() {
             stack.push(node);
+            stack_spans.push(last_span.unwrap());
         }
         if let Some(node) = to_push.take() {
             stack.push(node);
+            stack_spans.push(to_push_span);
+        }
+        if input_byte.is_some() && byte.is_none() {
+            data_iter.next().unwrap();
+            pos += 1;
         }
-        if byte_was_some && byte.is_none() {
+        for _ in 0..char_continuation_bytes {
             data_iter.next().unwrap();
+            pos += 1;
         }
     }
     crate::dprintln!("stack {:?}", stack);
     // Check for incomplete elements.  Example: br"(ab"
-    for node in stack.iter().rev() {
+    for (node, span) in stack.iter().zip(stack_spans).rev() {
         if let Node::NonFinal(non_final) = node {
-            return Err(non_final.reason());
+            return Err(error(non_final.error_kind(), span));
         }
     }
+    // Flags like `(?m)` leave no node.
+    if stack.is_empty() {
+        return Ok(FinalNode::Seq(Vec::new()));
+    }
     assert_eq!(1, stack.len());
-    Ok(stack.pop().unwrap().unwrap_final())
+    let final_node = stack.pop().unwrap().unwrap_final();
+    let size = crate::program::size(&final_node);
+    if size > crate::program::MAX_SIZE {
+        return Err(error(ParseErrorKind::TooLarge(size), 0..regex.len()));
+    }
+    Ok(final_node)
 }
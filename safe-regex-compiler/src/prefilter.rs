@@ -0,0 +1,133 @@
+//! Finds the bytes that a match must contain, so matchers can skip over data
+//! that cannot match.
+//!
+//! The generated matchers check these before they run their state machines.
+//! A search jumps to the first byte that can start a match.  Any matcher
+//! returns early when the data lacks a literal that every match contains.
+#![forbid(unsafe_code)]
+use crate::generator::OptimizedNode;
+use crate::parser::FinalNode;
+
+/// The facts about an expression that let a matcher skip data.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Prefilter {
+    /// The longest byte string that every match contains.
+    /// It is empty when there is none.
+    pub literal: Vec<u8>,
+    /// The bytes that can start a match.
+    /// This is `None` when the expression matches the empty string or when
+    /// any byte can start a match.
+    pub first_bytes: Option<[bool; 256]>,
+}
+
+/// Adds the byte strings that every match of `node` contains to `literals`.
+///
+/// `current` holds the bytes that every match has right before `node`.
+/// On return, it holds the bytes right at the end of `node`.
+fn collect_literals(node: &OptimizedNode, literals: &mut Vec<Vec<u8>>, current: &mut Vec<u8>) {
+    match node {
+        // Assertions do not read bytes.
+        OptimizedNode::Empty | OptimizedNode::Assert(_) => {}
+        OptimizedNode::Byte(predicate) => {
+            if let Some(b) = predicate.single_byte() {
+                current.push(b);
+            } else {
+                literals.push(core::mem::take(current));
+            }
+        }
+        OptimizedNode::Repeat(predicate, min, max, _) => {
+            if let Some(b) = predicate.single_byte() {
+                current.extend(core::iter::repeat_n(b, *min));
+                if min == max {
+                    return;
+                }
+            }
+            literals.push(core::mem::take(current));
+        }
+        OptimizedNode::Seq(nodes) => {
+            for node in nodes {
+                collect_literals(node, literals, current);
+            }
+        }
+        OptimizedNode::Group(_, node) => collect_literals(node, literals, current),
+        OptimizedNode::Alt(_) | OptimizedNode::Optional(..) | OptimizedNode::Star(..) => {
+            literals.push(core::mem::take(current));
+        }
+    }
+}
+
+/// Adds the bytes that can start a match of `node` to `bytes`.
+///
+/// Returns true when `node` matches the empty string.
+fn collect_first_bytes(node: &OptimizedNode, bytes: &mut [bool; 256]) -> bool {
+    match node {
+        OptimizedNode::Empty | OptimizedNode::Assert(_) => true,
+        OptimizedNode::Byte(predicate) | OptimizedNode::Repeat(predicate, ..) => {
+            for (entry, value) in bytes.iter_mut().zip(predicate.table().iter()) {
+                *entry |= *value;
+            }
+            matches!(node, OptimizedNode::Repeat(_, 0, _, _))
+        }
+        OptimizedNode::Seq(nodes) => nodes.iter().all(|node| collect_first_bytes(node, bytes)),
+        OptimizedNode::Alt(nodes) => {
+            let mut empty = false;
+            for node in nodes {
+                empty |= collect_first_bytes(node, bytes);
+            }
+            empty
+        }
+        OptimizedNode::Optional(node, _) | OptimizedNode::Star(node, _) => {
+            collect_first_bytes(node, bytes);
+            true
+        }
+        OptimizedNode::Group(_, node) => collect_first_bytes(node, bytes),
+    }
+}
+
+impl Prefilter {
+    pub(crate) fn from_optimized(node: &OptimizedNode) -> Self {
+        let mut literals = Vec::new();
+        let mut current = Vec::new();
+        collect_literals(node, &mut literals, &mut current);
+        literals.push(current);
+        let literal = literals.into_iter().fold(Vec::new(), |longest, literal| {
+            if literal.len() > longest.len() {
+                literal
+            } else {
+                longest
+            }
+        });
+        let mut bytes = [false; 256];
+        let empty = collect_first_bytes(node, &mut bytes);
+        let first_bytes = if empty || bytes.iter().all(|b| *b) {
+            None
+        } else {
+            Some(bytes)
+        };
+        Self {
+            literal,
+            first_bytes,
+        }
+    }
+}
+
+/// Finds the prefilter for `final_node`.
+///
+/// # Example
+/// ```
+/// use safe_regex_compiler::parser::parse;
+/// use safe_regex_compiler::prefilter::prefilter;
+/// let filter = prefilter(&parse(b".*(2G8H81RFNZ).*").unwrap());
+/// assert_eq!(b"2G8H81RFNZ".to_vec(), filter.literal);
+/// assert_eq!(None, filter.first_bytes);
+/// let filter = prefilter(&parse(b"[0-9]+-x").unwrap());
+/// assert_eq!(b"-x".to_vec(), filter.literal);
+/// assert!(filter.first_bytes.unwrap()[usize::from(b'7')]);
+/// assert!(!filter.first_bytes.unwrap()[usize::from(b'-')]);
+/// ```
+#[must_use]
+pub fn prefilter(final_node: &FinalNode) -> Prefilter {
+    Prefilter::from_optimized(
+        &OptimizedNode::from_final_node(final_node).unwrap_or(OptimizedNode::Empty),
+    )
+}
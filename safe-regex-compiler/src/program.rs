@@ -0,0 +1,526 @@
+//! Lowers an expression into a [`Program`](struct.Program.html) that a
+//! matching engine interprets at run time.
+//!
+//! The `safe_regex::runtime` module runs programs.
+//! A program has the same states as the code from
+//! [`generate`](../generator/fn.generate.html), and it chooses between threads
+//! that meet in a state the same way.  So both return the same captures.
+#![forbid(unsafe_code)]
+use crate::generator::{Assertion, OptimizedNode, Predicate, TaggedNode};
+use crate::parser::FinalNode;
+
+/// A value that a machine computes at each position from its states.
+///
+/// The value is a thread or nothing.  A thread holds capture ranges.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    /// The thread that starts a match at this position.
+    Start,
+    /// The thread in byte state `n` before this position.
+    Prev(usize),
+    /// The thread of expr `n` when the assertion holds at this position.
+    Assert(usize, Assertion),
+    /// The thread of the first expr that has one.
+    First(Vec<usize>),
+    /// The thread of expr `n` with range `group` set to the empty range at
+    /// this position.
+    OpenGroup(usize, usize),
+}
+
+/// A state that holds a thread after the machine reads a byte.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ByteState {
+    /// The index in `Machine::sets` of the bytes that the state accepts.
+    pub set: usize,
+    /// The expr whose thread moves into the state when it accepts a byte.
+    pub expr: usize,
+    /// The ranges that end after the byte.
+    pub groups: Vec<usize>,
+}
+
+/// A state machine.
+///
+/// At each position, the machine computes its exprs in order.
+/// Every expr refers only to exprs that come before it.
+/// Then it reads a byte and updates its byte states from the exprs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Machine {
+    pub exprs: Vec<Expr>,
+    pub states: Vec<ByteState>,
+    /// The byte sets of the states.  States that accept the same bytes share
+    /// a set.
+    pub sets: Vec<Predicate>,
+    /// The expr that holds the matching thread.
+    pub accept: usize,
+    /// The number of ranges in each thread.
+    pub num_ranges: usize,
+}
+
+/// Returns the index of `predicate` in `sets`, and adds it when it is new.
+fn set_index(sets: &mut Vec<Predicate>, predicate: &Predicate) -> usize {
+    if let Some(n) = sets.iter().position(|set| set == predicate) {
+        n
+    } else {
+        sets.push(*predicate);
+        sets.len() - 1
+    }
+}
+
+/// An instruction of an [`Nfa`](struct.Nfa.html).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Inst {
+    /// Reads a byte in set `.0` and continues at `.1`.
+    Byte(usize, usize),
+    /// Continues at `.0`, and with lower priority at `.1`.
+    Split(usize, usize),
+    /// Saves the position in slot `.0` and continues at `.1`.
+    Save(usize, usize),
+    /// Continues at `.1` when the assertion holds.
+    Assert(Assertion, usize),
+    /// Ends a match.
+    Match,
+}
+
+/// A program for `safe_regex::internal::search`, which finds the
+/// leftmost-first match.
+///
+/// Group `n` saves its start in slot `2 * n` and its end in slot `2 * n + 1`.
+/// The last two slots hold the start and end of the match.
+///
+/// Unlike the code for whole-data matches, the program has instructions for
+/// every count of a repetition, like `x{0,1000}`.  Threads that start at
+/// different positions share the repetition and save different slots, so the
+/// search needs one thread for each count.  This makes the program and its
+/// [`memory_size`](#method.memory_size) grow with the counts.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Nfa {
+    pub insts: Vec<Inst>,
+    /// The thread of each instruction.  A position holds at most one thread
+    /// with each id.
+    pub ids: Vec<usize>,
+    /// The first instruction.
+    pub start: usize,
+    /// The byte sets of the `Byte` instructions.
+    pub sets: Vec<Predicate>,
+    pub num_slots: usize,
+}
+impl Nfa {
+    fn new(num_groups: usize, tagged_node: &TaggedNode) -> Self {
+        let mut nfa = Self {
+            insts: vec![Inst::Match],
+            ids: vec![0],
+            start: 0,
+            sets: Vec::new(),
+            num_slots: 2 * num_groups + 2,
+        };
+        let end = nfa.push(Inst::Save(2 * num_groups + 1, 0));
+        let body = nfa.build(tagged_node, end);
+        nfa.start = nfa.push(Inst::Save(2 * num_groups, body));
+        nfa
+    }
+
+    /// Returns the bytes of stack that a `regex!` matcher uses to run the
+    /// program with `safe_regex::internal::search`, counting 8-byte `usize`
+    /// values.  It holds two threads and their slots for every instruction.
+    #[must_use]
+    pub fn memory_size(&self) -> usize {
+        let (num_insts, num_slots) = (self.insts.len(), self.num_slots);
+        // Like `safe_regex::internal::search_memory_len`, plus the slots of
+        // the match.
+        let len = 2 * num_insts * (2 + num_slots) + 2 * (num_insts + 1) + 2 * num_slots;
+        8 * len
+    }
+
+    fn push(&mut self, inst: Inst) -> usize {
+        self.ids.push(self.insts.len());
+        self.insts.push(inst);
+        self.insts.len() - 1
+    }
+
+    /// Gives the instructions from `copy` the ids of the matching ones from
+    /// `original`.  Both are the same expression and continue at the same
+    /// instruction, but only `original` saves slots.
+    fn share_ids(&mut self, original: usize, copy: usize) {
+        let mut stack = vec![(original, copy)];
+        let mut done = vec![false; self.insts.len()];
+        while let Some((mut original, copy)) = stack.pop() {
+            while let Inst::Save(_, next) = self.insts[original] {
+                original = next;
+            }
+            if original == copy || done[copy] {
+                continue;
+            }
+            done[copy] = true;
+            self.ids[copy] = self.ids[original];
+            match (self.insts[original], self.insts[copy]) {
+                (Inst::Byte(_, a), Inst::Byte(_, b)) | (Inst::Assert(_, a), Inst::Assert(_, b)) => {
+                    stack.push((a, b));
+                }
+                (Inst::Split(a1, a2), Inst::Split(b1, b2)) => {
+                    stack.push((a1, b1));
+                    stack.push((a2, b2));
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    fn set(&mut self, predicate: &Predicate) -> usize {
+        set_index(&mut self.sets, predicate)
+    }
+
+    /// Adds a `Split` that prefers `inner`, or `next` when `lazy` is true.
+    fn split(&mut self, lazy: bool, inner: usize, next: usize) -> usize {
+        if lazy {
+            self.push(Inst::Split(next, inner))
+        } else {
+            self.push(Inst::Split(inner, next))
+        }
+    }
+
+    /// Adds the loop of `inner*`, which continues at `next`.  Returns the
+    /// loop's `Split` and the first instruction of `inner`.
+    fn star(&mut self, inner: &TaggedNode, lazy: bool, next: usize) -> (usize, usize) {
+        // A placeholder until the loop's instructions exist.
+        let repeat = self.push(Inst::Match);
+        let inner = self.build(inner, repeat);
+        self.insts[repeat] = if lazy {
+            Inst::Split(next, inner)
+        } else {
+            Inst::Split(inner, next)
+        };
+        (repeat, inner)
+    }
+
+    /// Adds the instructions of `node`, which continue at `next`, and returns
+    /// the first one.
+    fn build(&mut self, node: &TaggedNode, next: usize) -> usize {
+        match node {
+            TaggedNode::Empty => next,
+            TaggedNode::Byte(predicate) => {
+                let set = self.set(predicate);
+                self.push(Inst::Byte(set, next))
+            }
+            TaggedNode::Assert(assertion) => self.push(Inst::Assert(*assertion, next)),
+            // `x+` becomes `x` followed by `x*`.  The `regex` crate runs one
+            // copy of `x` in a loop, so a thread that returns to `x` without
+            // reading a byte stops where it entered `x` before.  The loop
+            // here enters its copy at the `Split`, and the copy shares the
+            // ids of the first `x`.
+            TaggedNode::Seq(nodes) => {
+                let mut next = next;
+                let mut copy = None;
+                for (n, node) in nodes.iter().enumerate().rev() {
+                    let after_original = n > 0
+                        && matches!(
+                            node,
+                            TaggedNode::Star(inner, _) if nodes[n - 1].non_capturing() == **inner
+                        );
+                    let first = match node {
+                        TaggedNode::Star(inner, lazy) if after_original => {
+                            let (repeat, inner_first) = self.star(inner, *lazy, next);
+                            copy = Some(inner_first);
+                            next = repeat;
+                            continue;
+                        }
+                        _ => self.build(node, next),
+                    };
+                    if let Some(copy) = copy.take() {
+                        self.share_ids(first, copy);
+                    }
+                    next = first;
+                }
+                next
+            }
+            TaggedNode::Alt(nodes) => {
+                let arms: Vec<usize> = nodes.iter().map(|node| self.build(node, next)).collect();
+                let (last, arms) = arms.split_last().unwrap();
+                arms.iter()
+                    .rev()
+                    .fold(*last, |rest, arm| self.push(Inst::Split(*arm, rest)))
+            }
+            TaggedNode::Optional(inner, lazy) => {
+                let inner = self.build(inner, next);
+                self.split(*lazy, inner, next)
+            }
+            // Builds `x*` as `(x+)?` when `x` matches the empty string, like
+            // the `regex` crate.  A thread that enters `x` and returns to the
+            // loop without reading a byte then stops at the loop's `Split`,
+            // instead of at the one it entered through.  This keeps the
+            // priority order.
+            TaggedNode::Star(inner, lazy) => {
+                let (repeat, first) = self.star(inner, *lazy, next);
+                if inner.matches_empty() {
+                    self.split(*lazy, first, next)
+                } else {
+                    repeat
+                }
+            }
+            TaggedNode::Group(group_num, inner) => {
+                let end = self.push(Inst::Save(2 * group_num + 1, next));
+                let inner = self.build(inner, end);
+                self.push(Inst::Save(2 * group_num, inner))
+            }
+            // Nests the optional bytes, like `x(x(x)?)?`, so a thread that
+            // skips one skips the rest.  Each count gets its own instruction
+            // and thread.  See `Nfa`.
+            TaggedNode::Repeat(predicate, min, max, lazy) => {
+                let set = self.set(predicate);
+                let mut first = next;
+                for _ in *min..*max {
+                    let byte = self.push(Inst::Byte(set, first));
+                    first = self.split(*lazy, byte, next);
+                }
+                for _ in 0..*min {
+                    first = self.push(Inst::Byte(set, first));
+                }
+                first
+            }
+        }
+    }
+}
+
+/// A compiled expression.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Program {
+    /// The names of the capturing groups, or `None` for unnamed groups.
+    pub group_names: Vec<Option<String>>,
+    /// Matches all of the data.  Threads hold one range per capturing group.
+    /// Ranges of groups that did not participate hold `usize::MAX`.
+    pub anchored: Machine,
+    /// Searches for a match.
+    pub search: Nfa,
+}
+
+/// Builds a machine like the generator's `build` function builds code.
+struct Builder {
+    exprs: Vec<Expr>,
+    states: Vec<ByteState>,
+    sets: Vec<Predicate>,
+}
+impl Builder {
+    fn push(&mut self, expr: Expr) -> usize {
+        self.exprs.push(expr);
+        self.exprs.len() - 1
+    }
+
+    /// Combines two threads.  Prefers `a`, or `b` when `lazy` is true.
+    fn merge(&mut self, lazy: bool, a: usize, b: usize) -> usize {
+        let (a, b) = if lazy { (b, a) } else { (a, b) };
+        self.push(Expr::First(vec![a, b]))
+    }
+
+    /// Adds the states of `node` and returns the expr for the state after
+    /// `node`.
+    ///
+    /// On entry, `lazy` holds the laziness of the last repetition before `node`.
+    /// On return, it holds the laziness of the last repetition in or before `node`.
+    fn build(
+        &mut self,
+        enclosing_groups: &[usize],
+        lazy: &mut bool,
+        prev: usize,
+        node: &TaggedNode,
+    ) -> usize {
+        match node {
+            TaggedNode::Empty => prev,
+            TaggedNode::Assert(assertion) => self.push(Expr::Assert(prev, *assertion)),
+            TaggedNode::Byte(predicate) => {
+                self.states.push(ByteState {
+                    set: set_index(&mut self.sets, predicate),
+                    expr: prev,
+                    groups: enclosing_groups.to_vec(),
+                });
+                self.push(Expr::Prev(self.states.len() - 1))
+            }
+            TaggedNode::Seq(nodes) => nodes.iter().fold(prev, |prev, node| {
+                self.build(enclosing_groups, lazy, prev, node)
+            }),
+            TaggedNode::Alt(nodes) => {
+                let mut arms = Vec::new();
+                let mut any_arm_lazy = false;
+                for node in nodes {
+                    let mut arm_lazy = *lazy;
+                    arms.push(self.build(enclosing_groups, &mut arm_lazy, prev, node));
+                    any_arm_lazy |= arm_lazy;
+                }
+                *lazy = any_arm_lazy;
+                self.push(Expr::First(arms))
+            }
+            TaggedNode::Optional(inner, inner_lazy) => {
+                let inner_expr = self.build(enclosing_groups, &mut lazy.clone(), prev, inner);
+                let expr = self.merge(*lazy, prev, inner_expr);
+                *lazy = *inner_lazy;
+                expr
+            }
+            // Like the generator, this builds `inner` twice and keeps the
+            // states of the second copy.  The first copy's exprs refer to them.
+            TaggedNode::Star(inner, inner_lazy) => {
+                let num_states = self.states.len();
+                let first_expr = self.build(enclosing_groups, &mut lazy.clone(), prev, inner);
+                self.states.truncate(num_states);
+                let inner_prev = self.merge(*lazy, prev, first_expr);
+                let expr = self.build(enclosing_groups, &mut lazy.clone(), inner_prev, inner);
+                let expr = self.merge(*lazy, prev, expr);
+                *lazy = *inner_lazy;
+                expr
+            }
+            TaggedNode::Group(group_num, inner) => {
+                let inner_enclosing_groups: Vec<usize> = enclosing_groups
+                    .iter()
+                    .chain(core::iter::once(group_num))
+                    .copied()
+                    .collect();
+                let inner_prev = self.push(Expr::OpenGroup(prev, *group_num));
+                self.build(&inner_enclosing_groups, lazy, inner_prev, inner)
+            }
+            TaggedNode::Repeat(predicate, min, max, repeat_lazy) => self.build(
+                enclosing_groups,
+                lazy,
+                prev,
+                &TaggedNode::expand_repeat(predicate, *min, *max, *repeat_lazy),
+            ),
+        }
+    }
+
+    /// Removes the exprs that no state uses and returns the machine.
+    fn finish(self, accept: usize, num_ranges: usize) -> Machine {
+        let mut used = vec![false; self.exprs.len()];
+        used[accept] = true;
+        for state in &self.states {
+            used[state.expr] = true;
+        }
+        for n in (0..self.exprs.len()).rev() {
+            if !used[n] {
+                continue;
+            }
+            match &self.exprs[n] {
+                Expr::Start | Expr::Prev(_) => {}
+                Expr::Assert(expr, _) | Expr::OpenGroup(expr, _) => used[*expr] = true,
+                Expr::First(exprs) => {
+                    for expr in exprs {
+                        used[*expr] = true;
+                    }
+                }
+            }
+        }
+        let mut new_index = vec![usize::MAX; self.exprs.len()];
+        let mut exprs = Vec::new();
+        for (n, expr) in self.exprs.into_iter().enumerate() {
+            if !used[n] {
+                continue;
+            }
+            new_index[n] = exprs.len();
+            exprs.push(match expr {
+                Expr::Start | Expr::Prev(_) => expr,
+                Expr::Assert(expr, assertion) => Expr::Assert(new_index[expr], assertion),
+                Expr::First(exprs) => {
+                    Expr::First(exprs.into_iter().map(|expr| new_index[expr]).collect())
+                }
+                Expr::OpenGroup(expr, group) => Expr::OpenGroup(new_index[expr], group),
+            });
+        }
+        let states = self
+            .states
+            .into_iter()
+            .map(|state| ByteState {
+                expr: new_index[state.expr],
+                ..state
+            })
+            .collect();
+        Machine {
+            exprs,
+            states,
+            sets: self.sets,
+            accept: new_index[accept],
+            num_ranges,
+        }
+    }
+}
+
+impl Machine {
+    fn new(num_ranges: usize, tagged_node: &TaggedNode) -> Self {
+        let mut builder = Builder {
+            exprs: vec![Expr::Start],
+            states: Vec::new(),
+            sets: Vec::new(),
+        };
+        let accept = builder.build(&[], &mut false, 0, tagged_node);
+        builder.finish(accept, num_ranges)
+    }
+}
+
+/// The most instructions that an expression may compile to.
+/// [`parse`](../parser/fn.parse.html) rejects larger expressions, like
+/// `a{1000000}`, because their programs use too much time and memory.
+pub const MAX_SIZE: usize = 100_000;
+
+/// The most bytes of stack that the search of a `regex!` matcher may use.
+/// The generator rejects expressions whose [`Nfa`](struct.Nfa.html) needs
+/// more, like `([a-z]{0,5000})x`, so that searches fit in the stacks of
+/// spawned threads, which hold 2 MiB by default.
+pub const MAX_SEARCH_MEMORY: usize = 256 * 1024;
+
+/// Returns an upper bound on the number of instructions in the program of
+/// `final_node`, or `usize::MAX` when the bound overflows.
+///
+/// It visits each node once, without recursion, and multiplies each node's
+/// own instructions by the repetitions around it.
+#[must_use]
+pub fn size(final_node: &FinalNode) -> usize {
+    let mut size = 3_usize;
+    let mut stack = vec![(final_node, 1_usize)];
+    while let Some((node, copies)) = stack.pop() {
+        let own = match node {
+            FinalNode::Seq(nodes) => {
+                stack.extend(nodes.iter().map(|node| (node, copies)));
+                0
+            }
+            FinalNode::Alt(nodes) => {
+                stack.extend(nodes.iter().map(|node| (node, copies)));
+                nodes.len().saturating_sub(1)
+            }
+            FinalNode::Group(inner) | FinalNode::NamedGroup(_, inner) => {
+                stack.push((inner, copies));
+                2
+            }
+            FinalNode::NonCapturingGroup(inner) => {
+                stack.push((inner, copies));
+                0
+            }
+            // The copies after the required ones get a `Split` each, and a
+            // loop gets two.
+            FinalNode::Repeat(inner, min, max, _) => {
+                let inner_copies = max.unwrap_or(min.saturating_add(1)).max(1);
+                stack.push((inner, copies.saturating_mul(inner_copies)));
+                inner_copies.saturating_sub(*min).saturating_add(1)
+            }
+            _ => 1,
+        };
+        size = size.saturating_add(copies.saturating_mul(own));
+    }
+    size
+}
+
+/// Compiles `final_node` into a program.
+///
+/// # Example
+/// ```
+/// use safe_regex_compiler::parser::parse;
+/// use safe_regex_compiler::program::compile;
+/// let program = compile(&parse(b"(?P<a>x)*y").unwrap());
+/// assert_eq!(vec![Some("a".to_string())], program.group_names);
+/// assert_eq!(2, program.anchored.states.len());
+/// ```
+#[must_use]
+pub fn compile(final_node: &FinalNode) -> Program {
+    let optimized_node = OptimizedNode::from_final_node(final_node).unwrap_or(OptimizedNode::Empty);
+    let mut group_names: Vec<Option<String>> = Vec::new();
+    let tagged_node = TaggedNode::from_optimized(&mut group_names, &optimized_node);
+    let num_groups = group_names.len();
+    Program {
+        anchored: Machine::new(num_groups, &tagged_node),
+        search: Nfa::new(num_groups, &tagged_node),
+        group_names,
+    }
+}
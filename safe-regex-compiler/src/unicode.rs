@@ -0,0 +1,73 @@
+//! Unicode properties for `\p{..}` classes and Unicode `\w`.
+//!
+//! The tables are in the compiler so the generated matchers and the
+//! `safe-regex` runtime crate do not need them.
+#![forbid(unsafe_code)]
+mod tables;
+
+/// Every code point.
+const ANY: &[(u32, u32)] = &[(0, crate::utf8::MAX_CODE_POINT)];
+
+/// Lowercases `name` and removes spaces, `_`, and `-`, so `Decimal_Number`
+/// and `decimal number` both become `decimalnumber`.
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, ' ' | '_' | '-'))
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn find(
+    table: &'static [(&str, &'static [(u32, u32)])],
+    name: &str,
+) -> Option<&'static [(u32, u32)]> {
+    table
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map(|(_, ranges)| *ranges)
+}
+
+/// Returns the sorted ranges of code points that have the Unicode property
+/// `name`.
+///
+/// `name` may be:
+/// - A general category, like `L`, `Letter`, `Nd`, or `Decimal_Number`
+/// - A script, like `Greek` or `Grek`
+/// - A general category or script with its property name, like `gc=Nd`,
+///   `General_Category=Nd`, `sc=Greek`, or `Script=Greek`
+/// - `Any`
+///
+/// Matching ignores case, spaces, `_`, and `-`.
+///
+/// Returns `None` for unknown names.
+///
+/// # Example
+/// ```
+/// use safe_regex_compiler::unicode::property;
+/// assert_eq!(Some(&[(0x30, 0x39)][..]), property("Nd").map(|r| &r[..1]));
+/// assert_eq!(property("Greek"), property("sc=grek"));
+/// assert_eq!(None, property("Klingon"));
+/// ```
+#[must_use]
+pub fn property(name: &str) -> Option<&'static [(u32, u32)]> {
+    let name = normalize(name);
+    if let Some((key, value)) = name.split_once('=') {
+        return match key {
+            "gc" | "generalcategory" => find(tables::GENERAL_CATEGORY, value),
+            "sc" | "script" => find(tables::SCRIPT, value),
+            _ => None,
+        };
+    }
+    if name == "any" {
+        return Some(ANY);
+    }
+    find(tables::GENERAL_CATEGORY, &name).or_else(|| find(tables::SCRIPT, &name))
+}
+
+/// Returns the sorted ranges of Unicode word characters:
+/// letters, marks, decimal numbers, connector punctuation like `_`,
+/// and join controls.
+#[must_use]
+pub fn word() -> &'static [(u32, u32)] {
+    tables::PERL_WORD
+}
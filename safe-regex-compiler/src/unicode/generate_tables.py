@@ -0,0 +1,93 @@
+#!/usr/bin/env python3
+"""Generates `tables.rs` with the Unicode property tables used by `\\p{..}`.
+
+It reads the tables that `ucd-generate` made for the `regex-syntax` crate.
+Usage:
+    ./generate_tables.py ~/.cargo/registry/src/*/regex-syntax-0.8.11/src/unicode_tables >tables.rs
+"""
+import re
+import sys
+
+CHAR = r"'(\\u\{[0-9a-fA-F]+\}|\\.|[^\\'])'"
+RANGE = re.compile(r"\(" + CHAR + r",\s*" + CHAR + r"\)")
+TABLE = re.compile(r"pub const ([A-Z_0-9]+): &'static \[\(char, char\)\] =\s*&\[(.*?)\];", re.S)
+ESCAPES = {"\\'": "'", '\\"': '"', "\\\\": "\\", "\\n": "\n", "\\r": "\r", "\\t": "\t", "\\0": "\0"}
+
+
+def decode(c):
+    if c.startswith("\\u{"):
+        return int(c[3:-1], 16)
+    return ord(ESCAPES.get(c, c))
+
+
+def read_tables(path):
+    text = open(path, encoding="utf-8").read()
+    return {
+        name: [(decode(a), decode(b)) for a, b in RANGE.findall(body)]
+        for name, body in TABLE.findall(text)
+    }
+
+
+def read_aliases(path, prop):
+    text = open(path, encoding="utf-8").read()
+    body = re.search(r'\(\s*"' + prop + r'",\s*&\[(.*?)\]', text, re.S).group(1)
+    return re.findall(r'\("([^"]+)", "([^"]+)"\)', body)
+
+
+def const_name(prefix, canonical):
+    return prefix + re.sub(r"[^A-Z0-9]", "_", canonical.upper())
+
+
+def write_ranges(name, ranges):
+    print("const {}: &[(u32, u32)] = &[".format(name))
+    for a, b in ranges:
+        print("    (0x{:04X}, 0x{:04X}),".format(a, b))
+    print("];")
+    print()
+
+
+def main(tables_dir):
+    general_category = read_tables(tables_dir + "/general_category.rs")
+    script = read_tables(tables_dir + "/script.rs")
+    perl_word = read_tables(tables_dir + "/perl_word.rs")["PERL_WORD"]
+    values = tables_dir + "/property_values.rs"
+    print("// DO NOT EDIT THIS FILE.  `generate_tables.py` generated it from the")
+    print("// Unicode Character Database 16.0.0 tables in the `regex-syntax` crate.")
+    print()
+    print("/// General categories, like `Letter` and `Nd`, by normalized alias.")
+    print("pub const GENERAL_CATEGORY: &[(&str, &[(u32, u32)])] = &[")
+    used = []
+    for alias, canonical in read_aliases(values, "General_Category"):
+        name = const_name("GC_", canonical)
+        if canonical.upper().replace(" ", "_") not in general_category:
+            continue
+        print('    ("{}", {}),'.format(alias, name))
+        used.append((name, general_category[canonical.upper()]))
+    print("];")
+    print()
+    print("/// Scripts, like `Greek` and `Latn`, by normalized alias.")
+    print("pub const SCRIPT: &[(&str, &[(u32, u32)])] = &[")
+    for alias, canonical in read_aliases(values, "Script"):
+        name = const_name("SC_", canonical)
+        if canonical.upper() not in script:
+            continue
+        print('    ("{}", {}),'.format(alias, name))
+        used.append((name, script[canonical.upper()]))
+    print("];")
+    print()
+    print("/// Word characters: alphabetic, marks, decimal numbers, connector")
+    print("/// punctuation, and join controls.")
+    print("pub const PERL_WORD: &[(u32, u32)] = &[")
+    for a, b in perl_word:
+        print("    (0x{:04X}, 0x{:04X}),".format(a, b))
+    print("];")
+    print()
+    written = set()
+    for name, ranges in sorted(used):
+        if name not in written:
+            written.add(name)
+            write_ranges(name, ranges)
+
+
+if __name__ == "__main__":
+    main(sys.argv[1])
@@ -0,0 +1,131 @@
+//! Converts ranges of Unicode code points into sequences of UTF-8 byte
+//! ranges.
+//!
+//! The generated matchers work on bytes.
+//! To match a set of `char`s in a string, the parser converts the set into an
+//! alternation of byte sequences.
+//! Each sequence matches the complete UTF-8 encoding of some of the `char`s.
+#![forbid(unsafe_code)]
+
+/// The largest Unicode code point.
+pub const MAX_CODE_POINT: u32 = 0x0010_FFFF;
+
+/// Code points `0xD800-0xDFFF` are UTF-16 surrogates and are not `char`s.
+const SURROGATES: (u32, u32) = (0xD800, 0xDFFF);
+
+/// Sorts `ranges` and merges ranges that overlap or touch.
+#[must_use]
+pub fn normalize(mut ranges: Vec<(u32, u32)>) -> Vec<(u32, u32)> {
+    ranges.sort_unstable();
+    let mut result: Vec<(u32, u32)> = Vec::with_capacity(ranges.len());
+    for (a, b) in ranges {
+        match result.last_mut() {
+            Some((_, prev_b)) if a <= prev_b.saturating_add(1) => *prev_b = (*prev_b).max(b),
+            _ => result.push((a, b)),
+        }
+    }
+    result
+}
+
+/// Returns the ranges of code points that are not in `ranges`.
+///
+/// `ranges` must be normalized.
+#[must_use]
+pub fn complement(ranges: &[(u32, u32)]) -> Vec<(u32, u32)> {
+    let mut result = Vec::new();
+    let mut next = 0_u32;
+    for (a, b) in ranges.iter().copied() {
+        if next < a {
+            result.push((next, a - 1));
+        }
+        next = b.saturating_add(1);
+    }
+    if next <= MAX_CODE_POINT {
+        result.push((next, MAX_CODE_POINT));
+    }
+    result
+}
+
+/// Returns the UTF-8 encoding of code point `c`.
+/// `c` must not be a surrogate.
+fn encode(c: u32) -> Vec<u8> {
+    let mut buf = [0_u8; 4];
+    char::from_u32(c)
+        .unwrap()
+        .encode_utf8(&mut buf)
+        .as_bytes()
+        .to_vec()
+}
+
+/// Returns sequences of byte ranges that together match exactly the UTF-8
+/// encodings of the code points in `a..=b`.
+///
+/// Surrogates in the range are skipped.
+///
+/// # Example
+/// ```
+/// use safe_regex_compiler::utf8::sequences;
+/// assert_eq!(vec![vec![(b'a', b'z')]], sequences(0x61, 0x7A));
+/// assert_eq!(
+///     vec![vec![(0x7F, 0x7F)], vec![(0xC2, 0xC2), (0x80, 0x80)]],
+///     sequences(0x7F, 0x80),
+/// );
+/// ```
+#[must_use]
+pub fn sequences(a: u32, b: u32) -> Vec<Vec<(u8, u8)>> {
+    let mut result = Vec::new();
+    let mut todo = vec![(a, b.min(MAX_CODE_POINT))];
+    while let Some((a, b)) = todo.pop() {
+        if a > b {
+            continue;
+        }
+        // Skip surrogates.
+        let (s_lo, s_hi) = SURROGATES;
+        if a <= s_hi && s_lo <= b {
+            todo.push((s_hi + 1, b));
+            if a < s_lo {
+                todo.push((a, s_lo - 1));
+            }
+            continue;
+        }
+        // Split where the encoded length changes.
+        if let Some(max) = [0x7F, 0x7FF, 0xFFFF]
+            .iter()
+            .copied()
+            .find(|max| a <= *max && *max < b)
+        {
+            todo.push((max + 1, b));
+            todo.push((a, max));
+            continue;
+        }
+        if b <= 0x7F {
+            #[allow(clippy::cast_possible_truncation)]
+            result.push(vec![(a as u8, b as u8)]);
+            continue;
+        }
+        // Split until the range covers every value of the trailing bytes
+        // that differ.
+        let mut split = false;
+        for i in 1..4 {
+            let mask = (1_u32 << (6 * i)) - 1;
+            if a & !mask != b & !mask {
+                if a & mask != 0 {
+                    todo.push(((a | mask) + 1, b));
+                    todo.push((a, a | mask));
+                    split = true;
+                    break;
+                }
+                if b & mask != mask {
+                    todo.push((b & !mask, b));
+                    todo.push((a, (b & !mask) - 1));
+                    split = true;
+                    break;
+                }
+            }
+        }
+        if !split {
+            result.push(encode(a).into_iter().zip(encode(b)).collect());
+        }
+    }
+    result
+}
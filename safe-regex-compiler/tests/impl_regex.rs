@@ -1,32 +1,293 @@
 #![forbid(unsafe_code)]
 use safe_proc_macro2::TokenStream;
 use safe_quote::quote;
-use safe_regex_compiler::impl_regex;
+use safe_regex_compiler::{
+    impl_define_regex, impl_from_regex, impl_lexer, impl_regex, impl_regex_set, impl_regex_stream,
+    CompileError,
+};
 
 #[test]
 fn syntax_errors() {
-    #[allow(clippy::needless_pass_by_value)]
-    fn to_s(s: TokenStream) -> String {
-        format!("{}", s)
+    fn to_s(result: Result<TokenStream, CompileError>) -> Result<String, String> {
+        result.map(|s| format!("{}", s)).map_err(|e| e.message)
     }
-    let err = Err("expected a raw byte string, like br\"abc\"".to_string());
-    assert_eq!(err, impl_regex(quote! {"a"}).map(to_s));
-    assert_eq!(err, impl_regex(quote! {r"a"}).map(to_s));
-    assert_eq!(err, impl_regex(quote! {b"a"}).map(to_s));
-    assert_eq!(err, impl_regex(quote! {'a}).map(to_s));
-    assert_eq!(err, impl_regex(quote! {b'b'}).map(to_s));
-    assert_eq!(err, impl_regex(quote! {1}).map(to_s));
-    assert_eq!(err, impl_regex(quote! {(br"a")}).map(to_s));
-    assert_eq!(err, impl_regex(quote! {br"a";}).map(to_s));
-    assert_eq!(err, impl_regex(quote! {br"a" br"b"}).map(to_s));
+    let err = Err("expected a raw string, like r\"abc\" or br\"abc\"".to_string());
+    assert_eq!(err, to_s(impl_regex(quote! {})));
+    assert_eq!(err, to_s(impl_regex(quote! {"a"})));
+    assert_eq!(err, to_s(impl_regex(quote! {b"a"})));
+    assert_eq!(err, to_s(impl_regex(quote! {'a})));
+    assert_eq!(err, to_s(impl_regex(quote! {b'b'})));
+    assert_eq!(err, to_s(impl_regex(quote! {1})));
+    assert_eq!(err, to_s(impl_regex(quote! {(br"a")})));
+    assert_eq!(err, to_s(impl_regex(quote! {br"a";})));
+    assert_eq!(err, to_s(impl_regex(quote! {br"a" br"b"})));
+    // Outside of a proc macro, literals have no sub-spans, so the message
+    // shows where the problem is.
+    assert_eq!(
+        Err("missing closing `)`\nab(c\n  ^".to_string()),
+        to_s(impl_regex(quote! {br"ab(c"}))
+    );
+    assert_eq!(
+        Err("invalid escape sequence `\\q`\né\\q\n ^^".to_string()),
+        to_s(impl_regex(quote! {r#"é\q"#}))
+    );
+    // The search of a long repetition needs too much stack.
+    assert_eq!(
+        Ok(format!(
+            "{}",
+            quote! {
+                compile_error!(
+                    "expression is too large: its search uses 6720752 bytes of stack, more than 262144"
+                )
+            }
+        )),
+        to_s(impl_regex(quote! {br"([a-z]{0,30000})x"}))
+    );
+}
+
+#[test]
+fn compile_error() {
+    let e = impl_regex(quote! {br"ab(c"}).unwrap_err();
+    assert_eq!(
+        format!(
+            "{}",
+            quote! { compile_error!("missing closing `)`\nab(c\n  ^") }
+        ),
+        format!("{}", e.to_compile_error())
+    );
+}
+
+#[test]
+fn define_regex() {
+    fn to_s(result: Result<TokenStream, CompileError>) -> Result<(), String> {
+        result.map(|_| ()).map_err(|e| e.message)
+    }
+    let err = Err("expected definitions like `pub Name = br\"[0-9]+\";`".to_string());
+    assert_eq!(err, to_s(impl_define_regex(quote! {})));
+    assert_eq!(err, to_s(impl_define_regex(quote! {A})));
+    assert_eq!(err, to_s(impl_define_regex(quote! {= br"a"})));
+    assert_eq!(err, to_s(impl_define_regex(quote! {A =})));
+    assert_eq!(err, to_s(impl_define_regex(quote! {A = B})));
+    assert_eq!(err, to_s(impl_define_regex(quote! {A = br"a" B = br"b"})));
+    assert_eq!(
+        Err("missing closing `)`\nab(c\n  ^".to_string()),
+        to_s(impl_define_regex(quote! {A = br"ab(c"}))
+    );
+    assert_eq!(Ok(()), to_s(impl_define_regex(quote! {A = br"a"})));
+    let code = impl_define_regex(quote! {
+        /// Docs.
+        pub(crate) A = br"(a)(?P<b>b)";
+        B = r"é";
+    })
+    .unwrap()
+    .to_string();
+    assert!(
+        code.starts_with("# [doc = r\" Docs.\"] pub (crate) struct A ;"),
+        "{}",
+        code
+    );
+    assert!(code.contains("MatcherFn < 2usize > , 2usize"), "{}", code);
+    assert!(
+        code.contains("StrMatcher < safe_regex :: internal :: MatcherFn < 0usize >"),
+        "{}",
+        code
+    );
+    assert!(!code.contains("NamedMatcher"), "{}", code);
+}
+
+#[test]
+fn from_regex() {
+    fn to_s(result: Result<TokenStream, CompileError>) -> Result<(), String> {
+        result.map(|_| ()).map_err(|e| e.message)
+    }
+    let not_struct = Err("expected a struct without generic parameters".to_string());
+    assert_eq!(
+        not_struct,
+        to_s(impl_from_regex(quote! { #[regex(br"a")] enum A { B } }))
+    );
+    assert_eq!(
+        Err("generic parameters are not supported".to_string()),
+        to_s(impl_from_regex(
+            quote! { #[regex(br"(a)")] struct A<T> { b: T } }
+        ))
+    );
+    assert_eq!(
+        Err("generic parameters are not supported".to_string()),
+        to_s(impl_from_regex(
+            quote! { #[regex(br"(a)")] struct A<'a>(&'a [u8]); }
+        ))
+    );
+    let attr = Err("expected an attribute like `#[regex(br\"[0-9]+\")]`".to_string());
+    assert_eq!(attr, to_s(impl_from_regex(quote! { struct A; })));
+    assert_eq!(attr, to_s(impl_from_regex(quote! { #[regex] struct A; })));
+    assert_eq!(
+        attr,
+        to_s(impl_from_regex(quote! { #[regex(br"a", br"b")] struct A; }))
+    );
+    assert_eq!(
+        Err("expected only one `#[regex(...)]` attribute".to_string()),
+        to_s(impl_from_regex(
+            quote! { #[regex(br"a")] #[regex(br"b")] struct A; }
+        ))
+    );
+    assert_eq!(
+        Err("expected a raw string, like r\"abc\" or br\"abc\"".to_string()),
+        to_s(impl_from_regex(quote! { #[regex(b"a")] struct A; }))
+    );
+    assert_eq!(
+        Err("missing closing `)`\nab(c\n  ^".to_string()),
+        to_s(impl_from_regex(quote! { #[regex(br"ab(c")] struct A; }))
+    );
+    assert_eq!(
+        Err("the expression has 2 capturing groups but `A` has 1 fields".to_string()),
+        to_s(impl_from_regex(
+            quote! { #[regex(br"(a)(b)")] struct A { a: u8 } }
+        ))
+    );
+    assert_eq!(
+        Err("the expression has 1 capturing groups but `A` has 0 fields".to_string()),
+        to_s(impl_from_regex(quote! { #[regex(br"(a){3}")] struct A; }))
+    );
+    assert_eq!(
+        Err("the expression has 0 capturing groups but `A` has 2 fields".to_string()),
+        to_s(impl_from_regex(
+            quote! { #[regex(br"(?:a)")] struct A(u8, u8); }
+        ))
+    );
+    assert_eq!(
+        Ok(()),
+        to_s(impl_from_regex(quote! {
+            /// Docs.
+            #[derive(Debug)]
+            #[regex(br"(a)(?P<b>b)(c)")]
+            pub struct A {
+                #[allow(dead_code)]
+                pub(crate) a: std::collections::HashMap<u8, u8>,
+                b: fn(u8, u8) -> Vec<u8>,
+                c: u8,
+            }
+        }))
+    );
+    let code = impl_from_regex(quote! { #[regex(r"(é)")] struct A(pub char); })
+        .unwrap()
+        .to_string();
+    assert!(code.contains("FieldConverter :: < char >"), "{}", code);
+    assert!(
+        code.contains("impl core :: str :: FromStr for A"),
+        "{}",
+        code
+    );
+    assert!(code.contains("StrMatcher"), "{}", code);
+}
+
+#[test]
+fn lexer() {
+    fn to_s(result: Result<TokenStream, CompileError>) -> Result<(), String> {
+        result.map(|_| ()).map_err(|e| e.message)
+    }
+    let err =
+        Err("expected rules separated by commas, like Token::Word => br\"[a-z]+\"".to_string());
+    assert_eq!(err, to_s(impl_lexer(quote! {})));
+    assert_eq!(err, to_s(impl_lexer(quote! {Token::A})));
+    assert_eq!(err, to_s(impl_lexer(quote! {Token::A =>})));
+    assert_eq!(err, to_s(impl_lexer(quote! {=> br"a"})));
+    assert_eq!(err, to_s(impl_lexer(quote! {Token::A = br"a"})));
+    assert_eq!(err, to_s(impl_lexer(quote! {Token::A, Token::B => br"a"})));
+    assert_eq!(err, to_s(impl_lexer(quote! {Token::A => br"a" br"b"})));
+    assert_eq!(err, to_s(impl_lexer(quote! {Token::A => br"a",,})));
+    assert_eq!(
+        Err("expected a raw string, like r\"abc\" or br\"abc\"".to_string()),
+        to_s(impl_lexer(quote! {Token::A => "a"}))
+    );
+    assert_eq!(
+        Err("missing closing `)`\nab(c\n  ^".to_string()),
+        to_s(impl_lexer(quote! {Token::A => br"ab(c"}))
+    );
+    assert_eq!(Ok(()), to_s(impl_lexer(quote! {Token::A => br"a"})));
+    assert_eq!(
+        Ok(()),
+        to_s(impl_lexer(
+            quote! {Token::A => br"a", Token::Num(1) => r"é", make(2) => br"",}
+        ))
+    );
+}
+
+#[test]
+fn set() {
+    fn to_s(result: Result<TokenStream, CompileError>) -> Result<(), String> {
+        result.map(|_| ()).map_err(|e| e.message)
+    }
+    let err = Err("expected raw strings separated by commas, like br\"a\", br\"b\"".to_string());
+    assert_eq!(err, to_s(impl_regex_set(quote! {})));
+    assert_eq!(err, to_s(impl_regex_set(quote! {,})));
+    assert_eq!(err, to_s(impl_regex_set(quote! {br"a" br"b"})));
+    assert_eq!(err, to_s(impl_regex_set(quote! {br"a",,})));
+    assert_eq!(
+        Err("expected a raw string, like r\"abc\" or br\"abc\"".to_string()),
+        to_s(impl_regex_set(quote! {br"a", b"b"}))
+    );
+    assert_eq!(
+        Err("missing closing `)`\nab(c\n  ^".to_string()),
+        to_s(impl_regex_set(quote! {br"a", br"ab(c"}))
+    );
+    assert_eq!(Ok(()), to_s(impl_regex_set(quote! {br"a"})));
+    assert_eq!(Ok(()), to_s(impl_regex_set(quote! {br"a", r"é(b)",})));
+}
+
+#[test]
+fn stream() {
+    let err = "expected a raw string, like r\"abc\" or br\"abc\"".to_string();
+    assert_eq!(err, impl_regex_stream(quote! {b"a"}).unwrap_err().message);
+    assert_eq!(
+        "missing closing `)`\nab(c\n  ^",
+        impl_regex_stream(quote! {br"ab(c"}).unwrap_err().message
+    );
+    for stream in [
+        quote! {br"a(b)"},
+        quote! {r"é(b)"},
+        quote! {br""},
+        quote! {br"^\b"},
+    ] {
+        let code = impl_regex_stream(stream).unwrap().to_string();
+        assert!(code.contains("StreamState"), "{}", code);
+        assert!(!code.contains("data"), "{}", code);
+    }
+}
+
+#[test]
+fn long_repeat() {
+    // Stream matchers count the repetitions.  Searches hold a thread for
+    // each count, so `regex!` rejects long repetitions.
+    assert!(impl_regex_stream(quote! {br"[a-z]{0,5000}"}).is_ok());
+    assert_eq!(
+        format!(
+            "{}",
+            quote! {
+                compile_error!(
+                    "expression is too large: its search uses 800288 bytes of stack, more than 262144"
+                )
+            }
+        ),
+        format!("{}", impl_regex(quote! {br"[a-z]{0,5000}"}).unwrap())
+    );
+    assert!(
+        !format!("{}", impl_regex(quote! {br"[a-z]{0,1000}"}).unwrap())
+            .starts_with("compile_error")
+    );
 }
 
 #[test]
 fn empty() {
     let expected = quote! {
-        safe_regex::Matcher0::new(|data: &[u8]| {
-            if data.is_empty() {
-                Some(())
+        safe_regex::Matcher::new("", |data: &[u8], search_start: Option<usize>| {
+            if let Some(search_start) = search_start {
+                if search_start <= data.len() {
+                    Some((search_start..search_start, []))
+                } else {
+                    None
+                }
+            } else if data.is_empty() {
+                Some((0..0, []))
             } else {
                 None
             }
@@ -38,28 +299,116 @@ fn empty() {
     );
 }
 
+#[test]
+fn empty_str() {
+    let expected = quote! {
+        safe_regex::StrMatcher::new("", |data: &[u8], search_start: Option<usize>| {
+            if let Some(search_start) = search_start {
+                if search_start <= data.len() {
+                    Some((search_start..search_start, []))
+                } else {
+                    None
+                }
+            } else if data.is_empty() {
+                Some((0..0, []))
+            } else {
+                None
+            }
+        })
+    };
+    assert_eq!(
+        format!("{}", expected),
+        format!("{}", impl_regex(quote! { r"" }).unwrap())
+    );
+}
+
 #[test]
 fn byte() {
-    let expected = quote! { safe_regex::Matcher0::new(|data: &[u8]| {
-        let mut start = Some(());
-        let mut b0: Option<()> = None;
-        let mut data_iter = data.iter();
-        loop {
-            let prev_b0 = b0.clone();
-            if let Some(b) = data_iter.next() {
-                b0 = start.clone().filter(|_| {
-                    //
-                    *b == 97u8
-                });
-                start = None;
-                if b0.is_none() {
-                    return None;
+    let expected = quote! {
+        safe_regex::Matcher::new("a", |data: &[u8], search_start: Option<usize>| {
+            safe_regex::internal::find_literal(b"a", data.get(search_start.unwrap_or(0)..)?)?;
+            let search_start = match search_start {
+                Some(search_start) => {
+                    let rest = data.get(search_start..)?;
+                    Some(search_start + safe_regex::internal::find_any_byte(b"a", rest)?)
+                }
+                None => None,
+            };
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 1u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8
+            ];
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 6usize] = [0u16, 0u16, 2u16, 4u16, 0u16, 0u16];
+                const SEARCH_ACCEPT: [bool; 3usize] = [false, false, true];
+                const REVERSE: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 0u16];
+                const REVERSE_ACCEPT: [bool; 3usize] = [false, false, true];
+                let mut state = 2usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 2usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
+                }
+                let end = end?;
+                let mut start = end;
+                let mut state = 2usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 2usize] {
+                        start = n;
+                    }
                 }
+                Some((start..end, []))
             } else {
-                return prev_b0;
+                const ANCHORED: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 0u16];
+                const ANCHORED_ACCEPT: [bool; 3usize] = [false, false, true];
+                let mut state = 2usize;
+                for b in data {
+                    state = next(&ANCHORED, state, *b);
+                    if state == 0 {
+                        return None;
+                    }
+                }
+                if ANCHORED_ACCEPT[state / 2usize] {
+                    Some((0..data.len(), []))
+                } else {
+                    None
+                }
             }
-        }
-    }) };
+        })
+    };
     assert_eq!(
         format!("{}", expected),
         format!("{}", impl_regex(quote! { br"a" }).unwrap())
@@ -68,23 +417,83 @@ fn byte() {
 
 #[test]
 fn any_byte() {
-    let expected = quote! { safe_regex::Matcher0::new(|data: &[u8]| {
-        let mut start = Some(());
-        let mut b0: Option<()> = None;
-        let mut data_iter = data.iter();
-        loop {
-            let prev_b0 = b0.clone();
-            if let Some(b) = data_iter.next() {
-                b0 = start.clone();
-                start = None;
-                if b0.is_none() {
-                    return None;
+    let expected = quote! {
+        safe_regex::Matcher::new(".", |data: &[u8], search_start: Option<usize>| {
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8
+            ];
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 3usize] = [0u16, 2u16, 0u16];
+                const SEARCH_ACCEPT: [bool; 3usize] = [false, false, true];
+                const REVERSE: [u16; 3usize] = [0u16, 2u16, 0u16];
+                const REVERSE_ACCEPT: [bool; 3usize] = [false, false, true];
+                let mut state = 1usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
+                }
+                let end = end?;
+                let mut start = end;
+                let mut state = 1usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state] {
+                        start = n;
+                    }
                 }
+                Some((start..end, []))
             } else {
-                return prev_b0;
+                const ANCHORED: [u16; 3usize] = [0u16, 2u16, 0u16];
+                const ANCHORED_ACCEPT: [bool; 3usize] = [false, false, true];
+                let mut state = 1usize;
+                for b in data {
+                    state = next(&ANCHORED, state, *b);
+                    if state == 0 {
+                        return None;
+                    }
+                }
+                if ANCHORED_ACCEPT[state] {
+                    Some((0..data.len(), []))
+                } else {
+                    None
+                }
             }
-        }
-    }) };
+        })
+    };
     assert_eq!(
         format!("{}", expected),
         format!("{}", impl_regex(quote! { br"." }).unwrap())
@@ -93,25 +502,122 @@ fn any_byte() {
 
 #[test]
 fn class_inclusive() {
-    let expected = quote! { safe_regex::Matcher0::new(|data: &[u8]| {
-        let mut start = Some(());
-        let mut b0: Option<()> = None;
-        let mut data_iter = data.iter();
-        loop {
-            let prev_b0 = b0.clone();
-            if let Some(b) = data_iter.next() {
-                b0 = start.clone().filter(|_| {
-                    *b == 97u8 || *b == 98u8 || *b == 99u8 || (50u8..=52u8).contains(b)
-                });
-                start = None;
-                if b0.is_none() {
-                    return None;
+    let expected = quote! {
+        safe_regex::Matcher::new("[abc2-4]", |data: &[u8], search_start: Option<usize>| {
+            let search_start = match search_start {
+                Some(search_start) => {
+                    let rest = data.get(search_start..)?;
+                    Some(
+                        search_start + {
+                            const FIRST_BYTES: [bool; 256] = [
+                                false, false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false, false,
+                                true, true, true, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, true, true, true,
+                                false, false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false
+                            ];
+                            safe_regex::internal::find_in_set(&FIRST_BYTES, rest)
+                        }?
+                    )
+                }
+                None => None,
+            };
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 1u8,
+                1u8, 1u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 1u8, 1u8, 1u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8
+            ];
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 6usize] = [0u16, 0u16, 2u16, 4u16, 0u16, 0u16];
+                const SEARCH_ACCEPT: [bool; 3usize] = [false, false, true];
+                const REVERSE: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 0u16];
+                const REVERSE_ACCEPT: [bool; 3usize] = [false, false, true];
+                let mut state = 2usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 2usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
                 }
+                let end = end?;
+                let mut start = end;
+                let mut state = 2usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 2usize] {
+                        start = n;
+                    }
+                }
+                Some((start..end, []))
             } else {
-                return prev_b0;
+                const ANCHORED: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 0u16];
+                const ANCHORED_ACCEPT: [bool; 3usize] = [false, false, true];
+                let mut state = 2usize;
+                for b in data {
+                    state = next(&ANCHORED, state, *b);
+                    if state == 0 {
+                        return None;
+                    }
+                }
+                if ANCHORED_ACCEPT[state / 2usize] {
+                    Some((0..data.len(), []))
+                } else {
+                    None
+                }
             }
-        }
-    }) };
+        })
+    };
     assert_eq!(
         format!("{}", expected),
         format!("{}", impl_regex(quote! { br"[abc2-4]" }).unwrap())
@@ -120,25 +626,118 @@ fn class_inclusive() {
 
 #[test]
 fn class_exclusive() {
-    let expected = quote! { safe_regex::Matcher0::new(|data: &[u8]| {
-        let mut start = Some(());
-        let mut b0: Option<()> = None;
-        let mut data_iter = data.iter();
-        loop {
-            let prev_b0 = b0.clone();
-            if let Some(b) = data_iter.next() {
-                b0 = start.clone().filter(|_| {
-                    *b != 97u8 && *b != 98u8 && *b != 99u8 && !(50u8..=52u8).contains(b)
-                });
-                start = None;
-                if b0.is_none() {
-                    return None;
+    let expected = quote! {
+        safe_regex::Matcher::new("[^abc2-4]", |data: &[u8], search_start: Option<usize>| {
+            let search_start = match search_start {
+                Some(search_start) => {
+                    let rest = data.get(search_start..)?;
+                    Some(
+                        search_start + {
+                            const FIRST_BYTES: [bool; 256] = [
+                                true, true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, false, false, false, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, false, false, false, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true
+                            ];
+                            safe_regex::internal::find_in_set(&FIRST_BYTES, rest)
+                        }?
+                    )
+                }
+                None => None,
+            };
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 1u8,
+                1u8, 1u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 1u8, 1u8, 1u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8
+            ];
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 6usize] = [0u16, 0u16, 4u16, 2u16, 0u16, 0u16];
+                const SEARCH_ACCEPT: [bool; 3usize] = [false, false, true];
+                const REVERSE: [u16; 6usize] = [0u16, 0u16, 4u16, 0u16, 0u16, 0u16];
+                const REVERSE_ACCEPT: [bool; 3usize] = [false, false, true];
+                let mut state = 2usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 2usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
+                }
+                let end = end?;
+                let mut start = end;
+                let mut state = 2usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 2usize] {
+                        start = n;
+                    }
                 }
+                Some((start..end, []))
             } else {
-                return prev_b0;
+                const ANCHORED: [u16; 6usize] = [0u16, 0u16, 4u16, 0u16, 0u16, 0u16];
+                const ANCHORED_ACCEPT: [bool; 3usize] = [false, false, true];
+                let mut state = 2usize;
+                for b in data {
+                    state = next(&ANCHORED, state, *b);
+                    if state == 0 {
+                        return None;
+                    }
+                }
+                if ANCHORED_ACCEPT[state / 2usize] {
+                    Some((0..data.len(), []))
+                } else {
+                    None
+                }
             }
-        }
-    }) };
+        })
+    };
     assert_eq!(
         format!("{}", expected),
         format!("{}", impl_regex(quote! { br"[^abc2-4]" }).unwrap())
@@ -148,29 +747,100 @@ fn class_exclusive() {
 #[test]
 #[allow(clippy::too_many_lines)]
 fn seq() {
-    let expected = quote! { safe_regex::Matcher0::new(|data: &[u8]| {
-        let mut start = Some(());
-        let mut b0: Option<()> = None;
-        let mut b1: Option<()> = None;
-        let mut b2: Option<()> = None;
-        let mut data_iter = data.iter();
-        loop {
-            let prev_b0 = b0.clone();
-            let prev_b1 = b1.clone();
-            let prev_b2 = b2.clone();
-            if let Some(b) = data_iter.next() {
-                b2 = prev_b1.clone().filter(|_| { *b == 98u8 });
-                b1 = prev_b0.clone().filter(|_| { *b == 97u8 });
-                b0 = start.clone().filter(|_| { *b == 97u8 });
-                start = None;
-                if b0.is_none() && b1.is_none() && b2.is_none() {
-                    return None;
+    let expected = quote! {
+        safe_regex::Matcher::new("aab", |data: &[u8], search_start: Option<usize>| {
+            safe_regex::internal::find_literal(b"aab", data.get(search_start.unwrap_or(0)..)?)?;
+            let search_start = match search_start {
+                Some(search_start) => {
+                    let rest = data.get(search_start..)?;
+                    Some(search_start + safe_regex::internal::find_any_byte(b"a", rest)?)
+                }
+                None => None,
+            };
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 1u8, 2u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8
+            ];
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 15usize] = [
+                    0u16, 0u16, 0u16, 3u16, 6u16, 3u16, 3u16, 9u16, 3u16, 3u16, 9u16, 12u16, 0u16,
+                    0u16, 0u16
+                ];
+                const SEARCH_ACCEPT: [bool; 5usize] = [false, false, false, false, true];
+                const REVERSE: [u16; 15usize] = [
+                    0u16, 0u16, 0u16, 0u16, 0u16, 6u16, 0u16, 9u16, 0u16, 0u16, 12u16, 0u16, 0u16,
+                    0u16, 0u16
+                ];
+                const REVERSE_ACCEPT: [bool; 5usize] = [false, false, false, false, true];
+                let mut state = 3usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 3usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
+                }
+                let end = end?;
+                let mut start = end;
+                let mut state = 3usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 3usize] {
+                        start = n;
+                    }
                 }
+                Some((start..end, []))
             } else {
-                return prev_b2;
+                const ANCHORED: [u16; 15usize] = [
+                    0u16, 0u16, 0u16, 0u16, 6u16, 0u16, 0u16, 9u16, 0u16, 0u16, 0u16, 12u16, 0u16,
+                    0u16, 0u16
+                ];
+                const ANCHORED_ACCEPT: [bool; 5usize] = [false, false, false, false, true];
+                let mut state = 3usize;
+                for b in data {
+                    state = next(&ANCHORED, state, *b);
+                    if state == 0 {
+                        return None;
+                    }
+                }
+                if ANCHORED_ACCEPT[state / 3usize] {
+                    Some((0..data.len(), []))
+                } else {
+                    None
+                }
             }
-        }
-    }) };
+        })
+    };
     assert_eq!(
         format!("{}", expected),
         format!("{}", impl_regex(quote! { br"aab" }).unwrap())
@@ -179,32 +849,94 @@ fn seq() {
 
 #[test]
 fn alt() {
-    let expected = quote! { safe_regex::Matcher0::new(|data: &[u8]| {
-        let mut start = Some(());
-        let mut b0: Option<()> = None;
-        let mut b1: Option<()> = None;
-        let mut data_iter = data.iter();
-        loop {
-            let prev_b0 = b0.clone();
-            let prev_b1 = b1.clone();
-            if let Some(b) = data_iter.next() {
-                b1 = start.clone().filter(|_| {
-                    //
-                    *b == 98u8
-                });
-                b0 = start.clone().filter(|_| {
-                    //
-                    *b == 97u8
-                });
-                start = None;
-                if b0.is_none() && b1.is_none() {
-                    return None;
+    let expected = quote! {
+        safe_regex::Matcher::new("a|b", |data: &[u8], search_start: Option<usize>| {
+            let search_start = match search_start {
+                Some(search_start) => {
+                    let rest = data.get(search_start..)?;
+                    Some(search_start + safe_regex::internal::find_any_byte(b"ab", rest)?)
+                }
+                None => None,
+            };
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 1u8, 2u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8
+            ];
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 9usize] = [0u16, 0u16, 0u16, 3u16, 6u16, 6u16, 0u16, 0u16, 0u16];
+                const SEARCH_ACCEPT: [bool; 3usize] = [false, false, true];
+                const REVERSE: [u16; 12usize] = [
+                    0u16, 0u16, 0u16, 0u16, 6u16, 9u16, 0u16, 0u16, 0u16, 0u16, 0u16, 0u16
+                ];
+                const REVERSE_ACCEPT: [bool; 4usize] = [false, false, true, true];
+                let mut state = 3usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 3usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
+                }
+                let end = end?;
+                let mut start = end;
+                let mut state = 3usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 3usize] {
+                        start = n;
+                    }
                 }
+                Some((start..end, []))
             } else {
-                return None.or_else(|| prev_b0.clone()).or_else(|| prev_b1.clone());
+                const ANCHORED: [u16; 12usize] = [
+                    0u16, 0u16, 0u16, 0u16, 6u16, 9u16, 0u16, 0u16, 0u16, 0u16, 0u16, 0u16
+                ];
+                const ANCHORED_ACCEPT: [bool; 4usize] = [false, false, true, true];
+                let mut state = 3usize;
+                for b in data {
+                    state = next(&ANCHORED, state, *b);
+                    if state == 0 {
+                        return None;
+                    }
+                }
+                if ANCHORED_ACCEPT[state / 3usize] {
+                    Some((0..data.len(), []))
+                } else {
+                    None
+                }
             }
-        }
-    }) };
+        })
+    };
     assert_eq!(
         format!("{}", expected),
         format!("{}", impl_regex(quote! { br"a|b" }).unwrap())
@@ -213,45 +945,106 @@ fn alt() {
 
 #[test]
 fn group() {
-    let expected = quote! { safe_regex::Matcher1::new(|data: &[u8]| {
-        assert!(data.len() < usize::MAX - 2);
-        let mut start = Some((usize::MAX..usize::MAX,));
-        let mut b0: Option<(core::ops::Range<usize>,)> = None;
-        let mut accept: Option<(core::ops::Range<usize>,)> = None;
-        let mut data_iter = data.iter();
-        let mut n = 0;
-        loop {
-            let prev_b0 = b0.clone();
-            accept = prev_b0.clone();
-            if let Some(b) = data_iter.next() {
-                b0 = start
-                    .clone()
-                    .map(|(r0,)| (n..n,))
-                    .clone()
-                    .filter(|_| {
-                        //
-                        *b == 97u8
-                    })
-                    .map(|(r0,)| (r0.start..n + 1,));
-                start = None;
-                if b0.is_none() {
-                    return None;
+    let expected = quote! {
+        safe_regex::Matcher::new("(a)", |data: &[u8], search_start: Option<usize>| {
+            safe_regex::internal::find_literal(b"a", data.get(search_start.unwrap_or(0)..)?)?;
+            let search_start = match search_start {
+                Some(search_start) => {
+                    let rest = data.get(search_start..)?;
+                    Some(search_start + safe_regex::internal::find_any_byte(b"a", rest)?)
+                }
+                None => None,
+            };
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 1u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8
+            ];
+            let one_pass =
+                |data: &[u8], offset: usize| -> Option<[Option<core::ops::Range<usize>>; 1usize]> {
+                    const ONE_PASS: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 0u16];
+                    const ONE_PASS_ACTIONS: [u8; 6usize] = [0u8, 0u8, 0u8, 1u8, 0u8, 0u8];
+                    let mut ranges = [usize::MAX..usize::MAX];
+                    let mut state = 2usize;
+                    for (n, b) in data.iter().enumerate() {
+                        let index = state + usize::from(CLASSES[usize::from(*b)]);
+                        state = usize::from(ONE_PASS[index]);
+                        if state == 0 {
+                            return None;
+                        }
+                        let n = n + offset;
+                        if ONE_PASS_ACTIONS[index] == 1u8 {
+                            ranges[0usize] = n..n + 1;
+                        }
+                    }
+                    if let 4usize = state {
+                    } else {
+                        return None;
+                    }
+                    let [r0] = ranges;
+                    Some([if r0.start == usize::MAX || r0.end == usize::MAX {
+                        None
+                    } else {
+                        Some(r0)
+                    }])
+                };
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 6usize] = [0u16, 0u16, 2u16, 4u16, 0u16, 0u16];
+                const SEARCH_ACCEPT: [bool; 3usize] = [false, false, true];
+                const REVERSE: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 0u16];
+                const REVERSE_ACCEPT: [bool; 3usize] = [false, false, true];
+                let mut state = 2usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 2usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
                 }
+                let end = end?;
+                let mut start = end;
+                let mut state = 2usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 2usize] {
+                        start = n;
+                    }
+                }
+                one_pass(&data[start..end], start).map(|groups| (start..end, groups))
             } else {
-                break;
+                one_pass(data, 0).map(|groups| (0..data.len(), groups))
             }
-            n = n + 1;
-        }
-        accept.map(|(r0,)| {
-            [
-                if r0.start == usize::MAX || r0.end == usize::MAX || r0.is_empty() {
-                    0..0usize
-                } else {
-                    r0
-                },
-            ]
         })
-    }) };
+    };
     assert_eq!(
         format!("{}", expected),
         format!("{}", impl_regex(quote! { br"(a)" }).unwrap())
@@ -260,55 +1053,127 @@ fn group() {
 
 #[test]
 fn groups_nested() {
-    let expected = quote! { safe_regex::Matcher2::new(|data: &[u8]| {
-        assert!(data.len() < usize::MAX - 2);
-        let mut start = Some((usize::MAX..usize::MAX, usize::MAX..usize::MAX));
-        let mut b0: Option<(core::ops::Range<usize>, core::ops::Range<usize>)> = None;
-        let mut b1: Option<(core::ops::Range<usize>, core::ops::Range<usize>)> = None;
-        let mut accept: Option<(core::ops::Range<usize>, core::ops::Range<usize>)> = None;
-        let mut data_iter = data.iter();
-        let mut n = 0;
-        loop {
-            let prev_b0 = b0.clone();
-            let prev_b1 = b1.clone();
-            accept = prev_b1.clone();
-            if let Some(b) = data_iter.next() {
-                b1 = prev_b0
-                    .clone()
-                    .map(|(r0, r1)| (r0, n..n))
-                    .clone()
-                    .filter(|_| { *b == 98u8 })
-                    .map(|(r0, r1)| (r0.start..n + 1, r1.start..n + 1));
-                b0 = start
-                    .clone()
-                    .map(|(r0, r1)| (n..n, r1))
-                    .clone()
-                    .filter(|_| { *b == 97u8})
-                    .map(|(r0, r1)| (r0.start..n + 1, r1));
-                start = None;
-                if b0.is_none() && b1.is_none() {
-                    return None;
+    let expected = quote! {
+        safe_regex::Matcher::new("(a(b))", |data: &[u8], search_start: Option<usize>| {
+            safe_regex::internal::find_literal(b"ab", data.get(search_start.unwrap_or(0)..)?)?;
+            let search_start = match search_start {
+                Some(search_start) => {
+                    let rest = data.get(search_start..)?;
+                    Some(search_start + safe_regex::internal::find_any_byte(b"a", rest)?)
+                }
+                None => None,
+            };
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 1u8, 2u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8
+            ];
+            let one_pass =
+                |data: &[u8], offset: usize| -> Option<[Option<core::ops::Range<usize>>; 2usize]> {
+                    const ONE_PASS: [u16; 12usize] = [
+                        0u16, 0u16, 0u16, 0u16, 6u16, 0u16, 0u16, 0u16, 9u16, 0u16, 0u16, 0u16
+                    ];
+                    const ONE_PASS_ACTIONS: [u8; 12usize] =
+                        [0u8, 0u8, 0u8, 0u8, 1u8, 0u8, 0u8, 0u8, 2u8, 0u8, 0u8, 0u8];
+                    let mut ranges = [usize::MAX..usize::MAX, usize::MAX..usize::MAX];
+                    let mut state = 3usize;
+                    for (n, b) in data.iter().enumerate() {
+                        let index = state + usize::from(CLASSES[usize::from(*b)]);
+                        state = usize::from(ONE_PASS[index]);
+                        if state == 0 {
+                            return None;
+                        }
+                        let n = n + offset;
+                        match ONE_PASS_ACTIONS[index] {
+                            1u8 => {
+                                ranges[0usize] = n..n + 1;
+                            }
+                            2u8 => {
+                                ranges[1usize] = n..n + 1;
+                                ranges[0usize].end = n + 1;
+                            }
+                            _ => {}
+                        }
+                    }
+                    if let 9usize = state {
+                    } else {
+                        return None;
+                    }
+                    let [r0, r1] = ranges;
+                    Some([
+                        if r0.start == usize::MAX || r0.end == usize::MAX {
+                            None
+                        } else {
+                            Some(r0)
+                        },
+                        if r1.start == usize::MAX || r1.end == usize::MAX {
+                            None
+                        } else {
+                            Some(r1)
+                        }
+                    ])
+                };
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 12usize] = [
+                    0u16, 0u16, 0u16, 3u16, 6u16, 3u16, 3u16, 6u16, 9u16, 0u16, 0u16, 0u16
+                ];
+                const SEARCH_ACCEPT: [bool; 4usize] = [false, false, false, true];
+                const REVERSE: [u16; 12usize] = [
+                    0u16, 0u16, 0u16, 0u16, 0u16, 6u16, 0u16, 9u16, 0u16, 0u16, 0u16, 0u16
+                ];
+                const REVERSE_ACCEPT: [bool; 4usize] = [false, false, false, true];
+                let mut state = 3usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 3usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
+                }
+                let end = end?;
+                let mut start = end;
+                let mut state = 3usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 3usize] {
+                        start = n;
+                    }
                 }
+                one_pass(&data[start..end], start).map(|groups| (start..end, groups))
             } else {
-                break;
+                one_pass(data, 0).map(|groups| (0..data.len(), groups))
             }
-            n = n + 1;
-        }
-        accept.map(|(r0, r1)| {
-            [
-                if r0.start == usize::MAX || r0.end == usize::MAX || r0.is_empty() {
-                    0..0usize
-                } else {
-                    r0
-                },
-                if r1.start == usize::MAX || r1.end == usize::MAX || r1.is_empty() {
-                    0..0usize
-                } else {
-                    r1
-                },
-            ]
         })
-    }) };
+    };
     assert_eq!(
         format!("{}", expected),
         format!("{}", impl_regex(quote! { br"(a(b))" }).unwrap())
@@ -317,23 +1182,83 @@ fn groups_nested() {
 
 #[test]
 fn optional() {
-    let expected = quote! { safe_regex::Matcher0::new(|data: &[u8]| {
-        let mut start = Some(());
-        let mut b0: Option<()> = None;
-        let mut data_iter = data.iter();
-        loop {
-            let prev_b0 = b0.clone();
-            if let Some(b) = data_iter.next() {
-                b0 = start.clone().filter(|_| { *b == 97u8 });
-                start = None;
-                if b0.is_none() {
-                    return None;
+    let expected = quote! {
+        safe_regex::Matcher::new("a?", |data: &[u8], search_start: Option<usize>| {
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 1u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8
+            ];
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 0u16];
+                const SEARCH_ACCEPT: [bool; 3usize] = [false, true, true];
+                const REVERSE: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 0u16];
+                const REVERSE_ACCEPT: [bool; 3usize] = [false, true, true];
+                let mut state = 2usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 2usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
+                }
+                let end = end?;
+                let mut start = end;
+                let mut state = 2usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 2usize] {
+                        start = n;
+                    }
                 }
+                Some((start..end, []))
             } else {
-                return start.clone().or_else(|| prev_b0.clone());
+                const ANCHORED: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 0u16];
+                const ANCHORED_ACCEPT: [bool; 3usize] = [false, true, true];
+                let mut state = 2usize;
+                for b in data {
+                    state = next(&ANCHORED, state, *b);
+                    if state == 0 {
+                        return None;
+                    }
+                }
+                if ANCHORED_ACCEPT[state / 2usize] {
+                    Some((0..data.len(), []))
+                } else {
+                    None
+                }
             }
-        }
-    }) };
+        })
+    };
     assert_eq!(
         format!("{}", expected),
         format!("{}", impl_regex(quote! { br"a?" }).unwrap())
@@ -342,30 +1267,91 @@ fn optional() {
 
 #[test]
 fn optional_at_start() {
-    let expected = quote! { safe_regex::Matcher0::new(|data: &[u8]| {
-        let mut start = Some(());
-        let mut b0: Option<()> = None;
-        let mut b1: Option<()> = None;
-        let mut data_iter = data.iter();
-        loop {
-            let prev_b0 = b0.clone();
-            let prev_b1 = b1.clone();
-            if let Some(b) = data_iter.next() {
-                b1 = start
-                    .clone()
-                    .or_else(|| prev_b0.clone())
-                    .clone()
-                    .filter(|_| { *b == 97u8 });
-                b0 = start.clone().filter(|_| { *b == 97u8 });
-                start = None;
-                if b0.is_none() && b1.is_none() {
-                    return None;
+    let expected = quote! {
+        safe_regex::Matcher::new("a?a", |data: &[u8], search_start: Option<usize>| {
+            safe_regex::internal::find_literal(b"a", data.get(search_start.unwrap_or(0)..)?)?;
+            let search_start = match search_start {
+                Some(search_start) => {
+                    let rest = data.get(search_start..)?;
+                    Some(search_start + safe_regex::internal::find_any_byte(b"a", rest)?)
+                }
+                None => None,
+            };
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 1u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8
+            ];
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 8usize] = [0u16, 0u16, 2u16, 4u16, 0u16, 6u16, 0u16, 0u16];
+                const SEARCH_ACCEPT: [bool; 4usize] = [false, false, true, true];
+                const REVERSE: [u16; 8usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 6u16, 0u16, 0u16];
+                const REVERSE_ACCEPT: [bool; 4usize] = [false, false, true, true];
+                let mut state = 2usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 2usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
+                }
+                let end = end?;
+                let mut start = end;
+                let mut state = 2usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 2usize] {
+                        start = n;
+                    }
                 }
+                Some((start..end, []))
             } else {
-                return prev_b1;
+                const ANCHORED: [u16; 8usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 6u16, 0u16, 0u16];
+                const ANCHORED_ACCEPT: [bool; 4usize] = [false, false, true, true];
+                let mut state = 2usize;
+                for b in data {
+                    state = next(&ANCHORED, state, *b);
+                    if state == 0 {
+                        return None;
+                    }
+                }
+                if ANCHORED_ACCEPT[state / 2usize] {
+                    Some((0..data.len(), []))
+                } else {
+                    None
+                }
             }
-        }
-    }) };
+        })
+    };
     assert_eq!(
         format!("{}", expected),
         format!("{}", impl_regex(quote! { br"a?a" }).unwrap())
@@ -374,26 +1360,91 @@ fn optional_at_start() {
 
 #[test]
 fn optional_at_end() {
-    let expected = quote! { safe_regex::Matcher0::new(|data: &[u8]| {
-        let mut start = Some(());
-        let mut b0: Option<()> = None;
-        let mut b1: Option<()> = None;
-        let mut data_iter = data.iter();
-        loop {
-            let prev_b0 = b0.clone();
-            let prev_b1 = b1.clone();
-            if let Some(b) = data_iter.next() {
-                b1 = prev_b0.clone().filter(|_| { *b == 97u8 });
-                b0 = start.clone().filter(|_| { *b == 97u8 });
-                start = None;
-                if b0.is_none() && b1.is_none() {
-                    return None;
+    let expected = quote! {
+        safe_regex::Matcher::new("aa?", |data: &[u8], search_start: Option<usize>| {
+            safe_regex::internal::find_literal(b"a", data.get(search_start.unwrap_or(0)..)?)?;
+            let search_start = match search_start {
+                Some(search_start) => {
+                    let rest = data.get(search_start..)?;
+                    Some(search_start + safe_regex::internal::find_any_byte(b"a", rest)?)
+                }
+                None => None,
+            };
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 1u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8
+            ];
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 8usize] = [0u16, 0u16, 2u16, 4u16, 0u16, 6u16, 0u16, 0u16];
+                const SEARCH_ACCEPT: [bool; 4usize] = [false, false, true, true];
+                const REVERSE: [u16; 8usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 6u16, 0u16, 0u16];
+                const REVERSE_ACCEPT: [bool; 4usize] = [false, false, true, true];
+                let mut state = 2usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 2usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
                 }
+                let end = end?;
+                let mut start = end;
+                let mut state = 2usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 2usize] {
+                        start = n;
+                    }
+                }
+                Some((start..end, []))
             } else {
-                return prev_b0.clone().or_else(|| prev_b1.clone());
+                const ANCHORED: [u16; 8usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 6u16, 0u16, 0u16];
+                const ANCHORED_ACCEPT: [bool; 4usize] = [false, false, true, true];
+                let mut state = 2usize;
+                for b in data {
+                    state = next(&ANCHORED, state, *b);
+                    if state == 0 {
+                        return None;
+                    }
+                }
+                if ANCHORED_ACCEPT[state / 2usize] {
+                    Some((0..data.len(), []))
+                } else {
+                    None
+                }
             }
-        }
-    }) };
+        })
+    };
     assert_eq!(
         format!("{}", expected),
         format!("{}", impl_regex(quote! { br"aa?" }).unwrap())
@@ -402,45 +1453,86 @@ fn optional_at_end() {
 
 #[test]
 fn optionals_in_seq() {
-    let expected = quote! { safe_regex::Matcher0::new(|data: &[u8]| {
-        let mut start = Some(());
-        let mut b0: Option<()> = None;
-        let mut b1: Option<()> = None;
-        let mut b2: Option<()> = None;
-        let mut data_iter = data.iter();
-        loop {
-            let prev_b0 = b0.clone();
-            let prev_b1 = b1.clone();
-            let prev_b2 = b2.clone();
-            if let Some(b) = data_iter.next() {
-                b2 = start
-                    .clone()
-                    .or_else(|| prev_b0.clone())
-                    .clone()
-                    .or_else(|| prev_b1.clone())
-                    .clone()
-                    .filter(|_| { *b == 97u8 });
-                b1 = start
-                    .clone()
-                    .or_else(|| prev_b0.clone())
-                    .clone()
-                    .filter(|_| { *b == 97u8 });
-                b0 = start.clone().filter(|_| { *b == 97u8 });
-                start = None;
-                if b0.is_none() && b1.is_none() && b2.is_none() {
-                    return None;
+    let expected = quote! {
+        safe_regex::Matcher::new("a?a?a?", |data: &[u8], search_start: Option<usize>| {
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 1u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8
+            ];
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 10usize] =
+                    [0u16, 0u16, 0u16, 4u16, 0u16, 6u16, 0u16, 8u16, 0u16, 0u16];
+                const SEARCH_ACCEPT: [bool; 5usize] = [false, true, true, true, true];
+                const REVERSE: [u16; 10usize] =
+                    [0u16, 0u16, 0u16, 4u16, 0u16, 6u16, 0u16, 8u16, 0u16, 0u16];
+                const REVERSE_ACCEPT: [bool; 5usize] = [false, true, true, true, true];
+                let mut state = 2usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 2usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
+                }
+                let end = end?;
+                let mut start = end;
+                let mut state = 2usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 2usize] {
+                        start = n;
+                    }
                 }
+                Some((start..end, []))
             } else {
-                return start
-                    .clone()
-                    .or_else(|| prev_b0.clone())
-                    .clone()
-                    .or_else(|| prev_b1.clone())
-                    .clone()
-                    .or_else(|| prev_b2.clone());
+                const ANCHORED: [u16; 10usize] =
+                    [0u16, 0u16, 0u16, 4u16, 0u16, 6u16, 0u16, 8u16, 0u16, 0u16];
+                const ANCHORED_ACCEPT: [bool; 5usize] = [false, true, true, true, true];
+                let mut state = 2usize;
+                for b in data {
+                    state = next(&ANCHORED, state, *b);
+                    if state == 0 {
+                        return None;
+                    }
+                }
+                if ANCHORED_ACCEPT[state / 2usize] {
+                    Some((0..data.len(), []))
+                } else {
+                    None
+                }
             }
-        }
-    }) };
+        })
+    };
     assert_eq!(
         format!("{}", expected),
         format!("{}", impl_regex(quote! { br"a?a?a?" }).unwrap())
@@ -449,68 +1541,180 @@ fn optionals_in_seq() {
 
 #[test]
 fn optionals_in_groups() {
-    let expected = quote! { safe_regex::Matcher2::new(|data: &[u8]| {
-        assert!(data.len() < usize::MAX - 2);
-        let mut start = Some((usize::MAX..usize::MAX, usize::MAX..usize::MAX));
-        let mut b0: Option<(core::ops::Range<usize>, core::ops::Range<usize>)> = None;
-        let mut b1: Option<(core::ops::Range<usize>, core::ops::Range<usize>)> = None;
-        let mut accept: Option<(core::ops::Range<usize>, core::ops::Range<usize>)> = None;
-        let mut data_iter = data.iter();
-        let mut n = 0;
-        loop {
-            let prev_b0 = b0.clone();
-            let prev_b1 = b1.clone();
-            accept = start
-                .clone()
-                .map(|(r0, r1)| (n..n, r1))
-                .clone()
-                .or_else(|| prev_b0.clone())
-                .clone()
-                .map(|(r0, r1)| (r0, n..n))
-                .clone()
-                .or_else(|| prev_b1.clone())
-                .clone();
-            if let Some(b) = data_iter.next() {
-                b1 = start
-                    .clone()
-                    .map(|(r0, r1)| (n..n, r1))
-                    .clone()
-                    .or_else(|| prev_b0.clone())
-                    .clone()
-                    .map(|(r0, r1)| (r0, n..n))
-                    .clone()
-                    .filter(|_| { *b == 97u8 })
-                    .map(|(r0, r1)| (r0, r1.start..n + 1));
-                b0 = start
-                    .clone()
-                    .map(|(r0, r1)| (n..n, r1))
-                    .clone()
-                    .filter(|_| { *b == 97u8 })
-                    .map(|(r0, r1)| (r0.start..n + 1, r1));
-                start = None;
-                if b0.is_none() && b1.is_none() {
+    let expected = quote! {
+        safe_regex::Matcher::new("(a?)(a?)", |data: &[u8], search_start: Option<usize>| {
+            #[inline(never)]
+            fn search(
+                data: &[u8],
+                search_start: usize,
+            ) -> Option<(
+                core::ops::Range<usize>,
+                [Option<core::ops::Range<usize>>; 2usize]
+            )> {
+                static INSTS: [safe_regex::internal::Inst; 11usize] = [
+                    safe_regex::internal::Inst::Match,
+                    safe_regex::internal::Inst::Save(5usize, 0usize),
+                    safe_regex::internal::Inst::Save(3usize, 1usize),
+                    safe_regex::internal::Inst::Byte(0usize, 2usize),
+                    safe_regex::internal::Inst::Split(3usize, 2usize),
+                    safe_regex::internal::Inst::Save(2usize, 4usize),
+                    safe_regex::internal::Inst::Save(1usize, 5usize),
+                    safe_regex::internal::Inst::Byte(0usize, 6usize),
+                    safe_regex::internal::Inst::Split(7usize, 6usize),
+                    safe_regex::internal::Inst::Save(0usize, 8usize),
+                    safe_regex::internal::Inst::Save(4usize, 9usize)
+                ];
+                static IDS: [usize; 11usize] = [
+                    0usize, 1usize, 2usize, 3usize, 4usize, 5usize, 6usize, 7usize, 8usize, 9usize,
+                    10usize
+                ];
+                static SETS: [[bool; 256]; 1usize] = [[
+                    false, false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false, false,
+                    false, true, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false
+                ]];
+                let nfa = safe_regex::internal::Nfa {
+                    insts: &INSTS,
+                    ids: &IDS,
+                    sets: &SETS,
+                    start: 10usize,
+                };
+                let mut memory = [0_usize; safe_regex::internal::search_memory_len(11usize, 6usize)];
+                let mut slots = [usize::MAX; 6usize];
+                if !safe_regex::internal::search(&nfa, data, search_start, &mut memory, &mut slots,) {
                     return None;
                 }
+                Some((
+                    slots[4usize]..slots[5usize],
+                    [
+                        if slots[0usize] == usize::MAX || slots[1usize] == usize::MAX {
+                            None
+                        } else {
+                            Some(slots[0usize]..slots[1usize])
+                        },
+                        if slots[2usize] == usize::MAX || slots[3usize] == usize::MAX {
+                            None
+                        } else {
+                            Some(slots[2usize]..slots[3usize])
+                        }
+                    ]
+                ))
+            }
+            static BYTES0: [bool; 256] = [
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, true, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false
+            ];
+            if let Some(search_start) = search_start {
+                search(data, search_start)
             } else {
-                break;
+                assert!(data.len() < usize::MAX - 2);
+                let mut start = Some((usize::MAX..usize::MAX, usize::MAX..usize::MAX));
+                let mut b0: Option<(core::ops::Range<usize>, core::ops::Range<usize>)> = None;
+                let mut b1: Option<(core::ops::Range<usize>, core::ops::Range<usize>)> = None;
+                let mut accept: Option<(core::ops::Range<usize>, core::ops::Range<usize>)> = None;
+                let mut data_iter = data.iter();
+                let mut n = 0;
+                loop {
+                    let prev_b0 = b0.clone();
+                    let prev_b1 = b1.clone();
+                    accept = start
+                        .clone()
+                        .map(|(r0, r1)| (n..n, r1))
+                        .clone()
+                        .or_else(|| prev_b0.clone())
+                        .clone()
+                        .map(|(r0, r1)| (r0, n..n))
+                        .clone()
+                        .or_else(|| prev_b1.clone())
+                        .clone();
+                    if let Some(b) = data_iter.next() {
+                        b1 = if BYTES0[usize::from(*b)] {
+                            start
+                                .clone()
+                                .map(|(r0, r1)| (n..n, r1))
+                                .clone()
+                                .or_else(|| prev_b0.clone())
+                                .clone()
+                                .map(|(r0, r1)| (r0, n..n))
+                                .clone()
+                        } else {
+                            None
+                        }
+                        .map(|(r0, r1)| (r0, r1.start..n + 1));
+                        b0 = if BYTES0[usize::from(*b)] {
+                            start.clone().map(|(r0, r1)| (n..n, r1)).clone()
+                        } else {
+                            None
+                        }
+                        .map(|(r0, r1)| (r0.start..n + 1, r1));
+                        start = None;
+                        if b0.is_none() && b1.is_none() {
+                            return None;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
+                }
+                accept.map(|(r0, r1)| {
+                    (
+                        0..data.len(),
+                        [
+                            if r0.start == usize::MAX || r0.end == usize::MAX {
+                                None
+                            } else {
+                                Some(r0)
+                            },
+                            if r1.start == usize::MAX || r1.end == usize::MAX {
+                                None
+                            } else {
+                                Some(r1)
+                            }
+                        ]
+                    )
+                })
             }
-            n = n + 1;
-        }
-        accept.map(|(r0, r1)| {
-            [
-                if r0.start == usize::MAX || r0.end == usize::MAX || r0.is_empty() {
-                    0..0usize
-                } else {
-                    r0
-                },
-                if r1.start == usize::MAX || r1.end == usize::MAX || r1.is_empty() {
-                    0..0usize
-                } else {
-                    r1
-                },
-            ]
         })
-    }) };
+    };
     assert_eq!(
         format!("{}", expected),
         format!("{}", impl_regex(quote! { br"(a?)(a?)" }).unwrap())
@@ -519,27 +1723,83 @@ fn optionals_in_groups() {
 
 #[test]
 fn star() {
-    let expected = quote! { safe_regex::Matcher0::new(|data: &[u8]| {
-        let mut start = Some(());
-        let mut b0: Option<()> = None;
-        let mut data_iter = data.iter();
-        loop {
-            let prev_b0 = b0.clone();
-            if let Some(b) = data_iter.next() {
-                b0 = start
-                    .clone()
-                    .or_else(|| prev_b0.clone())
-                    .clone()
-                    .filter(|_| { *b == 97u8 });
-                start = None;
-                if b0.is_none() {
-                    return None;
+    let expected = quote! {
+        safe_regex::Matcher::new("a*", |data: &[u8], search_start: Option<usize>| {
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 1u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8
+            ];
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 4u16];
+                const SEARCH_ACCEPT: [bool; 3usize] = [false, true, true];
+                const REVERSE: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 4u16];
+                const REVERSE_ACCEPT: [bool; 3usize] = [false, true, true];
+                let mut state = 2usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 2usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
+                }
+                let end = end?;
+                let mut start = end;
+                let mut state = 2usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 2usize] {
+                        start = n;
+                    }
                 }
+                Some((start..end, []))
             } else {
-                return start.clone().or_else(|| prev_b0.clone());
+                const ANCHORED: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 4u16];
+                const ANCHORED_ACCEPT: [bool; 3usize] = [false, true, true];
+                let mut state = 2usize;
+                for b in data {
+                    state = next(&ANCHORED, state, *b);
+                    if state == 0 {
+                        return None;
+                    }
+                }
+                if ANCHORED_ACCEPT[state / 2usize] {
+                    Some((0..data.len(), []))
+                } else {
+                    None
+                }
             }
-        }
-    }) };
+        })
+    };
     assert_eq!(
         format!("{}", expected),
         format!("{}", impl_regex(quote! { br"a*" }).unwrap())
@@ -548,67 +1808,102 @@ fn star() {
 
 #[test]
 fn group_star1() {
-    let expected = quote! { safe_regex::Matcher1::new(|data: &[u8]| {
-        assert!(data.len() < usize::MAX - 2);
-        let mut start = Some((usize::MAX..usize::MAX,));
-        let mut b0: Option<(core::ops::Range<usize>,)> = None;
-        let mut accept: Option<(core::ops::Range<usize>,)> = None;
-        let mut data_iter = data.iter();
-        let mut n = 0;
-        loop {
-            let prev_b0 = b0.clone();
-            accept = start
-                .clone()
-                .or_else(|| start
-                        .clone()
-                        .or_else(|| start
-                                .clone()
-                                .map(|(r0,)| (n..n,))
-                                .clone()
-                                .or_else(|| prev_b0.clone())
-                                .clone()
-                        )
-                        .clone()
-                        .map(|(r0,)| (n..n,))
-                        .clone()
-                        .or_else(|| prev_b0.clone())
-                        .clone()
-                )
-                .clone();
-            if let Some(b) = data_iter.next() {
-                b0 = start
-                    .clone()
-                    .or_else(|| start
-                            .clone()
-                            .map(|(r0,)| (n..n,))
-                            .clone()
-                            .or_else(|| prev_b0.clone())
-                            .clone()
-                    )
-                    .clone()
-                    .map(|(r0,)| (n..n,))
-                    .clone()
-                    .filter(|_| { *b == 97u8 })
-                    .map(|(r0,)| (r0.start..n + 1,));
-                start = None;
-                if b0.is_none() {
-                    return None;
+    let expected = quote! {
+        safe_regex::Matcher::new("(a?)*", |data: &[u8], search_start: Option<usize>| {
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 1u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8
+            ];
+            let one_pass =
+                |data: &[u8], offset: usize| -> Option<[Option<core::ops::Range<usize>>; 1usize]> {
+                    const ONE_PASS: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 4u16];
+                    const ONE_PASS_ACTIONS: [u8; 6usize] = [0u8, 0u8, 0u8, 1u8, 0u8, 1u8];
+                    let mut ranges = [usize::MAX..usize::MAX];
+                    let mut state = 2usize;
+                    for (n, b) in data.iter().enumerate() {
+                        let index = state + usize::from(CLASSES[usize::from(*b)]);
+                        state = usize::from(ONE_PASS[index]);
+                        if state == 0 {
+                            return None;
+                        }
+                        let n = n + offset;
+                        if ONE_PASS_ACTIONS[index] == 1u8 {
+                            ranges[0usize] = n..n + 1;
+                        }
+                    }
+                    let n = data.len() + offset;
+                    match state {
+                        2usize => {}
+                        4usize => {
+                            ranges[0usize] = n..n;
+                        }
+                        _ => return None,
+                    }
+                    let [r0] = ranges;
+                    Some([if r0.start == usize::MAX || r0.end == usize::MAX {
+                        None
+                    } else {
+                        Some(r0)
+                    }])
+                };
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 4u16];
+                const SEARCH_ACCEPT: [bool; 3usize] = [false, true, true];
+                const REVERSE: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 4u16];
+                const REVERSE_ACCEPT: [bool; 3usize] = [false, true, true];
+                let mut state = 2usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 2usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
+                }
+                let end = end?;
+                let mut start = end;
+                let mut state = 2usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 2usize] {
+                        start = n;
+                    }
                 }
+                one_pass(&data[start..end], start).map(|groups| (start..end, groups))
             } else {
-                break;
+                one_pass(data, 0).map(|groups| (0..data.len(), groups))
             }
-            n = n + 1;
-        }
-        accept.map(|(r0,)| {
-            [
-                if r0.start == usize::MAX || r0.end == usize::MAX || r0.is_empty() {
-                    0..0usize
-                } else {
-                    r0
-                },
-            ]
         })
-    }) };
+    };
     assert_eq!(
         format!("{}", expected),
         format!("{}", impl_regex(quote! { br"(a?)*" }).unwrap())
@@ -617,58 +1912,116 @@ fn group_star1() {
 
 #[test]
 fn group_star2() {
-    let expected = quote! { safe_regex::Matcher1::new(|data: &[u8]| {
-        assert!(data.len() < usize::MAX - 2);
-        let mut start = Some((usize::MAX..usize::MAX,));
-        let mut b0: Option<(core::ops::Range<usize>,)> = None;
-        let mut b1: Option<(core::ops::Range<usize>,)> = None;
-        let mut b2: Option<(core::ops::Range<usize>,)> = None;
-        let mut accept: Option<(core::ops::Range<usize>,)> = None;
-        let mut data_iter = data.iter();
-        let mut n = 0;
-        loop {
-            let prev_b0 = b0.clone();
-            let prev_b1 = b1.clone();
-            let prev_b2 = b2.clone();
-            accept = start.clone().or_else(|| prev_b2.clone()).clone();
-            if let Some(b) = data_iter.next() {
-                b2 = prev_b0
-                    .clone()
-                    .or_else(|| prev_b1.clone())
-                    .clone()
-                    .filter(|_| { *b == 99u8 })
-                    .map(|(r0,)| (r0.start..n + 1,));
-                b1 = prev_b0
-                    .clone()
-                    .filter(|_| { *b == 98u8 })
-                    .map(|(r0,)| (r0.start..n + 1,));
-                b0 = start
-                    .clone()
-                    .or_else(|| prev_b2.clone())
-                    .clone()
-                    .map(|(r0,)| (n..n,))
-                    .clone()
-                    .filter(|_| { *b == 97u8 })
-                    .map(|(r0,)| (r0.start..n + 1,));
-                start = None;
-                if b0.is_none() && b1.is_none() && b2.is_none() {
-                    return None;
+    let expected = quote! {
+        safe_regex::Matcher::new("(ab?c)*", |data: &[u8], search_start: Option<usize>| {
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 1u8, 2u8, 3u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8
+            ];
+            let one_pass =
+                |data: &[u8], offset: usize| -> Option<[Option<core::ops::Range<usize>>; 1usize]> {
+                    const ONE_PASS: [u16; 20usize] = [
+                        0u16, 0u16, 0u16, 0u16, 0u16, 8u16, 0u16, 0u16, 0u16, 0u16, 12u16, 16u16, 0u16,
+                        0u16, 0u16, 16u16, 0u16, 8u16, 0u16, 0u16
+                    ];
+                    const ONE_PASS_ACTIONS: [u8; 20usize] = [
+                        0u8, 0u8, 0u8, 0u8, 0u8, 1u8, 0u8, 0u8, 0u8, 0u8, 2u8, 2u8, 0u8, 0u8, 0u8, 2u8,
+                        0u8, 1u8, 0u8, 0u8
+                    ];
+                    let mut ranges = [usize::MAX..usize::MAX];
+                    let mut state = 4usize;
+                    for (n, b) in data.iter().enumerate() {
+                        let index = state + usize::from(CLASSES[usize::from(*b)]);
+                        state = usize::from(ONE_PASS[index]);
+                        if state == 0 {
+                            return None;
+                        }
+                        let n = n + offset;
+                        match ONE_PASS_ACTIONS[index] {
+                            1u8 => {
+                                ranges[0usize] = n..n + 1;
+                            }
+                            2u8 => {
+                                ranges[0usize].end = n + 1;
+                            }
+                            _ => {}
+                        }
+                    }
+                    if let 4usize | 16usize = state {
+                    } else {
+                        return None;
+                    }
+                    let [r0] = ranges;
+                    Some([if r0.start == usize::MAX || r0.end == usize::MAX {
+                        None
+                    } else {
+                        Some(r0)
+                    }])
+                };
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 20usize] = [
+                    0u16, 0u16, 0u16, 0u16, 0u16, 8u16, 0u16, 0u16, 0u16, 0u16, 12u16, 16u16, 0u16,
+                    0u16, 0u16, 16u16, 0u16, 8u16, 0u16, 0u16
+                ];
+                const SEARCH_ACCEPT: [bool; 5usize] = [false, true, false, false, true];
+                const REVERSE: [u16; 20usize] = [
+                    0u16, 0u16, 0u16, 0u16, 0u16, 0u16, 0u16, 8u16, 0u16, 12u16, 16u16, 0u16, 0u16,
+                    0u16, 0u16, 8u16, 0u16, 12u16, 0u16, 0u16
+                ];
+                const REVERSE_ACCEPT: [bool; 5usize] = [false, true, false, true, false];
+                let mut state = 4usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 4usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
                 }
+                let end = end?;
+                let mut start = end;
+                let mut state = 4usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 4usize] {
+                        start = n;
+                    }
+                }
+                one_pass(&data[start..end], start).map(|groups| (start..end, groups))
             } else {
-                break;
+                one_pass(data, 0).map(|groups| (0..data.len(), groups))
             }
-            n = n + 1;
-        }
-        accept.map(|(r0,)| {
-            [
-                if r0.start == usize::MAX || r0.end == usize::MAX || r0.is_empty() {
-                    0..0usize
-                } else {
-                    r0
-                },
-            ]
         })
-    }) };
+    };
     assert_eq!(
         format!("{}", expected),
         format!("{}", impl_regex(quote! { br"(ab?c)*" }).unwrap())
@@ -677,33 +2030,92 @@ fn group_star2() {
 
 #[test]
 fn seq_in_star() {
-    let expected = quote! { safe_regex::Matcher0::new(|data: &[u8]| {
-        let mut start = Some(());
-        let mut b0: Option<()> = None;
-        let mut b1: Option<()> = None;
-        let mut b2: Option<()> = None;
-        let mut data_iter = data.iter();
-        loop {
-            let prev_b0 = b0.clone();
-            let prev_b1 = b1.clone();
-            let prev_b2 = b2.clone();
-            if let Some(b) = data_iter.next() {
-                b2 = prev_b1.clone().filter(|_| { *b == 99u8 });
-                b1 = prev_b0.clone().filter(|_| { *b == 98u8 });
-                b0 = start
-                    .clone()
-                    .or_else(|| prev_b2.clone())
-                    .clone()
-                    .filter(|_| { *b == 97u8 });
-                start = None;
-                if b0.is_none() && b1.is_none() && b2.is_none() {
-                    return None;
+    let expected = quote! {
+        safe_regex::Matcher::new("(?:abc)*", |data: &[u8], search_start: Option<usize>| {
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 1u8, 2u8, 3u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8
+            ];
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 20usize] = [
+                    0u16, 0u16, 0u16, 0u16, 0u16, 8u16, 0u16, 0u16, 0u16, 0u16, 12u16, 0u16, 0u16,
+                    0u16, 0u16, 16u16, 0u16, 8u16, 0u16, 0u16
+                ];
+                const SEARCH_ACCEPT: [bool; 5usize] = [false, true, false, false, true];
+                const REVERSE: [u16; 20usize] = [
+                    0u16, 0u16, 0u16, 0u16, 0u16, 0u16, 0u16, 8u16, 0u16, 0u16, 12u16, 0u16, 0u16,
+                    16u16, 0u16, 0u16, 0u16, 0u16, 0u16, 8u16
+                ];
+                const REVERSE_ACCEPT: [bool; 5usize] = [false, true, false, false, true];
+                let mut state = 4usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 4usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
+                }
+                let end = end?;
+                let mut start = end;
+                let mut state = 4usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 4usize] {
+                        start = n;
+                    }
                 }
+                Some((start..end, []))
             } else {
-                return start.clone().or_else(|| prev_b2.clone());
+                const ANCHORED: [u16; 20usize] = [
+                    0u16, 0u16, 0u16, 0u16, 0u16, 8u16, 0u16, 0u16, 0u16, 0u16, 12u16, 0u16, 0u16,
+                    0u16, 0u16, 16u16, 0u16, 8u16, 0u16, 0u16
+                ];
+                const ANCHORED_ACCEPT: [bool; 5usize] = [false, true, false, false, true];
+                let mut state = 4usize;
+                for b in data {
+                    state = next(&ANCHORED, state, *b);
+                    if state == 0 {
+                        return None;
+                    }
+                }
+                if ANCHORED_ACCEPT[state / 4usize] {
+                    Some((0..data.len(), []))
+                } else {
+                    None
+                }
             }
-        }
-    }) };
+        })
+    };
     assert_eq!(
         format!("{}", expected),
         format!("{}", impl_regex(quote! { br"(?:abc)*" }).unwrap())
@@ -712,57 +2124,127 @@ fn seq_in_star() {
 
 #[test]
 fn seq_in_group() {
-    let expected = quote! { safe_regex::Matcher1::new(|data: &[u8]| {
-        assert!(data.len() < usize::MAX - 2);
-        let mut start = Some((usize::MAX..usize::MAX,));
-        let mut b0: Option<(core::ops::Range<usize>,)> = None;
-        let mut b1: Option<(core::ops::Range<usize>,)> = None;
-        let mut b2: Option<(core::ops::Range<usize>,)> = None;
-        let mut b3: Option<(core::ops::Range<usize>,)> = None;
-        let mut accept: Option<(core::ops::Range<usize>,)> = None;
-        let mut data_iter = data.iter();
-        let mut n = 0;
-        loop {
-            let prev_b0 = b0.clone();
-            let prev_b1 = b1.clone();
-            let prev_b2 = b2.clone();
-            let prev_b3 = b3.clone();
-            accept = prev_b3.clone();
-            if let Some(b) = data_iter.next() {
-                b3 = prev_b2.clone().filter(|_| { *b == 100u8 });
-                b2 = prev_b1
-                    .clone()
-                    .filter(|_| { *b == 99u8 })
-                    .map(|(r0,)| (r0.start..n + 1,));
-                b1 = prev_b0
-                    .clone()
-                    .filter(|_| { *b == 98u8 })
-                    .map(|(r0,)| (r0.start..n + 1,));
-                b0 = start
-                    .clone()
-                    .map(|(r0,)| (n..n,))
-                    .clone()
-                    .filter(|_| { *b == 97u8 })
-                    .map(|(r0,)| (r0.start..n + 1,));
-                start = None;
-                if b0.is_none() && b1.is_none() && b2.is_none() && b3.is_none() {
-                    return None;
+    let expected = quote! {
+        safe_regex::Matcher::new("(abc)d", |data: &[u8], search_start: Option<usize>| {
+            safe_regex::internal::find_literal(b"abcd", data.get(search_start.unwrap_or(0)..)?)?;
+            let search_start = match search_start {
+                Some(search_start) => {
+                    let rest = data.get(search_start..)?;
+                    Some(search_start + safe_regex::internal::find_any_byte(b"a", rest)?)
+                }
+                None => None,
+            };
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 1u8, 2u8, 3u8, 4u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8
+            ];
+            let one_pass =
+                |data: &[u8], offset: usize| -> Option<[Option<core::ops::Range<usize>>; 1usize]> {
+                    const ONE_PASS: [u16; 30usize] = [
+                        0u16, 0u16, 0u16, 0u16, 0u16, 0u16, 10u16, 0u16, 0u16, 0u16, 0u16, 0u16, 15u16,
+                        0u16, 0u16, 0u16, 0u16, 0u16, 20u16, 0u16, 0u16, 0u16, 0u16, 0u16, 25u16, 0u16,
+                        0u16, 0u16, 0u16, 0u16
+                    ];
+                    const ONE_PASS_ACTIONS: [u8; 30usize] = [
+                        0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 1u8, 0u8, 0u8, 0u8, 0u8, 0u8, 2u8, 0u8, 0u8, 0u8,
+                        0u8, 0u8, 2u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8
+                    ];
+                    let mut ranges = [usize::MAX..usize::MAX];
+                    let mut state = 5usize;
+                    for (n, b) in data.iter().enumerate() {
+                        let index = state + usize::from(CLASSES[usize::from(*b)]);
+                        state = usize::from(ONE_PASS[index]);
+                        if state == 0 {
+                            return None;
+                        }
+                        let n = n + offset;
+                        match ONE_PASS_ACTIONS[index] {
+                            1u8 => {
+                                ranges[0usize] = n..n + 1;
+                            }
+                            2u8 => {
+                                ranges[0usize].end = n + 1;
+                            }
+                            _ => {}
+                        }
+                    }
+                    if let 25usize = state {
+                    } else {
+                        return None;
+                    }
+                    let [r0] = ranges;
+                    Some([if r0.start == usize::MAX || r0.end == usize::MAX {
+                        None
+                    } else {
+                        Some(r0)
+                    }])
+                };
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 30usize] = [
+                    0u16, 0u16, 0u16, 0u16, 0u16, 5u16, 10u16, 5u16, 5u16, 5u16, 5u16, 10u16, 15u16,
+                    5u16, 5u16, 5u16, 10u16, 5u16, 20u16, 5u16, 5u16, 10u16, 5u16, 5u16, 25u16, 0u16,
+                    0u16, 0u16, 0u16, 0u16
+                ];
+                const SEARCH_ACCEPT: [bool; 6usize] = [false, false, false, false, false, true];
+                const REVERSE: [u16; 30usize] = [
+                    0u16, 0u16, 0u16, 0u16, 0u16, 0u16, 0u16, 0u16, 0u16, 10u16, 0u16, 0u16, 0u16,
+                    15u16, 0u16, 0u16, 0u16, 20u16, 0u16, 0u16, 0u16, 25u16, 0u16, 0u16, 0u16, 0u16,
+                    0u16, 0u16, 0u16, 0u16
+                ];
+                const REVERSE_ACCEPT: [bool; 6usize] = [false, false, false, false, false, true];
+                let mut state = 5usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 5usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
                 }
+                let end = end?;
+                let mut start = end;
+                let mut state = 5usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 5usize] {
+                        start = n;
+                    }
+                }
+                one_pass(&data[start..end], start).map(|groups| (start..end, groups))
             } else {
-                break;
+                one_pass(data, 0).map(|groups| (0..data.len(), groups))
             }
-            n = n + 1;
-        }
-        accept.map(|(r0,)| {
-            [
-                if r0.start == usize::MAX || r0.end == usize::MAX || r0.is_empty() {
-                    0..0usize
-                } else {
-                    r0
-                },
-            ]
         })
-    }) };
+    };
     assert_eq!(
         format!("{}", expected),
         format!("{}", impl_regex(quote! { br"(abc)d" }).unwrap())
@@ -771,61 +2253,272 @@ fn seq_in_group() {
 
 #[test]
 fn alt_in_group() {
-    let expected = quote! { safe_regex::Matcher1::new(|data: &[u8]| {
-        assert!(data.len() < usize::MAX - 2);
-        let mut start = Some((usize::MAX..usize::MAX,));
-        let mut b0: Option<(core::ops::Range<usize>,)> = None;
-        let mut b1: Option<(core::ops::Range<usize>,)> = None;
-        let mut accept: Option<(core::ops::Range<usize>,)> = None;
-        let mut data_iter = data.iter();
-        let mut n = 0;
-        loop {
-            let prev_b0 = b0.clone();
-            let prev_b1 = b1.clone();
-            accept = None
-                .or_else(|| prev_b0.clone())
-                .or_else(|| prev_b1.clone())
-                .clone();
-            if let Some(b) = data_iter.next() {
-                b1 = start
-                    .clone()
-                    .map(|(r0,)| (n..n,))
-                    .clone()
-                    .filter(|_| {
-                        //
-                        *b == 98u8
-                    })
-                    .map(|(r0,)| (r0.start..n + 1,));
-                b0 = start
-                    .clone()
-                    .map(|(r0,)| (n..n,))
-                    .clone()
-                    .filter(|_| {
-                        //
-                        *b == 97u8
-                    })
-                    .map(|(r0,)| (r0.start..n + 1,));
-                start = None;
-                if b0.is_none() && b1.is_none() {
-                    return None;
+    let expected = quote! {
+        safe_regex::Matcher::new("(a|b)", |data: &[u8], search_start: Option<usize>| {
+            let search_start = match search_start {
+                Some(search_start) => {
+                    let rest = data.get(search_start..)?;
+                    Some(search_start + safe_regex::internal::find_any_byte(b"ab", rest)?)
+                }
+                None => None,
+            };
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 1u8, 2u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8
+            ];
+            let one_pass =
+                |data: &[u8], offset: usize| -> Option<[Option<core::ops::Range<usize>>; 1usize]> {
+                    const ONE_PASS: [u16; 12usize] = [
+                        0u16, 0u16, 0u16, 0u16, 6u16, 9u16, 0u16, 0u16, 0u16, 0u16, 0u16, 0u16
+                    ];
+                    const ONE_PASS_ACTIONS: [u8; 12usize] =
+                        [0u8, 0u8, 0u8, 0u8, 1u8, 1u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8];
+                    let mut ranges = [usize::MAX..usize::MAX];
+                    let mut state = 3usize;
+                    for (n, b) in data.iter().enumerate() {
+                        let index = state + usize::from(CLASSES[usize::from(*b)]);
+                        state = usize::from(ONE_PASS[index]);
+                        if state == 0 {
+                            return None;
+                        }
+                        let n = n + offset;
+                        if ONE_PASS_ACTIONS[index] == 1u8 {
+                            ranges[0usize] = n..n + 1;
+                        }
+                    }
+                    if let 6usize | 9usize = state {
+                    } else {
+                        return None;
+                    }
+                    let [r0] = ranges;
+                    Some([if r0.start == usize::MAX || r0.end == usize::MAX {
+                        None
+                    } else {
+                        Some(r0)
+                    }])
+                };
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 9usize] = [0u16, 0u16, 0u16, 3u16, 6u16, 6u16, 0u16, 0u16, 0u16];
+                const SEARCH_ACCEPT: [bool; 3usize] = [false, false, true];
+                const REVERSE: [u16; 12usize] = [
+                    0u16, 0u16, 0u16, 0u16, 6u16, 9u16, 0u16, 0u16, 0u16, 0u16, 0u16, 0u16
+                ];
+                const REVERSE_ACCEPT: [bool; 4usize] = [false, false, true, true];
+                let mut state = 3usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 3usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
+                }
+                let end = end?;
+                let mut start = end;
+                let mut state = 3usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 3usize] {
+                        start = n;
+                    }
                 }
+                one_pass(&data[start..end], start).map(|groups| (start..end, groups))
             } else {
-                break;
+                one_pass(data, 0).map(|groups| (0..data.len(), groups))
             }
-            n = n + 1;
-        }
-        accept.map(|(r0,)| {
-            [
-                if r0.start == usize::MAX || r0.end == usize::MAX || r0.is_empty() {
-                    0..0usize
-                } else {
-                    r0
-                },
-            ]
         })
-    }) };
+    };
     assert_eq!(
         format!("{}", expected),
         format!("{}", impl_regex(quote! { br"(a|b)" }).unwrap())
     );
 }
+
+#[test]
+fn named_group() {
+    let expected = quote! {
+        {
+            struct NamedMatcher<M> {
+                matcher: M,
+            }
+            impl<M> core::ops::Deref for NamedMatcher<M> {
+                type Target = M;
+                fn deref(&self) -> &M {
+                    &self.matcher
+                }
+            }
+            impl<M: safe_regex::IsMatch> safe_regex::IsMatch for NamedMatcher<M> {
+                fn is_match(&self, data: &[u8]) -> bool {
+                    self.matcher.is_match(data)
+                }
+            }
+            impl<M: safe_regex::Regex> safe_regex::Regex for NamedMatcher<M> {
+                fn pattern(&self) -> &str {
+                    self.matcher.pattern()
+                }
+                fn group_count(&self) -> usize {
+                    self.matcher.group_count()
+                }
+                fn match_ranges_into(
+                    &self,
+                    data: &[u8],
+                    ranges: &mut [core::ops::Range<usize>],
+                ) -> bool {
+                    self.matcher.match_ranges_into(data, ranges)
+                }
+            }
+            #[allow(dead_code)]
+            impl<
+                    F: Fn(
+                        &[u8],
+                        Option<usize>
+                    ) -> Option<(
+                        core::ops::Range<usize>,
+                        [Option<core::ops::Range<usize>>; 1usize]
+                    )>
+                > NamedMatcher<safe_regex::Matcher<F, 1usize>>
+            {
+                #[must_use]
+                pub fn match_ranges(&self, data: &[u8]) -> Option<NamedRanges> {
+                    Some(NamedRanges(self.matcher.match_ranges(data)?))
+                }
+                #[must_use]
+                pub fn match_slices<'d>(&self, data: &'d [u8]) -> Option<NamedSlices<'d>> {
+                    Some(NamedSlices(self.matcher.match_slices(data)?))
+                }
+            }
+            #[derive(Clone, Debug, PartialEq, Eq)]
+            struct NamedRanges([core::ops::Range<usize>; 1usize]);
+            #[allow(dead_code)]
+            impl NamedRanges {
+                #[must_use]
+                pub fn x(&self) -> core::ops::Range<usize> {
+                    self.0[0usize].clone()
+                }
+            }
+            #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+            struct NamedSlices<'d>([&'d [u8]; 1usize]);
+            #[allow(dead_code)]
+            impl<'d> NamedSlices<'d> {
+                #[must_use]
+                pub fn x(&self) -> &'d [u8] {
+                    self.0[0usize]
+                }
+            }
+            NamedMatcher {
+                matcher: safe_regex::Matcher::new(
+                    "(?P<x>a)",
+                    |data: &[u8], search_start: Option<usize>| {
+                        safe_regex::internal::find_literal(
+                            b"a",
+                            data.get(search_start.unwrap_or(0)..)?
+                        )?;
+                        let search_start = match search_start {
+                            Some(search_start) => {
+                                let rest = data.get(search_start..)?;
+                                Some(search_start + safe_regex::internal::find_any_byte(b"a", rest)?)
+                            }
+                            None => None,
+                        };
+                        const CLASSES: [u8; 256] = [
+                            0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                            0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                            0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                            0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                            0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                            0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                            0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 1u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                            0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                            0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                            0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                            0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                            0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                            0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                            0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                            0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                            0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                            0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                            0u8
+                        ];
+                        let one_pass = | data : & [u8] , offset : usize | -> Option < [Option < core :: ops :: Range < usize >> ; 1usize] > { const ONE_PASS : [u16 ; 6usize] = [0u16 , 0u16 , 0u16 , 4u16 , 0u16 , 0u16] ; const ONE_PASS_ACTIONS : [u8 ; 6usize] = [0u8 , 0u8 , 0u8 , 1u8 , 0u8 , 0u8] ; let mut ranges = [usize :: MAX .. usize :: MAX] ; let mut state = 2usize ; for (n , b) in data . iter () . enumerate () { let index = state + usize :: from (CLASSES [usize :: from (* b)]) ; state = usize :: from (ONE_PASS [index]) ; if state == 0 { return None ; } let n = n + offset ; if ONE_PASS_ACTIONS [index] == 1u8 { ranges [0usize] = n .. n + 1 ; } } if let 4usize = state { } else { return None ; } let [r0] = ranges ; Some ([if r0 . start == usize :: MAX || r0 . end == usize :: MAX { None } else { Some (r0) }]) } ;
+                        let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                            usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+                        };
+                        if let Some(search_start) = search_start {
+                            const SEARCH: [u16; 6usize] = [0u16, 0u16, 2u16, 4u16, 0u16, 0u16];
+                            const SEARCH_ACCEPT: [bool; 3usize] = [false, false, true];
+                            const REVERSE: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 0u16];
+                            const REVERSE_ACCEPT: [bool; 3usize] = [false, false, true];
+                            let mut state = 2usize;
+                            let mut end = None;
+                            let mut n = search_start;
+                            let mut data_iter = data.get(search_start..)?.iter();
+                            loop {
+                                if SEARCH_ACCEPT[state / 2usize] {
+                                    end = Some(n);
+                                }
+                                if let Some(b) = data_iter.next() {
+                                    state = next(&SEARCH, state, *b);
+                                    if state == 0 {
+                                        break;
+                                    }
+                                } else {
+                                    break;
+                                }
+                                n += 1;
+                            }
+                            let end = end?;
+                            let mut start = end;
+                            let mut state = 2usize;
+                            for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                                state = next(&REVERSE, state, *b);
+                                if state == 0 {
+                                    break;
+                                }
+                                if REVERSE_ACCEPT[state / 2usize] {
+                                    start = n;
+                                }
+                            }
+                            one_pass(&data[start..end], start).map(|groups| (start..end, groups))
+                        } else {
+                            one_pass(data, 0).map(|groups| (0..data.len(), groups))
+                        }
+                    }
+                )
+            }
+        }
+    };
+    assert_eq!(
+        format!("{}", expected),
+        format!("{}", impl_regex(quote! { br"(?P<x>a)" }).unwrap())
+    );
+}
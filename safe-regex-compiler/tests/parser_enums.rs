@@ -1,5 +1,7 @@
 #![forbid(unsafe_code)]
-use safe_regex_compiler::parser::{ClassItem, FinalNode, Node, NonFinalNode};
+use safe_regex_compiler::parser::{
+    ClassItem, FinalNode, Node, NonFinalNode, ParseError, ParseErrorKind,
+};
 
 #[test]
 fn node() {
@@ -32,12 +34,31 @@ fn class_item() {
         "ByteRange(0-9)",
         format!("{:?}", ClassItem::ByteRange(b'0', b'9'))
     );
+    assert_eq!(
+        "CharRange(à-é)",
+        format!("{:?}", ClassItem::CharRange('à', 'é'))
+    );
 }
 
 #[test]
 fn non_final_node() {
     assert_eq!("Escape", format!("{:?}", NonFinalNode::Escape));
     assert_eq!("HexEscape0", format!("{:?}", NonFinalNode::HexEscape0));
+    assert_eq!(
+        "PropertyEscape0(P)",
+        format!("{:?}", NonFinalNode::PropertyEscape0(true))
+    );
+    assert_eq!(
+        "PropertyEscape1(p,Gre)",
+        format!(
+            "{:?}",
+            NonFinalNode::PropertyEscape1(false, "Gre".to_string())
+        )
+    );
+    assert_eq!(
+        "OpenCharRange(é)",
+        format!("{:?}", NonFinalNode::OpenCharRange('é'))
+    );
     assert_eq!(
         "HexEscape1(a)",
         format!("{:?}", NonFinalNode::HexEscape1(b'a'))
@@ -58,6 +79,14 @@ fn non_final_node() {
             NonFinalNode::OpenClass(false, vec![ClassItem::Byte(b'a')])
         )
     );
+    assert_eq!(
+        "OpenClassBracket",
+        format!("{:?}", NonFinalNode::OpenClassBracket)
+    );
+    assert_eq!(
+        "OpenPosixClass(alpha)",
+        format!("{:?}", NonFinalNode::OpenPosixClass("alpha".to_string()))
+    );
     assert_eq!(
         "OpenByteRange(a)",
         format!("{:?}", NonFinalNode::OpenByteRange(b'a'))
@@ -71,6 +100,22 @@ fn non_final_node() {
         "OpenExtendedGroup",
         format!("{:?}", NonFinalNode::OpenExtendedGroup)
     );
+    assert_eq!(
+        "OpenExtendedGroupP",
+        format!("{:?}", NonFinalNode::OpenExtendedGroupP)
+    );
+    assert_eq!(
+        "OpenGroupName(ab)",
+        format!("{:?}", NonFinalNode::OpenGroupName("ab".to_string()))
+    );
+    assert_eq!(
+        "OpenNamedGroup(ab)",
+        format!("{:?}", NonFinalNode::OpenNamedGroup("ab".to_string()))
+    );
+    assert_eq!(
+        "OpenFlags(-m)",
+        format!("{:?}", NonFinalNode::OpenFlags("-m".to_string()))
+    );
     assert_eq!(
         "OpenNonCapturingGroup",
         format!("{:?}", NonFinalNode::OpenNonCapturingGroup)
@@ -94,7 +139,14 @@ fn non_final_node() {
         "RepeatToken(\"printable0\",5,Some(7))",
         format!(
             "{:?}",
-            NonFinalNode::RepeatToken("printable0".to_string(), 5, Some(7))
+            NonFinalNode::RepeatToken("printable0".to_string(), 5, Some(7), false)
+        )
+    );
+    assert_eq!(
+        "LazyRepeatToken(\"*?\",0,None)",
+        format!(
+            "{:?}",
+            NonFinalNode::RepeatToken("*?".to_string(), 0, None, true)
         )
     );
 
@@ -106,6 +158,18 @@ fn non_final_node() {
         vec![FinalNode::AnyByte],
         NonFinalNode::OpenAlt(vec![FinalNode::AnyByte]).unwrap_open_alt()
     );
+    assert_eq!(
+        "ab".to_string(),
+        NonFinalNode::OpenGroupName("ab".to_string()).unwrap_open_group_name()
+    );
+    assert_eq!(
+        "ab".to_string(),
+        NonFinalNode::OpenNamedGroup("ab".to_string()).unwrap_open_named_group()
+    );
+    assert_eq!(
+        "-m".to_string(),
+        NonFinalNode::OpenFlags("-m".to_string()).unwrap_open_flags()
+    );
     assert_eq!(
         "123".to_string(),
         NonFinalNode::RepeatMin("123".to_string()).unwrap_repeat_min()
@@ -128,6 +192,24 @@ fn unwrap_open_or() {
     NonFinalNode::OpenGroup.unwrap_open_alt();
 }
 
+#[test]
+#[should_panic]
+fn unwrap_open_group_name() {
+    NonFinalNode::OpenGroup.unwrap_open_group_name();
+}
+
+#[test]
+#[should_panic]
+fn unwrap_open_named_group() {
+    NonFinalNode::OpenGroup.unwrap_open_named_group();
+}
+
+#[test]
+#[should_panic]
+fn unwrap_open_flags() {
+    NonFinalNode::OpenGroup.unwrap_open_flags();
+}
+
 #[test]
 #[should_panic]
 fn unwrap_repeat_min() {
@@ -160,6 +242,13 @@ fn final_node() {
         "Group(AnyByte)",
         format!("{:?}", FinalNode::Group(Box::new(FinalNode::AnyByte)))
     );
+    assert_eq!(
+        "NamedGroup(x,AnyByte)",
+        format!(
+            "{:?}",
+            FinalNode::NamedGroup("x".to_string(), Box::new(FinalNode::AnyByte))
+        )
+    );
     assert_eq!(
         "NonCapturingGroup(AnyByte)",
         format!(
@@ -175,9 +264,25 @@ fn final_node() {
         "Repeat(AnyByte,5-Some(7))",
         format!(
             "{:?}",
-            FinalNode::Repeat(Box::new(FinalNode::AnyByte), 5, Some(7))
+            FinalNode::Repeat(Box::new(FinalNode::AnyByte), 5, Some(7), false)
+        )
+    );
+    assert_eq!(
+        "LazyRepeat(AnyByte,0-None)",
+        format!(
+            "{:?}",
+            FinalNode::Repeat(Box::new(FinalNode::AnyByte), 0, None, true)
         )
     );
+    assert_eq!("StartText", format!("{:?}", FinalNode::StartText));
+    assert_eq!("EndText", format!("{:?}", FinalNode::EndText));
+    assert_eq!("StartLine", format!("{:?}", FinalNode::StartLine));
+    assert_eq!("EndLine", format!("{:?}", FinalNode::EndLine));
+    assert_eq!("WordBoundary", format!("{:?}", FinalNode::WordBoundary));
+    assert_eq!(
+        "NotWordBoundary",
+        format!("{:?}", FinalNode::NotWordBoundary)
+    );
 
     assert_eq!(
         vec![FinalNode::AnyByte],
@@ -190,3 +295,40 @@ fn final_node() {
 fn unwrap_or() {
     FinalNode::AnyByte.unwrap_alt();
 }
+
+#[test]
+fn error_kind() {
+    assert_eq!(
+        ParseErrorKind::UnclosedGroup,
+        NonFinalNode::OpenNamedGroup("a".to_string()).error_kind()
+    );
+    assert_eq!(
+        ParseErrorKind::UnclosedRepeat("{1,2".to_string()),
+        NonFinalNode::RepeatMax("1".to_string(), "2".to_string()).error_kind()
+    );
+    assert_eq!(
+        "missing closing `}` of property name: `\\P{Gre`",
+        NonFinalNode::PropertyEscape1(true, "Gre".to_string())
+            .error_kind()
+            .to_string()
+    );
+    assert_eq!(
+        "unknown flag: `x`",
+        ParseErrorKind::UnknownFlag('x').to_string()
+    );
+    assert_eq!(
+        "invalid byte in group name: `\\x00`",
+        ParseErrorKind::InvalidGroupNameByte(0).to_string()
+    );
+}
+
+#[test]
+fn parse_error() {
+    let e = ParseError::new(ParseErrorKind::UnclosedClass, 1..2, b"a[b");
+    assert_eq!(b"a[b", e.pattern());
+    assert_eq!("missing closing `]`\na[b\n ^", e.to_string());
+    let e = ParseError::new(ParseErrorKind::UnclosedClass, 3..3, b"a[b");
+    assert_eq!("missing closing `]`\na[b\n   ^", e.to_string());
+    let e: Box<dyn std::error::Error> = Box::new(e);
+    assert!(e.to_string().starts_with("missing closing `]`"));
+}
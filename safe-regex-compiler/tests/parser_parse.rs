@@ -1,9 +1,26 @@
 #![forbid(unsafe_code)]
 #![allow(clippy::too_many_lines)]
 use safe_regex_compiler::parser::FinalNode::{
-    Alt, AnyByte, Byte, Class, Group, NonCapturingGroup, Repeat, Seq,
+    Alt, AnyByte, Byte, Class, EndLine, EndText, Group, NamedGroup, NonCapturingGroup,
+    NotWordBoundary, Repeat, Seq, StartLine, StartText, WordBoundary,
 };
-use safe_regex_compiler::parser::{parse, ClassItem};
+use safe_regex_compiler::parser::{ClassItem, FinalNode, ParseErrorKind};
+
+/// Parses `regex` and returns the error message, without the excerpt.
+fn parse(regex: &[u8]) -> Result<FinalNode, String> {
+    safe_regex_compiler::parser::parse(regex).map_err(|e| e.kind.to_string())
+}
+
+/// Parses `regex` and returns the error message, without the excerpt.
+fn parse_str(regex: &str) -> Result<FinalNode, String> {
+    safe_regex_compiler::parser::parse_str(regex).map_err(|e| e.kind.to_string())
+}
+
+/// Parses `regex` and returns the error kind and span.
+fn parse_err(regex: &[u8]) -> (ParseErrorKind, core::ops::Range<usize>) {
+    let e = safe_regex_compiler::parser::parse(regex).unwrap_err();
+    (e.kind, e.span)
+}
 
 #[test]
 fn test() {
@@ -236,6 +253,158 @@ fn class() {
     );
 }
 
+#[test]
+fn shorthand_class() {
+    let digit = vec![ClassItem::ByteRange(b'0', b'9')];
+    let word = vec![
+        ClassItem::ByteRange(b'0', b'9'),
+        ClassItem::ByteRange(b'A', b'Z'),
+        ClassItem::Byte(b'_'),
+        ClassItem::ByteRange(b'a', b'z'),
+    ];
+    let space = vec![ClassItem::ByteRange(b'\t', b'\r'), ClassItem::Byte(b' ')];
+    assert_eq!(Ok(Class(true, digit.clone())), parse(br"\d"));
+    assert_eq!(Ok(Class(false, digit.clone())), parse(br"\D"));
+    assert_eq!(Ok(Class(true, word.clone())), parse(br"\w"));
+    assert_eq!(Ok(Class(false, word)), parse(br"\W"));
+    assert_eq!(Ok(Class(true, space.clone())), parse(br"\s"));
+    assert_eq!(Ok(Class(false, space)), parse(br"\S"));
+    assert_eq!(
+        Ok(Seq(vec![
+            Byte(b'a'),
+            Repeat(Box::new(Class(true, digit.clone())), 1, None, false)
+        ])),
+        parse(br"a\d+")
+    );
+    assert_eq!(Ok(Class(true, digit.clone())), parse(br"[\d]"));
+    assert_eq!(Ok(Class(false, digit.clone())), parse(br"[^\d]"));
+    assert_eq!(
+        Ok(Class(
+            true,
+            vec![
+                ClassItem::Byte(b'x'),
+                ClassItem::ByteRange(b'0', b'9'),
+                ClassItem::Byte(b'_')
+            ]
+        )),
+        parse(br"[x\d_]")
+    );
+    assert_eq!(
+        Ok(Class(
+            true,
+            vec![
+                ClassItem::ByteRange(0, b'/'),
+                ClassItem::ByteRange(b':', 255)
+            ]
+        )),
+        parse(br"[\D]")
+    );
+    assert_eq!(
+        Ok(Class(
+            false,
+            vec![
+                ClassItem::ByteRange(0, b'/'),
+                ClassItem::ByteRange(b':', 255),
+                ClassItem::Byte(b'a')
+            ]
+        )),
+        parse(br"[^\Da]")
+    );
+    assert_eq!(
+        Err("expected byte after '-' symbol, not class: `a-`".to_string()),
+        parse(br"[a-\d]")
+    );
+}
+
+#[test]
+fn posix_class() {
+    assert_eq!(
+        Ok(Class(
+            true,
+            vec![
+                ClassItem::ByteRange(b'A', b'Z'),
+                ClassItem::ByteRange(b'a', b'z')
+            ]
+        )),
+        parse(br"[[:alpha:]]")
+    );
+    assert_eq!(
+        Ok(Class(
+            false,
+            vec![
+                ClassItem::ByteRange(b'A', b'Z'),
+                ClassItem::ByteRange(b'a', b'z')
+            ]
+        )),
+        parse(br"[^[:alpha:]]")
+    );
+    assert_eq!(
+        Ok(Class(
+            true,
+            vec![
+                ClassItem::Byte(b'-'),
+                ClassItem::ByteRange(b'0', b'9'),
+                ClassItem::ByteRange(b'A', b'F'),
+                ClassItem::ByteRange(b'a', b'f')
+            ]
+        )),
+        parse(br"[-[:xdigit:]]")
+    );
+    assert_eq!(
+        Ok(Class(
+            true,
+            vec![
+                ClassItem::Byte(b'x'),
+                ClassItem::ByteRange(0, b'@'),
+                ClassItem::ByteRange(b'[', b'`'),
+                ClassItem::ByteRange(b'{', 255)
+            ]
+        )),
+        parse(br"[x[:^alpha:]]")
+    );
+    for (name, len) in &[
+        ("alnum", 3),
+        ("ascii", 1),
+        ("blank", 2),
+        ("cntrl", 2),
+        ("digit", 1),
+        ("graph", 1),
+        ("lower", 1),
+        ("print", 1),
+        ("punct", 4),
+        ("space", 2),
+        ("upper", 1),
+        ("word", 4),
+    ] {
+        match parse(format!("[[:{}:]]", name).as_bytes()) {
+            Ok(Class(true, items)) => assert_eq!(*len, items.len(), "{}", name),
+            other => panic!("{} {:?}", name, other),
+        }
+    }
+    assert_eq!(
+        Ok(Class(
+            true,
+            vec![ClassItem::Byte(b'['), ClassItem::Byte(b'a')]
+        )),
+        parse(br"[[a]")
+    );
+    assert_eq!(Ok(Class(true, vec![ClassItem::Byte(b'[')])), parse(br"[[]"));
+    assert_eq!(Err("missing closing `]`".to_string()), parse(br"[["));
+    assert_eq!(
+        Err("missing closing `]`".to_string()),
+        parse(br"[[:alpha:]")
+    );
+    assert_eq!(Err("missing closing `]`".to_string()), parse(br"[[:alpha"));
+    assert_eq!(
+        Err("unknown POSIX class: `[:bogus:]`".to_string()),
+        parse(br"[[:bogus:]]")
+    );
+    assert_eq!(
+        Err("missing `:]` after POSIX class: `[:alpha]`".to_string()),
+        parse(br"[[:alpha]]")
+    );
+}
+
 #[test]
 fn group() {
     assert_eq!(Err("missing closing `)`".to_string()), parse(br"(."));
@@ -283,6 +452,253 @@ fn non_capturing_group() {
     );
 }
 
+#[test]
+fn named_group() {
+    assert_eq!(Err("missing closing `)`".to_string()), parse(br"(?P"));
+    assert_eq!(
+        Err("unexpected symbol after `(?P`".to_string()),
+        parse(br"(?Px>.)")
+    );
+    assert_eq!(
+        Err("missing closing `>` of group name".to_string()),
+        parse(br"(?P<x")
+    );
+    assert_eq!(
+        Err("missing closing `>` of group name".to_string()),
+        parse(br"(?<x")
+    );
+    assert_eq!(Err("missing group name".to_string()), parse(br"(?P<>.)"));
+    assert_eq!(
+        Err("invalid byte in group name: `-`".to_string()),
+        parse(br"(?P<a-b>.)")
+    );
+    assert_eq!(
+        Err("invalid byte in group name: `=`".to_string()),
+        parse(br"(?<=a)")
+    );
+    assert_eq!(
+        Err("group name starts with a digit: `1a`".to_string()),
+        parse(br"(?P<1a>.)")
+    );
+    assert_eq!(
+        Err("group name is a Rust keyword: `type`".to_string()),
+        parse(br"(?P<type>.)")
+    );
+    assert_eq!(
+        Err("duplicate group name: `x`".to_string()),
+        parse(br"(?P<x>.)(?<x>.)")
+    );
+    assert_eq!(Err("missing closing `)`".to_string()), parse(br"(?P<x>"));
+    assert_eq!(Err("missing closing `)`".to_string()), parse(br"(?P<x>."));
+    assert_eq!(Err("missing closing `]`".to_string()), parse(br"(?P<x>[)"));
+    assert_eq!(
+        Ok(NamedGroup("x".to_string(), Box::new(Seq(vec![])))),
+        parse(br"(?P<x>)")
+    );
+    assert_eq!(
+        Ok(NamedGroup("x".to_string(), Box::new(AnyByte))),
+        parse(br"(?P<x>.)")
+    );
+    assert_eq!(
+        Ok(NamedGroup("_Ab9".to_string(), Box::new(AnyByte))),
+        parse(br"(?<_Ab9>.)")
+    );
+    assert_eq!(
+        Ok(Seq(vec![
+            NamedGroup(
+                "x".to_string(),
+                Box::new(NamedGroup("y".to_string(), Box::new(AnyByte)))
+            ),
+            Group(Box::new(AnyByte)),
+            NamedGroup("z".to_string(), Box::new(AnyByte))
+        ])),
+        parse(br"(?P<x>(?P<y>.))(.)(?<z>.)")
+    );
+    assert_eq!(
+        Ok(Repeat(
+            Box::new(NamedGroup("x".to_string(), Box::new(AnyByte))),
+            0,
+            None,
+            false,
+        )),
+        parse(br"(?P<x>.)*")
+    );
+}
+
+#[test]
+fn anchors() {
+    assert_eq!(Ok(StartText), parse(br"^"));
+    assert_eq!(Ok(EndText), parse(br"$"));
+    assert_eq!(Ok(StartText), parse(br"\A"));
+    assert_eq!(Ok(EndText), parse(br"\z"));
+    assert_eq!(
+        Ok(Seq(vec![StartText, Byte(b'a'), EndText])),
+        parse(br"^a$")
+    );
+    assert_eq!(
+        Ok(Alt(vec![Seq(vec![StartText, Byte(b'a')]), EndText])),
+        parse(br"^a|$")
+    );
+    assert_eq!(
+        Ok(Repeat(Box::new(Group(Box::new(StartText))), 0, None, false)),
+        parse(br"(^)*")
+    );
+    assert_eq!(
+        Ok(Class(
+            true,
+            vec![ClassItem::Byte(b'$'), ClassItem::Byte(b'^')]
+        )),
+        parse(br"[$^]")
+    );
+    assert_eq!(Ok(Seq(vec![Byte(b'^'), Byte(b'$')])), parse(br"\^\$"));
+    assert_eq!(
+        Err("assertions are not allowed in classes".to_string()),
+        parse(br"[\A]")
+    );
+    assert_eq!(
+        Err("assertions are not allowed in classes".to_string()),
+        parse(br"[a-\z]")
+    );
+}
+
+#[test]
+fn word_boundary() {
+    assert_eq!(Ok(WordBoundary), parse(br"\b"));
+    assert_eq!(Ok(NotWordBoundary), parse(br"\B"));
+    assert_eq!(
+        Ok(Seq(vec![WordBoundary, Byte(b'a'), WordBoundary])),
+        parse(br"\ba\b")
+    );
+    assert_eq!(
+        Ok(Repeat(Box::new(NotWordBoundary), 1, None, false)),
+        parse(br"\B+")
+    );
+    assert_eq!(
+        Err("assertions are not allowed in classes".to_string()),
+        parse(br"[\b]")
+    );
+}
+
+#[test]
+fn flags() {
+    assert_eq!(Err("missing closing `)`".to_string()), parse(br"(?m"));
+    assert_eq!(Err("missing closing `)`".to_string()), parse(br"(?m:^"));
+    assert_eq!(Err("unknown flag: `x`".to_string()), parse(br"(?x)"));
+    assert_eq!(Err("unknown flag: `-`".to_string()), parse(br"(?-m-m)"));
+    assert_eq!(
+        Err("missing flag after `-`: `(?m-`".to_string()),
+        parse(br"(?m-)")
+    );
+    assert_eq!(
+        Err("unexpected symbol in flags: `.`".to_string()),
+        parse(br"(?m.)")
+    );
+    assert_eq!(Ok(Seq(vec![])), parse(br"(?m)"));
+    assert_eq!(Ok(Byte(b'a')), parse(br"a(?m)"));
+    assert_eq!(
+        Ok(Seq(vec![StartLine, Byte(b'a'), EndLine])),
+        parse(br"(?m)^a$")
+    );
+    assert_eq!(Ok(Seq(vec![StartText, EndText])), parse(br"(?m)(?-m)^$"));
+    assert_eq!(
+        Ok(Seq(vec![
+            NonCapturingGroup(Box::new(Seq(vec![StartLine, EndLine]))),
+            StartText,
+            EndText
+        ])),
+        parse(br"(?m:^$)^$")
+    );
+    assert_eq!(
+        Ok(Seq(vec![
+            Group(Box::new(Seq(vec![
+                NonCapturingGroup(Box::new(EndText)),
+                EndLine
+            ]))),
+            EndText
+        ])),
+        parse(br"((?m)(?-m:$)$)$")
+    );
+    assert_eq!(
+        Ok(Seq(vec![
+            NamedGroup("x".to_string(), Box::new(EndLine)),
+            EndText
+        ])),
+        parse(br"(?P<x>(?m)$)$")
+    );
+    assert_eq!(Ok(Alt(vec![StartText, StartLine])), parse(br"^|(?m)^"));
+}
+
+#[test]
+fn case_insensitive() {
+    let a = || Class(true, vec![ClassItem::Byte(b'a'), ClassItem::Byte(b'A')]);
+    assert_eq!(Err("unknown flag: `x`".to_string()), parse(br"(?ix)"));
+    assert_eq!(Ok(a()), parse(br"(?i)a"));
+    assert_eq!(
+        Ok(Seq(vec![
+            Byte(b'a'),
+            NonCapturingGroup(Box::new(Seq(vec![a(), Byte(b'1')]))),
+            Byte(b'a')
+        ])),
+        parse(br"a(?i:a1)a")
+    );
+    assert_eq!(
+        Ok(Seq(vec![a(), Group(Box::new(Byte(b'a'))), a()])),
+        parse(br"(?i)a((?-i)a)a")
+    );
+    assert_eq!(
+        Ok(Seq(vec![
+            Class(true, vec![ClassItem::Byte(b'A'), ClassItem::Byte(b'a')]),
+            Class(true, vec![ClassItem::Byte(b'Z'), ClassItem::Byte(b'z')]),
+            Byte(b'_')
+        ])),
+        parse(br"(?i)\x41Z_")
+    );
+    assert_eq!(
+        Ok(Class(
+            true,
+            vec![
+                ClassItem::ByteRange(b'0', b'c'),
+                ClassItem::ByteRange(b'a', b'z'),
+                ClassItem::ByteRange(b'A', b'C')
+            ]
+        )),
+        parse(br"(?i)[0-c]")
+    );
+    assert_eq!(
+        Ok(Class(
+            false,
+            vec![
+                ClassItem::Byte(b'-'),
+                ClassItem::Byte(b'x'),
+                ClassItem::Byte(b'X')
+            ]
+        )),
+        parse(br"(?i)[^-x]")
+    );
+    assert_eq!(
+        Ok(Class(
+            true,
+            vec![
+                ClassItem::ByteRange(b'0', b'9'),
+                ClassItem::ByteRange(b'A', b'Z'),
+                ClassItem::Byte(b'_'),
+                ClassItem::ByteRange(b'a', b'z')
+            ]
+        )),
+        parse(br"(?i)\w")
+    );
+    assert_eq!(
+        Ok(Class(
+            true,
+            vec![
+                ClassItem::ByteRange(b'a', b'z'),
+                ClassItem::ByteRange(b'A', b'Z')
+            ]
+        )),
+        parse(br"(?i)[[:lower:]]")
+    );
+}
+
 #[test]
 fn question_mark() {
     assert_eq!(
@@ -297,33 +713,42 @@ fn question_mark() {
         Err("unexpected symbol after `(?`".to_string()),
         parse(br"(?)")
     );
-    assert_eq!(Ok(Repeat(Box::new(AnyByte), 0, Some(1))), parse(br".?"));
+    assert_eq!(
+        Ok(Repeat(Box::new(AnyByte), 0, Some(1), false)),
+        parse(br".?")
+    );
     assert_eq!(
         Ok(Seq(vec![
             AnyByte,
             AnyByte,
-            Repeat(Box::new(AnyByte), 0, Some(1)),
+            Repeat(Box::new(AnyByte), 0, Some(1), false),
         ])),
         parse(br"...?")
     );
     assert_eq!(
         Ok(Alt(vec![
             Byte(b'a'),
-            Repeat(Box::new(Byte(b'b')), 0, Some(1)),
+            Repeat(Box::new(Byte(b'b')), 0, Some(1), false),
         ])),
         parse(br"a|b?")
     );
     assert_eq!(
         Ok(Alt(vec![
             Byte(b'a'),
-            Seq(vec![Repeat(Box::new(Byte(b'b')), 0, Some(1)), Byte(b'c')]),
+            Seq(vec![
+                Repeat(Box::new(Byte(b'b')), 0, Some(1), false),
+                Byte(b'c')
+            ]),
         ])),
         parse(br"a|b?c")
     );
     assert_eq!(
         Ok(Alt(vec![
             Byte(b'a'),
-            Seq(vec![Byte(b'b'), Repeat(Box::new(Byte(b'c')), 0, Some(1))]),
+            Seq(vec![
+                Byte(b'b'),
+                Repeat(Box::new(Byte(b'c')), 0, Some(1), false)
+            ]),
         ])),
         parse(br"a|bc?")
     );
@@ -339,7 +764,7 @@ fn star() {
         Err("missing element before repeat element: `*`".to_string()),
         parse(br"b|*")
     );
-    assert_eq!(Ok(Repeat(Box::new(AnyByte), 0, None)), parse(br".*"));
+    assert_eq!(Ok(Repeat(Box::new(AnyByte), 0, None, false)), parse(br".*"));
     assert_eq!(
         Err("missing element before repeat element: `*`".to_string()),
         parse(br"(*)")
@@ -348,7 +773,7 @@ fn star() {
         Ok(Seq(vec![
             AnyByte,
             AnyByte,
-            Repeat(Box::new(AnyByte), 0, None),
+            Repeat(Box::new(AnyByte), 0, None, false),
         ])),
         parse(br"...*")
     );
@@ -364,7 +789,7 @@ fn plus() {
         Err("missing element before repeat element: `+`".to_string()),
         parse(br"b|+")
     );
-    assert_eq!(Ok(Repeat(Box::new(AnyByte), 1, None)), parse(br".+"));
+    assert_eq!(Ok(Repeat(Box::new(AnyByte), 1, None, false)), parse(br".+"));
     assert_eq!(
         Err("missing element before repeat element: `+`".to_string()),
         parse(br"(+)")
@@ -373,7 +798,7 @@ fn plus() {
         Ok(Seq(vec![
             AnyByte,
             AnyByte,
-            Repeat(Box::new(AnyByte), 1, None),
+            Repeat(Box::new(AnyByte), 1, None, false),
         ])),
         parse(br"...+")
     );
@@ -405,17 +830,23 @@ fn repeat_single_num() {
         Err("invalid repetition value `{a}`: invalid digit found in string".to_string()),
         parse(br".{a}")
     );
-    assert_eq!(Ok(Repeat(Box::new(AnyByte), 0, Some(0))), parse(br".{0}"));
-    assert_eq!(Ok(Repeat(Box::new(AnyByte), 1, Some(1))), parse(br".{1}"));
     assert_eq!(
-        Ok(Repeat(Box::new(AnyByte), 99, Some(99))),
+        Ok(Repeat(Box::new(AnyByte), 0, Some(0), false)),
+        parse(br".{0}")
+    );
+    assert_eq!(
+        Ok(Repeat(Box::new(AnyByte), 1, Some(1), false)),
+        parse(br".{1}")
+    );
+    assert_eq!(
+        Ok(Repeat(Box::new(AnyByte), 99, Some(99), false)),
         parse(br".{99}")
     );
     assert_eq!(
         Ok(Seq(vec![
             AnyByte,
             AnyByte,
-            Repeat(Box::new(AnyByte), 1, Some(1)),
+            Repeat(Box::new(AnyByte), 1, Some(1), false),
         ])),
         parse(br"...{1}")
     );
@@ -431,12 +862,15 @@ fn repeat() {
         Err("missing closing `}` symbol: `{,`".to_string()),
         parse(br".{,")
     );
-    assert_eq!(Ok(Repeat(Box::new(AnyByte), 0, None)), parse(br".{,}"));
+    assert_eq!(
+        Ok(Repeat(Box::new(AnyByte), 0, None, false)),
+        parse(br".{,}")
+    );
     assert_eq!(
         Ok(Seq(vec![
             AnyByte,
             AnyByte,
-            Repeat(Box::new(AnyByte), 0, None),
+            Repeat(Box::new(AnyByte), 0, None, false),
         ])),
         parse(br"...{,}")
     );
@@ -456,14 +890,23 @@ fn repeat_min() {
         Err("invalid repetition value `{a,}`: invalid digit found in string".to_string()),
         parse(br".{a,}")
     );
-    assert_eq!(Ok(Repeat(Box::new(AnyByte), 0, None)), parse(br".{0,}"));
-    assert_eq!(Ok(Repeat(Box::new(AnyByte), 1, None)), parse(br".{1,}"));
-    assert_eq!(Ok(Repeat(Box::new(AnyByte), 99, None)), parse(br".{99,}"));
+    assert_eq!(
+        Ok(Repeat(Box::new(AnyByte), 0, None, false)),
+        parse(br".{0,}")
+    );
+    assert_eq!(
+        Ok(Repeat(Box::new(AnyByte), 1, None, false)),
+        parse(br".{1,}")
+    );
+    assert_eq!(
+        Ok(Repeat(Box::new(AnyByte), 99, None, false)),
+        parse(br".{99,}")
+    );
     assert_eq!(
         Ok(Seq(vec![
             AnyByte,
             AnyByte,
-            Repeat(Box::new(AnyByte), 1, None),
+            Repeat(Box::new(AnyByte), 1, None, false),
         ])),
         parse(br"...{1,}")
     );
@@ -483,17 +926,23 @@ fn repeat_max() {
         Err("invalid repetition value `{,a}`: invalid digit found in string".to_string()),
         parse(br".{,a}")
     );
-    assert_eq!(Ok(Repeat(Box::new(AnyByte), 0, Some(0))), parse(br".{,0}"));
-    assert_eq!(Ok(Repeat(Box::new(AnyByte), 0, Some(1))), parse(br".{,1}"));
     assert_eq!(
-        Ok(Repeat(Box::new(AnyByte), 0, Some(99))),
+        Ok(Repeat(Box::new(AnyByte), 0, Some(0), false)),
+        parse(br".{,0}")
+    );
+    assert_eq!(
+        Ok(Repeat(Box::new(AnyByte), 0, Some(1), false)),
+        parse(br".{,1}")
+    );
+    assert_eq!(
+        Ok(Repeat(Box::new(AnyByte), 0, Some(99), false)),
         parse(br".{,99}")
     );
     assert_eq!(
         Ok(Seq(vec![
             AnyByte,
             AnyByte,
-            Repeat(Box::new(AnyByte), 0, Some(1)),
+            Repeat(Box::new(AnyByte), 0, Some(1), false),
         ])),
         parse(br"...{,1}")
     );
@@ -525,22 +974,260 @@ fn repeat_min_and_max() {
         Err("repeating element has max that is smaller than min: `{2,1}`".to_string()),
         parse(br".{2,1}")
     );
-    assert_eq!(Ok(Repeat(Box::new(AnyByte), 0, Some(0))), parse(br".{0,0}"));
-    assert_eq!(Ok(Repeat(Box::new(AnyByte), 1, Some(2))), parse(br".{1,2}"));
     assert_eq!(
-        Ok(Repeat(Box::new(AnyByte), 10, Some(99))),
+        Ok(Repeat(Box::new(AnyByte), 0, Some(0), false)),
+        parse(br".{0,0}")
+    );
+    assert_eq!(
+        Ok(Repeat(Box::new(AnyByte), 1, Some(2), false)),
+        parse(br".{1,2}")
+    );
+    assert_eq!(
+        Ok(Repeat(Box::new(AnyByte), 10, Some(99), false)),
         parse(br".{10,99}")
     );
     assert_eq!(
         Ok(Seq(vec![
             AnyByte,
             AnyByte,
-            Repeat(Box::new(AnyByte), 1, Some(2)),
+            Repeat(Box::new(AnyByte), 1, Some(2), false),
         ])),
         parse(br"...{1,2}")
     );
 }
 
+#[test]
+fn lazy_repeat() {
+    assert_eq!(
+        Err("missing element before repeat element: `*?`".to_string()),
+        parse(br"*?")
+    );
+    assert_eq!(
+        Err("missing element before repeat element: `{1,2}?`".to_string()),
+        parse(br"({1,2}?)")
+    );
+    assert_eq!(Ok(Repeat(Box::new(AnyByte), 0, None, true)), parse(br".*?"));
+    assert_eq!(Ok(Repeat(Box::new(AnyByte), 1, None, true)), parse(br".+?"));
+    assert_eq!(
+        Ok(Repeat(Box::new(AnyByte), 0, Some(1), true)),
+        parse(br".??")
+    );
+    assert_eq!(
+        Ok(Repeat(Box::new(AnyByte), 2, Some(2), true)),
+        parse(br".{2}?")
+    );
+    assert_eq!(
+        Ok(Repeat(Box::new(AnyByte), 2, Some(3), true)),
+        parse(br".{2,3}?")
+    );
+    assert_eq!(
+        Ok(Seq(vec![
+            Byte(b'a'),
+            Repeat(Box::new(AnyByte), 0, None, true),
+            Byte(b'b')
+        ])),
+        parse(br"a.*?b")
+    );
+    assert_eq!(
+        Ok(Repeat(
+            Box::new(Repeat(Box::new(AnyByte), 0, None, true)),
+            0,
+            Some(1),
+            false
+        )),
+        parse(br".*??")
+    );
+    assert_eq!(
+        Ok(Alt(vec![
+            Repeat(Box::new(Byte(b'a')), 0, None, true),
+            Byte(b'b')
+        ])),
+        parse(br"a*?|b")
+    );
+}
+
+#[test]
+fn utf8() {
+    let r = |a: u8, b: u8| Class(true, vec![ClassItem::ByteRange(a, b)]);
+    let e_acute = || NonCapturingGroup(Box::new(Seq(vec![Byte(0xC3), Byte(0xA9)])));
+    assert_eq!(parse(br""), parse_str(r""));
+    assert_eq!(parse(br"a[b-c]\d(?i)x"), parse_str(r"a[b-c]\d(?i)x"));
+    assert_eq!(Ok(e_acute()), parse_str(r"é"));
+    assert_eq!(Ok(e_acute()), parse_str(r"\xE9"));
+    assert_eq!(Ok(e_acute()), parse_str(r"[é]"));
+    assert_eq!(Ok(e_acute()), parse_str(r"[\xE9]"));
+    assert_eq!(
+        Ok(Repeat(Box::new(e_acute()), 1, None, false)),
+        parse_str(r"é+")
+    );
+    assert_eq!(
+        Ok(Seq(vec![
+            Byte(b'c'),
+            Byte(b'a'),
+            Byte(b'f'),
+            NonCapturingGroup(Box::new(Alt(vec![
+                Byte(b'e'),
+                Seq(vec![Byte(0xC3), Byte(0xA9)])
+            ])))
+        ])),
+        parse_str(r"caf[eé]")
+    );
+    assert_eq!(
+        Ok(NonCapturingGroup(Box::new(Seq(vec![
+            Byte(0xE2),
+            Byte(0x82),
+            Byte(0xAC)
+        ])))),
+        parse_str(r"€")
+    );
+    assert_eq!(
+        Ok(NonCapturingGroup(Box::new(Seq(vec![
+            Byte(0xC3),
+            r(0xA0, 0xBF)
+        ])))),
+        parse_str(r"[à-ÿ]")
+    );
+    assert_eq!(
+        Ok(NonCapturingGroup(Box::new(Alt(vec![
+            r(b'a', 0x7F),
+            Seq(vec![Byte(0xC2), r(0x80, 0xBF)]),
+            Seq(vec![Byte(0xC3), r(0x80, 0xA9)]),
+        ])))),
+        parse_str(r"[a-é]")
+    );
+    let any_multi_byte = || {
+        vec![
+            Seq(vec![r(0xC2, 0xDF), r(0x80, 0xBF)]),
+            Seq(vec![Byte(0xE0), r(0xA0, 0xBF), r(0x80, 0xBF)]),
+            Seq(vec![
+                Class(
+                    true,
+                    vec![
+                        ClassItem::ByteRange(0xE1, 0xEC),
+                        ClassItem::ByteRange(0xEE, 0xEF),
+                    ],
+                ),
+                r(0x80, 0xBF),
+                r(0x80, 0xBF),
+            ]),
+            Seq(vec![Byte(0xED), r(0x80, 0x9F), r(0x80, 0xBF)]),
+            Seq(vec![
+                Byte(0xF0),
+                r(0x90, 0xBF),
+                r(0x80, 0xBF),
+                r(0x80, 0xBF),
+            ]),
+            Seq(vec![
+                r(0xF1, 0xF3),
+                r(0x80, 0xBF),
+                r(0x80, 0xBF),
+                r(0x80, 0xBF),
+            ]),
+            Seq(vec![
+                Byte(0xF4),
+                r(0x80, 0x8F),
+                r(0x80, 0xBF),
+                r(0x80, 0xBF),
+            ]),
+        ]
+    };
+    assert_eq!(
+        Ok(NonCapturingGroup(Box::new(Alt(core::iter::once(r(
+            0, 0x7F
+        ))
+        .chain(any_multi_byte())
+        .collect())))),
+        parse_str(r".")
+    );
+    assert_eq!(
+        Ok(NonCapturingGroup(Box::new(Alt(core::iter::once(Class(
+            true,
+            vec![
+                ClassItem::ByteRange(0, b'`'),
+                ClassItem::ByteRange(b'b', 0x7F)
+            ]
+        ))
+        .chain(any_multi_byte())
+        .collect())))),
+        parse_str(r"[^a]")
+    );
+    assert_eq!(parse_str(r"[^a]"), parse_str(r"[^\x61]"));
+    assert_eq!(parse_str(r"[^\d]"), parse_str(r"\D"));
+    assert_eq!(parse_str(r"[^\d]"), parse_str(r"[\D]"));
+    assert_eq!(
+        Err("missing byte to close range: `é-`".to_string()),
+        parse_str(r"[é-")
+    );
+    assert_eq!(
+        Err("expected byte before '-' symbol, not range: `à-é-`".to_string()),
+        parse_str(r"[à-é-ü]")
+    );
+    assert_eq!(
+        Err("expected byte after '-' symbol, not class: `é-`".to_string()),
+        parse_str(r"[é-\d]")
+    );
+}
+
+#[test]
+fn unicode_property() {
+    let r = |a: u8, b: u8| Class(true, vec![ClassItem::ByteRange(a, b)]);
+    let line_separator =
+        || NonCapturingGroup(Box::new(Seq(vec![Byte(0xE2), Byte(0x80), Byte(0xA8)])));
+    assert_eq!(Ok(line_separator()), parse(br"\p{Zl}"));
+    assert_eq!(Ok(line_separator()), parse(br"\p{Line_Separator}"));
+    assert_eq!(Ok(line_separator()), parse(br"\p{line separator}"));
+    assert_eq!(Ok(line_separator()), parse(br"\p{gc=Zl}"));
+    assert_eq!(Ok(line_separator()), parse(br"\p{General_Category=Zl}"));
+    assert_eq!(Ok(line_separator()), parse_str(r"\p{Zl}"));
+    assert_eq!(Ok(line_separator()), parse(br"[\p{Zl}]"));
+    assert_eq!(
+        Ok(NonCapturingGroup(Box::new(Seq(vec![
+            Byte(0xE2),
+            Byte(0x80),
+            r(0xA8, 0xA9)
+        ])))),
+        parse(br"[\p{Zl}\p{Zp}]")
+    );
+    assert_eq!(
+        Ok(Seq(vec![Byte(b'a'), line_separator(), Byte(b'b')])),
+        parse(br"a\p{Zl}b")
+    );
+    assert_eq!(
+        Ok(Repeat(Box::new(line_separator()), 1, None, false)),
+        parse(br"\p{Zl}+")
+    );
+    assert_eq!(parse(br"\p{L}"), parse(br"\pL"));
+    assert_eq!(parse(br"\p{Greek}"), parse(br"\p{sc=Grek}"));
+    assert_eq!(parse(br"\p{Greek}"), parse(br"\p{Script=greek}"));
+    assert_eq!(parse(br"\P{Greek}"), parse(br"[^\p{Greek}]"));
+    assert_eq!(parse(br"\p{Nd}"), parse(br"[^\P{Nd}]"));
+    assert_eq!(parse(br"[^a]"), parse(br"[^a]"));
+    assert_ne!(parse(br"\w"), parse(br"(?u)\w"));
+    assert_eq!(parse(br"\w"), parse(br"(?u)(?-u)\w"));
+    assert_eq!(parse(br"(?u)\W"), parse(br"(?u)[^\w]"));
+    assert_eq!(parse(br"(?u)\W"), parse(br"(?u)[\W]"));
+    assert_eq!(
+        Err("unknown Unicode property: `Klingon`".to_string()),
+        parse(br"\p{Klingon}")
+    );
+    assert_eq!(
+        Err("unknown Unicode property: `gc=Greek`".to_string()),
+        parse(br"\p{gc=Greek}")
+    );
+    assert_eq!(
+        Err("missing closing `}` of property name: `\\p{Gre`".to_string()),
+        parse(br"\p{Gre")
+    );
+    assert_eq!(
+        Err("incomplete escape sequence: `\\P`".to_string()),
+        parse(br"\P")
+    );
+    assert_eq!(
+        Err("expected property name after `\\p`: `\\p1`".to_string()),
+        parse(br"\p1")
+    );
+}
+
 #[test]
 fn precedence() {
     // Regular expressions have four types of syntax:
@@ -555,16 +1242,16 @@ fn precedence() {
     assert_eq!(
         Ok(Seq(vec![
             Byte(b'a'),
-            Repeat(Box::new(Byte(b'b')), 0, Some(1))
+            Repeat(Box::new(Byte(b'b')), 0, Some(1), false)
         ])),
         parse(br"ab?")
     );
     // Postfix & Alternation
     assert_eq!(
         Ok(Alt(vec![
-            Repeat(Box::new(Byte(b'a')), 0, None),
-            Repeat(Box::new(Byte(b'b')), 0, None),
-            Repeat(Box::new(Byte(b'c')), 0, None),
+            Repeat(Box::new(Byte(b'a')), 0, None, false),
+            Repeat(Box::new(Byte(b'b')), 0, None, false),
+            Repeat(Box::new(Byte(b'c')), 0, None, false),
         ])),
         parse(br"a*|b*|c*")
     );
@@ -578,3 +1265,130 @@ fn precedence() {
         parse(br"ab|cd|ef")
     );
 }
+
+#[test]
+fn error_spans() {
+    use ParseErrorKind::{
+        AssertionInClass, ClassInRange, DuplicateGroupName, IncompleteEscape, InvalidEscape,
+        InvalidExtendedGroup, InvalidFlagsByte, InvalidGroupNameByte, InvalidRepeatValue,
+        MissingElementAfterBar, MissingElementBeforeBar, MissingGroupName, MissingPropertyName,
+        MissingRepeatElement, RangeInRange, RepeatMaxBelowMin, TooLarge, UnclosedClass,
+        UnclosedGroup, UnclosedGroupName, UnclosedPosixClass, UnclosedRange, UnclosedRepeat,
+        UnknownFlag, UnknownPosixClass, UnknownProperty,
+    };
+    assert_eq!(
+        (IncompleteEscape(r"\".to_string()), 2..3),
+        parse_err(br"ab\")
+    );
+    assert_eq!(
+        (IncompleteEscape(r"\x4".to_string()), 1..4),
+        parse_err(br"a\x4")
+    );
+    assert_eq!(
+        (InvalidEscape(r"\e".to_string()), 1..3),
+        parse_err(br"a\eb")
+    );
+    assert_eq!(
+        (InvalidEscape(r"\x4g".to_string()), 0..4),
+        parse_err(br"\x4g")
+    );
+    assert_eq!(
+        (MissingPropertyName(r"\p1".to_string()), 1..4),
+        parse_err(br"a\p1")
+    );
+    assert_eq!(
+        (UnknownProperty("Klingon".to_string()), 1..12),
+        parse_err(br"a\p{Klingon}b")
+    );
+    assert_eq!((UnclosedClass, 1..3), parse_err(br"a[b"));
+    assert_eq!((UnclosedRange("b".to_string()), 3..4), parse_err(br"a[b-"));
+    assert_eq!((ClassInRange("a".to_string()), 2..5), parse_err(br"[a-\d]"));
+    assert_eq!(
+        (RangeInRange("a-b".to_string()), 4..5),
+        parse_err(br"[a-b-c]")
+    );
+    assert_eq!((AssertionInClass, 2..4), parse_err(br"[a\b]"));
+    assert_eq!(
+        (UnknownPosixClass("bogus:".to_string()), 1..10),
+        parse_err(br"[[:bogus:]]")
+    );
+    assert_eq!(
+        (UnclosedPosixClass("alpha".to_string()), 1..9),
+        parse_err(br"[[:alpha]]")
+    );
+    assert_eq!((UnclosedGroup, 2..3), parse_err(br"ab(c"));
+    assert_eq!((UnclosedGroup, 1..2), parse_err(br"a(b(c)"));
+    assert_eq!((UnclosedGroup, 0..6), parse_err(br"(?P<x>a"));
+    assert_eq!((UnclosedGroup, 0..2), parse_err(br"(?"));
+    assert_eq!(
+        (InvalidExtendedGroup("(?".to_string()), 0..3),
+        parse_err(br"(?Q)")
+    );
+    assert_eq!((UnclosedGroupName, 0..5), parse_err(br"(?<ab"));
+    assert_eq!((MissingGroupName, 0..4), parse_err(br"(?<>a)"));
+    assert_eq!((InvalidGroupNameByte(b'-'), 4..5), parse_err(br"(?<a-b>a)"));
+    assert_eq!(
+        (DuplicateGroupName("x".to_string()), 6..11),
+        parse_err(br"(?<x>)(?<x>)")
+    );
+    assert_eq!((InvalidFlagsByte(b'!'), 3..4), parse_err(br"(?m!)"));
+    assert_eq!((UnknownFlag('x'), 1..5), parse_err(br"a(?x)"));
+    assert_eq!((MissingElementBeforeBar, 0..1), parse_err(br"|a"));
+    assert_eq!((MissingElementAfterBar, 0..2), parse_err(br"a|"));
+    assert_eq!(
+        (MissingRepeatElement("*".to_string()), 0..1),
+        parse_err(br"*a")
+    );
+    assert_eq!(
+        (MissingRepeatElement("+?".to_string()), 2..4),
+        parse_err(br"a|+?")
+    );
+    assert_eq!(
+        (UnclosedRepeat("{2,3".to_string()), 1..5),
+        parse_err(br"a{2,3")
+    );
+    assert_eq!(
+        (UnclosedRepeat("{1".to_string()), 1..3),
+        parse_err(br"a{1\d")
+    );
+    assert_eq!(
+        (UnclosedRepeat("{1,".to_string()), 1..4),
+        parse_err(br"a{1,(b)")
+    );
+    assert_eq!(
+        (RepeatMaxBelowMin("{2,1}".to_string()), 1..6),
+        parse_err(br"a{2,1}b")
+    );
+    assert_eq!((TooLarge(100_004), 0..9), parse_err(br"a{100000}"));
+    assert_eq!(
+        (TooLarge(usize::MAX), 0..29),
+        parse_err(br"(?:a{4294967296}){4294967296}")
+    );
+    assert!(matches!(
+        parse_err(br"a{x}"),
+        (InvalidRepeatValue(s, _), span) if s == "{x}" && span == (1..4)
+    ));
+    let e = safe_regex_compiler::parser::parse_str("é(").unwrap_err();
+    assert_eq!((UnclosedGroup, 2..3), (e.kind, e.span));
+    let e = safe_regex_compiler::parser::parse_str(r"[é-\d]").unwrap_err();
+    assert_eq!((ClassInRange("é".to_string()), 3..6), (e.kind, e.span));
+}
+
+#[test]
+fn error_display() {
+    let to_s = |regex: &[u8]| {
+        safe_regex_compiler::parser::parse(regex)
+            .unwrap_err()
+            .to_string()
+    };
+    assert_eq!("missing closing `)`\nab(c\n  ^", to_s(br"ab(c"));
+    assert_eq!("invalid escape sequence `\\e`\na\\eb\n ^^", to_s(br"a\eb"));
+    assert_eq!("missing element after bar `|`\n\\t|\n^^^", to_s(b"\t|"));
+    assert_eq!("missing closing `]`\n\\xff[\n    ^", to_s(b"\xff["));
+    assert_eq!(
+        "missing closing `)`\né(\n ^",
+        safe_regex_compiler::parser::parse_str("é(")
+            .unwrap_err()
+            .to_string()
+    );
+}
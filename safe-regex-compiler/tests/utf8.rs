@@ -0,0 +1,84 @@
+#![forbid(unsafe_code)]
+use safe_regex_compiler::utf8::{complement, normalize, sequences, MAX_CODE_POINT};
+
+fn matches(sequences: &[Vec<(u8, u8)>], bytes: &[u8]) -> usize {
+    sequences
+        .iter()
+        .filter(|sequence| {
+            sequence.len() == bytes.len()
+                && sequence
+                    .iter()
+                    .zip(bytes)
+                    .all(|((a, b), byte)| (*a..=*b).contains(byte))
+        })
+        .count()
+}
+
+#[test]
+fn test_normalize() {
+    assert_eq!(Vec::<(u32, u32)>::new(), normalize(Vec::new()));
+    assert_eq!(vec![(1, 5)], normalize(vec![(3, 5), (1, 2)]));
+    assert_eq!(
+        vec![(1, 5), (7, 7)],
+        normalize(vec![(7, 7), (1, 4), (2, 5)])
+    );
+}
+
+#[test]
+fn test_complement() {
+    assert_eq!(vec![(0, MAX_CODE_POINT)], complement(&[]));
+    assert_eq!(Vec::<(u32, u32)>::new(), complement(&[(0, MAX_CODE_POINT)]));
+    assert_eq!(
+        vec![(0, 0x60), (0x62, MAX_CODE_POINT)],
+        complement(&[(0x61, 0x61)])
+    );
+}
+
+#[test]
+fn every_char() {
+    let all = sequences(0, MAX_CODE_POINT);
+    let mut buf = [0_u8; 4];
+    for n in 0..=MAX_CODE_POINT {
+        if let Some(c) = char::from_u32(n) {
+            let bytes = c.encode_utf8(&mut buf).as_bytes();
+            assert_eq!(1, matches(&all, bytes), "{:?}", c);
+        }
+    }
+    // Surrogates
+    assert_eq!(0, matches(&all, b"\xED\xA0\x80"));
+    assert_eq!(0, matches(&all, b"\xED\xBF\xBF"));
+    // Overlong encoding of `/`
+    assert_eq!(0, matches(&all, b"\xC0\xAF"));
+}
+
+#[test]
+fn sub_ranges() {
+    let mut buf = [0_u8; 4];
+    for (a, b) in [
+        (0x61_u32, 0xE9_u32),
+        (0x7FF, 0x800),
+        (0x3A3, 0x3A9),
+        (0xD7FF, 0xE000),
+        (0xFFF0, 0x1_0010),
+        (0x1_F600, 0x1_F64F),
+    ]
+    .iter()
+    .copied()
+    {
+        let seqs = sequences(a, b);
+        for n in a.saturating_sub(0x100)..=(b + 0x100) {
+            if let Some(c) = char::from_u32(n) {
+                let expected = usize::from((a..=b).contains(&n));
+                let bytes = c.encode_utf8(&mut buf).as_bytes();
+                assert_eq!(
+                    expected,
+                    matches(&seqs, bytes),
+                    "{:?} in {:x}-{:x}",
+                    c,
+                    a,
+                    b
+                );
+            }
+        }
+    }
+}
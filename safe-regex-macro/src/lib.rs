@@ -3,7 +3,8 @@
 //! [![unsafe forbidden](https://gitlab.com/leonhard-llc/safe-regex-rs/-/raw/main/unsafe-forbidden-success.svg)](https://github.com/rust-secure-code/safety-dance/)
 //! [![pipeline status](https://gitlab.com/leonhard-llc/safe-regex-rs/badges/main/pipeline.svg)](https://gitlab.com/leonhard-llc/safe-regex-rs/-/pipelines)
 //!
-//! This crate provides the `regex!` macro used by the
+//! This crate provides the `regex!`, `regex_set!`, `regex_stream!`, `lexer!`,
+//! and `define_regex!` macros and the `FromRegex` derive macro used by the
 //! [`safe-regex`](https://crates.io/crates/safe-regex) crate.
 //!
 //! It is a thin wrapper around the
@@ -22,12 +23,23 @@
 
 /// Compiles a regular expression into a Rust type.
 ///
-/// Returns a `MatcherN` struct where `N` is the number of capturing groups.
+/// Returns a `Matcher<_, N>` struct where `N` is the number of capturing groups.
 ///
 /// Specify the type of the expected matcher so your editor can
 /// show its functions and documentation:
 /// `let matcher: Matcher0<_> = regex!(br".")`.
 ///
+/// When the expression is a raw string like `r"caf[eé]"`,
+/// returns a `StrMatcher<_, N>` struct instead.
+/// It matches `&str` and returns `&str` captures.
+/// The expression may contain non-ASCII text, and its `.` and classes match
+/// whole UTF-8 code points.
+///
+/// When the expression has named groups like `(?P<year>[0-9]+)`,
+/// returns a struct that dereferences to the `Matcher`.
+/// Its `match_ranges` and `match_slices` functions return structs with one
+/// method per named group, like `year()`.
+///
 /// # Examples
 /// ```rust
 /// use safe_regex::{regex, Matcher0};
@@ -41,24 +53,239 @@
 /// use safe_regex::{regex, Matcher3};
 /// let matcher: Matcher3<_> =
 ///     regex!(br"([ab])([0-9]*)(suffix)?");
-/// let (prefix, digits, suffix) =
+/// let [prefix, digits, suffix] =
 ///     matcher.match_slices(b"a42").unwrap();
 /// assert_eq!(b"a", prefix);
 /// assert_eq!(b"42", digits);
 /// assert_eq!(b"", suffix);
-/// let (prefix_range, digits_r, suffix_r)
+/// let [prefix_range, digits_r, suffix_r]
 ///     = matcher.match_ranges(b"a42").unwrap();
 /// assert_eq!(0..1_usize, prefix_range);
 /// assert_eq!(1..3_usize, digits_r);
 /// assert_eq!(0..0_usize, suffix_r);
 /// ```
+///
+/// ```rust
+/// use safe_regex::regex;
+/// let matcher = regex!(br"(?P<key>[a-z]+)=(?P<value>[0-9]*)");
+/// let slices = matcher.match_slices(b"id=42").unwrap();
+/// assert_eq!(b"id", slices.key());
+/// assert_eq!(b"42", slices.value());
+/// ```
+///
+/// ```rust
+/// use safe_regex::{regex, StrMatcher1};
+/// let matcher: StrMatcher1<_> = regex!(r"prix: ([0-9]+) €");
+/// assert_eq!(["42"], matcher.match_slices("prix: 42 €").unwrap());
+/// ```
 #[proc_macro]
-#[allow(clippy::missing_panics_doc)]
 pub fn regex(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input2 = safe_proc_macro2::TokenStream::from(input);
     let output2 = match safe_regex_compiler::impl_regex(input2) {
         Ok(output2) => output2,
-        Err(reason) => panic!("{}", reason),
+        Err(e) => e.to_compile_error(),
+    };
+    proc_macro::TokenStream::from(output2)
+}
+
+/// Compiles a regular expression into a streaming matcher.
+///
+/// Returns a `StreamMatcher<_, N>` struct where `N` is the number of capturing
+/// groups.
+/// Feed it the input in chunks with `push` and get the result with `finish`.
+/// The matcher never stores the input, so its memory use does not depend on
+/// the length of the input.
+///
+/// This is a whole-input match.
+/// Capture ranges are `u64` byte positions counted from the first pushed byte.
+///
+/// # Example
+/// ```rust
+/// use safe_regex::{regex_stream, StreamMatcher1};
+/// let mut matcher: StreamMatcher1<_> = regex_stream!(br"id=([0-9]+)");
+/// matcher.push(b"id=4");
+/// matcher.push(b"2");
+/// assert_eq!(Some([3..5_u64]), matcher.finish());
+/// ```
+#[proc_macro]
+pub fn regex_stream(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input2 = safe_proc_macro2::TokenStream::from(input);
+    let output2 = match safe_regex_compiler::impl_regex_stream(input2) {
+        Ok(output2) => output2,
+        Err(e) => e.to_compile_error(),
+    };
+    proc_macro::TokenStream::from(output2)
+}
+
+/// Compiles several regular expressions into one matcher.
+///
+/// Returns a `RegexSet<_, N>` struct where `N` is the number of expressions.
+/// It checks all of the expressions in one pass over the data and reports
+/// which ones matched.
+///
+/// These are whole-string matches.
+/// Capturing groups are allowed and ignored.
+///
+/// # Example
+/// ```rust
+/// use safe_regex::{regex_set, RegexSet};
+/// let set: RegexSet<_, 3> = regex_set!(br"/users/[0-9]+", br"/users/.*", br"/admin");
+/// let matches = set.matches(b"/users/42");
+/// assert!(matches.matched(0));
+/// assert_eq!(vec![0, 1], matches.iter().collect::<Vec<usize>>());
+/// ```
+#[proc_macro]
+pub fn regex_set(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input2 = safe_proc_macro2::TokenStream::from(input);
+    let output2 = match safe_regex_compiler::impl_regex_set(input2) {
+        Ok(output2) => output2,
+        Err(e) => e.to_compile_error(),
+    };
+    proc_macro::TokenStream::from(output2)
+}
+
+/// Generates a lexer from rules like `Token::Num => br"[0-9]+"`.
+///
+/// Returns a `Lexer` struct.
+/// Its `tokens` method returns an iterator over the tokens in some data.
+/// Each token is the longest non-empty match of any rule.
+/// When several rules match the same bytes, the first rule wins.
+/// All rules step over the data together, so finding a token takes one pass
+/// over its bytes and the bytes after it that some rule could still match.
+///
+/// The left side of a rule can be any expression that makes a token.
+///
+/// # Example
+/// ```rust
+/// use safe_regex::{lexer, LexError};
+/// #[derive(Debug, PartialEq)]
+/// enum Token {
+///     If,
+///     Ident,
+///     Space,
+/// }
+/// let lexer = lexer!(
+///     Token::If => br"if",
+///     Token::Ident => br"[a-z]+",
+///     Token::Space => br" ",
+/// );
+/// let mut tokens = lexer.tokens(b"if iff !");
+/// assert_eq!(Some(Ok((Token::If, 0..2))), tokens.next());
+/// assert_eq!(Some(Ok((Token::Space, 2..3))), tokens.next());
+/// assert_eq!(Some(Ok((Token::Ident, 3..6))), tokens.next());
+/// assert_eq!(Some(Ok((Token::Space, 6..7))), tokens.next());
+/// assert_eq!(Some(Err(LexError { position: 7 })), tokens.next());
+/// assert_eq!(None, tokens.next());
+/// ```
+#[proc_macro]
+pub fn lexer(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input2 = safe_proc_macro2::TokenStream::from(input);
+    let output2 = match safe_regex_compiler::impl_lexer(input2) {
+        Ok(output2) => output2,
+        Err(e) => e.to_compile_error(),
+    };
+    proc_macro::TokenStream::from(output2)
+}
+
+/// Generates a `from_bytes` function that parses the captures of a regular
+/// expression into the fields of a struct.
+///
+/// Put the expression in a `#[regex(br"...")]` attribute.
+/// It must have one capturing group for each field.
+/// The function converts each group's bytes into the field at the same
+/// position.
+/// It uses the field type's `FromStr` implementation when it has one,
+/// otherwise its `TryFrom<&[u8]>` implementation.
+///
+/// With a `#[regex(r"...")]` attribute, the macro implements `FromStr` for
+/// the struct instead, matching strings like `StrMatcher` does.
+///
+/// `from_bytes` matches all of the data, like `Matcher::match_slices`.
+/// It returns `Err(FromRegexError::NoMatch)` when the expression does not
+/// match, and `Err(FromRegexError::InvalidField("name"))` when a capture does
+/// not convert into field `name`.
+///
+/// # Example
+/// ```rust
+/// use safe_regex::{FromRegex, FromRegexError};
+/// #[derive(FromRegex)]
+/// #[regex(br"([0-9]+)-([0-9]+)-([0-9]+)")]
+/// struct Date {
+///     year: u16,
+///     month: u8,
+///     day: u8,
+/// }
+/// let date = Date::from_bytes(b"2021-05-17").unwrap();
+/// assert_eq!(2021, date.year);
+/// assert_eq!(5, date.month);
+/// assert_eq!(17, date.day);
+/// assert_eq!(Err(FromRegexError::NoMatch), Date::from_bytes(b"2021-05").map(|_| ()));
+/// // 500 does not fit in a `u8`.
+/// assert_eq!(
+///     Err(FromRegexError::InvalidField("month")),
+///     Date::from_bytes(b"2021-500-17").map(|_| ()),
+/// );
+/// ```
+#[proc_macro_derive(FromRegex, attributes(regex))]
+pub fn from_regex(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input2 = safe_proc_macro2::TokenStream::from(input);
+    let output2 = match safe_regex_compiler::impl_from_regex(input2) {
+        Ok(output2) => output2,
+        Err(e) => e.to_compile_error(),
+    };
+    proc_macro::TokenStream::from(output2)
+}
+
+/// Defines a named type for a regular expression.
+///
+/// `define_regex!(pub Name = br"...");` defines a zero-sized struct `Name`
+/// with a `const fn new()`.
+/// The struct dereferences to a `Matcher<_, N>` that lives in a `static`,
+/// so it has all of the `Matcher` methods like `match_ranges`,
+/// `match_slices`, and `find_iter`.
+/// It also implements `IsMatch`.
+/// Unlike the result of `regex!`, you can name the type,
+/// so you can put it in statics and struct fields, and return it from trait
+/// methods.
+///
+/// When the expression is a raw string like `r"..."`, the struct
+/// dereferences to a `StrMatcher<_, N>` instead.
+///
+/// Attributes like `#[derive(Clone, Copy)]` and doc comments before the name
+/// apply to the struct.
+/// One invocation can define several types separated by `;`.
+///
+/// The `Matcher` calls the expression's code through a function pointer.
+/// For the fastest matching, use `regex!`.
+///
+/// # Example
+/// ```rust
+/// use safe_regex::{define_regex, IsMatch};
+/// define_regex!(
+///     /// A phone number like `555-1234`.
+///     #[derive(Clone, Copy, Debug, Default)]
+///     pub PhoneNumber = br"([0-9]{3})-([0-9]{4})";
+///     Word = r"[^ ]+";
+/// );
+/// static PHONE_NUMBER: PhoneNumber = PhoneNumber::new();
+/// assert!(PHONE_NUMBER.is_match(b"555-1234"));
+/// assert_eq!([0..3_usize, 4..8_usize], PHONE_NUMBER.match_ranges(b"555-1234").unwrap());
+///
+/// struct Validator {
+///     pattern: PhoneNumber,
+/// }
+/// let validator = Validator { pattern: PhoneNumber::new() };
+/// let is_match: &dyn IsMatch = &validator.pattern;
+/// assert!(!is_match.is_match(b"5551234"));
+///
+/// assert_eq!(Some(0..5_usize), Word::new().find("été !"));
+/// ```
+#[proc_macro]
+pub fn define_regex(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input2 = safe_proc_macro2::TokenStream::from(input);
+    let output2 = match safe_regex_compiler::impl_define_regex(input2) {
+        Ok(output2) => output2,
+        Err(e) => e.to_compile_error(),
     };
     proc_macro::TokenStream::from(output2)
 }
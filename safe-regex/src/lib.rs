@@ -16,26 +16,62 @@
 //!   - Any byte: `.`
 //!   - Sequences: `abc`
 //!   - Classes: `[-ab0-9]`, `[^ab]`
+//!   - Shorthand classes: `\d`, `\D`, `\w`, `\W`, `\s`, `\S`
+//!   - POSIX classes: `[[:alpha:]]`, `[[:^space:]]`, `[_[:xdigit:]]`
 //!   - Repetition: `a?`, `a*`, `a+`, `a{1}`, `a{1,}`, `a{,1}`, `a{1,2}`, `a{,}`
+//!   - Lazy repetition: `a??`, `a*?`, `a+?`, `a{1,2}?`
 //!   - Alternates: `a|b|c`
 //!   - Capturing groups: `a(bc)?`
+//!   - Named capturing groups: `(?P<name>a)`, `(?<name>a)`
 //!   - Non-capturing groups: `a(?:bc)?`
+//!   - Anchors: `^`, `$`, `\A`, `\z`
+//!   - ASCII word boundaries: `\b`, `\B`
+//!   - Multi-line mode, where `^` and `$` match at line breaks: `(?m)^a$`, `(?m:^a)`
+//!   - ASCII case-insensitive mode: `(?i)get`, `(?i:get) /`, `(?i)[^a-z]`
+//!   - Unicode properties: `\p{L}`, `\pN`, `\p{Greek}`, `\P{Nd}`, `[\p{Greek}\d]`
+//!   - Unicode word characters: `(?u)\w`
+//! - Whole-string matching and sub-string search
+//! - Matches byte slices with `regex!(br"...")` and strings with
+//!   `regex!(r"...")`.
+//!   String expressions may contain non-ASCII text like `r"café"`.
+//!   Their `.` and classes match whole UTF-8 code points.
+//! - Lexers: `lexer!(Token::Num => br"[0-9]+", ...)` splits data into
+//!   tokens.  It picks the longest match and breaks ties by rule order.
+//! - Named matcher types: `define_regex!(pub Id = br"[0-9]+");` makes a
+//!   zero-sized type for use in statics, struct fields, and function
+//!   signatures.
+//! - Object-safe `Regex` trait for keeping different matchers in one
+//!   collection, like `Vec<Box<dyn Regex>>`.
+//! - Typed captures: `#[derive(FromRegex)]` parses captures into struct
+//!   fields.
+//! - Sets: `regex_set!(br"...", br"...")` checks many expressions in one
+//!   pass and reports which ones matched.
+//! - Streaming: `regex_stream!(br"...")` matches input that arrives in
+//!   chunks, with constant memory.
+//! - Expressions known only at run time:
+//!   `safe_regex::runtime::Regex::new(b"...")` compiles an expression when
+//!   the program runs.  It returns the same captures as `regex!`.
+//!   It needs the `runtime` feature.
 //!
 //! # Limitations
-//! - Only works on byte slices, not strings.
-//! - Partially optimized.  Runtime is about 10 times slower than
+//! - Large Unicode classes like `\p{L}` and `(?u)\w` become thousands of
+//!   byte comparisons.  Each one adds many seconds to compile time.
+//! - Partially optimized.  Expressions without capturing groups compile to a
+//!   DFA and run about as fast as the
 //!   [`regex`](https://crates.io/crates/regex) crate.
+//!   Expressions with capturing groups run about 10 to 250 times slower.
 //!   Here are relative runtimes measured with
 //!   [`safe-regex-rs/bench`](https://gitlab.com/leonhard-llc/safe-regex-rs/-/tree/main/bench)
-//!   run on a 2018 Macbook Pro:
+//!   run on a one-core Intel Xeon Linux VM:
 //!
 //!   | `regex` | `safe_regex` | expression |
 //!   | ----- | ---------- | ---------- |
-//!   | 1 | 6 | find phone num `.*([0-9]{3})[-. ]?([0-9]{3})[-. ]?([0-9]{4}).*` |
-//!   | 1 | 20 | find date time `.*([0-9]+)-([0-9]+)-([0-9]+) ([0-9]+):([0-9]+).*` |
-//!   | 1 | 0.75 | parse date time `([0-9]+)-([0-9]+)-([0-9]+) ([0-9]+):([0-9]+)` |
-//!   | 1 | 50 | check PEM Base64 `[a-zA-Z0-9+/]{0,64}=*` |
-//!   | 1 | 20-500 | substring search `.*(2G8H81RFNZ).*` |
+//!   | 1 | 8-10 | find phone num `.*([0-9]{3})[-. ]?([0-9]{3})[-. ]?([0-9]{4}).*` |
+//!   | 1 | 0.5-250 | find date time `.*([0-9]+)-([0-9]+)-([0-9]+) ([0-9]+):([0-9]+).*` |
+//!   | 1 | 0.5 | parse date time `([0-9]+)-([0-9]+)-([0-9]+) ([0-9]+):([0-9]+)` |
+//!   | 1 | 0.7 | check PEM Base64 `[a-zA-Z0-9+/]{0,64}=*` |
+//!   | 1 | 0.1-0.2 | check repetition `a{10,20}` |
+//!   | 1 | 0.4-4 | substring search `.*2G8H81RFNZ.*` |
 //!
 //! # Alternatives
 //! - [`regex`](https://crates.io/crates/regex)
@@ -63,19 +99,209 @@
 //! use safe_regex::{regex, Matcher3};
 //! let matcher: Matcher3<_> =
 //!     regex!(br"([ab])([0-9]*)(suffix)?");
-//! let (prefix, digits, suffix) =
+//! let [prefix, digits, suffix] =
 //!     matcher.match_slices(b"a42").unwrap();
 //! assert_eq!(b"a", prefix);
 //! assert_eq!(b"42", digits);
 //! assert_eq!(b"", suffix);
-//! let (prefix_range, digits_r, suffix_r)
+//! let [prefix_range, digits_r, suffix_r]
 //!     = matcher.match_ranges(b"a42").unwrap();
 //! assert_eq!(0..1_usize, prefix_range);
 //! assert_eq!(1..3_usize, digits_r);
 //! assert_eq!(0..0_usize, suffix_r);
 //! ```
 //!
+//! ```rust
+//! use safe_regex::{regex, Matcher1};
+//! let matcher: Matcher1<_> =
+//!     regex!(br"id=([0-9]+)");
+//! assert_eq!(Some(2..7), matcher.find(b"? id=42 id=7"));
+//! let ids: Vec<&[u8]> = matcher
+//!     .captures_iter(b"? id=42 id=7")
+//!     .map(|(_, [id])| &b"? id=42 id=7"[id])
+//!     .collect();
+//! assert_eq!(vec![b"42".as_ref(), b"7".as_ref()], ids);
+//! ```
+//!
+//! ```rust
+//! use safe_regex::regex;
+//! let matcher = regex!(br"(?P<year>[0-9]{4})-(?P<month>[0-9]{2})");
+//! let date = matcher.match_slices(b"2021-05").unwrap();
+//! assert_eq!(b"2021", date.year());
+//! assert_eq!(b"05", date.month());
+//! assert_eq!(5..7_usize, matcher.match_ranges(b"2021-05").unwrap().month());
+//! ```
+//!
+//! ```rust
+//! use safe_regex::{regex, StrMatcher2};
+//! let matcher: StrMatcher2<_> =
+//!     regex!(r"([^ ]+) (.+)");
+//! let [city, country] =
+//!     matcher.match_slices("Zürich Schweiz").unwrap();
+//! assert_eq!("Zürich", city);
+//! assert_eq!("Schweiz", country);
+//! ```
+//!
+//! ```rust
+//! use safe_regex::{regex_set, RegexSet};
+//! let routes: RegexSet<_, 3> =
+//!     regex_set!(br"/", br"/users/[0-9]+", br"/users/.*");
+//! let matches = routes.matches(b"/users/42");
+//! assert_eq!(vec![1, 2], matches.iter().collect::<Vec<usize>>());
+//! assert!(!routes.is_match(b"/about"));
+//! ```
+//!
+//! ```rust
+//! use safe_regex::lexer;
+//! #[derive(Debug, PartialEq)]
+//! enum Token {
+//!     Let,
+//!     Ident,
+//!     Space,
+//! }
+//! let lexer = lexer!(
+//!     Token::Let => br"let",
+//!     Token::Ident => br"[a-z]+",
+//!     Token::Space => br" +",
+//! );
+//! let tokens: Vec<Token> = lexer
+//!     .tokens(b"let letter")
+//!     .map(|result| result.unwrap().0)
+//!     .collect();
+//! assert_eq!(vec![Token::Let, Token::Space, Token::Ident], tokens);
+//! ```
+//!
+//! ```rust
+//! safe_regex::define_regex!(pub PhoneNumber = br"([0-9]{3})-([0-9]{4})");
+//! static PHONE_NUMBER: PhoneNumber = PhoneNumber::new();
+//! let [prefix, line] = PHONE_NUMBER.match_slices(b"555-1234").unwrap();
+//! assert_eq!(b"555", prefix);
+//! assert_eq!(b"1234", line);
+//! ```
+//!
+//! ```rust
+//! use safe_regex::{regex, Regex};
+//! let rules: Vec<Box<dyn Regex>> = vec![
+//!     Box::new(regex!(br"GET (/[a-z]*)")),
+//!     Box::new(regex!(br"POST (/[a-z]*) ([0-9]+)")),
+//! ];
+//! let mut ranges = [0..0, 0..0];
+//! let rule = rules
+//!     .iter()
+//!     .find(|rule| rule.match_ranges_into(b"POST /a 12", &mut ranges))
+//!     .unwrap();
+//! assert_eq!(2, rule.group_count());
+//! assert_eq!([5..7_usize, 8..10_usize], ranges);
+//! ```
+//!
+//! ```rust
+//! #[derive(safe_regex::FromRegex)]
+//! #[regex(br"([0-9]{4})-([0-9]{2})-([0-9]{2})")]
+//! struct Date {
+//!     year: u16,
+//!     month: u8,
+//!     day: u8,
+//! }
+//! let date = Date::from_bytes(b"2021-05-17").unwrap();
+//! assert_eq!((2021, 5, 17), (date.year, date.month, date.day));
+//! ```
+//!
+//! ```rust
+//! use safe_regex::{regex_stream, StreamMatcher1};
+//! let mut matcher: StreamMatcher1<_> =
+//!     regex_stream!(br".*?Content-Length: ([0-9]+)\r\n.*");
+//! matcher.push(b"Host: a\r\nContent-Len");
+//! matcher.push(b"gth: 42\r\n\r\n");
+//! assert_eq!(Some([25..27_u64]), matcher.finish());
+//! ```
+//!
+//! ```rust
+//! # #[cfg(feature = "runtime")]
+//! # {
+//! use safe_regex::runtime::Regex;
+//! let pattern: Vec<u8> = b"user=([a-z]+)".to_vec(); // From a config file
+//! let regex = Regex::new(&pattern).unwrap();
+//! assert_eq!(Some(vec![5..8_usize]), regex.match_ranges(b"user=bob"));
+//! # }
+//! ```
+//!
 //! # Changelog
+//! - Unreleased
+//!   - Add `find`, `find_at`, `find_iter`, and `captures_iter` for sub-string search.
+//!     They return leftmost-first matches, like the `regex` crate.
+//!   - Replace `Matcher0` through `Matcher10` with `Matcher<F, N>`, which
+//!     supports any number of capturing groups.
+//!     `Matcher0` through `Matcher10` are now type aliases.
+//!   - `match_ranges` and `match_slices` return arrays instead of tuples.
+//!   - Add `match_ranges_opt` and `match_slices_opt`, which return `None` for
+//!     capturing groups that did not participate in the match.
+//!   - Empty capturing groups return their real position instead of `0..0`.
+//!   - Support empty groups `()` and alternates with empty arms like `a{0}|b`.
+//!   - Named capturing groups.  When a regex has named groups, `regex!`
+//!     returns a struct whose `match_ranges` and `match_slices` results
+//!     have one method per group name.
+//!   - Shorthand classes `\d` `\D` `\w` `\W` `\s` `\S` and POSIX classes like
+//!     `[[:alpha:]]`.
+//!   - Anchors `^` `$` `\A` `\z` and the multi-line flag `(?m)`.
+//!   - Word boundary assertions `\b` and `\B`.
+//!   - Lazy repetition `*?` `+?` `??` `{n,m}?`.  A lazy repetition in a
+//!     capturing group gets the shortest sub-match, so `(.*?),(.*)` captures
+//!     up to the first comma.
+//!   - Case-insensitive flag `(?i)` and scoped `(?i:ab)` groups for ASCII letters.
+//!   - String matching.  `regex!(r"...")` returns a `StrMatcher` whose
+//!     methods take `&str` and return `&str` captures.
+//!     The expression may contain non-ASCII text.
+//!     Its `.` and classes match whole UTF-8 code points.
+//!   - Unicode property classes `\p{..}` `\P{..}` `\pL` for general categories
+//!     and scripts, and the Unicode flag `(?u)` which makes `\w` match Unicode
+//!     word characters.  They match UTF-8 encoded text in byte slices, too.
+//!   - `safe_regex_compiler::parser::parse` returns a `ParseError` with the kind
+//!     of problem and its byte range in the expression.
+//!     Error messages show the expression with the problem underlined.
+//!   - `regex!` reports syntax errors as compiler errors instead of panicking.
+//!     On nightly compilers, the error underlines the bad part of the
+//!     expression, like the `(` in `regex!(br"ab(c")`.
+//!   - Streaming matcher.  `regex_stream!` returns a `StreamMatcher` with
+//!     `push`, `finish`, and `reset`.  It matches input that arrives in chunks
+//!     and reports captures as `u64` positions.
+//!   - Expression sets.  `regex_set!` combines several expressions into one
+//!     `RegexSet` that reports all of the expressions that match, in a single
+//!     pass over the data.
+//!   - Lexers.  `lexer!` takes rules like `Token::Num => br"[0-9]+"` and
+//!     returns a `Lexer` whose `tokens` method iterates over the tokens with
+//!     their ranges.  Unmatched input produces a `LexError` with its position.
+//!   - `#[derive(FromRegex)]` with a `#[regex(br"...")]` attribute generates a
+//!     `from_bytes` function that converts the captures into the struct's
+//!     fields with `FromStr` or `TryFrom<&[u8]>`.
+//!     A `#[regex(r"...")]` attribute implements `FromStr` for the struct.
+//!     The expression must have one capturing group per field.
+//!   - `define_regex!(pub Name = br"...");` defines a zero-sized `Name` type
+//!     with a `const fn new()`.  It dereferences to a `Matcher`.
+//!     Put it in statics and struct fields, or return it from functions.
+//!   - `Matcher::new` and `StrMatcher::new` are `const`.
+//!   - Object-safe `Regex` trait with `pattern`, `group_count`, and
+//!     `match_ranges_into`, for keeping different matchers in collections
+//!     like `Vec<Box<dyn Regex>>`.
+//!   - `runtime::Regex` for expressions that are known only at run time,
+//!     behind the `runtime` feature.
+//!     Expressions that compile to more than 100,000 instructions, like
+//!     `a{1000000}`, are errors.
+//!   - Expressions without capturing groups or assertions compile to DFAs
+//!     with constant transition tables.  They read each byte with one table
+//!     lookup.
+//!   - One-pass expressions with capturing groups, like
+//!     `([0-9]+)-([0-9]+)`, compile to DFAs that record captures directly.
+//!   - Matchers skip to the bytes that can start a match, and return early
+//!     when the data lacks a literal that every match contains.
+//!   - Long repetitions of one byte or class, like `[a-z]{0,1000}`, use a
+//!     counter instead of one state per repetition, also inside capturing
+//!     groups and in stream matchers.  They compile quickly and match in
+//!     linear time.
+//!     Searches like `find` and `captures_iter` still hold a thread for each
+//!     count, so `regex!` rejects expressions whose search needs more than
+//!     256 KiB of stack, like `[a-z]{0,5000}`.  `regex_stream!` accepts them.
+//!   - Matchers test each byte against a class with one lookup in a shared
+//!     256-entry table, instead of a chain of comparisons.
 //! - v0.2.4
 //!   - Bug fixes, reducing performance.
 //!   - Optimize non-match runtime.
@@ -92,19 +318,11 @@
 //! - v0.1.0 - First published version
 //!
 //! # TO DO
-//! - 11+ capturing groups
 //! - Increase coverage
 //! - Add fuzzing tests
-//! - Common character classes: whitespace, letters, punctuation, etc.
-//! - Match strings
 //! - Implement optimizations explained in <https://swtch.com/%7Ersc/regexp/regexp3.html> .
 //!   Some of the code already exists in `tests/dfa_single_pass.rs`
 //!   and `tests/nfa_without_capturing.rs`.
-//! - Once [const generics](https://github.com/rust-lang/rust/issues/44580)
-//!   are stable, use the feature to simplify some types.
-//! - Once
-//!   [trait bounds on `const fn` parameters are stable](https://github.com/rust-lang/rust/issues/57563),
-//!   make the `MatcherN::new` functions `const`.
 //! # Release Process
 //! 1. Edit `Cargo.toml` and bump version number.
 //! 1. Run `../release.sh`
@@ -114,8 +332,12 @@
 
 #![forbid(unsafe_code)]
 #![allow(clippy::type_complexity)]
+use core::marker::PhantomData;
 use core::ops::Range;
-pub use safe_regex_macro::regex;
+pub use safe_regex_macro::{define_regex, lexer, regex, regex_set, regex_stream, FromRegex};
+
+#[cfg(feature = "runtime")]
+pub mod runtime;
 
 /// Provides an `is_match` function.
 pub trait IsMatch {
@@ -123,36 +345,99 @@ pub trait IsMatch {
     /// otherwise returns `false`.
     ///
     /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
+    /// For sub-string search, use the matcher's `find` method.
     fn is_match(&self, data: &[u8]) -> bool;
 }
 
-/// A compiled regular expression with no capturing groups.
+/// A compiled regular expression whose type does not tell its number of
+/// capturing groups.
+///
+/// Use it to keep different matchers in one collection, like
+/// `Vec<Box<dyn Regex>>`.
+/// `Matcher`, `StrMatcher`, and the types made by `define_regex!` implement
+/// it.
+///
+/// # Example
+/// ```rust
+/// use safe_regex::{regex, Regex};
+/// let rules: Vec<Box<dyn Regex>> = vec![
+///     Box::new(regex!(br"id=([0-9]+)")),
+///     Box::new(regex!(br"([a-z]+)=([a-z]+)")),
+/// ];
+/// let mut ranges = [0..0, 0..0];
+/// let rule = rules
+///     .iter()
+///     .find(|rule| rule.match_ranges_into(b"key=value", &mut ranges))
+///     .unwrap();
+/// assert_eq!("([a-z]+)=([a-z]+)", rule.pattern());
+/// assert_eq!(2, rule.group_count());
+/// assert_eq!([0..3_usize, 4..9_usize], ranges);
+/// ```
+pub trait Regex {
+    /// Returns the regular expression, like `"id=([0-9]+)"`.
+    fn pattern(&self) -> &str;
+
+    /// Returns the number of capturing groups in the regular expression.
+    fn group_count(&self) -> usize;
+
+    /// Executes the regular expression against the byte string `data`.
+    ///
+    /// Returns `true` if the expression matched all of the bytes in `data`.
+    /// Then `ranges` holds the ranges of bytes in `data` that matched capturing
+    /// groups in the expression, like the result of
+    /// [`Matcher::match_ranges`](struct.Matcher.html#method.match_ranges).
+    /// When `ranges` is shorter than [`group_count`](#tymethod.group_count),
+    /// it gets only the first groups.
+    /// When it is longer, the extra elements do not change.
+    ///
+    /// Returns `false` and does not change `ranges` if the expression did not
+    /// match `data`.
+    fn match_ranges_into(&self, data: &[u8], ranges: &mut [Range<usize>]) -> bool;
+}
+
+/// Implements [`Regex::match_ranges_into`](trait.Regex.html#tymethod.match_ranges_into)
+/// for the matcher function `f`.
+fn match_ranges_into<F, const N: usize>(f: &F, data: &[u8], ranges: &mut [Range<usize>]) -> bool
+where
+    F: Fn(&[u8], Option<usize>) -> Option<(Range<usize>, [Option<Range<usize>>; N])>,
+{
+    if let Some((_, groups)) = f(data, None) {
+        for (range, group) in ranges.iter_mut().zip(groups) {
+            *range = group.unwrap_or(0..0);
+        }
+        true
+    } else {
+        false
+    }
+}
+
+/// A compiled regular expression with `N` capturing groups.
 ///
 /// This is a zero-length type.
 /// The `regex!` macro generates a Rust type that implements the regular expression.
 /// This struct holds that type.
-pub struct Matcher0<F>
+pub struct Matcher<F, const N: usize>
 where
-    F: Fn(&[u8]) -> Option<()>,
+    F: Fn(&[u8], Option<usize>) -> Option<(Range<usize>, [Option<Range<usize>>; N])>,
 {
+    pattern: &'static str,
     f: F,
 }
-impl<F> Matcher0<F>
+impl<F, const N: usize> Matcher<F, N>
 where
-    F: Fn(&[u8]) -> Option<()>,
+    F: Fn(&[u8], Option<usize>) -> Option<(Range<usize>, [Option<Range<usize>>; N])>,
 {
-    /// This is used internally by the `regex!` macro.
+    /// This is used internally by the `regex!` and `define_regex!` macros.
     #[must_use]
-    pub fn new(f: F) -> Self {
-        Self { f }
+    pub const fn new(pattern: &'static str, f: F) -> Self {
+        Self { pattern, f }
     }
 
     /// Returns `true` if `data` matches the regular expression,
     /// otherwise returns `false`.
     ///
     /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
+    /// For sub-string search, use [`find`](#method.find).
     ///
     /// # Example
     /// ```rust
@@ -163,45 +448,21 @@ where
     /// ```
     #[must_use]
     pub fn is_match(&self, data: &[u8]) -> bool {
-        (self.f)(data).is_some()
-    }
-
-    /// Executes the regular expression against the byte string `data`.
-    ///
-    /// Returns `Some((&[u8],&[u8],...))`
-    /// if the expression matched all of the bytes in `data`.
-    /// The tuple fields are slices of `data` that matched
-    /// capturing groups in the expression.
-    ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
-    ///
-    /// Returns `None` if the expression did not match `data`.
-    ///
-    /// # Example
-    /// ```rust
-    /// use safe_regex::{regex, Matcher3};
-    /// let matcher: Matcher3<_> = regex!(br"([abc])([0-9]*)(suffix)?");
-    /// let (prefix, digits, suffix) = matcher.match_slices(b"a42").unwrap();
-    /// assert_eq!(b"a", prefix);
-    /// assert_eq!(b"42", digits);
-    /// assert!(suffix.is_empty());
-    /// ```
-    #[must_use]
-    pub fn match_slices(&self, data: &[u8]) -> Option<()> {
-        (self.f)(data)
+        (self.f)(data, None).is_some()
     }
 
     /// Executes the regular expression against the byte string `data`.
     ///
-    /// Returns `Some((Range<u32>,Range<u32>,...))` if the expression matched all of the bytes in `data`.
-    /// The tuple fields are ranges of bytes in `data` that matched capturing
+    /// Returns `Some([Range<usize>,Range<usize>,...])` if the expression matched all of the bytes in `data`.
+    /// The array elements are ranges of bytes in `data` that matched capturing
     /// groups in the expression.
-    /// A capturing group that matches no bytes will produce as a zero-length
-    /// range.
+    /// A capturing group that matches no bytes produces a zero-length range at
+    /// the position where it matched.
+    /// A capturing group that did not participate in the match produces `0..0`.
+    /// To tell these apart, use [`match_ranges_opt`](#method.match_ranges_opt).
     ///
     /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
+    /// For sub-string search, use [`find`](#method.find).
     ///
     /// Returns `None` if the expression did not match `data`.
     ///
@@ -209,71 +470,53 @@ where
     /// ```rust
     /// use safe_regex::{regex, Matcher3};
     /// let matcher: Matcher3<_> = regex!(br"([abc])([0-9]*)(suffix)?");
-    /// let (prefix, digits, suffix) = matcher.match_ranges(b"a42").unwrap();
+    /// let [prefix, digits, suffix] = matcher.match_ranges(b"a42").unwrap();
     /// assert_eq!(0..1_usize, prefix);
     /// assert_eq!(1..3_usize, digits);
     /// assert_eq!(0..0_usize, suffix);
     /// ```
     #[must_use]
-    pub fn match_ranges(&self, data: &[u8]) -> Option<()> {
-        (self.f)(data)
-    }
-}
-impl<F: Fn(&[u8]) -> Option<()>> IsMatch for Matcher0<F> {
-    fn is_match(&self, data: &[u8]) -> bool {
-        self.is_match(data)
-    }
-}
-
-/// A compiled regular expression with 1 capturing groups.
-///
-/// This is a zero-length type.
-/// The `regex!` macro generates a Rust type that implements the regular expression.
-/// This struct holds that type.
-pub struct Matcher1<F>
-where
-    F: Fn(&[u8]) -> Option<[Range<usize>; 1]>,
-{
-    f: F,
-}
-impl<F> Matcher1<F>
-where
-    F: Fn(&[u8]) -> Option<[Range<usize>; 1]>,
-{
-    /// This is used internally by the `regex!` macro.
-    #[must_use]
-    pub fn new(f: F) -> Self {
-        Self { f }
+    pub fn match_ranges(&self, data: &[u8]) -> Option<[Range<usize>; N]> {
+        let (_, ranges) = (self.f)(data, None)?;
+        Some(ranges.map(|range| range.unwrap_or(0..0)))
     }
 
-    /// Returns `true` if `data` matches the regular expression,
-    /// otherwise returns `false`.
+    /// Executes the regular expression against the byte string `data`.
     ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
+    /// Returns `Some([Option<Range<usize>>,...])` if the expression matched
+    /// all of the bytes in `data`.
+    /// The array elements are ranges of bytes in `data` that matched capturing
+    /// groups in the expression.
+    /// A capturing group that did not participate in the match produces
+    /// `None`.
+    /// A capturing group that matches no bytes produces a zero-length range at
+    /// the position where it matched.
+    ///
+    /// Returns `None` if the expression did not match `data`.
     ///
     /// # Example
     /// ```rust
-    /// use safe_regex::{regex, Matcher0};
-    /// let matcher: Matcher0<_> = regex!(br"[abc][0-9]*");
-    /// assert!(matcher.is_match(b"a42"));
-    /// assert!(!matcher.is_match(b"X"));
+    /// use safe_regex::{regex, Matcher2};
+    /// let matcher: Matcher2<_> = regex!(br"x(a)?()");
+    /// let [a, empty] = matcher.match_ranges_opt(b"x").unwrap();
+    /// assert_eq!(None, a);
+    /// assert_eq!(Some(1..1_usize), empty);
     /// ```
     #[must_use]
-    pub fn is_match(&self, data: &[u8]) -> bool {
-        (self.f)(data).is_some()
+    pub fn match_ranges_opt(&self, data: &[u8]) -> Option<[Option<Range<usize>>; N]> {
+        let (_, ranges) = (self.f)(data, None)?;
+        Some(ranges)
     }
 
     /// Executes the regular expression against the byte string `data`.
     ///
-    /// Returns `Some((Range<u32>,Range<u32>,...))` if the expression matched all of the bytes in `data`.
-    /// The tuple fields are ranges of bytes in `data` that matched capturing
-    /// groups in the expression.
-    /// A capturing group that matches no bytes will produce as a zero-length
-    /// range.
+    /// Returns `Some([&[u8],&[u8],...])`
+    /// if the expression matched all of the bytes in `data`.
+    /// The array elements are slices of `data` that matched
+    /// capturing groups in the expression.
     ///
     /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
+    /// For sub-string search, use [`find`](#method.find).
     ///
     /// Returns `None` if the expression did not match `data`.
     ///
@@ -281,1094 +524,1284 @@ where
     /// ```rust
     /// use safe_regex::{regex, Matcher3};
     /// let matcher: Matcher3<_> = regex!(br"([abc])([0-9]*)(suffix)?");
-    /// let (prefix, digits, suffix) = matcher.match_ranges(b"a42").unwrap();
-    /// assert_eq!(0..1_usize, prefix);
-    /// assert_eq!(1..3_usize, digits);
-    /// assert_eq!(0..0_usize, suffix);
+    /// let [prefix, digits, suffix] = matcher.match_slices(b"a42").unwrap();
+    /// assert_eq!(b"a", prefix);
+    /// assert_eq!(b"42", digits);
+    /// assert!(suffix.is_empty());
     /// ```
     #[must_use]
-    pub fn match_ranges(&self, data: &[u8]) -> Option<(Range<usize>,)> {
-        let [r0] = (self.f)(data)?;
-        Some((r0,))
+    pub fn match_slices<'d>(&self, data: &'d [u8]) -> Option<[&'d [u8]; N]> {
+        let (_, ranges) = (self.f)(data, None)?;
+        Some(ranges.map(|range| &data[range.unwrap_or(0..0)]))
     }
 
     /// Executes the regular expression against the byte string `data`.
     ///
-    /// Returns `Some((&[u8],&[u8],...))`
+    /// Returns `Some([Option<&[u8]>,...])`
     /// if the expression matched all of the bytes in `data`.
-    /// The tuple fields are slices of `data` that matched
+    /// The array elements are slices of `data` that matched
     /// capturing groups in the expression.
-    ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
+    /// A capturing group that did not participate in the match produces
+    /// `None`.
     ///
     /// Returns `None` if the expression did not match `data`.
     ///
     /// # Example
     /// ```rust
-    /// use safe_regex::{regex, Matcher3};
-    /// let matcher: Matcher3<_> = regex!(br"([abc])([0-9]*)(suffix)?");
-    /// let (prefix, digits, suffix) = matcher.match_slices(b"a42").unwrap();
-    /// assert_eq!(b"a", prefix);
-    /// assert_eq!(b"42", digits);
-    /// assert!(suffix.is_empty());
+    /// use safe_regex::{regex, Matcher2};
+    /// let matcher: Matcher2<_> = regex!(br"x(a)?()");
+    /// let [a, empty] = matcher.match_slices_opt(b"x").unwrap();
+    /// assert_eq!(None, a);
+    /// assert_eq!(Some(b"".as_ref()), empty);
     /// ```
     #[must_use]
-    pub fn match_slices<'d>(&self, data: &'d [u8]) -> Option<(&'d [u8],)> {
-        let [r0] = (self.f)(data)?;
-        Some((&data[r0],))
-    }
-}
-impl<F: Fn(&[u8]) -> Option<[Range<usize>; 1]>> IsMatch for Matcher1<F> {
-    fn is_match(&self, data: &[u8]) -> bool {
-        self.is_match(data)
-    }
-}
-
-/// A compiled regular expression with 2 capturing groups.
-///
-/// This is a zero-length type.
-/// The `regex!` macro generates a Rust type that implements the regular expression.
-/// This struct holds that type.
-pub struct Matcher2<F>
-where
-    F: Fn(&[u8]) -> Option<[Range<usize>; 2]>,
-{
-    f: F,
-}
-impl<F> Matcher2<F>
-where
-    F: Fn(&[u8]) -> Option<[Range<usize>; 2]>,
-{
-    /// This is used internally by the `regex!` macro.
-    #[must_use]
-    pub fn new(f: F) -> Self {
-        Self { f }
+    pub fn match_slices_opt<'d>(&self, data: &'d [u8]) -> Option<[Option<&'d [u8]>; N]> {
+        let (_, ranges) = (self.f)(data, None)?;
+        Some(ranges.map(|range| range.map(|range| &data[range])))
     }
 
-    /// Returns `true` if `data` matches the regular expression,
-    /// otherwise returns `false`.
+    /// Searches `data` for the first sub-string that matches the regular
+    /// expression.
     ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
+    /// Returns the range of bytes in `data` that matched,
+    /// or `None` if no sub-string of `data` matches.
+    ///
+    /// Of the matches that start at the earliest position, this returns the
+    /// one that the expression prefers, like the `regex` crate.  It tries
+    /// alternatives in order, greedy repetitions as many times as they can
+    /// repeat, and lazy repetitions as few times.
+    /// So `a|ab` matches `a` in `ab`, and `a+?` matches `a` in `aa`.
     ///
     /// # Example
     /// ```rust
-    /// use safe_regex::{regex, Matcher0};
-    /// let matcher: Matcher0<_> = regex!(br"[abc][0-9]*");
-    /// assert!(matcher.is_match(b"a42"));
-    /// assert!(!matcher.is_match(b"X"));
+    /// use safe_regex::{regex, Matcher1};
+    /// let matcher: Matcher1<_> = regex!(br"a([0-9]+)");
+    /// assert_eq!(Some(2..5_usize), matcher.find(b"..a42.."));
+    /// assert_eq!(None, matcher.find(b"..."));
     /// ```
     #[must_use]
-    pub fn is_match(&self, data: &[u8]) -> bool {
-        (self.f)(data).is_some()
+    pub fn find(&self, data: &[u8]) -> Option<Range<usize>> {
+        self.find_at(data, 0)
     }
 
-    /// Executes the regular expression against the byte string `data`.
-    ///
-    /// Returns `Some((Range<u32>,Range<u32>,...))` if the expression matched all of the bytes in `data`.
-    /// The tuple fields are ranges of bytes in `data` that matched capturing
-    /// groups in the expression.
-    /// A capturing group that matches no bytes will produce as a zero-length
-    /// range.
+    /// Searches `data` for the first sub-string that matches the regular
+    /// expression and starts at or after byte `start`.
     ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
+    /// The returned range is relative to the beginning of `data`.
     ///
-    /// Returns `None` if the expression did not match `data`.
+    /// Returns `None` if no such sub-string exists or if `start` is past the
+    /// end of `data`.
     ///
     /// # Example
     /// ```rust
-    /// use safe_regex::{regex, Matcher3};
-    /// let matcher: Matcher3<_> = regex!(br"([abc])([0-9]*)(suffix)?");
-    /// let (prefix, digits, suffix) = matcher.match_ranges(b"a42").unwrap();
-    /// assert_eq!(0..1_usize, prefix);
-    /// assert_eq!(1..3_usize, digits);
-    /// assert_eq!(0..0_usize, suffix);
+    /// use safe_regex::{regex, Matcher1};
+    /// let matcher: Matcher1<_> = regex!(br"a([0-9]+)");
+    /// assert_eq!(Some(3..5_usize), matcher.find_at(b"a1.a2", 1));
+    /// assert_eq!(None, matcher.find_at(b"a1.a2", 4));
     /// ```
     #[must_use]
-    pub fn match_ranges(&self, data: &[u8]) -> Option<(Range<usize>, Range<usize>)> {
-        let [r0, r1] = (self.f)(data)?;
-        Some((r0, r1))
+    pub fn find_at(&self, data: &[u8], start: usize) -> Option<Range<usize>> {
+        (self.f)(data, Some(start)).map(|(range, _)| range)
     }
 
-    /// Executes the regular expression against the byte string `data`.
+    /// Returns an iterator over the non-overlapping sub-strings of `data` that
+    /// match the regular expression.
     ///
-    /// Returns `Some((&[u8],&[u8],...))`
-    /// if the expression matched all of the bytes in `data`.
-    /// The tuple fields are slices of `data` that matched
-    /// capturing groups in the expression.
+    /// The iterator yields the range of bytes of each match.
     ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
+    /// # Example
+    /// ```rust
+    /// use safe_regex::{regex, Matcher1};
+    /// let matcher: Matcher1<_> = regex!(br"a([0-9]+)");
+    /// let ranges: Vec<core::ops::Range<usize>> = matcher.find_iter(b"a1.a23").collect();
+    /// assert_eq!(vec![0..2_usize, 3..6_usize], ranges);
+    /// ```
+    pub fn find_iter<'a>(&'a self, data: &'a [u8]) -> impl Iterator<Item = Range<usize>> + 'a {
+        Matches::new(&self.f, data).map(|(range, _)| range)
+    }
+
+    /// Returns an iterator over the non-overlapping sub-strings of `data` that
+    /// match the regular expression.
     ///
-    /// Returns `None` if the expression did not match `data`.
+    /// The iterator yields the range of bytes of each match,
+    /// together with an array of the ranges of bytes that matched capturing
+    /// groups in the expression.
     ///
     /// # Example
     /// ```rust
-    /// use safe_regex::{regex, Matcher3};
-    /// let matcher: Matcher3<_> = regex!(br"([abc])([0-9]*)(suffix)?");
-    /// let (prefix, digits, suffix) = matcher.match_slices(b"a42").unwrap();
-    /// assert_eq!(b"a", prefix);
-    /// assert_eq!(b"42", digits);
-    /// assert!(suffix.is_empty());
+    /// use safe_regex::{regex, Matcher1};
+    /// let matcher: Matcher1<_> = regex!(br"a([0-9]+)");
+    /// let mut iter = matcher.captures_iter(b"a1.a23");
+    /// assert_eq!(Some((0..2_usize, [1..2_usize])), iter.next());
+    /// assert_eq!(Some((3..6_usize, [4..6_usize])), iter.next());
+    /// assert_eq!(None, iter.next());
     /// ```
-    #[must_use]
-    pub fn match_slices<'d>(&self, data: &'d [u8]) -> Option<(&'d [u8], &'d [u8])> {
-        let [r0, r1] = (self.f)(data)?;
-        Some((&data[r0], &data[r1]))
+    pub fn captures_iter<'a>(
+        &'a self,
+        data: &'a [u8],
+    ) -> impl Iterator<Item = (Range<usize>, [Range<usize>; N])> + 'a {
+        Matches::new(&self.f, data)
+            .map(|(range, groups)| (range, groups.map(|group| group.unwrap_or(0..0))))
     }
 }
-impl<F: Fn(&[u8]) -> Option<[Range<usize>; 2]>> IsMatch for Matcher2<F> {
+impl<F, const N: usize> IsMatch for Matcher<F, N>
+where
+    F: Fn(&[u8], Option<usize>) -> Option<(Range<usize>, [Option<Range<usize>>; N])>,
+{
     fn is_match(&self, data: &[u8]) -> bool {
         self.is_match(data)
     }
 }
+impl<F, const N: usize> Regex for Matcher<F, N>
+where
+    F: Fn(&[u8], Option<usize>) -> Option<(Range<usize>, [Option<Range<usize>>; N])>,
+{
+    fn pattern(&self) -> &str {
+        self.pattern
+    }
+
+    fn group_count(&self) -> usize {
+        N
+    }
+
+    fn match_ranges_into(&self, data: &[u8], ranges: &mut [Range<usize>]) -> bool {
+        match_ranges_into(&self.f, data, ranges)
+    }
+}
 
+/// A compiled regular expression with no capturing groups.
+pub type Matcher0<F> = Matcher<F, 0>;
+/// A compiled regular expression with 1 capturing group.
+pub type Matcher1<F> = Matcher<F, 1>;
+/// A compiled regular expression with 2 capturing groups.
+pub type Matcher2<F> = Matcher<F, 2>;
 /// A compiled regular expression with 3 capturing groups.
+pub type Matcher3<F> = Matcher<F, 3>;
+/// A compiled regular expression with 4 capturing groups.
+pub type Matcher4<F> = Matcher<F, 4>;
+/// A compiled regular expression with 5 capturing groups.
+pub type Matcher5<F> = Matcher<F, 5>;
+/// A compiled regular expression with 6 capturing groups.
+pub type Matcher6<F> = Matcher<F, 6>;
+/// A compiled regular expression with 7 capturing groups.
+pub type Matcher7<F> = Matcher<F, 7>;
+/// A compiled regular expression with 8 capturing groups.
+pub type Matcher8<F> = Matcher<F, 8>;
+/// A compiled regular expression with 9 capturing groups.
+pub type Matcher9<F> = Matcher<F, 9>;
+/// A compiled regular expression with 10 capturing groups.
+pub type Matcher10<F> = Matcher<F, 10>;
+
+/// A compiled regular expression with `N` capturing groups that matches
+/// strings.
 ///
-/// This is a zero-length type.
-/// The `regex!` macro generates a Rust type that implements the regular expression.
-/// This struct holds that type.
-pub struct Matcher3<F>
+/// The `regex!` macro returns this struct when the expression is a raw
+/// string like `r"..."`.
+/// The expression matches whole UTF-8 code points,
+/// so every range it returns starts and ends on a `char` boundary.
+pub struct StrMatcher<F, const N: usize>
 where
-    F: Fn(&[u8]) -> Option<[Range<usize>; 3]>,
+    F: Fn(&[u8], Option<usize>) -> Option<(Range<usize>, [Option<Range<usize>>; N])>,
 {
+    pattern: &'static str,
     f: F,
 }
-impl<F> Matcher3<F>
+impl<F, const N: usize> StrMatcher<F, N>
 where
-    F: Fn(&[u8]) -> Option<[Range<usize>; 3]>,
+    F: Fn(&[u8], Option<usize>) -> Option<(Range<usize>, [Option<Range<usize>>; N])>,
 {
-    /// This is used internally by the `regex!` macro.
+    /// This is used internally by the `regex!` and `define_regex!` macros.
     #[must_use]
-    pub fn new(f: F) -> Self {
-        Self { f }
+    pub const fn new(pattern: &'static str, f: F) -> Self {
+        Self { pattern, f }
     }
 
     /// Returns `true` if `data` matches the regular expression,
     /// otherwise returns `false`.
     ///
     /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
+    /// For sub-string search, use [`find`](#method.find).
     ///
     /// # Example
     /// ```rust
-    /// use safe_regex::{regex, Matcher0};
-    /// let matcher: Matcher0<_> = regex!(br"[abc][0-9]*");
-    /// assert!(matcher.is_match(b"a42"));
-    /// assert!(!matcher.is_match(b"X"));
+    /// use safe_regex::{regex, StrMatcher0};
+    /// let matcher: StrMatcher0<_> = regex!(r"caf[eé]");
+    /// assert!(matcher.is_match("café"));
+    /// assert!(!matcher.is_match("cafe\u{301}"));
     /// ```
     #[must_use]
-    pub fn is_match(&self, data: &[u8]) -> bool {
-        (self.f)(data).is_some()
+    pub fn is_match(&self, data: &str) -> bool {
+        (self.f)(data.as_bytes(), None).is_some()
     }
 
-    /// Executes the regular expression against the byte string `data`.
+    /// Executes the regular expression against the string `data`.
     ///
-    /// Returns `Some((Range<u32>,Range<u32>,...))` if the expression matched all of the bytes in `data`.
-    /// The tuple fields are ranges of bytes in `data` that matched capturing
+    /// Returns `Some([Range<usize>,Range<usize>,...])` if the expression matched all of `data`.
+    /// The array elements are ranges of bytes in `data` that matched capturing
     /// groups in the expression.
-    /// A capturing group that matches no bytes will produce as a zero-length
-    /// range.
-    ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
+    /// A capturing group that did not participate in the match produces `0..0`.
     ///
     /// Returns `None` if the expression did not match `data`.
     ///
     /// # Example
     /// ```rust
-    /// use safe_regex::{regex, Matcher3};
-    /// let matcher: Matcher3<_> = regex!(br"([abc])([0-9]*)(suffix)?");
-    /// let (prefix, digits, suffix) = matcher.match_ranges(b"a42").unwrap();
-    /// assert_eq!(0..1_usize, prefix);
-    /// assert_eq!(1..3_usize, digits);
-    /// assert_eq!(0..0_usize, suffix);
+    /// use safe_regex::{regex, StrMatcher2};
+    /// let matcher: StrMatcher2<_> = regex!(r"(.)(.*)");
+    /// let [first, rest] = matcher.match_ranges("été").unwrap();
+    /// assert_eq!(0..2_usize, first);
+    /// assert_eq!(2..5_usize, rest);
     /// ```
     #[must_use]
-    pub fn match_ranges(&self, data: &[u8]) -> Option<(Range<usize>, Range<usize>, Range<usize>)> {
-        let [r0, r1, r2] = (self.f)(data)?;
-        Some((r0, r1, r2))
+    pub fn match_ranges(&self, data: &str) -> Option<[Range<usize>; N]> {
+        let (_, ranges) = (self.f)(data.as_bytes(), None)?;
+        Some(ranges.map(|range| range.unwrap_or(0..0)))
     }
 
-    /// Executes the regular expression against the byte string `data`.
+    /// Executes the regular expression against the string `data`.
     ///
-    /// Returns `Some((&[u8],&[u8],...))`
-    /// if the expression matched all of the bytes in `data`.
-    /// The tuple fields are slices of `data` that matched
-    /// capturing groups in the expression.
+    /// Returns `Some([Option<Range<usize>>,...])` if the expression matched
+    /// all of `data`.
+    /// A capturing group that did not participate in the match produces
+    /// `None`.
     ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
+    /// Returns `None` if the expression did not match `data`.
+    #[must_use]
+    pub fn match_ranges_opt(&self, data: &str) -> Option<[Option<Range<usize>>; N]> {
+        let (_, ranges) = (self.f)(data.as_bytes(), None)?;
+        Some(ranges)
+    }
+
+    /// Executes the regular expression against the string `data`.
+    ///
+    /// Returns `Some([&str,&str,...])` if the expression matched all of
+    /// `data`.
+    /// The array elements are slices of `data` that matched
+    /// capturing groups in the expression.
     ///
     /// Returns `None` if the expression did not match `data`.
     ///
     /// # Example
     /// ```rust
-    /// use safe_regex::{regex, Matcher3};
-    /// let matcher: Matcher3<_> = regex!(br"([abc])([0-9]*)(suffix)?");
-    /// let (prefix, digits, suffix) = matcher.match_slices(b"a42").unwrap();
-    /// assert_eq!(b"a", prefix);
-    /// assert_eq!(b"42", digits);
-    /// assert!(suffix.is_empty());
+    /// use safe_regex::{regex, StrMatcher2};
+    /// let matcher: StrMatcher2<_> = regex!(r"([^:]+):(.*)");
+    /// let [key, value] = matcher.match_slices("clé:värde").unwrap();
+    /// assert_eq!("clé", key);
+    /// assert_eq!("värde", value);
     /// ```
     #[must_use]
-    pub fn match_slices<'d>(&self, data: &'d [u8]) -> Option<(&'d [u8], &'d [u8], &'d [u8])> {
-        let [r0, r1, r2] = (self.f)(data)?;
-        Some((&data[r0], &data[r1], &data[r2]))
-    }
-}
-impl<F: Fn(&[u8]) -> Option<[Range<usize>; 3]>> IsMatch for Matcher3<F> {
-    fn is_match(&self, data: &[u8]) -> bool {
-        self.is_match(data)
+    pub fn match_slices<'d>(&self, data: &'d str) -> Option<[&'d str; N]> {
+        let (_, ranges) = (self.f)(data.as_bytes(), None)?;
+        Some(ranges.map(|range| &data[range.unwrap_or(0..0)]))
     }
-}
 
-/// A compiled regular expression with 4 capturing groups.
-///
-/// This is a zero-length type.
-/// The `regex!` macro generates a Rust type that implements the regular expression.
-/// This struct holds that type.
-pub struct Matcher4<F>
-where
-    F: Fn(&[u8]) -> Option<[Range<usize>; 4]>,
-{
-    f: F,
-}
-impl<F> Matcher4<F>
-where
-    F: Fn(&[u8]) -> Option<[Range<usize>; 4]>,
-{
-    /// This is used internally by the `regex!` macro.
+    /// Executes the regular expression against the string `data`.
+    ///
+    /// Returns `Some([Option<&str>,...])` if the expression matched all of
+    /// `data`.
+    /// A capturing group that did not participate in the match produces
+    /// `None`.
+    ///
+    /// Returns `None` if the expression did not match `data`.
     #[must_use]
-    pub fn new(f: F) -> Self {
-        Self { f }
+    pub fn match_slices_opt<'d>(&self, data: &'d str) -> Option<[Option<&'d str>; N]> {
+        let (_, ranges) = (self.f)(data.as_bytes(), None)?;
+        Some(ranges.map(|range| range.map(|range| &data[range])))
     }
 
-    /// Returns `true` if `data` matches the regular expression,
-    /// otherwise returns `false`.
+    /// Searches `data` for the first sub-string that matches the regular
+    /// expression.
     ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
+    /// Returns the range of bytes in `data` that matched,
+    /// or `None` if no sub-string of `data` matches.
     ///
     /// # Example
     /// ```rust
-    /// use safe_regex::{regex, Matcher0};
-    /// let matcher: Matcher0<_> = regex!(br"[abc][0-9]*");
-    /// assert!(matcher.is_match(b"a42"));
-    /// assert!(!matcher.is_match(b"X"));
+    /// use safe_regex::{regex, StrMatcher0};
+    /// let matcher: StrMatcher0<_> = regex!(r"ö+");
+    /// assert_eq!(Some(1..5_usize), matcher.find("jöös"));
     /// ```
     #[must_use]
-    pub fn is_match(&self, data: &[u8]) -> bool {
-        (self.f)(data).is_some()
+    pub fn find(&self, data: &str) -> Option<Range<usize>> {
+        self.find_at(data, 0)
     }
 
-    /// Executes the regular expression against the byte string `data`.
+    /// Searches `data` for the first sub-string that matches the regular
+    /// expression and starts at or after byte `start`.
     ///
-    /// Returns `Some((Range<u32>,Range<u32>,...))` if the expression matched all of the bytes in `data`.
-    /// The tuple fields are ranges of bytes in `data` that matched capturing
-    /// groups in the expression.
-    /// A capturing group that matches no bytes will produce as a zero-length
-    /// range.
-    ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
-    ///
-    /// Returns `None` if the expression did not match `data`.
+    /// When `start` is inside a multi-byte `char`, the search starts at the
+    /// next `char` boundary.
     ///
-    /// # Example
-    /// ```rust
-    /// use safe_regex::{regex, Matcher3};
-    /// let matcher: Matcher3<_> = regex!(br"([abc])([0-9]*)(suffix)?");
-    /// let (prefix, digits, suffix) = matcher.match_ranges(b"a42").unwrap();
-    /// assert_eq!(0..1_usize, prefix);
-    /// assert_eq!(1..3_usize, digits);
-    /// assert_eq!(0..0_usize, suffix);
-    /// ```
+    /// Returns `None` if no such sub-string exists or if `start` is past the
+    /// end of `data`.
     #[must_use]
-    pub fn match_ranges(
-        &self,
-        data: &[u8],
-    ) -> Option<(Range<usize>, Range<usize>, Range<usize>, Range<usize>)> {
-        let [r0, r1, r2, r3] = (self.f)(data)?;
-        Some((r0, r1, r2, r3))
+    pub fn find_at(&self, data: &str, start: usize) -> Option<Range<usize>> {
+        let start = next_char_boundary(data.as_bytes(), start);
+        (self.f)(data.as_bytes(), Some(start)).map(|(range, _)| range)
     }
 
-    /// Executes the regular expression against the byte string `data`.
+    /// Returns an iterator over the non-overlapping sub-strings of `data` that
+    /// match the regular expression.
     ///
-    /// Returns `Some((&[u8],&[u8],...))`
-    /// if the expression matched all of the bytes in `data`.
-    /// The tuple fields are slices of `data` that matched
-    /// capturing groups in the expression.
-    ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
-    ///
-    /// Returns `None` if the expression did not match `data`.
+    /// The iterator yields the range of bytes of each match.
     ///
     /// # Example
     /// ```rust
-    /// use safe_regex::{regex, Matcher3};
-    /// let matcher: Matcher3<_> = regex!(br"([abc])([0-9]*)(suffix)?");
-    /// let (prefix, digits, suffix) = matcher.match_slices(b"a42").unwrap();
-    /// assert_eq!(b"a", prefix);
-    /// assert_eq!(b"42", digits);
-    /// assert!(suffix.is_empty());
+    /// use safe_regex::{regex, StrMatcher0};
+    /// let matcher: StrMatcher0<_> = regex!(r"[^ ]+");
+    /// let words: Vec<&str> = matcher
+    ///     .find_iter("ça va")
+    ///     .map(|range| &"ça va"[range])
+    ///     .collect();
+    /// assert_eq!(vec!["ça", "va"], words);
     /// ```
-    #[must_use]
-    pub fn match_slices<'d>(
-        &self,
-        data: &'d [u8],
-    ) -> Option<(&'d [u8], &'d [u8], &'d [u8], &'d [u8])> {
-        let [r0, r1, r2, r3] = (self.f)(data)?;
-        Some((&data[r0], &data[r1], &data[r2], &data[r3]))
+    pub fn find_iter<'a>(&'a self, data: &'a str) -> impl Iterator<Item = Range<usize>> + 'a {
+        Matches::new_utf8(&self.f, data.as_bytes()).map(|(range, _)| range)
+    }
+
+    /// Returns an iterator over the non-overlapping sub-strings of `data` that
+    /// match the regular expression.
+    ///
+    /// The iterator yields the range of bytes of each match,
+    /// together with an array of the ranges of bytes that matched capturing
+    /// groups in the expression.
+    pub fn captures_iter<'a>(
+        &'a self,
+        data: &'a str,
+    ) -> impl Iterator<Item = (Range<usize>, [Range<usize>; N])> + 'a {
+        Matches::new_utf8(&self.f, data.as_bytes())
+            .map(|(range, groups)| (range, groups.map(|group| group.unwrap_or(0..0))))
     }
 }
-impl<F: Fn(&[u8]) -> Option<[Range<usize>; 4]>> IsMatch for Matcher4<F> {
-    fn is_match(&self, data: &[u8]) -> bool {
-        self.is_match(data)
+
+impl<F, const N: usize> Regex for StrMatcher<F, N>
+where
+    F: Fn(&[u8], Option<usize>) -> Option<(Range<usize>, [Option<Range<usize>>; N])>,
+{
+    fn pattern(&self) -> &str {
+        self.pattern
+    }
+
+    fn group_count(&self) -> usize {
+        N
+    }
+
+    fn match_ranges_into(&self, data: &[u8], ranges: &mut [Range<usize>]) -> bool {
+        match_ranges_into(&self.f, data, ranges)
     }
 }
 
-/// A compiled regular expression with 5 capturing groups.
+/// A compiled string regular expression with no capturing groups.
+pub type StrMatcher0<F> = StrMatcher<F, 0>;
+/// A compiled string regular expression with 1 capturing group.
+pub type StrMatcher1<F> = StrMatcher<F, 1>;
+/// A compiled string regular expression with 2 capturing groups.
+pub type StrMatcher2<F> = StrMatcher<F, 2>;
+/// A compiled string regular expression with 3 capturing groups.
+pub type StrMatcher3<F> = StrMatcher<F, 3>;
+/// A compiled string regular expression with 4 capturing groups.
+pub type StrMatcher4<F> = StrMatcher<F, 4>;
+/// A compiled string regular expression with 5 capturing groups.
+pub type StrMatcher5<F> = StrMatcher<F, 5>;
+/// A compiled string regular expression with 6 capturing groups.
+pub type StrMatcher6<F> = StrMatcher<F, 6>;
+/// A compiled string regular expression with 7 capturing groups.
+pub type StrMatcher7<F> = StrMatcher<F, 7>;
+/// A compiled string regular expression with 8 capturing groups.
+pub type StrMatcher8<F> = StrMatcher<F, 8>;
+/// A compiled string regular expression with 9 capturing groups.
+pub type StrMatcher9<F> = StrMatcher<F, 9>;
+/// A compiled string regular expression with 10 capturing groups.
+pub type StrMatcher10<F> = StrMatcher<F, 10>;
+
+/// Several compiled regular expressions that match together.
 ///
-/// This is a zero-length type.
-/// The `regex!` macro generates a Rust type that implements the regular expression.
-/// This struct holds that type.
-pub struct Matcher5<F>
+/// The `regex_set!` macro returns this struct.
+/// `N` is the number of expressions.
+/// The set checks all of them in one pass over the data,
+/// so it is faster than checking them one at a time.
+/// It does not allocate.
+pub struct RegexSet<F, const N: usize>
 where
-    F: Fn(&[u8]) -> Option<[Range<usize>; 5]>,
+    F: Fn(&[u8]) -> [bool; N],
 {
     f: F,
 }
-impl<F> Matcher5<F>
+impl<F, const N: usize> RegexSet<F, N>
 where
-    F: Fn(&[u8]) -> Option<[Range<usize>; 5]>,
+    F: Fn(&[u8]) -> [bool; N],
 {
-    /// This is used internally by the `regex!` macro.
+    /// This is used internally by the `regex_set!` macro.
     #[must_use]
     pub fn new(f: F) -> Self {
         Self { f }
     }
 
-    /// Returns `true` if `data` matches the regular expression,
-    /// otherwise returns `false`.
-    ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
+    /// Returns the expressions that match all of `data`.
     ///
     /// # Example
     /// ```rust
-    /// use safe_regex::{regex, Matcher0};
-    /// let matcher: Matcher0<_> = regex!(br"[abc][0-9]*");
-    /// assert!(matcher.is_match(b"a42"));
-    /// assert!(!matcher.is_match(b"X"));
+    /// use safe_regex::{regex_set, RegexSet};
+    /// let set: RegexSet<_, 3> = regex_set!(br"a.*", br".*b", br"c");
+    /// let matches = set.matches(b"ab");
+    /// assert!(matches.matched(0));
+    /// assert!(matches.matched(1));
+    /// assert!(!matches.matched(2));
+    /// assert_eq!(vec![0, 1], matches.iter().collect::<Vec<usize>>());
     /// ```
     #[must_use]
-    pub fn is_match(&self, data: &[u8]) -> bool {
-        (self.f)(data).is_some()
-    }
-
-    /// Executes the regular expression against the byte string `data`.
-    ///
-    /// Returns `Some((Range<u32>,Range<u32>,...))` if the expression matched all of the bytes in `data`.
-    /// The tuple fields are ranges of bytes in `data` that matched capturing
-    /// groups in the expression.
-    /// A capturing group that matches no bytes will produce as a zero-length
-    /// range.
-    ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
-    ///
-    /// Returns `None` if the expression did not match `data`.
-    ///
-    /// # Example
-    /// ```rust
-    /// use safe_regex::{regex, Matcher3};
-    /// let matcher: Matcher3<_> = regex!(br"([abc])([0-9]*)(suffix)?");
-    /// let (prefix, digits, suffix) = matcher.match_ranges(b"a42").unwrap();
-    /// assert_eq!(0..1_usize, prefix);
-    /// assert_eq!(1..3_usize, digits);
-    /// assert_eq!(0..0_usize, suffix);
-    /// ```
-    #[must_use]
-    pub fn match_ranges(
-        &self,
-        data: &[u8],
-    ) -> Option<(
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-    )> {
-        let [r0, r1, r2, r3, r4] = (self.f)(data)?;
-        Some((r0, r1, r2, r3, r4))
+    pub fn matches(&self, data: &[u8]) -> SetMatches<N> {
+        SetMatches {
+            matched: (self.f)(data),
+        }
     }
 
-    /// Executes the regular expression against the byte string `data`.
-    ///
-    /// Returns `Some((&[u8],&[u8],...))`
-    /// if the expression matched all of the bytes in `data`.
-    /// The tuple fields are slices of `data` that matched
-    /// capturing groups in the expression.
-    ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
-    ///
-    /// Returns `None` if the expression did not match `data`.
-    ///
-    /// # Example
-    /// ```rust
-    /// use safe_regex::{regex, Matcher3};
-    /// let matcher: Matcher3<_> = regex!(br"([abc])([0-9]*)(suffix)?");
-    /// let (prefix, digits, suffix) = matcher.match_slices(b"a42").unwrap();
-    /// assert_eq!(b"a", prefix);
-    /// assert_eq!(b"42", digits);
-    /// assert!(suffix.is_empty());
-    /// ```
+    /// Returns `true` if any of the expressions match all of `data`.
     #[must_use]
-    pub fn match_slices<'d>(
-        &self,
-        data: &'d [u8],
-    ) -> Option<(&'d [u8], &'d [u8], &'d [u8], &'d [u8], &'d [u8])> {
-        let [r0, r1, r2, r3, r4] = (self.f)(data)?;
-        Some((&data[r0], &data[r1], &data[r2], &data[r3], &data[r4]))
+    pub fn is_match(&self, data: &[u8]) -> bool {
+        self.matches(data).matched_any()
     }
 }
-impl<F: Fn(&[u8]) -> Option<[Range<usize>; 5]>> IsMatch for Matcher5<F> {
+impl<F, const N: usize> IsMatch for RegexSet<F, N>
+where
+    F: Fn(&[u8]) -> [bool; N],
+{
     fn is_match(&self, data: &[u8]) -> bool {
         self.is_match(data)
     }
 }
 
-/// A compiled regular expression with 6 capturing groups.
-///
-/// This is a zero-length type.
-/// The `regex!` macro generates a Rust type that implements the regular expression.
-/// This struct holds that type.
-pub struct Matcher6<F>
-where
-    F: Fn(&[u8]) -> Option<[Range<usize>; 6]>,
-{
-    f: F,
+/// The expressions of a [`RegexSet`](struct.RegexSet.html) that matched.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SetMatches<const N: usize> {
+    matched: [bool; N],
 }
-impl<F> Matcher6<F>
-where
-    F: Fn(&[u8]) -> Option<[Range<usize>; 6]>,
-{
-    /// This is used internally by the `regex!` macro.
+impl<const N: usize> SetMatches<N> {
+    /// Returns `true` if expression number `index` matched.
+    ///
+    /// # Panics
+    /// Panics when `index` is not less than `N`.
     #[must_use]
-    pub fn new(f: F) -> Self {
-        Self { f }
+    pub fn matched(&self, index: usize) -> bool {
+        self.matched[index]
     }
 
-    /// Returns `true` if `data` matches the regular expression,
-    /// otherwise returns `false`.
-    ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
-    ///
-    /// # Example
-    /// ```rust
-    /// use safe_regex::{regex, Matcher0};
-    /// let matcher: Matcher0<_> = regex!(br"[abc][0-9]*");
-    /// assert!(matcher.is_match(b"a42"));
-    /// assert!(!matcher.is_match(b"X"));
-    /// ```
+    /// Returns `true` if any of the expressions matched.
     #[must_use]
-    pub fn is_match(&self, data: &[u8]) -> bool {
-        (self.f)(data).is_some()
+    pub fn matched_any(&self) -> bool {
+        self.matched.contains(&true)
     }
 
-    /// Executes the regular expression against the byte string `data`.
-    ///
-    /// Returns `Some((Range<u32>,Range<u32>,...))` if the expression matched all of the bytes in `data`.
-    /// The tuple fields are ranges of bytes in `data` that matched capturing
-    /// groups in the expression.
-    /// A capturing group that matches no bytes will produce as a zero-length
-    /// range.
-    ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
-    ///
-    /// Returns `None` if the expression did not match `data`.
-    ///
-    /// # Example
-    /// ```rust
-    /// use safe_regex::{regex, Matcher3};
-    /// let matcher: Matcher3<_> = regex!(br"([abc])([0-9]*)(suffix)?");
-    /// let (prefix, digits, suffix) = matcher.match_ranges(b"a42").unwrap();
-    /// assert_eq!(0..1_usize, prefix);
-    /// assert_eq!(1..3_usize, digits);
-    /// assert_eq!(0..0_usize, suffix);
-    /// ```
+    /// Returns the number of expressions in the set.
     #[must_use]
-    pub fn match_ranges(
-        &self,
-        data: &[u8],
-    ) -> Option<(
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-    )> {
-        let [r0, r1, r2, r3, r4, r5] = (self.f)(data)?;
-        Some((r0, r1, r2, r3, r4, r5))
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        N
     }
 
-    /// Executes the regular expression against the byte string `data`.
-    ///
-    /// Returns `Some((&[u8],&[u8],...))`
-    /// if the expression matched all of the bytes in `data`.
-    /// The tuple fields are slices of `data` that matched
-    /// capturing groups in the expression.
-    ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
-    ///
-    /// Returns `None` if the expression did not match `data`.
-    ///
-    /// # Example
-    /// ```rust
-    /// use safe_regex::{regex, Matcher3};
-    /// let matcher: Matcher3<_> = regex!(br"([abc])([0-9]*)(suffix)?");
-    /// let (prefix, digits, suffix) = matcher.match_slices(b"a42").unwrap();
-    /// assert_eq!(b"a", prefix);
-    /// assert_eq!(b"42", digits);
-    /// assert!(suffix.is_empty());
-    /// ```
-    #[must_use]
-    pub fn match_slices<'d>(
-        &self,
-        data: &'d [u8],
-    ) -> Option<(&'d [u8], &'d [u8], &'d [u8], &'d [u8], &'d [u8], &'d [u8])> {
-        let [r0, r1, r2, r3, r4, r5] = (self.f)(data)?;
-        Some((
-            &data[r0], &data[r1], &data[r2], &data[r3], &data[r4], &data[r5],
-        ))
+    /// Returns an iterator over the indices of the expressions that matched,
+    /// in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> {
+        let matched = self.matched;
+        (0..N).filter(move |index| matched[*index])
     }
-}
-impl<F: Fn(&[u8]) -> Option<[Range<usize>; 6]>> IsMatch for Matcher6<F> {
-    fn is_match(&self, data: &[u8]) -> bool {
-        self.is_match(data)
+
+    /// Returns an array with `true` for each expression that matched.
+    #[must_use]
+    pub fn into_array(self) -> [bool; N] {
+        self.matched
     }
 }
 
-/// A compiled regular expression with 7 capturing groups.
+/// A lexer that splits data into tokens of type `T`.
 ///
-/// This is a zero-length type.
-/// The `regex!` macro generates a Rust type that implements the regular expression.
-/// This struct holds that type.
-pub struct Matcher7<F>
+/// The `lexer!` macro returns this struct.
+pub struct Lexer<T, F>
 where
-    F: Fn(&[u8]) -> Option<[Range<usize>; 7]>,
+    F: Fn(&[u8], usize) -> Option<(T, usize)>,
 {
     f: F,
+    phantom: PhantomData<fn() -> T>,
 }
-impl<F> Matcher7<F>
+impl<T, F> Lexer<T, F>
 where
-    F: Fn(&[u8]) -> Option<[Range<usize>; 7]>,
+    F: Fn(&[u8], usize) -> Option<(T, usize)>,
 {
-    /// This is used internally by the `regex!` macro.
+    /// This is used internally by the `lexer!` macro.
     #[must_use]
     pub fn new(f: F) -> Self {
-        Self { f }
+        Self {
+            f,
+            phantom: PhantomData,
+        }
     }
 
-    /// Returns `true` if `data` matches the regular expression,
-    /// otherwise returns `false`.
+    /// Returns an iterator over the tokens in `data`.
     ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
+    /// The iterator yields each token with the range of bytes in `data` that
+    /// it matched.
+    /// Each token is the longest non-empty match of any rule.
+    /// When several rules match the same bytes, the first rule wins.
+    ///
+    /// When no rule matches at some position, the iterator yields a
+    /// [`LexError`](struct.LexError.html) with the position and then ends.
     ///
     /// # Example
     /// ```rust
-    /// use safe_regex::{regex, Matcher0};
-    /// let matcher: Matcher0<_> = regex!(br"[abc][0-9]*");
-    /// assert!(matcher.is_match(b"a42"));
-    /// assert!(!matcher.is_match(b"X"));
+    /// use safe_regex::{lexer, LexError};
+    /// #[derive(Debug, PartialEq)]
+    /// enum Token {
+    ///     Num,
+    ///     Plus,
+    /// }
+    /// let lexer = lexer!(Token::Num => br"[0-9]+", Token::Plus => br"\+");
+    /// let mut tokens = lexer.tokens(b"12+3?");
+    /// assert_eq!(Some(Ok((Token::Num, 0..2))), tokens.next());
+    /// assert_eq!(Some(Ok((Token::Plus, 2..3))), tokens.next());
+    /// assert_eq!(Some(Ok((Token::Num, 3..4))), tokens.next());
+    /// assert_eq!(Some(Err(LexError { position: 4 })), tokens.next());
+    /// assert_eq!(None, tokens.next());
     /// ```
-    #[must_use]
-    pub fn is_match(&self, data: &[u8]) -> bool {
-        (self.f)(data).is_some()
+    pub fn tokens<'a>(&'a self, data: &'a [u8]) -> Tokens<'a, T, F> {
+        Tokens {
+            f: &self.f,
+            data,
+            next_start: 0,
+            failed: false,
+            phantom: PhantomData,
+        }
     }
+}
 
-    /// Executes the regular expression against the byte string `data`.
-    ///
-    /// Returns `Some((Range<u32>,Range<u32>,...))` if the expression matched all of the bytes in `data`.
-    /// The tuple fields are ranges of bytes in `data` that matched capturing
-    /// groups in the expression.
-    /// A capturing group that matches no bytes will produce as a zero-length
-    /// range.
-    ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
-    ///
-    /// Returns `None` if the expression did not match `data`.
-    ///
-    /// # Example
-    /// ```rust
-    /// use safe_regex::{regex, Matcher3};
-    /// let matcher: Matcher3<_> = regex!(br"([abc])([0-9]*)(suffix)?");
-    /// let (prefix, digits, suffix) = matcher.match_ranges(b"a42").unwrap();
-    /// assert_eq!(0..1_usize, prefix);
-    /// assert_eq!(1..3_usize, digits);
-    /// assert_eq!(0..0_usize, suffix);
-    /// ```
-    #[must_use]
-    pub fn match_ranges(
-        &self,
-        data: &[u8],
-    ) -> Option<(
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-    )> {
-        let [r0, r1, r2, r3, r4, r5, r6] = (self.f)(data)?;
-        Some((r0, r1, r2, r3, r4, r5, r6))
+/// An iterator over the tokens of some data.
+///
+/// [`Lexer::tokens`](struct.Lexer.html#method.tokens) returns this struct.
+pub struct Tokens<'a, T, F> {
+    f: &'a F,
+    data: &'a [u8],
+    next_start: usize,
+    failed: bool,
+    phantom: PhantomData<fn() -> T>,
+}
+impl<T, F> Iterator for Tokens<'_, T, F>
+where
+    F: Fn(&[u8], usize) -> Option<(T, usize)>,
+{
+    type Item = Result<(T, Range<usize>), LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.next_start >= self.data.len() {
+            return None;
+        }
+        let start = self.next_start;
+        if let Some((token, end)) = (self.f)(self.data, start) {
+            self.next_start = end;
+            Some(Ok((token, start..end)))
+        } else {
+            self.failed = true;
+            Some(Err(LexError { position: start }))
+        }
     }
+}
 
-    /// Executes the regular expression against the byte string `data`.
-    ///
-    /// Returns `Some((&[u8],&[u8],...))`
-    /// if the expression matched all of the bytes in `data`.
-    /// The tuple fields are slices of `data` that matched
-    /// capturing groups in the expression.
-    ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
-    ///
-    /// Returns `None` if the expression did not match `data`.
-    ///
-    /// # Example
-    /// ```rust
-    /// use safe_regex::{regex, Matcher3};
-    /// let matcher: Matcher3<_> = regex!(br"([abc])([0-9]*)(suffix)?");
-    /// let (prefix, digits, suffix) = matcher.match_slices(b"a42").unwrap();
-    /// assert_eq!(b"a", prefix);
-    /// assert_eq!(b"42", digits);
-    /// assert!(suffix.is_empty());
-    /// ```
-    #[must_use]
-    pub fn match_slices<'d>(
-        &self,
-        data: &'d [u8],
-    ) -> Option<(
-        &'d [u8],
-        &'d [u8],
-        &'d [u8],
-        &'d [u8],
-        &'d [u8],
-        &'d [u8],
-        &'d [u8],
-    )> {
-        let [r0, r1, r2, r3, r4, r5, r6] = (self.f)(data)?;
-        Some((
-            &data[r0], &data[r1], &data[r2], &data[r3], &data[r4], &data[r5], &data[r6],
-        ))
+/// No lexer rule matches the data at `position`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LexError {
+    pub position: usize,
+}
+impl core::fmt::Display for LexError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "no token matches at byte {}", self.position)
     }
 }
-impl<F: Fn(&[u8]) -> Option<[Range<usize>; 7]>> IsMatch for Matcher7<F> {
-    fn is_match(&self, data: &[u8]) -> bool {
-        self.is_match(data)
+impl core::error::Error for LexError {}
+
+/// The error that `from_bytes` functions and `FromStr` implementations made
+/// by `#[derive(FromRegex)]` return.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FromRegexError {
+    /// The regular expression did not match the data.
+    NoMatch,
+    /// The capture for the named field did not convert into the field's type.
+    /// Fields of tuple structs have names like `"0"`.
+    InvalidField(&'static str),
+}
+impl core::fmt::Display for FromRegexError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            FromRegexError::NoMatch => write!(f, "data does not match the regular expression"),
+            FromRegexError::InvalidField(name) => write!(f, "invalid value for field `{name}`"),
+        }
     }
 }
+impl core::error::Error for FromRegexError {}
 
-/// A compiled regular expression with 8 capturing groups.
+/// A compiled regular expression with `N` capturing groups that matches input
+/// which arrives in chunks.
 ///
-/// This is a zero-length type.
-/// The `regex!` macro generates a Rust type that implements the regular expression.
-/// This struct holds that type.
-pub struct Matcher8<F>
+/// The `regex_stream!` macro returns this struct.
+/// It holds the state of the matcher between chunks, and never the input,
+/// so its size does not depend on the length of the input.
+/// It does not allocate.
+///
+/// This is a whole-input match.
+/// To find a match anywhere in the input, surround the expression with
+/// `.*?` and `.*`, like `regex_stream!(br".*?(needle).*")`.
+///
+/// Capture ranges are `u64` byte positions counted from the first byte pushed
+/// since the matcher was created or reset.
+#[derive(Clone)]
+pub struct StreamMatcher<S, const N: usize>
 where
-    F: Fn(&[u8]) -> Option<[Range<usize>; 8]>,
+    S: internal::StreamState<N>,
 {
-    f: F,
+    state: S,
 }
-impl<F> Matcher8<F>
+impl<S, const N: usize> StreamMatcher<S, N>
 where
-    F: Fn(&[u8]) -> Option<[Range<usize>; 8]>,
+    S: internal::StreamState<N>,
 {
-    /// This is used internally by the `regex!` macro.
+    /// This is used internally by the `regex_stream!` macro.
     #[must_use]
-    pub fn new(f: F) -> Self {
-        Self { f }
+    pub fn new(state: S) -> Self {
+        Self { state }
     }
 
-    /// Returns `true` if `data` matches the regular expression,
-    /// otherwise returns `false`.
-    ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
+    /// Feeds the next chunk of input to the matcher.
     ///
-    /// # Example
-    /// ```rust
-    /// use safe_regex::{regex, Matcher0};
-    /// let matcher: Matcher0<_> = regex!(br"[abc][0-9]*");
-    /// assert!(matcher.is_match(b"a42"));
-    /// assert!(!matcher.is_match(b"X"));
-    /// ```
-    #[must_use]
-    pub fn is_match(&self, data: &[u8]) -> bool {
-        (self.f)(data).is_some()
+    /// Once the matcher knows that the input cannot match, it ignores the
+    /// rest of the input and returns quickly.
+    pub fn push(&mut self, chunk: &[u8]) {
+        self.state.push(chunk);
     }
 
-    /// Executes the regular expression against the byte string `data`.
+    /// Ends the input.
     ///
-    /// Returns `Some((Range<u32>,Range<u32>,...))` if the expression matched all of the bytes in `data`.
-    /// The tuple fields are ranges of bytes in `data` that matched capturing
+    /// Returns `Some([Range<u64>,Range<u64>,...])` if the expression matched
+    /// all of the pushed bytes.
+    /// The array elements are the ranges of bytes that matched capturing
     /// groups in the expression.
-    /// A capturing group that matches no bytes will produce as a zero-length
-    /// range.
-    ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
+    /// A capturing group that did not participate in the match produces `0..0`.
+    /// To tell these apart from empty matches, use
+    /// [`finish_opt`](#method.finish_opt).
     ///
-    /// Returns `None` if the expression did not match `data`.
+    /// Returns `None` if the expression did not match.
     ///
     /// # Example
     /// ```rust
-    /// use safe_regex::{regex, Matcher3};
-    /// let matcher: Matcher3<_> = regex!(br"([abc])([0-9]*)(suffix)?");
-    /// let (prefix, digits, suffix) = matcher.match_ranges(b"a42").unwrap();
-    /// assert_eq!(0..1_usize, prefix);
-    /// assert_eq!(1..3_usize, digits);
-    /// assert_eq!(0..0_usize, suffix);
+    /// use safe_regex::{regex_stream, StreamMatcher2};
+    /// let mut matcher: StreamMatcher2<_> = regex_stream!(br"([a-z]+)=([0-9]*)");
+    /// matcher.push(b"ke");
+    /// matcher.push(b"y=4");
+    /// matcher.push(b"2");
+    /// assert_eq!(Some([0..3_u64, 4..6_u64]), matcher.finish());
     /// ```
     #[must_use]
-    pub fn match_ranges(
-        &self,
-        data: &[u8],
-    ) -> Option<(
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-    )> {
-        let [r0, r1, r2, r3, r4, r5, r6, r7] = (self.f)(data)?;
-        Some((r0, r1, r2, r3, r4, r5, r6, r7))
+    pub fn finish(self) -> Option<[Range<u64>; N]> {
+        Some(self.finish_opt()?.map(|range| range.unwrap_or(0..0)))
     }
 
-    /// Executes the regular expression against the byte string `data`.
-    ///
-    /// Returns `Some((&[u8],&[u8],...))`
-    /// if the expression matched all of the bytes in `data`.
-    /// The tuple fields are slices of `data` that matched
-    /// capturing groups in the expression.
+    /// Ends the input.
     ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
-    ///
-    /// Returns `None` if the expression did not match `data`.
+    /// Returns `Some([Option<Range<u64>>,...])` if the expression matched
+    /// all of the pushed bytes.
+    /// A capturing group that did not participate in the match produces
+    /// `None`.
     ///
-    /// # Example
-    /// ```rust
-    /// use safe_regex::{regex, Matcher3};
-    /// let matcher: Matcher3<_> = regex!(br"([abc])([0-9]*)(suffix)?");
-    /// let (prefix, digits, suffix) = matcher.match_slices(b"a42").unwrap();
-    /// assert_eq!(b"a", prefix);
-    /// assert_eq!(b"42", digits);
-    /// assert!(suffix.is_empty());
-    /// ```
+    /// Returns `None` if the expression did not match.
     #[must_use]
-    pub fn match_slices<'d>(
-        &self,
-        data: &'d [u8],
-    ) -> Option<(
-        &'d [u8],
-        &'d [u8],
-        &'d [u8],
-        &'d [u8],
-        &'d [u8],
-        &'d [u8],
-        &'d [u8],
-        &'d [u8],
-    )> {
-        let [r0, r1, r2, r3, r4, r5, r6, r7] = (self.f)(data)?;
-        Some((
-            &data[r0], &data[r1], &data[r2], &data[r3], &data[r4], &data[r5], &data[r6], &data[r7],
-        ))
+    pub fn finish_opt(self) -> Option<[Option<Range<u64>>; N]> {
+        self.state.finish()
+    }
+
+    /// Forgets all pushed input, so the matcher can match new input.
+    pub fn reset(&mut self) {
+        self.state = S::new();
     }
 }
-impl<F: Fn(&[u8]) -> Option<[Range<usize>; 8]>> IsMatch for Matcher8<F> {
-    fn is_match(&self, data: &[u8]) -> bool {
-        self.is_match(data)
+
+/// A streaming matcher with no capturing groups.
+pub type StreamMatcher0<S> = StreamMatcher<S, 0>;
+/// A streaming matcher with 1 capturing group.
+pub type StreamMatcher1<S> = StreamMatcher<S, 1>;
+/// A streaming matcher with 2 capturing groups.
+pub type StreamMatcher2<S> = StreamMatcher<S, 2>;
+/// A streaming matcher with 3 capturing groups.
+pub type StreamMatcher3<S> = StreamMatcher<S, 3>;
+/// A streaming matcher with 4 capturing groups.
+pub type StreamMatcher4<S> = StreamMatcher<S, 4>;
+/// A streaming matcher with 5 capturing groups.
+pub type StreamMatcher5<S> = StreamMatcher<S, 5>;
+/// A streaming matcher with 6 capturing groups.
+pub type StreamMatcher6<S> = StreamMatcher<S, 6>;
+/// A streaming matcher with 7 capturing groups.
+pub type StreamMatcher7<S> = StreamMatcher<S, 7>;
+/// A streaming matcher with 8 capturing groups.
+pub type StreamMatcher8<S> = StreamMatcher<S, 8>;
+/// A streaming matcher with 9 capturing groups.
+pub type StreamMatcher9<S> = StreamMatcher<S, 9>;
+/// A streaming matcher with 10 capturing groups.
+pub type StreamMatcher10<S> = StreamMatcher<S, 10>;
+
+/// Returns `n` if it is a `char` boundary in the UTF-8 string `data`,
+/// otherwise the next `char` boundary after it.
+fn next_char_boundary(data: &[u8], mut n: usize) -> usize {
+    // UTF-8 continuation bytes look like `0b10xxxxxx`.
+    while n < data.len() && data[n] & 0xC0 == 0x80 {
+        n += 1;
     }
+    n
 }
 
-/// A compiled regular expression with 9 capturing groups.
-///
-/// This is a zero-length type.
-/// The `regex!` macro generates a Rust type that implements the regular expression.
-/// This struct holds that type.
-pub struct Matcher9<F>
-where
-    F: Fn(&[u8]) -> Option<[Range<usize>; 9]>,
-{
+/// An iterator over the non-overlapping matches of a regular expression.
+struct Matches<'a, F, G> {
     f: F,
+    data: &'a [u8],
+    next_start: usize,
+    last_match_end: Option<usize>,
+    /// When `true`, `data` is UTF-8 and searches start only on `char`
+    /// boundaries.
+    utf8: bool,
+    groups: PhantomData<G>,
+}
+impl<'a, F, G> Matches<'a, F, G> {
+    fn new(f: F, data: &'a [u8]) -> Self {
+        Self {
+            f,
+            data,
+            next_start: 0,
+            last_match_end: None,
+            utf8: false,
+            groups: PhantomData,
+        }
+    }
+
+    fn new_utf8(f: F, data: &'a [u8]) -> Self {
+        Self {
+            utf8: true,
+            ..Self::new(f, data)
+        }
+    }
 }
-impl<F> Matcher9<F>
+impl<F, G> Iterator for Matches<'_, F, G>
 where
-    F: Fn(&[u8]) -> Option<[Range<usize>; 9]>,
+    F: Fn(&[u8], Option<usize>) -> Option<(Range<usize>, G)>,
 {
-    /// This is used internally by the `regex!` macro.
-    #[must_use]
-    pub fn new(f: F) -> Self {
-        Self { f }
+    type Item = (Range<usize>, G);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (range, groups) = (self.f)(self.data, Some(self.next_start))?;
+            // Never return an empty match right after the previous match.
+            // Never return the same empty match twice.
+            self.next_start = if range.is_empty() {
+                range.end + 1
+            } else {
+                range.end
+            };
+            if self.utf8 {
+                self.next_start = next_char_boundary(self.data, self.next_start);
+            }
+            if range.is_empty() && self.last_match_end == Some(range.end) {
+                continue;
+            }
+            self.last_match_end = Some(range.end);
+            return Some((range, groups));
+        }
     }
+}
 
-    /// Returns `true` if `data` matches the regular expression,
-    /// otherwise returns `false`.
-    ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
-    ///
-    /// # Example
-    /// ```rust
-    /// use safe_regex::{regex, Matcher0};
-    /// let matcher: Matcher0<_> = regex!(br"[abc][0-9]*");
-    /// assert!(matcher.is_match(b"a42"));
-    /// assert!(!matcher.is_match(b"X"));
-    /// ```
-    #[must_use]
-    pub fn is_match(&self, data: &[u8]) -> bool {
-        (self.f)(data).is_some()
+pub mod internal {
+    use core::marker::PhantomData;
+    use core::ops::Range;
+
+    /// The type of the function inside matchers made by `define_regex!`.
+    pub type MatcherFn<const N: usize> =
+        fn(&[u8], Option<usize>) -> Option<(Range<usize>, [Option<Range<usize>>; N])>;
+
+    /// Converts captures into values of type `T`.
+    ///
+    /// Code made by `#[derive(FromRegex)]` calls
+    /// `(&FieldConverter::<T>::new()).convert(capture)`.
+    /// When `T` implements `FromStr`, the call resolves to
+    /// [`ConvertViaFromStr`](trait.ConvertViaFromStr.html).
+    /// Otherwise it takes one more auto-ref and resolves to
+    /// [`ConvertViaTryFrom`](trait.ConvertViaTryFrom.html).
+    pub struct FieldConverter<T>(PhantomData<fn() -> T>);
+    impl<T> FieldConverter<T> {
+        #[must_use]
+        pub fn new() -> Self {
+            Self(PhantomData)
+        }
+    }
+    impl<T> Default for FieldConverter<T> {
+        fn default() -> Self {
+            Self::new()
+        }
     }
 
-    /// Executes the regular expression against the byte string `data`.
-    ///
-    /// Returns `Some((Range<u32>,Range<u32>,...))` if the expression matched all of the bytes in `data`.
-    /// The tuple fields are ranges of bytes in `data` that matched capturing
-    /// groups in the expression.
-    /// A capturing group that matches no bytes will produce as a zero-length
-    /// range.
-    ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
-    ///
-    /// Returns `None` if the expression did not match `data`.
-    ///
-    /// # Example
-    /// ```rust
-    /// use safe_regex::{regex, Matcher3};
-    /// let matcher: Matcher3<_> = regex!(br"([abc])([0-9]*)(suffix)?");
-    /// let (prefix, digits, suffix) = matcher.match_ranges(b"a42").unwrap();
-    /// assert_eq!(0..1_usize, prefix);
-    /// assert_eq!(1..3_usize, digits);
-    /// assert_eq!(0..0_usize, suffix);
-    /// ```
-    #[must_use]
-    pub fn match_ranges(
-        &self,
-        data: &[u8],
-    ) -> Option<(
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-    )> {
-        let [r0, r1, r2, r3, r4, r5, r6, r7, r8] = (self.f)(data)?;
-        Some((r0, r1, r2, r3, r4, r5, r6, r7, r8))
+    /// Converts UTF-8 captures with `FromStr`.
+    pub trait ConvertViaFromStr<T> {
+        fn convert(&self, capture: &[u8]) -> Option<T>;
+    }
+    impl<T: core::str::FromStr> ConvertViaFromStr<T> for FieldConverter<T> {
+        fn convert(&self, capture: &[u8]) -> Option<T> {
+            core::str::from_utf8(capture).ok()?.parse().ok()
+        }
     }
 
-    /// Executes the regular expression against the byte string `data`.
-    ///
-    /// Returns `Some((&[u8],&[u8],...))`
-    /// if the expression matched all of the bytes in `data`.
-    /// The tuple fields are slices of `data` that matched
-    /// capturing groups in the expression.
-    ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
-    ///
-    /// Returns `None` if the expression did not match `data`.
-    ///
-    /// # Example
-    /// ```rust
-    /// use safe_regex::{regex, Matcher3};
-    /// let matcher: Matcher3<_> = regex!(br"([abc])([0-9]*)(suffix)?");
-    /// let (prefix, digits, suffix) = matcher.match_slices(b"a42").unwrap();
-    /// assert_eq!(b"a", prefix);
-    /// assert_eq!(b"42", digits);
-    /// assert!(suffix.is_empty());
-    /// ```
-    #[must_use]
-    pub fn match_slices<'d>(
-        &self,
-        data: &'d [u8],
-    ) -> Option<(
-        &'d [u8],
-        &'d [u8],
-        &'d [u8],
-        &'d [u8],
-        &'d [u8],
-        &'d [u8],
-        &'d [u8],
-        &'d [u8],
-        &'d [u8],
-    )> {
-        let [r0, r1, r2, r3, r4, r5, r6, r7, r8] = (self.f)(data)?;
-        Some((
-            &data[r0], &data[r1], &data[r2], &data[r3], &data[r4], &data[r5], &data[r6], &data[r7],
-            &data[r8],
-        ))
+    /// Converts captures with `TryFrom<&[u8]>`.
+    pub trait ConvertViaTryFrom<T> {
+        fn convert(&self, capture: &[u8]) -> Option<T>;
     }
-}
-impl<F: Fn(&[u8]) -> Option<[Range<usize>; 9]>> IsMatch for Matcher9<F> {
-    fn is_match(&self, data: &[u8]) -> bool {
-        self.is_match(data)
+    impl<T> ConvertViaTryFrom<T> for &FieldConverter<T>
+    where
+        T: for<'a> core::convert::TryFrom<&'a [u8]>,
+    {
+        fn convert(&self, capture: &[u8]) -> Option<T> {
+            T::try_from(capture).ok()
+        }
     }
-}
 
-/// A compiled regular expression with 10 capturing groups.
-///
-/// This is a zero-length type.
-/// The `regex!` macro generates a Rust type that implements the regular expression.
-/// This struct holds that type.
-pub struct Matcher10<F>
-where
-    F: Fn(&[u8]) -> Option<[Range<usize>; 10]>,
-{
-    f: F,
-}
-impl<F> Matcher10<F>
-where
-    F: Fn(&[u8]) -> Option<[Range<usize>; 10]>,
-{
-    /// This is used internally by the `regex!` macro.
-    #[must_use]
-    pub fn new(f: F) -> Self {
-        Self { f }
+    /// The state of a streaming matcher between chunks of input.
+    ///
+    /// The `regex_stream!` macro generates a type that implements this.
+    pub trait StreamState<const N: usize>: Clone {
+        /// Returns the state before any input.
+        fn new() -> Self;
+        /// Advances the state over the bytes of `chunk`.
+        fn push(&mut self, chunk: &[u8]);
+        /// Returns the capture ranges if the input so far matches.
+        fn finish(&self) -> Option<[Option<Range<u64>>; N]>;
     }
 
-    /// Returns `true` if `data` matches the regular expression,
-    /// otherwise returns `false`.
-    ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
-    ///
-    /// # Example
-    /// ```rust
-    /// use safe_regex::{regex, Matcher0};
-    /// let matcher: Matcher0<_> = regex!(br"[abc][0-9]*");
-    /// assert!(matcher.is_match(b"a42"));
-    /// assert!(!matcher.is_match(b"X"));
-    /// ```
-    #[must_use]
-    pub fn is_match(&self, data: &[u8]) -> bool {
-        (self.f)(data).is_some()
+    /// The threads in a repetition of a single-byte predicate, like
+    /// `[a-z]{0,1000}`.
+    ///
+    /// Matchers use this for long repetitions instead of one state per byte.
+    /// All threads in the repetition read the same bytes, so they differ only
+    /// in when they entered.  Each thread holds its data `T`, which holds its
+    /// capture ranges.
+    ///
+    /// A thread may leave after it reads `PENDING + 1` bytes, and must leave
+    /// before it reads more than `PENDING + WINDOW` bytes.
+    /// Of the threads that can leave, [`exit`](#method.exit) returns the
+    /// newest one, or the oldest one when `OLDEST` is true.
+    /// Each method takes constant amortized time.
+    #[derive(Clone, Debug)]
+    pub struct CountedRepeat<T, const PENDING: usize, const WINDOW: usize, const OLDEST: bool> {
+        /// The threads that cannot leave yet, at their entry steps modulo
+        /// `PENDING`.  Each holds its entry step and its data.
+        pending: [Option<(usize, T)>; PENDING],
+        /// A ring buffer of the threads that can leave, oldest first.
+        /// It keeps only the threads that `exit` may return now or later.
+        window: [Option<(usize, T)>; WINDOW],
+        head: usize,
+        len: usize,
+        /// The number of bytes read.  The first entry step is 1.
+        steps: usize,
+        /// Threads that entered at this step or before it are dead.
+        killed: usize,
+        /// The entry step of the newest thread.
+        newest: usize,
     }
+    impl<T: Clone, const PENDING: usize, const WINDOW: usize, const OLDEST: bool>
+        CountedRepeat<T, PENDING, WINDOW, OLDEST>
+    {
+        #[must_use]
+        pub fn new() -> Self {
+            Self {
+                pending: core::array::from_fn(|_| None),
+                window: core::array::from_fn(|_| None),
+                head: 0,
+                len: 0,
+                steps: 0,
+                killed: 0,
+                newest: 0,
+            }
+        }
 
-    /// Executes the regular expression against the byte string `data`.
-    ///
-    /// Returns `Some((Range<u32>,Range<u32>,...))` if the expression matched all of the bytes in `data`.
-    /// The tuple fields are ranges of bytes in `data` that matched capturing
-    /// groups in the expression.
-    /// A capturing group that matches no bytes will produce as a zero-length
-    /// range.
-    ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
-    ///
-    /// Returns `None` if the expression did not match `data`.
-    ///
-    /// # Example
-    /// ```rust
-    /// use safe_regex::{regex, Matcher3};
-    /// let matcher: Matcher3<_> = regex!(br"([abc])([0-9]*)(suffix)?");
-    /// let (prefix, digits, suffix) = matcher.match_ranges(b"a42").unwrap();
-    /// assert_eq!(0..1_usize, prefix);
-    /// assert_eq!(1..3_usize, digits);
-    /// assert_eq!(0..0_usize, suffix);
-    /// ```
+        /// Returns the data of the thread that leaves the repetition.
+        #[must_use]
+        pub fn exit(&self) -> Option<T> {
+            if self.len == 0 {
+                None
+            } else {
+                self.window[self.head]
+                    .as_ref()
+                    .map(|(_, data)| data.clone())
+            }
+        }
+
+        /// Returns true when the repetition has no threads.
+        #[must_use]
+        pub fn is_empty(&self) -> bool {
+            self.newest <= self.killed || PENDING + WINDOW <= self.steps - self.newest
+        }
+
+        /// Moves the threads over one byte.
+        ///
+        /// `matches` is true when the byte matches the predicate.
+        /// `entry` holds the data of the thread that enters the repetition
+        /// before the byte.
+        pub fn push(&mut self, matches: bool, entry: Option<T>) {
+            self.steps += 1;
+            if !matches {
+                self.killed = self.steps;
+                self.len = 0;
+                return;
+            }
+            while self.len > 0
+                && matches!(
+                    &self.window[self.head],
+                    Some((step, _)) if PENDING + WINDOW <= self.steps - step
+                )
+            {
+                self.window[self.head] = None;
+                self.head = (self.head + 1) % WINDOW;
+                self.len -= 1;
+            }
+            let entry = entry.map(|data| (self.steps, data));
+            if entry.is_some() {
+                self.newest = self.steps;
+            }
+            // A thread that entered `PENDING` steps ago can leave now.
+            let ready = if let Some(index) = self.steps.checked_rem(PENDING) {
+                core::mem::replace(&mut self.pending[index], entry)
+                    .filter(|(step, _)| *step > self.killed)
+            } else {
+                entry
+            };
+            if let Some(ready) = ready {
+                // The new thread hides the others, unless `OLDEST` is true.
+                if !OLDEST {
+                    self.len = 0;
+                }
+                self.window[(self.head + self.len) % WINDOW] = Some(ready);
+                self.len += 1;
+            }
+        }
+    }
+    impl<T: Clone, const PENDING: usize, const WINDOW: usize, const OLDEST: bool> Default
+        for CountedRepeat<T, PENDING, WINDOW, OLDEST>
+    {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    /// A zero-width assertion in a [`search`](fn.search.html) program.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Assertion {
+        StartText,
+        EndText,
+        StartLine,
+        EndLine,
+        WordBoundary,
+        NotWordBoundary,
+    }
+    impl Assertion {
+        /// Returns true when the assertion holds at position `n` in `data`.
+        #[must_use]
+        pub fn holds(self, data: &[u8], n: usize) -> bool {
+            let is_word_byte = |c: &u8| c.is_ascii_alphanumeric() || *c == b'_';
+            let before = n.checked_sub(1).and_then(|i| data.get(i));
+            let after = data.get(n);
+            match self {
+                Assertion::StartText => n == 0,
+                Assertion::EndText => n == data.len(),
+                Assertion::StartLine => matches!(before, None | Some(b'\n')),
+                Assertion::EndLine => matches!(after, None | Some(b'\n')),
+                Assertion::WordBoundary => {
+                    before.is_some_and(is_word_byte) != after.is_some_and(is_word_byte)
+                }
+                Assertion::NotWordBoundary => {
+                    before.is_some_and(is_word_byte) == after.is_some_and(is_word_byte)
+                }
+            }
+        }
+    }
+
+    /// An instruction of a [`search`](fn.search.html) program.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Inst {
+        /// Reads a byte in set `.0` and continues at `.1`.
+        Byte(usize, usize),
+        /// Continues at `.0`, and with lower priority at `.1`.
+        Split(usize, usize),
+        /// Saves the position in slot `.0` and continues at `.1`.
+        Save(usize, usize),
+        /// Continues at `.1` when the assertion holds.
+        Assert(Assertion, usize),
+        /// Ends a match.
+        Match,
+    }
+
+    /// A program for [`search`](fn.search.html).
+    #[derive(Clone, Copy, Debug)]
+    pub struct Nfa<'p> {
+        pub insts: &'p [Inst],
+        /// The thread of each instruction.  A position holds at most one
+        /// thread with each id.  Instructions that share an id behave the
+        /// same, except for the slots that they save.
+        pub ids: &'p [usize],
+        /// The byte sets of the `Byte` instructions.
+        pub sets: &'p [[bool; 256]],
+        /// The first instruction.
+        pub start: usize,
+    }
+
+    /// Returns the length of the `memory` slice that [`search`](fn.search.html)
+    /// needs for a program with `num_insts` instructions and `num_slots`
+    /// slots.
     #[must_use]
-    pub fn match_ranges(
-        &self,
+    pub const fn search_memory_len(num_insts: usize, num_slots: usize) -> usize {
+        2 * num_insts * (2 + num_slots) + 2 * (num_insts + 1) + num_slots
+    }
+
+    /// The threads of a search at one position, at most one per id, in
+    /// priority order.
+    ///
+    /// `dense` and `sparse` form a set of the instructions that the threads
+    /// reached, so each id gets only its first thread.
+    struct ThreadList<'m> {
+        dense: &'m mut [usize],
+        sparse: &'m mut [usize],
+        /// The slots of the thread at each `Byte` or `Match` instruction.
+        slots: &'m mut [usize],
+        len: usize,
+        /// The number of threads at `Byte` or `Match` instructions.
+        live: usize,
+    }
+    impl<'m> ThreadList<'m> {
+        /// Takes the list's memory from the start of `memory` and returns the
+        /// rest.
+        fn new(memory: &'m mut [usize], num_insts: usize, width: usize) -> (Self, &'m mut [usize]) {
+            let (dense, memory) = memory.split_at_mut(num_insts);
+            let (sparse, memory) = memory.split_at_mut(num_insts);
+            let (slots, memory) = memory.split_at_mut(num_insts * width);
+            let list = Self {
+                dense,
+                sparse,
+                slots,
+                len: 0,
+                live: 0,
+            };
+            (list, memory)
+        }
+
+        fn contains(&self, ids: &[usize], pc: usize) -> bool {
+            let index = self.sparse[ids[pc]];
+            index < self.len && ids[self.dense[index]] == ids[pc]
+        }
+
+        fn insert(&mut self, ids: &[usize], pc: usize) {
+            self.sparse[ids[pc]] = self.len;
+            self.dense[self.len] = pc;
+            self.len += 1;
+        }
+
+        fn clear(&mut self) {
+            self.len = 0;
+            self.live = 0;
+        }
+    }
+
+    /// Adds a thread at instruction `pc` to `list`, and follows it to the
+    /// `Byte` and `Match` instructions that it reaches without reading a byte,
+    /// in priority order.  `n` is the position.
+    ///
+    /// `slots` holds the thread's slots.  It holds them again on return.
+    /// `stack` holds pairs of a slot and its old value, or of `usize::MAX` and
+    /// an instruction to follow later.
+    fn add_thread(
+        nfa: &Nfa<'_>,
         data: &[u8],
-    ) -> Option<(
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-        Range<usize>,
-    )> {
-        let [r0, r1, r2, r3, r4, r5, r6, r7, r8, r9] = (self.f)(data)?;
-        Some((r0, r1, r2, r3, r4, r5, r6, r7, r8, r9))
+        n: usize,
+        list: &mut ThreadList<'_>,
+        stack: &mut [usize],
+        slots: &mut [usize],
+        pc: usize,
+    ) {
+        let width = slots.len();
+        stack[0] = usize::MAX;
+        stack[1] = pc;
+        let mut depth = 1;
+        while depth > 0 {
+            depth -= 1;
+            let (slot, value) = (stack[2 * depth], stack[2 * depth + 1]);
+            if slot != usize::MAX {
+                slots[slot] = value;
+                continue;
+            }
+            let mut pc = value;
+            while !list.contains(nfa.ids, pc) {
+                list.insert(nfa.ids, pc);
+                match nfa.insts[pc] {
+                    Inst::Byte(..) | Inst::Match => {
+                        list.slots[pc * width..(pc + 1) * width].copy_from_slice(slots);
+                        list.live += 1;
+                        break;
+                    }
+                    Inst::Split(first, second) => {
+                        stack[2 * depth] = usize::MAX;
+                        stack[2 * depth + 1] = second;
+                        depth += 1;
+                        pc = first;
+                    }
+                    Inst::Save(slot, next) => {
+                        stack[2 * depth] = slot;
+                        stack[2 * depth + 1] = slots[slot];
+                        depth += 1;
+                        slots[slot] = n;
+                        pc = next;
+                    }
+                    Inst::Assert(assertion, next) => {
+                        if !assertion.holds(data, n) {
+                            break;
+                        }
+                        pc = next;
+                    }
+                }
+            }
+        }
     }
 
-    /// Executes the regular expression against the byte string `data`.
-    ///
-    /// Returns `Some((&[u8],&[u8],...))`
-    /// if the expression matched all of the bytes in `data`.
-    /// The tuple fields are slices of `data` that matched
-    /// capturing groups in the expression.
-    ///
-    /// This is a whole-string match.
-    /// For sub-string search, put `.*` at the beginning and end of the regex.
+    /// Searches `data[search_start..]` for the leftmost-first match of `nfa`.
+    /// Returns true when it finds one.
+    ///
+    /// The search runs one thread per id, like a Pike VM.  Threads
+    /// keep the priority order of the expression's alternatives and
+    /// repetitions, and threads that start earlier come first.  When the
+    /// first thread at a position matches, the search drops the threads after
+    /// it.  It stops when no threads remain.
+    ///
+    /// On a match, `slots` holds the positions that the matching thread saved,
+    /// and `usize::MAX` in the slots that it did not save.
+    /// `memory` must hold at least
+    /// [`search_memory_len`](fn.search_memory_len.html) elements.
+    /// Runtime is linear in the length of `data`.
+    ///
+    /// # Panics
+    /// Panics when `memory` is too short.
+    pub fn search(
+        nfa: &Nfa<'_>,
+        data: &[u8],
+        search_start: usize,
+        memory: &mut [usize],
+        slots: &mut [usize],
+    ) -> bool {
+        if search_start > data.len() {
+            return false;
+        }
+        let width = slots.len();
+        let num_insts = nfa.insts.len();
+        let (mut current, memory) = ThreadList::new(memory, num_insts, width);
+        let (mut next, memory) = ThreadList::new(memory, num_insts, width);
+        let (stack, memory) = memory.split_at_mut(2 * (num_insts + 1));
+        let thread = &mut memory[..width];
+        let mut found = false;
+        let mut n = search_start;
+        loop {
+            if !found {
+                thread.fill(usize::MAX);
+                add_thread(nfa, data, n, &mut current, stack, thread, nfa.start);
+            }
+            if found && current.live == 0 {
+                break;
+            }
+            let byte = data.get(n);
+            for &pc in &current.dense[..current.len] {
+                let thread_slots = &current.slots[pc * width..(pc + 1) * width];
+                match nfa.insts[pc] {
+                    Inst::Match => {
+                        slots.copy_from_slice(thread_slots);
+                        found = true;
+                        break;
+                    }
+                    Inst::Byte(set, to) if byte.is_some_and(|b| nfa.sets[set][usize::from(*b)]) => {
+                        thread.copy_from_slice(thread_slots);
+                        add_thread(nfa, data, n + 1, &mut next, stack, thread, to);
+                    }
+                    _ => {}
+                }
+            }
+            if byte.is_none() {
+                break;
+            }
+            core::mem::swap(&mut current, &mut next);
+            next.clear();
+            n += 1;
+        }
+        found
+    }
+
+    /// Returns the index of the first byte in `data` that equals one of
+    /// `needles`.
     ///
-    /// Returns `None` if the expression did not match `data`.
+    /// It checks eight bytes at a time.  Matchers call it to skip to the
+    /// positions where a match can start.
+    #[inline]
+    #[must_use]
+    pub fn find_any_byte(needles: &[u8], data: &[u8]) -> Option<usize> {
+        const LOW_BITS: u64 = 0x0101_0101_0101_0101;
+        const HIGH_BITS: u64 = 0x8080_8080_8080_8080;
+        let mut chunks = data.chunks_exact(8);
+        let mut n = 0;
+        for chunk in &mut chunks {
+            let mut bytes = [0_u8; 8];
+            bytes.copy_from_slice(chunk);
+            let word = u64::from_le_bytes(bytes);
+            // Sets the high bit of each byte that equals a needle.  A borrow
+            // can set the bits of later bytes too, but never of earlier ones.
+            let mut found = 0;
+            for needle in needles {
+                let x = word ^ (LOW_BITS * u64::from(*needle));
+                found |= x.wrapping_sub(LOW_BITS) & !x & HIGH_BITS;
+            }
+            if found != 0 {
+                return found
+                    .to_le_bytes()
+                    .iter()
+                    .position(|b| *b != 0)
+                    .map(|i| n + i);
+            }
+            n += 8;
+        }
+        chunks
+            .remainder()
+            .iter()
+            .position(|b| needles.contains(b))
+            .map(|i| n + i)
+    }
+
+    /// Returns the index of the first byte in `data` whose entry in `set` is
+    /// true.
     ///
-    /// # Example
-    /// ```rust
-    /// use safe_regex::{regex, Matcher3};
-    /// let matcher: Matcher3<_> = regex!(br"([abc])([0-9]*)(suffix)?");
-    /// let (prefix, digits, suffix) = matcher.match_slices(b"a42").unwrap();
-    /// assert_eq!(b"a", prefix);
-    /// assert_eq!(b"42", digits);
-    /// assert!(suffix.is_empty());
-    /// ```
+    /// This is a plain loop with one table lookup per byte.
+    /// Unlike [`find_any_byte`](fn.find_any_byte.html), it does not test
+    /// eight bytes at a time, because a set can have any number of bytes.
+    #[inline]
     #[must_use]
-    pub fn match_slices<'d>(
-        &self,
-        data: &'d [u8],
-    ) -> Option<(
-        &'d [u8],
-        &'d [u8],
-        &'d [u8],
-        &'d [u8],
-        &'d [u8],
-        &'d [u8],
-        &'d [u8],
-        &'d [u8],
-        &'d [u8],
-        &'d [u8],
-    )> {
-        let [r0, r1, r2, r3, r4, r5, r6, r7, r8, r9] = (self.f)(data)?;
-        Some((
-            &data[r0], &data[r1], &data[r2], &data[r3], &data[r4], &data[r5], &data[r6], &data[r7],
-            &data[r8], &data[r9],
-        ))
+    pub fn find_in_set(set: &[bool; 256], data: &[u8]) -> Option<usize> {
+        data.iter().position(|b| set[usize::from(*b)])
     }
-}
-impl<F: Fn(&[u8]) -> Option<[Range<usize>; 10]>> IsMatch for Matcher10<F> {
-    fn is_match(&self, data: &[u8]) -> bool {
-        self.is_match(data)
+
+    /// Returns the index of the first occurrence of `literal` in `data`.
+    #[must_use]
+    pub fn find_literal(literal: &[u8], data: &[u8]) -> Option<usize> {
+        let Some((first, rest)) = literal.split_first() else {
+            return Some(0);
+        };
+        let mut start = 0;
+        loop {
+            let n = start + find_any_byte(&[*first], data.get(start..)?)?;
+            if data[n + 1..].starts_with(rest) {
+                return Some(n);
+            }
+            start = n + 1;
+        }
     }
-}
 
-pub mod internal {
     /// Converts the bytes into an ASCII string.
     #[allow(clippy::missing_panics_doc)]
     pub fn escape_ascii(input: impl AsRef<[u8]>) -> String {
@@ -0,0 +1,475 @@
+//! Regular expressions that are known only at run time.
+//!
+//! [`Regex::new`](struct.Regex.html#method.new) parses and compiles an
+//! expression when the program runs, like one loaded from a configuration
+//! file.
+//! The `regex!` macro is faster, so prefer it for expressions that are known
+//! at compile time.
+//!
+//! The engine simulates the same state machine that `regex!` generates, one
+//! thread per state.
+//! Searches run the same program as the searches that `regex!` generates.
+//! So it returns the same captures as `regex!`.
+//! Runtime is linear.  Memory usage depends on the expression but not on the
+//! data.
+#![forbid(unsafe_code)]
+use crate::internal::{Assertion, Inst};
+use crate::{IsMatch, Matches};
+use core::ops::Range;
+use safe_regex_compiler::generator;
+use safe_regex_compiler::program::{compile, Expr, Machine, Nfa, Program};
+
+pub use safe_regex_compiler::parser::ParseError;
+
+/// A range of a capturing group.
+/// Groups that did not participate have `usize::MAX` in the range.
+type Slot = (usize, usize);
+
+const NO_SLOT: Slot = (usize::MAX, usize::MAX);
+
+/// One optional thread for each of a machine's exprs or states.
+/// A thread holds `width` ranges.
+struct Threads {
+    present: Vec<bool>,
+    slots: Vec<Slot>,
+    width: usize,
+}
+impl Threads {
+    fn new(len: usize, width: usize) -> Self {
+        Self {
+            present: vec![false; len],
+            slots: vec![NO_SLOT; len * width],
+            width,
+        }
+    }
+
+    fn get(&self, n: usize) -> Option<&[Slot]> {
+        if self.present[n] {
+            Some(&self.slots[n * self.width..(n + 1) * self.width])
+        } else {
+            None
+        }
+    }
+
+    fn set(&mut self, n: usize, thread: Option<&[Slot]>) {
+        self.present[n] = thread.is_some();
+        if let Some(slots) = thread {
+            self.slots[n * self.width..(n + 1) * self.width].copy_from_slice(slots);
+        }
+    }
+
+    /// Copies thread `from` into `n`.  `from` must be less than `n`.
+    fn copy(&mut self, from: usize, n: usize) {
+        self.present[n] = self.present[from];
+        if self.present[from] {
+            let (before, after) = self.slots.split_at_mut(n * self.width);
+            after[..self.width]
+                .copy_from_slice(&before[from * self.width..(from + 1) * self.width]);
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        !self.present.iter().any(|present| *present)
+    }
+}
+
+/// Converts the ranges of a thread into the ranges that matchers return.
+fn groups(slots: &[Slot]) -> Vec<Option<Range<usize>>> {
+    slots
+        .iter()
+        .map(|(start, end)| {
+            if *start == usize::MAX || *end == usize::MAX {
+                None
+            } else {
+                Some(*start..*end)
+            }
+        })
+        .collect()
+}
+
+/// The threads of a running machine.
+struct Runner<'m> {
+    machine: &'m Machine,
+    values: Threads,
+    states: Threads,
+}
+impl<'m> Runner<'m> {
+    fn new(machine: &'m Machine) -> Self {
+        Self {
+            machine,
+            values: Threads::new(machine.exprs.len(), machine.num_ranges),
+            states: Threads::new(machine.states.len(), machine.num_ranges),
+        }
+    }
+
+    /// Computes the exprs at `position` in `data`.
+    fn compute(&mut self, data: &[u8], position: usize, start: Option<&[Slot]>) {
+        let values = &mut self.values;
+        for (index, expr) in self.machine.exprs.iter().enumerate() {
+            match expr {
+                Expr::Start => values.set(index, start),
+                Expr::Prev(state) => values.set(index, self.states.get(*state)),
+                Expr::Assert(expr, assertion) => {
+                    if assertion.holds(data, position) {
+                        values.copy(*expr, index);
+                    } else {
+                        values.set(index, None);
+                    }
+                }
+                Expr::First(exprs) => match exprs.iter().find(|expr| values.present[**expr]) {
+                    Some(expr) => values.copy(*expr, index),
+                    None => values.set(index, None),
+                },
+                Expr::OpenGroup(expr, group) => {
+                    values.copy(*expr, index);
+                    values.slots[index * values.width + group] = (position, position);
+                }
+            }
+        }
+    }
+
+    /// Moves threads from the exprs into the states that accept `byte` at
+    /// `position`.
+    fn read(&mut self, position: usize, byte: u8) {
+        let width = self.machine.num_ranges;
+        for (index, state) in self.machine.states.iter().enumerate() {
+            if self.machine.sets[state.set].contains(byte) {
+                self.states.set(index, self.values.get(state.expr));
+                for group in &state.groups {
+                    self.states.slots[index * width + group].1 = position + 1;
+                }
+            } else {
+                self.states.set(index, None);
+            }
+        }
+    }
+
+    /// Returns the matching thread at the current position.
+    fn accept(&self) -> Option<&[Slot]> {
+        self.values.get(self.machine.accept)
+    }
+}
+
+/// Matches `machine` against all of `data`.
+fn match_anchored(machine: &Machine, data: &[u8]) -> Option<Vec<Option<Range<usize>>>> {
+    assert!(data.len() < usize::MAX - 2);
+    let mut runner = Runner::new(machine);
+    let start = vec![NO_SLOT; machine.num_ranges];
+    let mut position = 0;
+    loop {
+        runner.compute(
+            data,
+            position,
+            if position == 0 { Some(&start) } else { None },
+        );
+        if let Some(byte) = data.get(position) {
+            runner.read(position, *byte);
+            if runner.states.is_empty() {
+                return None;
+            }
+        } else {
+            return runner.accept().map(groups);
+        }
+        position += 1;
+    }
+}
+
+/// A search program in the form that `crate::internal::search` runs.
+#[derive(Clone, Debug)]
+struct Search {
+    insts: Vec<Inst>,
+    ids: Vec<usize>,
+    start: usize,
+    sets: Vec<[bool; 256]>,
+    num_slots: usize,
+}
+impl Search {
+    fn new(nfa: &Nfa) -> Self {
+        use safe_regex_compiler::program::Inst as NfaInst;
+        let insts = nfa
+            .insts
+            .iter()
+            .map(|inst| match *inst {
+                NfaInst::Byte(set, next) => Inst::Byte(set, next),
+                NfaInst::Split(first, second) => Inst::Split(first, second),
+                NfaInst::Save(slot, next) => Inst::Save(slot, next),
+                NfaInst::Assert(assertion, next) => {
+                    let assertion = match assertion {
+                        generator::Assertion::StartText => Assertion::StartText,
+                        generator::Assertion::EndText => Assertion::EndText,
+                        generator::Assertion::StartLine => Assertion::StartLine,
+                        generator::Assertion::EndLine => Assertion::EndLine,
+                        generator::Assertion::WordBoundary => Assertion::WordBoundary,
+                        generator::Assertion::NotWordBoundary => Assertion::NotWordBoundary,
+                    };
+                    Inst::Assert(assertion, next)
+                }
+                NfaInst::Match => Inst::Match,
+            })
+            .collect();
+        Self {
+            insts,
+            ids: nfa.ids.clone(),
+            start: nfa.start,
+            sets: nfa.sets.iter().map(generator::Predicate::table).collect(),
+            num_slots: nfa.num_slots,
+        }
+    }
+
+    /// Searches for the leftmost-first match in `data[search_start..]`.
+    fn run(
+        &self,
+        data: &[u8],
+        search_start: usize,
+    ) -> Option<(Range<usize>, Vec<Option<Range<usize>>>)> {
+        let mut memory =
+            vec![0; crate::internal::search_memory_len(self.insts.len(), self.num_slots)];
+        let mut slots = vec![usize::MAX; self.num_slots];
+        let nfa = crate::internal::Nfa {
+            insts: &self.insts,
+            ids: &self.ids,
+            sets: &self.sets,
+            start: self.start,
+        };
+        if !crate::internal::search(&nfa, data, search_start, &mut memory, &mut slots) {
+            return None;
+        }
+        let ranges: Vec<Slot> = slots
+            .chunks_exact(2)
+            .map(|slot| (slot[0], slot[1]))
+            .collect();
+        let (last, groups_slots) = ranges.split_last().unwrap();
+        Some((last.0..last.1, groups(groups_slots)))
+    }
+}
+
+/// A regular expression compiled at run time.
+///
+/// It has the same methods as [`Matcher`](../struct.Matcher.html), but they
+/// return `Vec`s instead of arrays.
+///
+/// # Example
+/// ```rust
+/// use safe_regex::runtime::Regex;
+/// let pattern: &[u8] = br"([a-z]+)=([0-9]+)";
+/// let regex = Regex::new(pattern).unwrap();
+/// assert_eq!(Some(vec![0..3_usize, 4..6_usize]), regex.match_ranges(b"key=42"));
+/// assert!(Regex::new(br"ab(c").is_err());
+/// ```
+#[derive(Clone, Debug)]
+pub struct Regex {
+    pattern: String,
+    program: Program,
+    search: Search,
+}
+impl Regex {
+    /// Parses and compiles the regular expression `pattern`.
+    ///
+    /// It supports the same syntax as `regex!(br"...")`.
+    ///
+    /// # Errors
+    /// Returns an error when `pattern` is not a valid expression, or when it
+    /// compiles to more than 100,000 instructions, like `a{1000000}`.
+    /// The error's `Display` output shows the problem underlined with `^`
+    /// characters.
+    pub fn new(pattern: &[u8]) -> Result<Self, ParseError> {
+        let final_node = safe_regex_compiler::parser::parse(pattern)?;
+        let program = compile(&final_node);
+        Ok(Self {
+            pattern: String::from_utf8_lossy(pattern).into_owned(),
+            search: Search::new(&program.search),
+            program,
+        })
+    }
+
+    /// Returns the number of capturing groups in the expression.
+    #[must_use]
+    pub fn group_count(&self) -> usize {
+        self.program.group_names.len()
+    }
+
+    /// Returns the index of the capturing group named `name`,
+    /// or `None` if the expression has no such group.
+    ///
+    /// # Example
+    /// ```rust
+    /// use safe_regex::runtime::Regex;
+    /// let regex = Regex::new(br"(?P<key>[a-z]+)=(?P<value>[0-9]+)").unwrap();
+    /// assert_eq!(Some(1), regex.group_index("value"));
+    /// let ranges = regex.match_ranges(b"key=42").unwrap();
+    /// assert_eq!(4..6_usize, ranges[1]);
+    /// ```
+    #[must_use]
+    pub fn group_index(&self, name: &str) -> Option<usize> {
+        self.program
+            .group_names
+            .iter()
+            .position(|group_name| group_name.as_deref() == Some(name))
+    }
+
+    /// Returns `true` if `data` matches the regular expression,
+    /// otherwise returns `false`.
+    ///
+    /// This is a whole-string match.
+    /// For sub-string search, use [`find`](#method.find).
+    #[must_use]
+    pub fn is_match(&self, data: &[u8]) -> bool {
+        match_anchored(&self.program.anchored, data).is_some()
+    }
+
+    /// Executes the regular expression against the byte string `data`.
+    ///
+    /// Returns the ranges of bytes in `data` that matched capturing groups,
+    /// like [`Matcher::match_ranges`](../struct.Matcher.html#method.match_ranges).
+    /// A capturing group that did not participate in the match produces `0..0`.
+    ///
+    /// Returns `None` if the expression did not match all of `data`.
+    #[must_use]
+    pub fn match_ranges(&self, data: &[u8]) -> Option<Vec<Range<usize>>> {
+        let ranges = self.match_ranges_opt(data)?;
+        Some(
+            ranges
+                .into_iter()
+                .map(|range| range.unwrap_or(0..0))
+                .collect(),
+        )
+    }
+
+    /// Executes the regular expression against the byte string `data`.
+    ///
+    /// Like [`match_ranges`](#method.match_ranges), but a capturing group
+    /// that did not participate in the match produces `None`.
+    #[must_use]
+    pub fn match_ranges_opt(&self, data: &[u8]) -> Option<Vec<Option<Range<usize>>>> {
+        match_anchored(&self.program.anchored, data)
+    }
+
+    /// Executes the regular expression against the byte string `data`.
+    ///
+    /// Returns the slices of `data` that matched capturing groups.
+    /// A capturing group that did not participate in the match produces an
+    /// empty slice.
+    ///
+    /// Returns `None` if the expression did not match all of `data`.
+    #[must_use]
+    pub fn match_slices<'d>(&self, data: &'d [u8]) -> Option<Vec<&'d [u8]>> {
+        let ranges = self.match_ranges(data)?;
+        Some(ranges.into_iter().map(|range| &data[range]).collect())
+    }
+
+    /// Executes the regular expression against the byte string `data`.
+    ///
+    /// Like [`match_slices`](#method.match_slices), but a capturing group
+    /// that did not participate in the match produces `None`.
+    #[must_use]
+    pub fn match_slices_opt<'d>(&self, data: &'d [u8]) -> Option<Vec<Option<&'d [u8]>>> {
+        let ranges = self.match_ranges_opt(data)?;
+        Some(
+            ranges
+                .into_iter()
+                .map(|range| range.map(|range| &data[range]))
+                .collect(),
+        )
+    }
+
+    /// Searches `data` for the first sub-string that matches the regular
+    /// expression, like [`Matcher::find`](../struct.Matcher.html#method.find).
+    ///
+    /// # Example
+    /// ```rust
+    /// use safe_regex::runtime::Regex;
+    /// let regex = Regex::new(br"a([0-9]+)").unwrap();
+    /// assert_eq!(Some(2..5_usize), regex.find(b"..a42.."));
+    /// assert_eq!(None, regex.find(b"..."));
+    /// ```
+    #[must_use]
+    pub fn find(&self, data: &[u8]) -> Option<Range<usize>> {
+        self.find_at(data, 0)
+    }
+
+    /// Searches `data` for the first sub-string that matches the regular
+    /// expression and starts at or after byte `start`.
+    ///
+    /// The returned range is relative to the beginning of `data`.
+    ///
+    /// Returns `None` if no such sub-string exists or if `start` is past the
+    /// end of `data`.
+    #[must_use]
+    pub fn find_at(&self, data: &[u8], start: usize) -> Option<Range<usize>> {
+        self.search.run(data, start).map(|(range, _)| range)
+    }
+
+    /// Returns an iterator over the non-overlapping sub-strings of `data` that
+    /// match the regular expression.
+    ///
+    /// The iterator yields the range of bytes of each match.
+    pub fn find_iter<'a>(&'a self, data: &'a [u8]) -> impl Iterator<Item = Range<usize>> + 'a {
+        self.matches(data).map(|(range, _)| range)
+    }
+
+    /// Returns an iterator over the non-overlapping sub-strings of `data` that
+    /// match the regular expression.
+    ///
+    /// The iterator yields the range of bytes of each match,
+    /// together with the ranges of bytes that matched capturing groups in the
+    /// expression.
+    ///
+    /// # Example
+    /// ```rust
+    /// use safe_regex::runtime::Regex;
+    /// let regex = Regex::new(br"a([0-9]+)").unwrap();
+    /// let mut iter = regex.captures_iter(b"a1.a23");
+    /// assert_eq!(Some((0..2_usize, vec![1..2_usize])), iter.next());
+    /// assert_eq!(Some((3..6_usize, vec![4..6_usize])), iter.next());
+    /// assert_eq!(None, iter.next());
+    /// ```
+    pub fn captures_iter<'a>(
+        &'a self,
+        data: &'a [u8],
+    ) -> impl Iterator<Item = (Range<usize>, Vec<Range<usize>>)> + 'a {
+        self.matches(data).map(|(range, groups)| {
+            (
+                range,
+                groups
+                    .into_iter()
+                    .map(|group| group.unwrap_or(0..0))
+                    .collect(),
+            )
+        })
+    }
+
+    fn matches<'a>(
+        &'a self,
+        data: &'a [u8],
+    ) -> impl Iterator<Item = (Range<usize>, Vec<Option<Range<usize>>>)> + 'a {
+        Matches::new(
+            move |data: &[u8], start: Option<usize>| self.search.run(data, start?),
+            data,
+        )
+    }
+}
+impl IsMatch for Regex {
+    fn is_match(&self, data: &[u8]) -> bool {
+        self.is_match(data)
+    }
+}
+impl crate::Regex for Regex {
+    fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    fn group_count(&self) -> usize {
+        self.group_count()
+    }
+
+    fn match_ranges_into(&self, data: &[u8], ranges: &mut [Range<usize>]) -> bool {
+        if let Some(groups) = self.match_ranges_opt(data) {
+            for (range, group) in ranges.iter_mut().zip(groups) {
+                *range = group.unwrap_or(0..0);
+            }
+            true
+        } else {
+            false
+        }
+    }
+}
@@ -2,6 +2,7 @@
 #![allow(unused_variables)]
 #![allow(unused_assignments)]
 #![allow(clippy::unseparated_literal_suffix)]
+#![allow(clippy::single_range_in_vec_init)]
 use safe_regex::internal::escape_ascii;
 use safe_regex::{Matcher0, Matcher1, Matcher2};
 
@@ -13,54 +14,475 @@ use safe_regex::{Matcher0, Matcher1, Matcher2};
 #[test]
 fn byte() {
     // regex!(br"a")
-    let re: Matcher0<_> = safe_regex::Matcher0::new(|data: &[u8]| {
-        let mut start = Some(());
-        let mut b0: Option<()> = None;
-        let mut data_iter = data.iter();
-        loop {
-            let prev_b0 = b0.clone();
-            if let Some(b) = data_iter.next() {
-                b0 = start.clone().filter(|_| {
-                    //
-                    *b == 97u8
-                });
-                start = None;
-                if b0.is_none() {
-                    return None;
+    let re: Matcher0<_> =
+        safe_regex::Matcher::new("a", |data: &[u8], search_start: Option<usize>| {
+            safe_regex::internal::find_literal(b"a", data.get(search_start.unwrap_or(0)..)?)?;
+            let search_start = match search_start {
+                Some(search_start) => {
+                    let rest = data.get(search_start..)?;
+                    Some(search_start + safe_regex::internal::find_any_byte(b"a", rest)?)
+                }
+                None => None,
+            };
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 1u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+            ];
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 6usize] = [0u16, 0u16, 2u16, 4u16, 0u16, 0u16];
+                const SEARCH_ACCEPT: [bool; 3usize] = [false, false, true];
+                const REVERSE: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 0u16];
+                const REVERSE_ACCEPT: [bool; 3usize] = [false, false, true];
+                let mut state = 2usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 2usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
+                }
+                let end = end?;
+                let mut start = end;
+                let mut state = 2usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 2usize] {
+                        start = n;
+                    }
                 }
+                Some((start..end, []))
             } else {
-                return prev_b0;
+                const ANCHORED: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 0u16];
+                const ANCHORED_ACCEPT: [bool; 3usize] = [false, false, true];
+                let mut state = 2usize;
+                for b in data {
+                    state = next(&ANCHORED, state, *b);
+                    if state == 0 {
+                        return None;
+                    }
+                }
+                if ANCHORED_ACCEPT[state / 2usize] {
+                    Some((0..data.len(), []))
+                } else {
+                    None
+                }
             }
-        }
-    });
+        });
     assert!(!re.is_match(b""));
     assert!(!re.is_match(b"X"));
     assert!(re.is_match(b"a"));
     assert!(!re.is_match(b"aX"));
     assert!(!re.is_match(b"Xa"));
     assert!(!re.is_match(b"aa"));
+    assert_eq!(None, re.find(b""));
+    assert_eq!(Some(1..2), re.find(b"Xa"));
+    assert_eq!(Some(2..3), re.find_at(b"aXa", 1));
+}
+
+#[test]
+fn anchors() {
+    // regex!(br"(?m)^a$")
+    let re: Matcher0<_> =
+        safe_regex::Matcher::new("(?m)^a$", |data: &[u8], search_start: Option<usize>| {
+            #[inline(never)]
+            fn search(
+                data: &[u8],
+                search_start: usize,
+            ) -> Option<(
+                core::ops::Range<usize>,
+                [Option<core::ops::Range<usize>>; 0usize],
+            )> {
+                static INSTS: [safe_regex::internal::Inst; 6usize] = [
+                    safe_regex::internal::Inst::Match,
+                    safe_regex::internal::Inst::Save(1usize, 0usize),
+                    safe_regex::internal::Inst::Assert(
+                        safe_regex::internal::Assertion::EndLine,
+                        1usize,
+                    ),
+                    safe_regex::internal::Inst::Byte(0usize, 2usize),
+                    safe_regex::internal::Inst::Assert(
+                        safe_regex::internal::Assertion::StartLine,
+                        3usize,
+                    ),
+                    safe_regex::internal::Inst::Save(0usize, 4usize),
+                ];
+                static IDS: [usize; 6usize] = [0usize, 1usize, 2usize, 3usize, 4usize, 5usize];
+                static SETS: [[bool; 256]; 1usize] = [[
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, true, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false,
+                ]];
+                let nfa = safe_regex::internal::Nfa {
+                    insts: &INSTS,
+                    ids: &IDS,
+                    sets: &SETS,
+                    start: 5usize,
+                };
+                let mut memory = [0_usize; safe_regex::internal::search_memory_len(6usize, 2usize)];
+                let mut slots = [usize::MAX; 2usize];
+                if !safe_regex::internal::search(&nfa, data, search_start, &mut memory, &mut slots)
+                {
+                    return None;
+                }
+                Some((slots[0usize]..slots[1usize], []))
+            }
+            safe_regex::internal::find_literal(b"a", data.get(search_start.unwrap_or(0)..)?)?;
+            let search_start = match search_start {
+                Some(search_start) => {
+                    let rest = data.get(search_start..)?;
+                    Some(search_start + safe_regex::internal::find_any_byte(b"a", rest)?)
+                }
+                None => None,
+            };
+            static BYTES0: [bool; 256] = [
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, true, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false,
+            ];
+            if let Some(search_start) = search_start {
+                search(data, search_start)
+            } else {
+                let mut start = Some(());
+                let mut b0: Option<()> = None;
+                let mut data_iter = data.iter();
+                let mut n = 0;
+                loop {
+                    let prev_b0 = b0.clone();
+                    if let Some(b) = data_iter.next() {
+                        b0 = if BYTES0[usize::from(*b)] {
+                            start
+                                .clone()
+                                .filter(|_| n == 0 || data[n - 1] == b'\n')
+                                .clone()
+                        } else {
+                            None
+                        };
+                        start = None;
+                        if b0.is_none() {
+                            return None;
+                        }
+                    } else {
+                        return prev_b0
+                            .clone()
+                            .filter(|_| n == data.len() || data[n] == b'\n')
+                            .map(|_| (0..data.len(), []));
+                    }
+                    n += 1;
+                }
+            }
+        });
+    assert!(!re.is_match(b""));
+    assert!(re.is_match(b"a"));
+    assert!(!re.is_match(b"aa"));
+    assert!(!re.is_match(b"a\n"));
+    assert!(!re.is_match(b"\na"));
+    assert_eq!(None, re.find(b"Xa"));
+    assert_eq!(Some(2..3), re.find(b"X\na\nb"));
+    assert_eq!(Some(5..6), re.find_at(b"a\nXa\na", 1));
+}
+
+#[test]
+fn word_boundary() {
+    // regex!(br"\ba\B")
+    let re: Matcher0<_> =
+        safe_regex::Matcher::new("\\ba\\B", |data: &[u8], search_start: Option<usize>| {
+            #[inline(never)]
+            fn search(
+                data: &[u8],
+                search_start: usize,
+            ) -> Option<(
+                core::ops::Range<usize>,
+                [Option<core::ops::Range<usize>>; 0usize],
+            )> {
+                static INSTS: [safe_regex::internal::Inst; 6usize] = [
+                    safe_regex::internal::Inst::Match,
+                    safe_regex::internal::Inst::Save(1usize, 0usize),
+                    safe_regex::internal::Inst::Assert(
+                        safe_regex::internal::Assertion::NotWordBoundary,
+                        1usize,
+                    ),
+                    safe_regex::internal::Inst::Byte(0usize, 2usize),
+                    safe_regex::internal::Inst::Assert(
+                        safe_regex::internal::Assertion::WordBoundary,
+                        3usize,
+                    ),
+                    safe_regex::internal::Inst::Save(0usize, 4usize),
+                ];
+                static IDS: [usize; 6usize] = [0usize, 1usize, 2usize, 3usize, 4usize, 5usize];
+                static SETS: [[bool; 256]; 1usize] = [[
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, true, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false,
+                ]];
+                let nfa = safe_regex::internal::Nfa {
+                    insts: &INSTS,
+                    ids: &IDS,
+                    sets: &SETS,
+                    start: 5usize,
+                };
+                let mut memory = [0_usize; safe_regex::internal::search_memory_len(6usize, 2usize)];
+                let mut slots = [usize::MAX; 2usize];
+                if !safe_regex::internal::search(&nfa, data, search_start, &mut memory, &mut slots)
+                {
+                    return None;
+                }
+                Some((slots[0usize]..slots[1usize], []))
+            }
+            safe_regex::internal::find_literal(b"a", data.get(search_start.unwrap_or(0)..)?)?;
+            let search_start = match search_start {
+                Some(search_start) => {
+                    let rest = data.get(search_start..)?;
+                    Some(search_start + safe_regex::internal::find_any_byte(b"a", rest)?)
+                }
+                None => None,
+            };
+            static BYTES0: [bool; 256] = [
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, true, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false,
+            ];
+            if let Some(search_start) = search_start {
+                search(data, search_start)
+            } else {
+                let mut start = Some(());
+                let mut b0: Option<()> = None;
+                let mut data_iter = data.iter();
+                let mut n = 0;
+                loop {
+                    let prev_b0 = b0.clone();
+                    if let Some(b) = data_iter.next() {
+                        b0 = if BYTES0[usize::from(*b)] {
+                            start
+                                .clone()
+                                .filter(|_| {
+                                    (n > 0
+                                        && (data[n - 1].is_ascii_alphanumeric()
+                                            || data[n - 1] == b'_'))
+                                        != (n < data.len()
+                                            && (data[n].is_ascii_alphanumeric() || data[n] == b'_'))
+                                })
+                                .clone()
+                        } else {
+                            None
+                        };
+                        start = None;
+                        if b0.is_none() {
+                            return None;
+                        }
+                    } else {
+                        return prev_b0
+                            .clone()
+                            .filter(|_| {
+                                (n > 0
+                                    && (data[n - 1].is_ascii_alphanumeric() || data[n - 1] == b'_'))
+                                    == (n < data.len()
+                                        && (data[n].is_ascii_alphanumeric() || data[n] == b'_'))
+                            })
+                            .map(|_| (0..data.len(), []));
+                    }
+                    n += 1;
+                }
+            }
+        });
+    assert!(!re.is_match(b""));
+    assert!(!re.is_match(b"a"));
+    assert!(!re.is_match(b"aa"));
+    assert_eq!(Some(0..1), re.find(b"aa"));
+    assert_eq!(None, re.find(b"a-"));
+    assert_eq!(None, re.find(b"ba"));
+    assert_eq!(Some(1..2), re.find(b"-a_"));
+    assert_eq!(Some(3..4), re.find_at(b"ab a9", 1));
 }
 
 #[test]
 fn any_byte() {
     // regex!(br".")
-    let re: Matcher0<_> = safe_regex::Matcher0::new(|data: &[u8]| {
-        let mut start = Some(());
-        let mut b0: Option<()> = None;
-        let mut data_iter = data.iter();
-        loop {
-            let prev_b0 = b0.clone();
-            if let Some(b) = data_iter.next() {
-                b0 = start.clone();
-                start = None;
-                if b0.is_none() {
-                    return None;
+    let re: Matcher0<_> =
+        safe_regex::Matcher::new(".", |data: &[u8], search_start: Option<usize>| {
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+            ];
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 3usize] = [0u16, 2u16, 0u16];
+                const SEARCH_ACCEPT: [bool; 3usize] = [false, false, true];
+                const REVERSE: [u16; 3usize] = [0u16, 2u16, 0u16];
+                const REVERSE_ACCEPT: [bool; 3usize] = [false, false, true];
+                let mut state = 1usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
                 }
+                let end = end?;
+                let mut start = end;
+                let mut state = 1usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state] {
+                        start = n;
+                    }
+                }
+                Some((start..end, []))
             } else {
-                return prev_b0;
+                const ANCHORED: [u16; 3usize] = [0u16, 2u16, 0u16];
+                const ANCHORED_ACCEPT: [bool; 3usize] = [false, false, true];
+                let mut state = 1usize;
+                for b in data {
+                    state = next(&ANCHORED, state, *b);
+                    if state == 0 {
+                        return None;
+                    }
+                }
+                if ANCHORED_ACCEPT[state] {
+                    Some((0..data.len(), []))
+                } else {
+                    None
+                }
             }
-        }
-    });
+        });
     assert!(!re.is_match(b""));
     assert!(re.is_match(b"X"));
     assert!(!re.is_match(b"XY"));
@@ -69,25 +491,124 @@ fn any_byte() {
 #[test]
 fn class_inclusive() {
     // regex!(br"[abc2-4]")
-    let re: Matcher0<_> = safe_regex::Matcher0::new(|data: &[u8]| {
-        let mut start = Some(());
-        let mut b0: Option<()> = None;
-        let mut data_iter = data.iter();
-        loop {
-            let prev_b0 = b0.clone();
-            if let Some(b) = data_iter.next() {
-                b0 = start.clone().filter(|_| {
-                    *b == 97u8 || *b == 98u8 || *b == 99u8 || (50u8..=52u8).contains(b)
-                });
-                start = None;
-                if b0.is_none() {
-                    return None;
+    let re: Matcher0<_> =
+        safe_regex::Matcher::new("[abc2-4]", |data: &[u8], search_start: Option<usize>| {
+            let search_start = match search_start {
+                Some(search_start) => {
+                    let rest = data.get(search_start..)?;
+                    Some(
+                        search_start + {
+                            const FIRST_BYTES: [bool; 256] = [
+                                false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, true, true, true, false, false,
+                                false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, true, true, true, false,
+                                false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false,
+                                false, false, false, false, false, false, false, false, false,
+                                false, false,
+                            ];
+                            safe_regex::internal::find_in_set(&FIRST_BYTES, rest)
+                        }?,
+                    )
                 }
+                None => None,
+            };
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 1u8, 1u8, 1u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 1u8, 1u8, 1u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+            ];
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 6usize] = [0u16, 0u16, 2u16, 4u16, 0u16, 0u16];
+                const SEARCH_ACCEPT: [bool; 3usize] = [false, false, true];
+                const REVERSE: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 0u16];
+                const REVERSE_ACCEPT: [bool; 3usize] = [false, false, true];
+                let mut state = 2usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 2usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
+                }
+                let end = end?;
+                let mut start = end;
+                let mut state = 2usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 2usize] {
+                        start = n;
+                    }
+                }
+                Some((start..end, []))
             } else {
-                return prev_b0;
+                const ANCHORED: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 0u16];
+                const ANCHORED_ACCEPT: [bool; 3usize] = [false, false, true];
+                let mut state = 2usize;
+                for b in data {
+                    state = next(&ANCHORED, state, *b);
+                    if state == 0 {
+                        return None;
+                    }
+                }
+                if ANCHORED_ACCEPT[state / 2usize] {
+                    Some((0..data.len(), []))
+                } else {
+                    None
+                }
             }
-        }
-    });
+        });
     assert!(!re.is_match(b""));
     assert!(!re.is_match(b"X"));
     assert!(re.is_match(b"a"));
@@ -107,25 +628,119 @@ fn class_inclusive() {
 #[test]
 fn class_exclusive() {
     // regex!(br"[^abc2-4]")
-    let re: Matcher0<_> = safe_regex::Matcher0::new(|data: &[u8]| {
-        let mut start = Some(());
-        let mut b0: Option<()> = None;
-        let mut data_iter = data.iter();
-        loop {
-            let prev_b0 = b0.clone();
-            if let Some(b) = data_iter.next() {
-                b0 = start.clone().filter(|_| {
-                    *b != 97u8 && *b != 98u8 && *b != 99u8 && !(50u8..=52u8).contains(b)
-                });
-                start = None;
-                if b0.is_none() {
-                    return None;
+    let re: Matcher0<_> =
+        safe_regex::Matcher::new("[^abc2-4]", |data: &[u8], search_start: Option<usize>| {
+            let search_start = match search_start {
+                Some(search_start) => {
+                    let rest = data.get(search_start..)?;
+                    Some(
+                        search_start + {
+                            const FIRST_BYTES: [bool; 256] = [
+                                true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, false, false, false, true,
+                                true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, false,
+                                false, false, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true, true, true, true, true, true, true, true,
+                                true, true, true, true,
+                            ];
+                            safe_regex::internal::find_in_set(&FIRST_BYTES, rest)
+                        }?,
+                    )
+                }
+                None => None,
+            };
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 1u8, 1u8, 1u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 1u8, 1u8, 1u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+            ];
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 6usize] = [0u16, 0u16, 4u16, 2u16, 0u16, 0u16];
+                const SEARCH_ACCEPT: [bool; 3usize] = [false, false, true];
+                const REVERSE: [u16; 6usize] = [0u16, 0u16, 4u16, 0u16, 0u16, 0u16];
+                const REVERSE_ACCEPT: [bool; 3usize] = [false, false, true];
+                let mut state = 2usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 2usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
+                }
+                let end = end?;
+                let mut start = end;
+                let mut state = 2usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 2usize] {
+                        start = n;
+                    }
                 }
+                Some((start..end, []))
             } else {
-                return prev_b0;
+                const ANCHORED: [u16; 6usize] = [0u16, 0u16, 4u16, 0u16, 0u16, 0u16];
+                const ANCHORED_ACCEPT: [bool; 3usize] = [false, false, true];
+                let mut state = 2usize;
+                for b in data {
+                    state = next(&ANCHORED, state, *b);
+                    if state == 0 {
+                        return None;
+                    }
+                }
+                if ANCHORED_ACCEPT[state / 2usize] {
+                    Some((0..data.len(), []))
+                } else {
+                    None
+                }
             }
-        }
-    });
+        });
     assert!(!re.is_match(b""));
     assert!(re.is_match(b"X"));
     assert!(re.is_match(b"Y"));
@@ -143,29 +758,99 @@ fn class_exclusive() {
 #[test]
 fn seq() {
     // regex!(br"aab")
-    let re: Matcher0<_> = safe_regex::Matcher0::new(|data: &[u8]| {
-        let mut start = Some(());
-        let mut b0: Option<()> = None;
-        let mut b1: Option<()> = None;
-        let mut b2: Option<()> = None;
-        let mut data_iter = data.iter();
-        loop {
-            let prev_b0 = b0.clone();
-            let prev_b1 = b1.clone();
-            let prev_b2 = b2.clone();
-            if let Some(b) = data_iter.next() {
-                b2 = prev_b1.clone().filter(|_| *b == 98u8);
-                b1 = prev_b0.clone().filter(|_| *b == 97u8);
-                b0 = start.clone().filter(|_| *b == 97u8);
-                start = None;
-                if b0.is_none() && b1.is_none() && b2.is_none() {
-                    return None;
+    let re: Matcher0<_> =
+        safe_regex::Matcher::new("aab", |data: &[u8], search_start: Option<usize>| {
+            safe_regex::internal::find_literal(b"aab", data.get(search_start.unwrap_or(0)..)?)?;
+            let search_start = match search_start {
+                Some(search_start) => {
+                    let rest = data.get(search_start..)?;
+                    Some(search_start + safe_regex::internal::find_any_byte(b"a", rest)?)
                 }
+                None => None,
+            };
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 1u8, 2u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+            ];
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 15usize] = [
+                    0u16, 0u16, 0u16, 3u16, 6u16, 3u16, 3u16, 9u16, 3u16, 3u16, 9u16, 12u16, 0u16,
+                    0u16, 0u16,
+                ];
+                const SEARCH_ACCEPT: [bool; 5usize] = [false, false, false, false, true];
+                const REVERSE: [u16; 15usize] = [
+                    0u16, 0u16, 0u16, 0u16, 0u16, 6u16, 0u16, 9u16, 0u16, 0u16, 12u16, 0u16, 0u16,
+                    0u16, 0u16,
+                ];
+                const REVERSE_ACCEPT: [bool; 5usize] = [false, false, false, false, true];
+                let mut state = 3usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 3usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
+                }
+                let end = end?;
+                let mut start = end;
+                let mut state = 3usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 3usize] {
+                        start = n;
+                    }
+                }
+                Some((start..end, []))
             } else {
-                return prev_b2;
+                const ANCHORED: [u16; 15usize] = [
+                    0u16, 0u16, 0u16, 0u16, 6u16, 0u16, 0u16, 9u16, 0u16, 0u16, 0u16, 12u16, 0u16,
+                    0u16, 0u16,
+                ];
+                const ANCHORED_ACCEPT: [bool; 5usize] = [false, false, false, false, true];
+                let mut state = 3usize;
+                for b in data {
+                    state = next(&ANCHORED, state, *b);
+                    if state == 0 {
+                        return None;
+                    }
+                }
+                if ANCHORED_ACCEPT[state / 3usize] {
+                    Some((0..data.len(), []))
+                } else {
+                    None
+                }
             }
-        }
-    });
+        });
     assert!(!re.is_match(b""));
     assert!(!re.is_match(b"X"));
     assert!(!re.is_match(b"aX"));
@@ -183,37 +868,101 @@ fn seq() {
     assert!(!re.is_match(b"aaba"));
     assert!(!re.is_match(b"aabaa"));
     assert!(!re.is_match(b"aabaab"));
+    assert_eq!(Some(2..5), re.find(b"Xaaab"));
+    assert_eq!(None, re.find(b"aaXb"));
 }
 
 #[test]
 fn alt() {
     // regex!(br"a|b")
-    let re: Matcher0<_> = safe_regex::Matcher0::new(|data: &[u8]| {
-        let mut start = Some(());
-        let mut b0: Option<()> = None;
-        let mut b1: Option<()> = None;
-        let mut data_iter = data.iter();
-        loop {
-            let prev_b0 = b0.clone();
-            let prev_b1 = b1.clone();
-            if let Some(b) = data_iter.next() {
-                b1 = start.clone().filter(|_| {
-                    //
-                    *b == 98u8
-                });
-                b0 = start.clone().filter(|_| {
-                    //
-                    *b == 97u8
-                });
-                start = None;
-                if b0.is_none() && b1.is_none() {
-                    return None;
+    let re: Matcher0<_> =
+        safe_regex::Matcher::new("a|b", |data: &[u8], search_start: Option<usize>| {
+            let search_start = match search_start {
+                Some(search_start) => {
+                    let rest = data.get(search_start..)?;
+                    Some(search_start + safe_regex::internal::find_any_byte(b"ab", rest)?)
+                }
+                None => None,
+            };
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 1u8, 2u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+            ];
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 9usize] =
+                    [0u16, 0u16, 0u16, 3u16, 6u16, 6u16, 0u16, 0u16, 0u16];
+                const SEARCH_ACCEPT: [bool; 3usize] = [false, false, true];
+                const REVERSE: [u16; 12usize] = [
+                    0u16, 0u16, 0u16, 0u16, 6u16, 9u16, 0u16, 0u16, 0u16, 0u16, 0u16, 0u16,
+                ];
+                const REVERSE_ACCEPT: [bool; 4usize] = [false, false, true, true];
+                let mut state = 3usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 3usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
+                }
+                let end = end?;
+                let mut start = end;
+                let mut state = 3usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 3usize] {
+                        start = n;
+                    }
                 }
+                Some((start..end, []))
             } else {
-                return None.or_else(|| prev_b0.clone()).or_else(|| prev_b1.clone());
+                const ANCHORED: [u16; 12usize] = [
+                    0u16, 0u16, 0u16, 0u16, 6u16, 9u16, 0u16, 0u16, 0u16, 0u16, 0u16, 0u16,
+                ];
+                const ANCHORED_ACCEPT: [bool; 4usize] = [false, false, true, true];
+                let mut state = 3usize;
+                for b in data {
+                    state = next(&ANCHORED, state, *b);
+                    if state == 0 {
+                        return None;
+                    }
+                }
+                if ANCHORED_ACCEPT[state / 3usize] {
+                    Some((0..data.len(), []))
+                } else {
+                    None
+                }
             }
-        }
-    });
+        });
     assert!(!re.is_match(b""));
     assert!(!re.is_match(b"X"));
     assert!(re.is_match(b"a"));
@@ -231,113 +980,245 @@ fn alt() {
 #[test]
 fn group() {
     // regex!(br"(a)")
-    let re: safe_regex::Matcher1<_> = safe_regex::Matcher1::new(|data: &[u8]| {
-        assert!(data.len() < usize::MAX - 2);
-        let mut start = Some((usize::MAX..usize::MAX,));
-        let mut b0: Option<(core::ops::Range<usize>,)> = None;
-        let mut accept: Option<(core::ops::Range<usize>,)> = None;
-        let mut data_iter = data.iter();
-        let mut n = 0;
-        loop {
-            let prev_b0 = b0.clone();
-            accept = prev_b0.clone();
-            if let Some(b) = data_iter.next() {
-                b0 = start
-                    .clone()
-                    .map(|(r0,)| (n..n,))
-                    .clone()
-                    .filter(|_| {
-                        //
-                        *b == 97u8
-                    })
-                    .map(|(r0,)| (r0.start..n + 1,));
-                start = None;
-                if b0.is_none() {
-                    return None;
+    let re: safe_regex::Matcher1<_> =
+        safe_regex::Matcher::new("(a)", |data: &[u8], search_start: Option<usize>| {
+            safe_regex::internal::find_literal(b"a", data.get(search_start.unwrap_or(0)..)?)?;
+            let search_start = match search_start {
+                Some(search_start) => {
+                    let rest = data.get(search_start..)?;
+                    Some(search_start + safe_regex::internal::find_any_byte(b"a", rest)?)
                 }
+                None => None,
+            };
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 1u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+            ];
+            let one_pass =
+                |data: &[u8], offset: usize| -> Option<[Option<core::ops::Range<usize>>; 1usize]> {
+                    const ONE_PASS: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 0u16];
+                    const ONE_PASS_ACTIONS: [u8; 6usize] = [0u8, 0u8, 0u8, 1u8, 0u8, 0u8];
+                    let mut ranges = [usize::MAX..usize::MAX];
+                    let mut state = 2usize;
+                    for (n, b) in data.iter().enumerate() {
+                        let index = state + usize::from(CLASSES[usize::from(*b)]);
+                        state = usize::from(ONE_PASS[index]);
+                        if state == 0 {
+                            return None;
+                        }
+                        let n = n + offset;
+                        if ONE_PASS_ACTIONS[index] == 1u8 {
+                            ranges[0usize] = n..n + 1;
+                        }
+                    }
+                    if let 4usize = state {
+                    } else {
+                        return None;
+                    }
+                    let [r0] = ranges;
+                    Some([if r0.start == usize::MAX || r0.end == usize::MAX {
+                        None
+                    } else {
+                        Some(r0)
+                    }])
+                };
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 6usize] = [0u16, 0u16, 2u16, 4u16, 0u16, 0u16];
+                const SEARCH_ACCEPT: [bool; 3usize] = [false, false, true];
+                const REVERSE: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 0u16];
+                const REVERSE_ACCEPT: [bool; 3usize] = [false, false, true];
+                let mut state = 2usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 2usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
+                }
+                let end = end?;
+                let mut start = end;
+                let mut state = 2usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 2usize] {
+                        start = n;
+                    }
+                }
+                one_pass(&data[start..end], start).map(|groups| (start..end, groups))
             } else {
-                break;
+                one_pass(data, 0).map(|groups| (0..data.len(), groups))
             }
-            n = n + 1;
-        }
-        accept.map(|(r0,)| {
-            [
-                if r0.start == usize::MAX || r0.end == usize::MAX || r0.is_empty() {
-                    0..0usize
-                } else {
-                    r0
-                },
-            ]
-        })
-    });
+        });
     assert_eq!(None, re.match_slices(b""));
     assert_eq!(None, re.match_slices(b"X"));
     assert_eq!(None, re.match_slices(b"aX"));
     assert_eq!(None, re.match_slices(b"Xa"));
     assert_eq!(None, re.match_slices(b"aa"));
-    assert_eq!("a", escape_ascii(re.match_slices(b"a").unwrap().0));
+    assert_eq!("a", escape_ascii(re.match_slices(b"a").unwrap()[0]));
+    assert_eq!(Some((1..2, [1..2])), re.captures_iter(b"Xa").next());
 }
 
 #[test]
 fn groups_nested() {
     // regex!(br"(a(b))")
-    let re: safe_regex::Matcher2<_> = safe_regex::Matcher2::new(|data: &[u8]| {
-        assert!(data.len() < usize::MAX - 2);
-        let mut start = Some((usize::MAX..usize::MAX, usize::MAX..usize::MAX));
-        let mut b0: Option<(core::ops::Range<usize>, core::ops::Range<usize>)> = None;
-        let mut b1: Option<(core::ops::Range<usize>, core::ops::Range<usize>)> = None;
-        let mut accept: Option<(core::ops::Range<usize>, core::ops::Range<usize>)> = None;
-        let mut data_iter = data.iter();
-        let mut n = 0;
-        loop {
-            let prev_b0 = b0.clone();
-            let prev_b1 = b1.clone();
-            accept = prev_b1.clone();
-            if let Some(b) = data_iter.next() {
-                b1 = prev_b0
-                    .clone()
-                    .map(|(r0, r1)| (r0, n..n))
-                    .clone()
-                    .filter(|_| *b == 98u8)
-                    .map(|(r0, r1)| (r0.start..n + 1, r1.start..n + 1));
-                b0 = start
-                    .clone()
-                    .map(|(r0, r1)| (n..n, r1))
-                    .clone()
-                    .filter(|_| *b == 97u8)
-                    .map(|(r0, r1)| (r0.start..n + 1, r1));
-                start = None;
-                if b0.is_none() && b1.is_none() {
-                    return None;
+    let re: safe_regex::Matcher2<_> =
+        safe_regex::Matcher::new("(a(b))", |data: &[u8], search_start: Option<usize>| {
+            safe_regex::internal::find_literal(b"ab", data.get(search_start.unwrap_or(0)..)?)?;
+            let search_start = match search_start {
+                Some(search_start) => {
+                    let rest = data.get(search_start..)?;
+                    Some(search_start + safe_regex::internal::find_any_byte(b"a", rest)?)
                 }
+                None => None,
+            };
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 1u8, 2u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+            ];
+            let one_pass =
+                |data: &[u8], offset: usize| -> Option<[Option<core::ops::Range<usize>>; 2usize]> {
+                    const ONE_PASS: [u16; 12usize] = [
+                        0u16, 0u16, 0u16, 0u16, 6u16, 0u16, 0u16, 0u16, 9u16, 0u16, 0u16, 0u16,
+                    ];
+                    const ONE_PASS_ACTIONS: [u8; 12usize] =
+                        [0u8, 0u8, 0u8, 0u8, 1u8, 0u8, 0u8, 0u8, 2u8, 0u8, 0u8, 0u8];
+                    let mut ranges = [usize::MAX..usize::MAX, usize::MAX..usize::MAX];
+                    let mut state = 3usize;
+                    for (n, b) in data.iter().enumerate() {
+                        let index = state + usize::from(CLASSES[usize::from(*b)]);
+                        state = usize::from(ONE_PASS[index]);
+                        if state == 0 {
+                            return None;
+                        }
+                        let n = n + offset;
+                        match ONE_PASS_ACTIONS[index] {
+                            1u8 => {
+                                ranges[0usize] = n..n + 1;
+                            }
+                            2u8 => {
+                                ranges[1usize] = n..n + 1;
+                                ranges[0usize].end = n + 1;
+                            }
+                            _ => {}
+                        }
+                    }
+                    if let 9usize = state {
+                    } else {
+                        return None;
+                    }
+                    let [r0, r1] = ranges;
+                    Some([
+                        if r0.start == usize::MAX || r0.end == usize::MAX {
+                            None
+                        } else {
+                            Some(r0)
+                        },
+                        if r1.start == usize::MAX || r1.end == usize::MAX {
+                            None
+                        } else {
+                            Some(r1)
+                        },
+                    ])
+                };
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 12usize] = [
+                    0u16, 0u16, 0u16, 3u16, 6u16, 3u16, 3u16, 6u16, 9u16, 0u16, 0u16, 0u16,
+                ];
+                const SEARCH_ACCEPT: [bool; 4usize] = [false, false, false, true];
+                const REVERSE: [u16; 12usize] = [
+                    0u16, 0u16, 0u16, 0u16, 0u16, 6u16, 0u16, 9u16, 0u16, 0u16, 0u16, 0u16,
+                ];
+                const REVERSE_ACCEPT: [bool; 4usize] = [false, false, false, true];
+                let mut state = 3usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 3usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
+                }
+                let end = end?;
+                let mut start = end;
+                let mut state = 3usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 3usize] {
+                        start = n;
+                    }
+                }
+                one_pass(&data[start..end], start).map(|groups| (start..end, groups))
             } else {
-                break;
+                one_pass(data, 0).map(|groups| (0..data.len(), groups))
             }
-            n = n + 1;
-        }
-        accept.map(|(r0, r1)| {
-            [
-                if r0.start == usize::MAX || r0.end == usize::MAX || r0.is_empty() {
-                    0..0usize
-                } else {
-                    r0
-                },
-                if r1.start == usize::MAX || r1.end == usize::MAX || r1.is_empty() {
-                    0..0usize
-                } else {
-                    r1
-                },
-            ]
-        })
-    });
+        });
     assert_eq!(None, re.match_slices(b""));
     assert_eq!(None, re.match_slices(b"X"));
     assert_eq!(None, re.match_slices(b"aX"));
     assert_eq!(None, re.match_slices(b"Xa"));
     assert_eq!(None, re.match_slices(b"aa"));
     let groups = re.match_slices(b"ab").unwrap();
-    assert_eq!("ab", escape_ascii(groups.0));
-    assert_eq!("b", escape_ascii(groups.1));
+    assert_eq!("ab", escape_ascii(groups[0]));
+    assert_eq!("b", escape_ascii(groups[1]));
     assert_eq!(None, re.match_slices(b"ba"));
     assert_eq!(None, re.match_slices(b"bb"));
     assert_eq!(None, re.match_slices(b"Xab"));
@@ -351,23 +1232,82 @@ fn groups_nested() {
 #[test]
 fn optional() {
     // regex!(br"a?")
-    let re: Matcher0<_> = safe_regex::Matcher0::new(|data: &[u8]| {
-        let mut start = Some(());
-        let mut b0: Option<()> = None;
-        let mut data_iter = data.iter();
-        loop {
-            let prev_b0 = b0.clone();
-            if let Some(b) = data_iter.next() {
-                b0 = start.clone().filter(|_| *b == 97u8);
-                start = None;
-                if b0.is_none() {
-                    return None;
+    let re: Matcher0<_> =
+        safe_regex::Matcher::new("a?", |data: &[u8], search_start: Option<usize>| {
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 1u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+            ];
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 0u16];
+                const SEARCH_ACCEPT: [bool; 3usize] = [false, true, true];
+                const REVERSE: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 0u16];
+                const REVERSE_ACCEPT: [bool; 3usize] = [false, true, true];
+                let mut state = 2usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 2usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
                 }
+                let end = end?;
+                let mut start = end;
+                let mut state = 2usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 2usize] {
+                        start = n;
+                    }
+                }
+                Some((start..end, []))
             } else {
-                return start.clone().or_else(|| prev_b0.clone());
+                const ANCHORED: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 0u16];
+                const ANCHORED_ACCEPT: [bool; 3usize] = [false, true, true];
+                let mut state = 2usize;
+                for b in data {
+                    state = next(&ANCHORED, state, *b);
+                    if state == 0 {
+                        return None;
+                    }
+                }
+                if ANCHORED_ACCEPT[state / 2usize] {
+                    Some((0..data.len(), []))
+                } else {
+                    None
+                }
             }
-        }
-    });
+        });
     assert!(re.is_match(b""));
     assert!(re.is_match(b"a"));
     assert!(!re.is_match(b"X"));
@@ -380,30 +1320,90 @@ fn optional() {
 #[test]
 fn optional_at_start() {
     // regex!(br"a?a")
-    let re: Matcher0<_> = safe_regex::Matcher0::new(|data: &[u8]| {
-        let mut start = Some(());
-        let mut b0: Option<()> = None;
-        let mut b1: Option<()> = None;
-        let mut data_iter = data.iter();
-        loop {
-            let prev_b0 = b0.clone();
-            let prev_b1 = b1.clone();
-            if let Some(b) = data_iter.next() {
-                b1 = start
-                    .clone()
-                    .or_else(|| prev_b0.clone())
-                    .clone()
-                    .filter(|_| *b == 97u8);
-                b0 = start.clone().filter(|_| *b == 97u8);
-                start = None;
-                if b0.is_none() && b1.is_none() {
-                    return None;
+    let re: Matcher0<_> =
+        safe_regex::Matcher::new("a?a", |data: &[u8], search_start: Option<usize>| {
+            safe_regex::internal::find_literal(b"a", data.get(search_start.unwrap_or(0)..)?)?;
+            let search_start = match search_start {
+                Some(search_start) => {
+                    let rest = data.get(search_start..)?;
+                    Some(search_start + safe_regex::internal::find_any_byte(b"a", rest)?)
+                }
+                None => None,
+            };
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 1u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+            ];
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 8usize] = [0u16, 0u16, 2u16, 4u16, 0u16, 6u16, 0u16, 0u16];
+                const SEARCH_ACCEPT: [bool; 4usize] = [false, false, true, true];
+                const REVERSE: [u16; 8usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 6u16, 0u16, 0u16];
+                const REVERSE_ACCEPT: [bool; 4usize] = [false, false, true, true];
+                let mut state = 2usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 2usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
                 }
+                let end = end?;
+                let mut start = end;
+                let mut state = 2usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 2usize] {
+                        start = n;
+                    }
+                }
+                Some((start..end, []))
             } else {
-                return prev_b1;
+                const ANCHORED: [u16; 8usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 6u16, 0u16, 0u16];
+                const ANCHORED_ACCEPT: [bool; 4usize] = [false, false, true, true];
+                let mut state = 2usize;
+                for b in data {
+                    state = next(&ANCHORED, state, *b);
+                    if state == 0 {
+                        return None;
+                    }
+                }
+                if ANCHORED_ACCEPT[state / 2usize] {
+                    Some((0..data.len(), []))
+                } else {
+                    None
+                }
             }
-        }
-    });
+        });
     assert!(!re.is_match(b""));
     assert!(!re.is_match(b"X"));
     assert!(re.is_match(b"a"));
@@ -419,26 +1419,90 @@ fn optional_at_start() {
 #[test]
 fn optional_at_end() {
     // regex!(br"aa?")
-    let re: Matcher0<_> = safe_regex::Matcher0::new(|data: &[u8]| {
-        let mut start = Some(());
-        let mut b0: Option<()> = None;
-        let mut b1: Option<()> = None;
-        let mut data_iter = data.iter();
-        loop {
-            let prev_b0 = b0.clone();
-            let prev_b1 = b1.clone();
-            if let Some(b) = data_iter.next() {
-                b1 = prev_b0.clone().filter(|_| *b == 97u8);
-                b0 = start.clone().filter(|_| *b == 97u8);
-                start = None;
-                if b0.is_none() && b1.is_none() {
-                    return None;
+    let re: Matcher0<_> =
+        safe_regex::Matcher::new("aa?", |data: &[u8], search_start: Option<usize>| {
+            safe_regex::internal::find_literal(b"a", data.get(search_start.unwrap_or(0)..)?)?;
+            let search_start = match search_start {
+                Some(search_start) => {
+                    let rest = data.get(search_start..)?;
+                    Some(search_start + safe_regex::internal::find_any_byte(b"a", rest)?)
+                }
+                None => None,
+            };
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 1u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+            ];
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 8usize] = [0u16, 0u16, 2u16, 4u16, 0u16, 6u16, 0u16, 0u16];
+                const SEARCH_ACCEPT: [bool; 4usize] = [false, false, true, true];
+                const REVERSE: [u16; 8usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 6u16, 0u16, 0u16];
+                const REVERSE_ACCEPT: [bool; 4usize] = [false, false, true, true];
+                let mut state = 2usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 2usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
                 }
+                let end = end?;
+                let mut start = end;
+                let mut state = 2usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 2usize] {
+                        start = n;
+                    }
+                }
+                Some((start..end, []))
             } else {
-                return prev_b0.clone().or_else(|| prev_b1.clone());
+                const ANCHORED: [u16; 8usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 6u16, 0u16, 0u16];
+                const ANCHORED_ACCEPT: [bool; 4usize] = [false, false, true, true];
+                let mut state = 2usize;
+                for b in data {
+                    state = next(&ANCHORED, state, *b);
+                    if state == 0 {
+                        return None;
+                    }
+                }
+                if ANCHORED_ACCEPT[state / 2usize] {
+                    Some((0..data.len(), []))
+                } else {
+                    None
+                }
             }
-        }
-    });
+        });
     assert!(!re.is_match(b""));
     assert!(!re.is_match(b"X"));
     assert!(re.is_match(b"a"));
@@ -454,45 +1518,85 @@ fn optional_at_end() {
 #[test]
 fn optionals_in_seq() {
     // regex!(br"a?a?a?")
-    let re: Matcher0<_> = safe_regex::Matcher0::new(|data: &[u8]| {
-        let mut start = Some(());
-        let mut b0: Option<()> = None;
-        let mut b1: Option<()> = None;
-        let mut b2: Option<()> = None;
-        let mut data_iter = data.iter();
-        loop {
-            let prev_b0 = b0.clone();
-            let prev_b1 = b1.clone();
-            let prev_b2 = b2.clone();
-            if let Some(b) = data_iter.next() {
-                b2 = start
-                    .clone()
-                    .or_else(|| prev_b0.clone())
-                    .clone()
-                    .or_else(|| prev_b1.clone())
-                    .clone()
-                    .filter(|_| *b == 97u8);
-                b1 = start
-                    .clone()
-                    .or_else(|| prev_b0.clone())
-                    .clone()
-                    .filter(|_| *b == 97u8);
-                b0 = start.clone().filter(|_| *b == 97u8);
-                start = None;
-                if b0.is_none() && b1.is_none() && b2.is_none() {
-                    return None;
+    let re: Matcher0<_> =
+        safe_regex::Matcher::new("a?a?a?", |data: &[u8], search_start: Option<usize>| {
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 1u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+            ];
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 10usize] =
+                    [0u16, 0u16, 0u16, 4u16, 0u16, 6u16, 0u16, 8u16, 0u16, 0u16];
+                const SEARCH_ACCEPT: [bool; 5usize] = [false, true, true, true, true];
+                const REVERSE: [u16; 10usize] =
+                    [0u16, 0u16, 0u16, 4u16, 0u16, 6u16, 0u16, 8u16, 0u16, 0u16];
+                const REVERSE_ACCEPT: [bool; 5usize] = [false, true, true, true, true];
+                let mut state = 2usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 2usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
+                }
+                let end = end?;
+                let mut start = end;
+                let mut state = 2usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 2usize] {
+                        start = n;
+                    }
                 }
+                Some((start..end, []))
             } else {
-                return start
-                    .clone()
-                    .or_else(|| prev_b0.clone())
-                    .clone()
-                    .or_else(|| prev_b1.clone())
-                    .clone()
-                    .or_else(|| prev_b2.clone());
+                const ANCHORED: [u16; 10usize] =
+                    [0u16, 0u16, 0u16, 4u16, 0u16, 6u16, 0u16, 8u16, 0u16, 0u16];
+                const ANCHORED_ACCEPT: [bool; 5usize] = [false, true, true, true, true];
+                let mut state = 2usize;
+                for b in data {
+                    state = next(&ANCHORED, state, *b);
+                    if state == 0 {
+                        return None;
+                    }
+                }
+                if ANCHORED_ACCEPT[state / 2usize] {
+                    Some((0..data.len(), []))
+                } else {
+                    None
+                }
             }
-        }
-    });
+        });
     assert!(re.is_match(b""));
     assert!(!re.is_match(b"X"));
     assert!(re.is_match(b"a"));
@@ -514,80 +1618,195 @@ fn optionals_in_seq() {
 #[test]
 fn optionals_in_groups() {
     // regex!(br"(a?)(a?)")
-    let re: Matcher2<_> = safe_regex::Matcher2::new(|data: &[u8]| {
-        assert!(data.len() < usize::MAX - 2);
-        let mut start = Some((usize::MAX..usize::MAX, usize::MAX..usize::MAX));
-        let mut b0: Option<(core::ops::Range<usize>, core::ops::Range<usize>)> = None;
-        let mut b1: Option<(core::ops::Range<usize>, core::ops::Range<usize>)> = None;
-        let mut accept: Option<(core::ops::Range<usize>, core::ops::Range<usize>)> = None;
-        let mut data_iter = data.iter();
-        let mut n = 0;
-        loop {
-            let prev_b0 = b0.clone();
-            let prev_b1 = b1.clone();
-            accept = start
-                .clone()
-                .map(|(r0, r1)| (n..n, r1))
-                .clone()
-                .or_else(|| prev_b0.clone())
-                .clone()
-                .map(|(r0, r1)| (r0, n..n))
-                .clone()
-                .or_else(|| prev_b1.clone())
-                .clone();
-            if let Some(b) = data_iter.next() {
-                b1 = start
-                    .clone()
-                    .map(|(r0, r1)| (n..n, r1))
-                    .clone()
-                    .or_else(|| prev_b0.clone())
-                    .clone()
-                    .map(|(r0, r1)| (r0, n..n))
-                    .clone()
-                    .filter(|_| *b == 97u8)
-                    .map(|(r0, r1)| (r0, r1.start..n + 1));
-                b0 = start
-                    .clone()
-                    .map(|(r0, r1)| (n..n, r1))
-                    .clone()
-                    .filter(|_| *b == 97u8)
-                    .map(|(r0, r1)| (r0.start..n + 1, r1));
-                start = None;
-                if b0.is_none() && b1.is_none() {
+    let re: Matcher2<_> =
+        safe_regex::Matcher::new("(a?)(a?)", |data: &[u8], search_start: Option<usize>| {
+            #[inline(never)]
+            fn search(
+                data: &[u8],
+                search_start: usize,
+            ) -> Option<(
+                core::ops::Range<usize>,
+                [Option<core::ops::Range<usize>>; 2usize],
+            )> {
+                static INSTS: [safe_regex::internal::Inst; 11usize] = [
+                    safe_regex::internal::Inst::Match,
+                    safe_regex::internal::Inst::Save(5usize, 0usize),
+                    safe_regex::internal::Inst::Save(3usize, 1usize),
+                    safe_regex::internal::Inst::Byte(0usize, 2usize),
+                    safe_regex::internal::Inst::Split(3usize, 2usize),
+                    safe_regex::internal::Inst::Save(2usize, 4usize),
+                    safe_regex::internal::Inst::Save(1usize, 5usize),
+                    safe_regex::internal::Inst::Byte(0usize, 6usize),
+                    safe_regex::internal::Inst::Split(7usize, 6usize),
+                    safe_regex::internal::Inst::Save(0usize, 8usize),
+                    safe_regex::internal::Inst::Save(4usize, 9usize),
+                ];
+                static IDS: [usize; 11usize] = [
+                    0usize, 1usize, 2usize, 3usize, 4usize, 5usize, 6usize, 7usize, 8usize, 9usize,
+                    10usize,
+                ];
+                static SETS: [[bool; 256]; 1usize] = [[
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, true, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false,
+                ]];
+                let nfa = safe_regex::internal::Nfa {
+                    insts: &INSTS,
+                    ids: &IDS,
+                    sets: &SETS,
+                    start: 10usize,
+                };
+                let mut memory =
+                    [0_usize; safe_regex::internal::search_memory_len(11usize, 6usize)];
+                let mut slots = [usize::MAX; 6usize];
+                if !safe_regex::internal::search(&nfa, data, search_start, &mut memory, &mut slots)
+                {
                     return None;
                 }
+                Some((
+                    slots[4usize]..slots[5usize],
+                    [
+                        if slots[0usize] == usize::MAX || slots[1usize] == usize::MAX {
+                            None
+                        } else {
+                            Some(slots[0usize]..slots[1usize])
+                        },
+                        if slots[2usize] == usize::MAX || slots[3usize] == usize::MAX {
+                            None
+                        } else {
+                            Some(slots[2usize]..slots[3usize])
+                        },
+                    ],
+                ))
+            }
+            static BYTES0: [bool; 256] = [
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, true, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false,
+            ];
+            if let Some(search_start) = search_start {
+                search(data, search_start)
             } else {
-                break;
+                assert!(data.len() < usize::MAX - 2);
+                let mut start = Some((usize::MAX..usize::MAX, usize::MAX..usize::MAX));
+                let mut b0: Option<(core::ops::Range<usize>, core::ops::Range<usize>)> = None;
+                let mut b1: Option<(core::ops::Range<usize>, core::ops::Range<usize>)> = None;
+                let mut accept: Option<(core::ops::Range<usize>, core::ops::Range<usize>)> = None;
+                let mut data_iter = data.iter();
+                let mut n = 0;
+                loop {
+                    let prev_b0 = b0.clone();
+                    let prev_b1 = b1.clone();
+                    accept = start
+                        .clone()
+                        .map(|(r0, r1)| (n..n, r1))
+                        .clone()
+                        .or_else(|| prev_b0.clone())
+                        .clone()
+                        .map(|(r0, r1)| (r0, n..n))
+                        .clone()
+                        .or_else(|| prev_b1.clone())
+                        .clone();
+                    if let Some(b) = data_iter.next() {
+                        b1 = if BYTES0[usize::from(*b)] {
+                            start
+                                .clone()
+                                .map(|(r0, r1)| (n..n, r1))
+                                .clone()
+                                .or_else(|| prev_b0.clone())
+                                .clone()
+                                .map(|(r0, r1)| (r0, n..n))
+                                .clone()
+                        } else {
+                            None
+                        }
+                        .map(|(r0, r1)| (r0, r1.start..n + 1));
+                        b0 = if BYTES0[usize::from(*b)] {
+                            start.clone().map(|(r0, r1)| (n..n, r1)).clone()
+                        } else {
+                            None
+                        }
+                        .map(|(r0, r1)| (r0.start..n + 1, r1));
+                        start = None;
+                        if b0.is_none() && b1.is_none() {
+                            return None;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
+                }
+                accept.map(|(r0, r1)| {
+                    (
+                        0..data.len(),
+                        [
+                            if r0.start == usize::MAX || r0.end == usize::MAX {
+                                None
+                            } else {
+                                Some(r0)
+                            },
+                            if r1.start == usize::MAX || r1.end == usize::MAX {
+                                None
+                            } else {
+                                Some(r1)
+                            },
+                        ],
+                    )
+                })
             }
-            n = n + 1;
-        }
-        accept.map(|(r0, r1)| {
-            [
-                if r0.start == usize::MAX || r0.end == usize::MAX || r0.is_empty() {
-                    0..0usize
-                } else {
-                    r0
-                },
-                if r1.start == usize::MAX || r1.end == usize::MAX || r1.is_empty() {
-                    0..0usize
-                } else {
-                    r1
-                },
-            ]
-        })
-    });
+        });
     let groups = re.match_slices(b"").unwrap();
-    assert_eq!("", escape_ascii(groups.0));
-    assert_eq!("", escape_ascii(groups.1));
+    assert_eq!("", escape_ascii(groups[0]));
+    assert_eq!("", escape_ascii(groups[1]));
     assert!(!re.is_match(b"X"));
     let groups = re.match_slices(b"a").unwrap();
-    assert_eq!("a", escape_ascii(groups.0));
-    assert_eq!("", escape_ascii(groups.1));
+    assert_eq!("a", escape_ascii(groups[0]));
+    assert_eq!("", escape_ascii(groups[1]));
     assert!(!re.is_match(b"Xa"));
     assert!(!re.is_match(b"aX"));
     let groups = re.match_slices(b"aa").unwrap();
-    assert_eq!("a", escape_ascii(groups.0));
-    assert_eq!("a", escape_ascii(groups.1));
+    assert_eq!("a", escape_ascii(groups[0]));
+    assert_eq!("a", escape_ascii(groups[1]));
     assert!(!re.is_match(b"Xaa"));
     assert!(!re.is_match(b"aXa"));
     assert!(!re.is_match(b"aaX"));
@@ -598,27 +1817,82 @@ fn optionals_in_groups() {
 #[test]
 fn star() {
     // regex!(br"a*")
-    let re: Matcher0<_> = safe_regex::Matcher0::new(|data: &[u8]| {
-        let mut start = Some(());
-        let mut b0: Option<()> = None;
-        let mut data_iter = data.iter();
-        loop {
-            let prev_b0 = b0.clone();
-            if let Some(b) = data_iter.next() {
-                b0 = prev_b0
-                    .clone()
-                    .or_else(|| start.clone())
-                    .clone()
-                    .filter(|_| *b == 97u8);
-                start = None;
-                if b0.is_none() {
-                    return None;
+    let re: Matcher0<_> =
+        safe_regex::Matcher::new("a*", |data: &[u8], search_start: Option<usize>| {
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 1u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+            ];
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 4u16];
+                const SEARCH_ACCEPT: [bool; 3usize] = [false, true, true];
+                const REVERSE: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 4u16];
+                const REVERSE_ACCEPT: [bool; 3usize] = [false, true, true];
+                let mut state = 2usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 2usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
                 }
+                let end = end?;
+                let mut start = end;
+                let mut state = 2usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 2usize] {
+                        start = n;
+                    }
+                }
+                Some((start..end, []))
             } else {
-                return prev_b0.clone().or_else(|| start.clone());
+                const ANCHORED: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 4u16];
+                const ANCHORED_ACCEPT: [bool; 3usize] = [false, true, true];
+                let mut state = 2usize;
+                for b in data {
+                    state = next(&ANCHORED, state, *b);
+                    if state == 0 {
+                        return None;
+                    }
+                }
+                if ANCHORED_ACCEPT[state / 2usize] {
+                    Some((0..data.len(), []))
+                } else {
+                    None
+                }
             }
-        }
-    });
+        });
     assert!(re.is_match(b""));
     assert!(!re.is_match(b"X"));
     assert!(re.is_match(b"a"));
@@ -641,38 +1915,298 @@ fn star() {
     assert!(!re.is_match(b"aaaaX"));
     assert!(re.is_match(b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"));
     assert!(!re.is_match(b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaX"));
+    assert_eq!(Some(0..0), re.find(b"Xaa"));
+    assert_eq!(Some(1..3), re.find_at(b"Xaa", 1));
+}
+
+#[test]
+fn lazy_star() {
+    // regex!(br"(a*?)(a*)")
+    let re: Matcher2<_> =
+        safe_regex::Matcher::new("(a*?)(a*)", |data: &[u8], search_start: Option<usize>| {
+            #[inline(never)]
+            fn search(
+                data: &[u8],
+                search_start: usize,
+            ) -> Option<(
+                core::ops::Range<usize>,
+                [Option<core::ops::Range<usize>>; 2usize],
+            )> {
+                static INSTS: [safe_regex::internal::Inst; 11usize] = [
+                    safe_regex::internal::Inst::Match,
+                    safe_regex::internal::Inst::Save(5usize, 0usize),
+                    safe_regex::internal::Inst::Save(3usize, 1usize),
+                    safe_regex::internal::Inst::Split(4usize, 2usize),
+                    safe_regex::internal::Inst::Byte(0usize, 3usize),
+                    safe_regex::internal::Inst::Save(2usize, 3usize),
+                    safe_regex::internal::Inst::Save(1usize, 5usize),
+                    safe_regex::internal::Inst::Split(6usize, 8usize),
+                    safe_regex::internal::Inst::Byte(0usize, 7usize),
+                    safe_regex::internal::Inst::Save(0usize, 7usize),
+                    safe_regex::internal::Inst::Save(4usize, 9usize),
+                ];
+                static IDS: [usize; 11usize] = [
+                    0usize, 1usize, 2usize, 3usize, 4usize, 5usize, 6usize, 7usize, 8usize, 9usize,
+                    10usize,
+                ];
+                static SETS: [[bool; 256]; 1usize] = [[
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, true, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false, false, false, false, false, false, false, false, false,
+                    false, false, false,
+                ]];
+                let nfa = safe_regex::internal::Nfa {
+                    insts: &INSTS,
+                    ids: &IDS,
+                    sets: &SETS,
+                    start: 10usize,
+                };
+                let mut memory =
+                    [0_usize; safe_regex::internal::search_memory_len(11usize, 6usize)];
+                let mut slots = [usize::MAX; 6usize];
+                if !safe_regex::internal::search(&nfa, data, search_start, &mut memory, &mut slots)
+                {
+                    return None;
+                }
+                Some((
+                    slots[4usize]..slots[5usize],
+                    [
+                        if slots[0usize] == usize::MAX || slots[1usize] == usize::MAX {
+                            None
+                        } else {
+                            Some(slots[0usize]..slots[1usize])
+                        },
+                        if slots[2usize] == usize::MAX || slots[3usize] == usize::MAX {
+                            None
+                        } else {
+                            Some(slots[2usize]..slots[3usize])
+                        },
+                    ],
+                ))
+            }
+            static BYTES0: [bool; 256] = [
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, true, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false, false, false, false, false, false, false, false, false,
+                false, false, false, false,
+            ];
+            if let Some(search_start) = search_start {
+                search(data, search_start)
+            } else {
+                assert!(data.len() < usize::MAX - 2);
+                let mut start = Some((usize::MAX..usize::MAX, usize::MAX..usize::MAX));
+                let mut b0: Option<(core::ops::Range<usize>, core::ops::Range<usize>)> = None;
+                let mut b1: Option<(core::ops::Range<usize>, core::ops::Range<usize>)> = None;
+                let mut accept: Option<(core::ops::Range<usize>, core::ops::Range<usize>)> = None;
+                let mut data_iter = data.iter();
+                let mut n = 0;
+                loop {
+                    let prev_b0 = b0.clone();
+                    let prev_b1 = b1.clone();
+                    accept = prev_b1
+                        .clone()
+                        .or_else(|| {
+                            start
+                                .clone()
+                                .map(|(r0, r1)| (n..n, r1))
+                                .clone()
+                                .or_else(|| prev_b0.clone())
+                                .clone()
+                                .map(|(r0, r1)| (r0, n..n))
+                                .clone()
+                        })
+                        .clone();
+                    if let Some(b) = data_iter.next() {
+                        b1 = if BYTES0[usize::from(*b)] {
+                            prev_b1
+                                .clone()
+                                .or_else(|| {
+                                    start
+                                        .clone()
+                                        .map(|(r0, r1)| (n..n, r1))
+                                        .clone()
+                                        .or_else(|| prev_b0.clone())
+                                        .clone()
+                                        .map(|(r0, r1)| (r0, n..n))
+                                        .clone()
+                                })
+                                .clone()
+                        } else {
+                            None
+                        }
+                        .map(|(r0, r1)| (r0, r1.start..n + 1));
+                        b0 = if BYTES0[usize::from(*b)] {
+                            start
+                                .clone()
+                                .map(|(r0, r1)| (n..n, r1))
+                                .clone()
+                                .or_else(|| prev_b0.clone())
+                                .clone()
+                        } else {
+                            None
+                        }
+                        .map(|(r0, r1)| (r0.start..n + 1, r1));
+                        start = None;
+                        if b0.is_none() && b1.is_none() {
+                            return None;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
+                }
+                accept.map(|(r0, r1)| {
+                    (
+                        0..data.len(),
+                        [
+                            if r0.start == usize::MAX || r0.end == usize::MAX {
+                                None
+                            } else {
+                                Some(r0)
+                            },
+                            if r1.start == usize::MAX || r1.end == usize::MAX {
+                                None
+                            } else {
+                                Some(r1)
+                            },
+                        ],
+                    )
+                })
+            }
+        });
+    assert!(re.is_match(b""));
+    assert!(re.is_match(b"aa"));
+    assert!(!re.is_match(b"ab"));
+    assert_eq!([0..0, 0..2], re.match_ranges(b"aa").unwrap());
+    assert_eq!(Some((0..1, [0..0, 0..1])), re.captures_iter(b"aX").next());
 }
 
 #[test]
 fn seq_in_star() {
     // regex!(br"(?:abc)*")
-    let re: Matcher0<_> = safe_regex::Matcher0::new(|data: &[u8]| {
-        let mut start = Some(());
-        let mut b0: Option<()> = None;
-        let mut b1: Option<()> = None;
-        let mut b2: Option<()> = None;
-        let mut data_iter = data.iter();
-        loop {
-            let prev_b0 = b0.clone();
-            let prev_b1 = b1.clone();
-            let prev_b2 = b2.clone();
-            if let Some(b) = data_iter.next() {
-                b2 = prev_b1.clone().filter(|_| *b == 99u8);
-                b1 = prev_b0.clone().filter(|_| *b == 98u8);
-                b0 = start
-                    .clone()
-                    .or_else(|| prev_b2.clone())
-                    .clone()
-                    .filter(|_| *b == 97u8);
-                start = None;
-                if b0.is_none() && b1.is_none() && b2.is_none() {
-                    return None;
+    let re: Matcher0<_> =
+        safe_regex::Matcher::new("(?:abc)*", |data: &[u8], search_start: Option<usize>| {
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 1u8, 2u8, 3u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+            ];
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 20usize] = [
+                    0u16, 0u16, 0u16, 0u16, 0u16, 8u16, 0u16, 0u16, 0u16, 0u16, 12u16, 0u16, 0u16,
+                    0u16, 0u16, 16u16, 0u16, 8u16, 0u16, 0u16,
+                ];
+                const SEARCH_ACCEPT: [bool; 5usize] = [false, true, false, false, true];
+                const REVERSE: [u16; 20usize] = [
+                    0u16, 0u16, 0u16, 0u16, 0u16, 0u16, 0u16, 8u16, 0u16, 0u16, 12u16, 0u16, 0u16,
+                    16u16, 0u16, 0u16, 0u16, 0u16, 0u16, 8u16,
+                ];
+                const REVERSE_ACCEPT: [bool; 5usize] = [false, true, false, false, true];
+                let mut state = 4usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 4usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
                 }
+                let end = end?;
+                let mut start = end;
+                let mut state = 4usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 4usize] {
+                        start = n;
+                    }
+                }
+                Some((start..end, []))
             } else {
-                return start.clone().or_else(|| prev_b2.clone());
+                const ANCHORED: [u16; 20usize] = [
+                    0u16, 0u16, 0u16, 0u16, 0u16, 8u16, 0u16, 0u16, 0u16, 0u16, 12u16, 0u16, 0u16,
+                    0u16, 0u16, 16u16, 0u16, 8u16, 0u16, 0u16,
+                ];
+                const ANCHORED_ACCEPT: [bool; 5usize] = [false, true, false, false, true];
+                let mut state = 4usize;
+                for b in data {
+                    state = next(&ANCHORED, state, *b);
+                    if state == 0 {
+                        return None;
+                    }
+                }
+                if ANCHORED_ACCEPT[state / 4usize] {
+                    Some((0..data.len(), []))
+                } else {
+                    None
+                }
             }
-        }
-    });
+        });
     assert!(re.is_match(b""));
     assert!(!re.is_match(b"X"));
     assert!(!re.is_match(b"a"));
@@ -694,57 +2228,126 @@ fn seq_in_star() {
 #[test]
 fn seq_in_group() {
     // regex!(br"(abc)d")
-    let re: Matcher1<_> = safe_regex::Matcher1::new(|data: &[u8]| {
-        assert!(data.len() < usize::MAX - 2);
-        let mut start = Some((usize::MAX..usize::MAX,));
-        let mut b0: Option<(core::ops::Range<usize>,)> = None;
-        let mut b1: Option<(core::ops::Range<usize>,)> = None;
-        let mut b2: Option<(core::ops::Range<usize>,)> = None;
-        let mut b3: Option<(core::ops::Range<usize>,)> = None;
-        let mut accept: Option<(core::ops::Range<usize>,)> = None;
-        let mut data_iter = data.iter();
-        let mut n = 0;
-        loop {
-            let prev_b0 = b0.clone();
-            let prev_b1 = b1.clone();
-            let prev_b2 = b2.clone();
-            let prev_b3 = b3.clone();
-            accept = prev_b3.clone();
-            if let Some(b) = data_iter.next() {
-                b3 = prev_b2.clone().filter(|_| *b == 100u8);
-                b2 = prev_b1
-                    .clone()
-                    .filter(|_| *b == 99u8)
-                    .map(|(r0,)| (r0.start..n + 1,));
-                b1 = prev_b0
-                    .clone()
-                    .filter(|_| *b == 98u8)
-                    .map(|(r0,)| (r0.start..n + 1,));
-                b0 = start
-                    .clone()
-                    .map(|(r0,)| (n..n,))
-                    .clone()
-                    .filter(|_| *b == 97u8)
-                    .map(|(r0,)| (r0.start..n + 1,));
-                start = None;
-                if b0.is_none() && b1.is_none() && b2.is_none() && b3.is_none() {
-                    return None;
+    let re: Matcher1<_> =
+        safe_regex::Matcher::new("(abc)d", |data: &[u8], search_start: Option<usize>| {
+            safe_regex::internal::find_literal(b"abcd", data.get(search_start.unwrap_or(0)..)?)?;
+            let search_start = match search_start {
+                Some(search_start) => {
+                    let rest = data.get(search_start..)?;
+                    Some(search_start + safe_regex::internal::find_any_byte(b"a", rest)?)
+                }
+                None => None,
+            };
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 1u8, 2u8, 3u8, 4u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+            ];
+            let one_pass =
+                |data: &[u8], offset: usize| -> Option<[Option<core::ops::Range<usize>>; 1usize]> {
+                    const ONE_PASS: [u16; 30usize] = [
+                        0u16, 0u16, 0u16, 0u16, 0u16, 0u16, 10u16, 0u16, 0u16, 0u16, 0u16, 0u16,
+                        15u16, 0u16, 0u16, 0u16, 0u16, 0u16, 20u16, 0u16, 0u16, 0u16, 0u16, 0u16,
+                        25u16, 0u16, 0u16, 0u16, 0u16, 0u16,
+                    ];
+                    const ONE_PASS_ACTIONS: [u8; 30usize] = [
+                        0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 1u8, 0u8, 0u8, 0u8, 0u8, 0u8, 2u8, 0u8, 0u8,
+                        0u8, 0u8, 0u8, 2u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                    ];
+                    let mut ranges = [usize::MAX..usize::MAX];
+                    let mut state = 5usize;
+                    for (n, b) in data.iter().enumerate() {
+                        let index = state + usize::from(CLASSES[usize::from(*b)]);
+                        state = usize::from(ONE_PASS[index]);
+                        if state == 0 {
+                            return None;
+                        }
+                        let n = n + offset;
+                        match ONE_PASS_ACTIONS[index] {
+                            1u8 => {
+                                ranges[0usize] = n..n + 1;
+                            }
+                            2u8 => {
+                                ranges[0usize].end = n + 1;
+                            }
+                            _ => {}
+                        }
+                    }
+                    if let 25usize = state {
+                    } else {
+                        return None;
+                    }
+                    let [r0] = ranges;
+                    Some([if r0.start == usize::MAX || r0.end == usize::MAX {
+                        None
+                    } else {
+                        Some(r0)
+                    }])
+                };
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 30usize] = [
+                    0u16, 0u16, 0u16, 0u16, 0u16, 5u16, 10u16, 5u16, 5u16, 5u16, 5u16, 10u16,
+                    15u16, 5u16, 5u16, 5u16, 10u16, 5u16, 20u16, 5u16, 5u16, 10u16, 5u16, 5u16,
+                    25u16, 0u16, 0u16, 0u16, 0u16, 0u16,
+                ];
+                const SEARCH_ACCEPT: [bool; 6usize] = [false, false, false, false, false, true];
+                const REVERSE: [u16; 30usize] = [
+                    0u16, 0u16, 0u16, 0u16, 0u16, 0u16, 0u16, 0u16, 0u16, 10u16, 0u16, 0u16, 0u16,
+                    15u16, 0u16, 0u16, 0u16, 20u16, 0u16, 0u16, 0u16, 25u16, 0u16, 0u16, 0u16,
+                    0u16, 0u16, 0u16, 0u16, 0u16,
+                ];
+                const REVERSE_ACCEPT: [bool; 6usize] = [false, false, false, false, false, true];
+                let mut state = 5usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 5usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
                 }
+                let end = end?;
+                let mut start = end;
+                let mut state = 5usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 5usize] {
+                        start = n;
+                    }
+                }
+                one_pass(&data[start..end], start).map(|groups| (start..end, groups))
             } else {
-                break;
+                one_pass(data, 0).map(|groups| (0..data.len(), groups))
             }
-            n = n + 1;
-        }
-        accept.map(|(r0,)| {
-            [
-                if r0.start == usize::MAX || r0.end == usize::MAX || r0.is_empty() {
-                    0..0usize
-                } else {
-                    r0
-                },
-            ]
-        })
-    });
+        });
     assert!(!re.is_match(b""));
     assert!(!re.is_match(b"a"));
     assert!(!re.is_match(b"d"));
@@ -753,7 +2356,7 @@ fn seq_in_group() {
     assert!(!re.is_match(b"cd"));
     assert!(!re.is_match(b"abc"));
     assert!(!re.is_match(b"acd"));
-    assert_eq!("abc", escape_ascii(re.match_slices(b"abcd").unwrap().0));
+    assert_eq!("abc", escape_ascii(re.match_slices(b"abcd").unwrap()[0]));
     assert!(!re.is_match(b"abcda"));
     assert!(!re.is_match(b"abcdabcd"));
     assert!(!re.is_match(b"Xabcd"));
@@ -766,63 +2369,114 @@ fn seq_in_group() {
 #[test]
 fn alt_in_group() {
     // regex!(br"(a|b)")
-    let re: Matcher1<_> = safe_regex::Matcher1::new(|data: &[u8]| {
-        assert!(data.len() < usize::MAX - 2);
-        let mut start = Some((usize::MAX..usize::MAX,));
-        let mut b0: Option<(core::ops::Range<usize>,)> = None;
-        let mut b1: Option<(core::ops::Range<usize>,)> = None;
-        let mut accept: Option<(core::ops::Range<usize>,)> = None;
-        let mut data_iter = data.iter();
-        let mut n = 0;
-        loop {
-            let prev_b0 = b0.clone();
-            let prev_b1 = b1.clone();
-            accept = None
-                .or_else(|| prev_b0.clone())
-                .or_else(|| prev_b1.clone())
-                .clone();
-            if let Some(b) = data_iter.next() {
-                b1 = start
-                    .clone()
-                    .map(|(r0,)| (n..n,))
-                    .clone()
-                    .filter(|_| {
-                        //
-                        *b == 98u8
-                    })
-                    .map(|(r0,)| (r0.start..n + 1,));
-                b0 = start
-                    .clone()
-                    .map(|(r0,)| (n..n,))
-                    .clone()
-                    .filter(|_| {
-                        //
-                        *b == 97u8
-                    })
-                    .map(|(r0,)| (r0.start..n + 1,));
-                start = None;
-                if b0.is_none() && b1.is_none() {
-                    return None;
+    let re: Matcher1<_> =
+        safe_regex::Matcher::new("(a|b)", |data: &[u8], search_start: Option<usize>| {
+            let search_start = match search_start {
+                Some(search_start) => {
+                    let rest = data.get(search_start..)?;
+                    Some(search_start + safe_regex::internal::find_any_byte(b"ab", rest)?)
+                }
+                None => None,
+            };
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 1u8, 2u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+            ];
+            let one_pass =
+                |data: &[u8], offset: usize| -> Option<[Option<core::ops::Range<usize>>; 1usize]> {
+                    const ONE_PASS: [u16; 12usize] = [
+                        0u16, 0u16, 0u16, 0u16, 6u16, 9u16, 0u16, 0u16, 0u16, 0u16, 0u16, 0u16,
+                    ];
+                    const ONE_PASS_ACTIONS: [u8; 12usize] =
+                        [0u8, 0u8, 0u8, 0u8, 1u8, 1u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8];
+                    let mut ranges = [usize::MAX..usize::MAX];
+                    let mut state = 3usize;
+                    for (n, b) in data.iter().enumerate() {
+                        let index = state + usize::from(CLASSES[usize::from(*b)]);
+                        state = usize::from(ONE_PASS[index]);
+                        if state == 0 {
+                            return None;
+                        }
+                        let n = n + offset;
+                        if ONE_PASS_ACTIONS[index] == 1u8 {
+                            ranges[0usize] = n..n + 1;
+                        }
+                    }
+                    if let 6usize | 9usize = state {
+                    } else {
+                        return None;
+                    }
+                    let [r0] = ranges;
+                    Some([if r0.start == usize::MAX || r0.end == usize::MAX {
+                        None
+                    } else {
+                        Some(r0)
+                    }])
+                };
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 9usize] =
+                    [0u16, 0u16, 0u16, 3u16, 6u16, 6u16, 0u16, 0u16, 0u16];
+                const SEARCH_ACCEPT: [bool; 3usize] = [false, false, true];
+                const REVERSE: [u16; 12usize] = [
+                    0u16, 0u16, 0u16, 0u16, 6u16, 9u16, 0u16, 0u16, 0u16, 0u16, 0u16, 0u16,
+                ];
+                const REVERSE_ACCEPT: [bool; 4usize] = [false, false, true, true];
+                let mut state = 3usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 3usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
                 }
+                let end = end?;
+                let mut start = end;
+                let mut state = 3usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 3usize] {
+                        start = n;
+                    }
+                }
+                one_pass(&data[start..end], start).map(|groups| (start..end, groups))
             } else {
-                break;
+                one_pass(data, 0).map(|groups| (0..data.len(), groups))
             }
-            n = n + 1;
-        }
-        accept.map(|(r0,)| {
-            [
-                if r0.start == usize::MAX || r0.end == usize::MAX || r0.is_empty() {
-                    0..0usize
-                } else {
-                    r0
-                },
-            ]
-        })
-    });
+        });
     assert!(!re.is_match(b""));
     assert!(!re.is_match(b"X"));
-    assert_eq!("a", escape_ascii(re.match_slices(b"a").unwrap().0));
-    assert_eq!("b", escape_ascii(re.match_slices(b"b").unwrap().0));
+    assert_eq!("a", escape_ascii(re.match_slices(b"a").unwrap()[0]));
+    assert_eq!("b", escape_ascii(re.match_slices(b"b").unwrap()[0]));
     assert!(!re.is_match(b"aX"));
     assert!(!re.is_match(b"Xa"));
     assert!(!re.is_match(b"bX"));
@@ -836,61 +2490,101 @@ fn alt_in_group() {
 #[test]
 fn group_star1() {
     // regex!(br"(a?)*")
-    let re: Matcher1<_> = safe_regex::Matcher1::new(|data: &[u8]| {
-        assert!(data.len() < usize::MAX - 2);
-        let mut start = Some((usize::MAX..usize::MAX,));
-        let mut b0: Option<(core::ops::Range<usize>,)> = None;
-        let mut accept: Option<(core::ops::Range<usize>,)> = None;
-        let mut data_iter = data.iter();
-        let mut n = 0;
-        loop {
-            let prev_b0 = b0.clone();
-            accept = start
-                .clone()
-                .map(|(r0,)| (n..n,))
-                .clone()
-                .or_else(|| prev_b0.clone())
-                .clone()
-                .or_else(|| start.clone())
-                .clone()
-                .map(|(r0,)| (n..n,))
-                .clone()
-                .or_else(|| prev_b0.clone())
-                .clone()
-                .or_else(|| start.clone())
-                .clone();
-            if let Some(b) = data_iter.next() {
-                b0 = start
-                    .clone()
-                    .map(|(r0,)| (n..n,))
-                    .clone()
-                    .or_else(|| prev_b0.clone())
-                    .clone()
-                    .or_else(|| start.clone())
-                    .clone()
-                    .map(|(r0,)| (n..n,))
-                    .clone()
-                    .filter(|_| *b == 97u8)
-                    .map(|(r0,)| (r0.start..n + 1,));
-                start = None;
-                if b0.is_none() {
-                    return None;
+    let re: Matcher1<_> =
+        safe_regex::Matcher::new("(a?)*", |data: &[u8], search_start: Option<usize>| {
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 1u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+            ];
+            let one_pass =
+                |data: &[u8], offset: usize| -> Option<[Option<core::ops::Range<usize>>; 1usize]> {
+                    const ONE_PASS: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 4u16];
+                    const ONE_PASS_ACTIONS: [u8; 6usize] = [0u8, 0u8, 0u8, 1u8, 0u8, 1u8];
+                    let mut ranges = [usize::MAX..usize::MAX];
+                    let mut state = 2usize;
+                    for (n, b) in data.iter().enumerate() {
+                        let index = state + usize::from(CLASSES[usize::from(*b)]);
+                        state = usize::from(ONE_PASS[index]);
+                        if state == 0 {
+                            return None;
+                        }
+                        let n = n + offset;
+                        if ONE_PASS_ACTIONS[index] == 1u8 {
+                            ranges[0usize] = n..n + 1;
+                        }
+                    }
+                    let n = data.len() + offset;
+                    match state {
+                        2usize => {}
+                        4usize => {
+                            ranges[0usize] = n..n;
+                        }
+                        _ => return None,
+                    }
+                    let [r0] = ranges;
+                    Some([if r0.start == usize::MAX || r0.end == usize::MAX {
+                        None
+                    } else {
+                        Some(r0)
+                    }])
+                };
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 4u16];
+                const SEARCH_ACCEPT: [bool; 3usize] = [false, true, true];
+                const REVERSE: [u16; 6usize] = [0u16, 0u16, 0u16, 4u16, 0u16, 4u16];
+                const REVERSE_ACCEPT: [bool; 3usize] = [false, true, true];
+                let mut state = 2usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 2usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
+                }
+                let end = end?;
+                let mut start = end;
+                let mut state = 2usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 2usize] {
+                        start = n;
+                    }
                 }
+                one_pass(&data[start..end], start).map(|groups| (start..end, groups))
             } else {
-                break;
+                one_pass(data, 0).map(|groups| (0..data.len(), groups))
             }
-            n = n + 1;
-        }
-        accept.map(|(r0,)| {
-            [
-                if r0.start == usize::MAX || r0.end == usize::MAX || r0.is_empty() {
-                    0..0usize
-                } else {
-                    r0
-                },
-            ]
-        })
-    });
+        });
     assert!(re.is_match(b""));
     assert!(re.is_match(b"a"));
     assert!(re.is_match(b"aa"));
@@ -909,66 +2603,123 @@ fn group_star1() {
     assert!(!re.is_match(b"aaaX"));
     assert!(!re.is_match(b"Xaaa"));
     assert!(!re.is_match(b"aXaa"));
-    assert_eq!(b"", re.match_slices(b"").unwrap().0);
-    assert_eq!(b"", re.match_slices(b"a").unwrap().0);
-    assert_eq!(b"", re.match_slices(b"aa").unwrap().0);
+    assert_eq!(b"", re.match_slices(b"").unwrap()[0]);
+    assert_eq!(b"", re.match_slices(b"a").unwrap()[0]);
+    assert_eq!(b"", re.match_slices(b"aa").unwrap()[0]);
 }
 
 #[test]
 fn group_star2() {
     // regex!(br"(ab?c)*")
-    let re: Matcher1<_> = safe_regex::Matcher1::new(|data: &[u8]| {
-        assert!(data.len() < usize::MAX - 2);
-        let mut start = Some((usize::MAX..usize::MAX,));
-        let mut b0: Option<(core::ops::Range<usize>,)> = None;
-        let mut b1: Option<(core::ops::Range<usize>,)> = None;
-        let mut b2: Option<(core::ops::Range<usize>,)> = None;
-        let mut accept: Option<(core::ops::Range<usize>,)> = None;
-        let mut data_iter = data.iter();
-        let mut n = 0;
-        loop {
-            let prev_b0 = b0.clone();
-            let prev_b1 = b1.clone();
-            let prev_b2 = b2.clone();
-            accept = start.clone().or_else(|| prev_b2.clone()).clone();
-            if let Some(b) = data_iter.next() {
-                b2 = prev_b0
-                    .clone()
-                    .or_else(|| prev_b1.clone())
-                    .clone()
-                    .filter(|_| *b == 99u8)
-                    .map(|(r0,)| (r0.start..n + 1,));
-                b1 = prev_b0
-                    .clone()
-                    .filter(|_| *b == 98u8)
-                    .map(|(r0,)| (r0.start..n + 1,));
-                b0 = start
-                    .clone()
-                    .or_else(|| prev_b2.clone())
-                    .clone()
-                    .map(|(r0,)| (n..n,))
-                    .clone()
-                    .filter(|_| *b == 97u8)
-                    .map(|(r0,)| (r0.start..n + 1,));
-                start = None;
-                if b0.is_none() && b1.is_none() && b2.is_none() {
-                    return None;
+    let re: Matcher1<_> =
+        safe_regex::Matcher::new("(ab?c)*", |data: &[u8], search_start: Option<usize>| {
+            const CLASSES: [u8; 256] = [
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 1u8, 2u8, 3u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+                0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8,
+            ];
+            let one_pass =
+                |data: &[u8], offset: usize| -> Option<[Option<core::ops::Range<usize>>; 1usize]> {
+                    const ONE_PASS: [u16; 20usize] = [
+                        0u16, 0u16, 0u16, 0u16, 0u16, 8u16, 0u16, 0u16, 0u16, 0u16, 12u16, 16u16,
+                        0u16, 0u16, 0u16, 16u16, 0u16, 8u16, 0u16, 0u16,
+                    ];
+                    const ONE_PASS_ACTIONS: [u8; 20usize] = [
+                        0u8, 0u8, 0u8, 0u8, 0u8, 1u8, 0u8, 0u8, 0u8, 0u8, 2u8, 2u8, 0u8, 0u8, 0u8,
+                        2u8, 0u8, 1u8, 0u8, 0u8,
+                    ];
+                    let mut ranges = [usize::MAX..usize::MAX];
+                    let mut state = 4usize;
+                    for (n, b) in data.iter().enumerate() {
+                        let index = state + usize::from(CLASSES[usize::from(*b)]);
+                        state = usize::from(ONE_PASS[index]);
+                        if state == 0 {
+                            return None;
+                        }
+                        let n = n + offset;
+                        match ONE_PASS_ACTIONS[index] {
+                            1u8 => {
+                                ranges[0usize] = n..n + 1;
+                            }
+                            2u8 => {
+                                ranges[0usize].end = n + 1;
+                            }
+                            _ => {}
+                        }
+                    }
+                    if let 4usize | 16usize = state {
+                    } else {
+                        return None;
+                    }
+                    let [r0] = ranges;
+                    Some([if r0.start == usize::MAX || r0.end == usize::MAX {
+                        None
+                    } else {
+                        Some(r0)
+                    }])
+                };
+            let next = |transitions: &[u16], state: usize, b: u8| -> usize {
+                usize::from(transitions[state + usize::from(CLASSES[usize::from(b)])])
+            };
+            if let Some(search_start) = search_start {
+                const SEARCH: [u16; 20usize] = [
+                    0u16, 0u16, 0u16, 0u16, 0u16, 8u16, 0u16, 0u16, 0u16, 0u16, 12u16, 16u16, 0u16,
+                    0u16, 0u16, 16u16, 0u16, 8u16, 0u16, 0u16,
+                ];
+                const SEARCH_ACCEPT: [bool; 5usize] = [false, true, false, false, true];
+                const REVERSE: [u16; 20usize] = [
+                    0u16, 0u16, 0u16, 0u16, 0u16, 0u16, 0u16, 8u16, 0u16, 12u16, 16u16, 0u16, 0u16,
+                    0u16, 0u16, 8u16, 0u16, 12u16, 0u16, 0u16,
+                ];
+                const REVERSE_ACCEPT: [bool; 5usize] = [false, true, false, true, false];
+                let mut state = 4usize;
+                let mut end = None;
+                let mut n = search_start;
+                let mut data_iter = data.get(search_start..)?.iter();
+                loop {
+                    if SEARCH_ACCEPT[state / 4usize] {
+                        end = Some(n);
+                    }
+                    if let Some(b) = data_iter.next() {
+                        state = next(&SEARCH, state, *b);
+                        if state == 0 {
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                    n += 1;
                 }
+                let end = end?;
+                let mut start = end;
+                let mut state = 4usize;
+                for (n, b) in data[..end].iter().enumerate().skip(search_start).rev() {
+                    state = next(&REVERSE, state, *b);
+                    if state == 0 {
+                        break;
+                    }
+                    if REVERSE_ACCEPT[state / 4usize] {
+                        start = n;
+                    }
+                }
+                one_pass(&data[start..end], start).map(|groups| (start..end, groups))
             } else {
-                break;
+                one_pass(data, 0).map(|groups| (0..data.len(), groups))
             }
-            n = n + 1;
-        }
-        accept.map(|(r0,)| {
-            [
-                if r0.start == usize::MAX || r0.end == usize::MAX || r0.is_empty() {
-                    0..0usize
-                } else {
-                    r0
-                },
-            ]
-        })
-    });
+        });
     assert!(re.is_match(b""));
     assert!(re.is_match(b"ac"));
     assert!(re.is_match(b"abc"));
@@ -994,9 +2745,11 @@ fn group_star2() {
     assert!(!re.is_match(b"aca"));
     assert!(!re.is_match(b"abca"));
     assert!(!re.is_match(b"abcab"));
-    assert_eq!(b"", re.match_slices(b"").unwrap().0);
-    assert_eq!("ac", escape_ascii(re.match_slices(b"ac").unwrap().0));
-    assert_eq!("ac", escape_ascii(re.match_slices(b"acac").unwrap().0));
-    assert_eq!("abc", escape_ascii(re.match_slices(b"abc").unwrap().0));
-    assert_eq!("abc", escape_ascii(re.match_slices(b"abcabc").unwrap().0));
+    assert_eq!(b"", re.match_slices(b"").unwrap()[0]);
+    assert_eq!("ac", escape_ascii(re.match_slices(b"ac").unwrap()[0]));
+    assert_eq!("ac", escape_ascii(re.match_slices(b"acac").unwrap()[0]));
+    assert_eq!("abc", escape_ascii(re.match_slices(b"abc").unwrap()[0]));
+    assert_eq!("abc", escape_ascii(re.match_slices(b"abcabc").unwrap()[0]));
+    assert_eq!(Some((0..5, [2..5])), re.captures_iter(b"acabcX").next());
+    assert_eq!(Some(1..3), re.find_at(b"XacX", 1));
 }
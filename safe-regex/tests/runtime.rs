@@ -0,0 +1,240 @@
+#![forbid(unsafe_code)]
+use safe_regex::runtime::Regex;
+use safe_regex::{regex, IsMatch, Matcher};
+use std::ops::Range;
+
+/// Calls `f` with every string of length `0..=max_len` made of `alphabet`.
+fn for_each_string(alphabet: &[u8], max_len: usize, f: &mut impl FnMut(&[u8])) {
+    fn recurse(alphabet: &[u8], prefix: &mut Vec<u8>, len: usize, f: &mut impl FnMut(&[u8])) {
+        f(prefix);
+        if len == 0 {
+            return;
+        }
+        for b in alphabet {
+            prefix.push(*b);
+            recurse(alphabet, prefix, len - 1, f);
+            prefix.pop();
+        }
+    }
+    recurse(alphabet, &mut Vec::new(), max_len, f);
+}
+
+/// Checks that a runtime regex returns the same results as `matcher`.
+fn check_same<F, const N: usize>(matcher: &Matcher<F, N>, alphabet: &[u8], max_len: usize)
+where
+    F: Fn(&[u8], Option<usize>) -> Option<(Range<usize>, [Option<Range<usize>>; N])>,
+{
+    let pattern = safe_regex::Regex::pattern(matcher);
+    let regex = Regex::new(pattern.as_bytes()).unwrap();
+    assert_eq!(N, regex.group_count(), "{}", pattern);
+    for_each_string(alphabet, max_len, &mut |data| {
+        let expected = matcher.match_ranges_opt(data).map(|ranges| ranges.to_vec());
+        assert_eq!(
+            expected,
+            regex.match_ranges_opt(data),
+            "{} {:?}",
+            pattern,
+            std::str::from_utf8(data).unwrap()
+        );
+        for start in 0..=data.len() + 1 {
+            assert_eq!(
+                matcher.find_at(data, start),
+                regex.find_at(data, start),
+                "{} {:?} {}",
+                pattern,
+                std::str::from_utf8(data).unwrap(),
+                start
+            );
+        }
+        let expected: Vec<(Range<usize>, Vec<Range<usize>>)> = matcher
+            .captures_iter(data)
+            .map(|(range, groups)| (range, groups.to_vec()))
+            .collect();
+        assert_eq!(
+            expected,
+            regex.captures_iter(data).collect::<Vec<_>>(),
+            "{} {:?}",
+            pattern,
+            std::str::from_utf8(data).unwrap()
+        );
+    });
+}
+
+#[test]
+fn same_as_regex_macro() {
+    check_same(&regex!(br""), b"a", 2);
+    check_same(&regex!(br"a{0}"), b"a", 2);
+    check_same(&regex!(br"()"), b"a", 2);
+    check_same(&regex!(br"a"), b"ab", 3);
+    check_same(&regex!(br"."), b"ab", 3);
+    check_same(&regex!(br"[^a]"), b"ab", 3);
+    check_same(&regex!(br"a|b|c?"), b"abc", 3);
+    check_same(&regex!(br"(a)|(b)"), b"abc", 3);
+    check_same(&regex!(br"(a|ab)(c|bcd)"), b"abcd", 4);
+    check_same(&regex!(br"(a*)(a*)"), b"ab", 4);
+    check_same(&regex!(br"(a*?)(a*)"), b"ab", 4);
+    check_same(&regex!(br"(a+)(b+)?"), b"ab", 5);
+    check_same(&regex!(br"(a|b)*(b)"), b"ab", 5);
+    check_same(&regex!(br"((a)|(b))*"), b"ab", 5);
+    check_same(&regex!(br"(ab|a)(bc|c)?"), b"abc", 4);
+    check_same(&regex!(br"(a?)+(b)"), b"ab", 4);
+    check_same(&regex!(br"(?:(a)b)*"), b"ab", 5);
+    check_same(&regex!(br"a{2,3}(b{1,2}?)"), b"ab", 6);
+    check_same(&regex!(br"(a{2})*"), b"ab", 6);
+    check_same(&regex!(br"a.*?(b)"), b"ab", 5);
+    check_same(&regex!(br"(a.*?)b"), b"ab", 5);
+    check_same(&regex!(br"(a??)(a?)"), b"a", 3);
+    check_same(&regex!(br"(a){0,2}?"), b"ab", 4);
+    check_same(&regex!(br"(a){0,3}?b"), b"ab", 5);
+    check_same(&regex!(br"(?:(a)b){0,2}?"), b"ab", 5);
+    check_same(&*regex!(br"(?P<x>a)(?P<y>b)?"), b"ab", 3);
+    check_same(&regex!(br"^(a)$"), b"ab", 3);
+    check_same(&regex!(br"(?m)^a$"), b"a\n", 4);
+    check_same(&regex!(br"(?m)(^|a)+"), b"a\n", 4);
+    check_same(&regex!(br"\ba\b"), b"a ", 4);
+    check_same(&regex!(br"\B(a+)"), b"a ", 4);
+    check_same(&regex!(br"\Aa|b\z"), b"ab", 4);
+    check_same(&regex!(br"(?i)(a)B"), b"aAbB", 3);
+    check_same(&regex!(br"[a-c]+([0-9]{1,2})"), b"b1", 5);
+    // Expressions without groups.
+    check_same(&regex!(br"a*"), b"ab", 4);
+    check_same(&regex!(br"a+b"), b"ab", 5);
+    check_same(&regex!(br"a*?b"), b"ab", 5);
+    check_same(&regex!(br"ab*?"), b"ab", 5);
+    check_same(&regex!(br"a.*?"), b"ab", 5);
+    check_same(&regex!(br"(?:a|b)*b"), b"abc", 5);
+    check_same(&regex!(br"(?:ab|a)(?:bc|c)?"), b"abc", 5);
+    check_same(&regex!(br"(?:a|ab)(?:c|bcd)"), b"abcd", 5);
+    check_same(&regex!(br"a{2,3}b{0,2}?"), b"ab", 6);
+    check_same(&regex!(br".*ab.*"), b"abc", 5);
+    check_same(&regex!(br"[^a]+a?"), b"ab", 5);
+    check_same(&regex!(br"(?:a*?b)+?"), b"ab", 5);
+    // One-pass expressions.
+    check_same(&regex!(br"([0-9]+)-([0-9]+)-([0-9]+)"), b"1-", 7);
+    check_same(&regex!(br"(a|b)*(c)"), b"abc", 4);
+    check_same(&regex!(br"(?:(a)|b)+"), b"ab", 5);
+    check_same(&regex!(br"(a*)b(c?)"), b"abc", 4);
+    check_same(&regex!(br"()a()"), b"a", 3);
+    check_same(&regex!(br"(a(b)?)+"), b"ab", 5);
+    check_same(&regex!(br"[^a]+(a)"), b"ab", 5);
+    // Expressions with prefilters.
+    check_same(&regex!(br".*(ab).*"), b"abc", 5);
+    check_same(&regex!(br"b+a"), b"ab", 5);
+    check_same(&regex!(br"(?:a|b)c"), b"abc", 4);
+    check_same(&regex!(br"[a-e]+(b)"), b"abf", 4);
+    check_same(&regex!(br"\bab"), b"ab ", 4);
+    // Expressions with counted repetitions.
+    check_same(&regex!(br"\ba{0,16}b"), b"ab", 5);
+    check_same(&regex!(br"\ba{0,16}?b?"), b"ab", 5);
+    check_same(&regex!(br"a{2,17}\b"), b"a", 20);
+    check_same(&regex!(br"\b[ab]{3,20}?"), b"ab ", 5);
+    check_same(&regex!(br"\Bx{16}"), b"x", 19);
+    check_same(&regex!(br"[^a]{1,16}\b"), b"a ", 5);
+    check_same(&regex!(br"(a{0,16})\b"), b"a", 18);
+    check_same(&regex!(br"(a{0,16})(b{0,16})"), b"ab", 10);
+    check_same(&regex!(br"(a*)(a{2,17})(a*)"), b"a", 20);
+    check_same(&regex!(br"(a*?)(a{1,16}?)(a*)"), b"a", 20);
+    check_same(&regex!(br"([ab]{0,16}?)(b+)"), b"ab", 10);
+    check_same(&regex!(br"a*(b{0,20}?)b*"), b"ab", 10);
+    check_same(&regex!(br"a*([a-z]{2,20}?)"), b"ab", 8);
+    check_same(&regex!(br"k=([a-z]{0,16})\b"), b"k=a", 8);
+}
+
+#[test]
+fn new() {
+    let regex = Regex::new(br"(?P<key>[a-z]+)=([0-9]+)?").unwrap();
+    assert_eq!(2, regex.group_count());
+    assert_eq!(Some(0), regex.group_index("key"));
+    assert_eq!(None, regex.group_index("value"));
+    assert_eq!(
+        "(?P<key>[a-z]+)=([0-9]+)?",
+        safe_regex::Regex::pattern(&regex)
+    );
+    let err = Regex::new(br"ab(c").unwrap_err();
+    assert_eq!(b"ab(c", err.pattern());
+    assert_eq!("missing closing `)`\nab(c\n  ^", format!("{}", err));
+    let regex = Regex::new(b"\xFF+").unwrap();
+    assert!(regex.is_match(b"\xFF\xFF"));
+    assert_eq!("\u{FFFD}+", safe_regex::Regex::pattern(&regex));
+    let err = Regex::new(br"(a{1000}){1000}").unwrap_err();
+    assert_eq!(
+        "expression is too large: it compiles to 1003004 instructions, more than 100000\n\
+         (a{1000}){1000}\n\
+         ^^^^^^^^^^^^^^^",
+        format!("{}", err)
+    );
+    assert!(Regex::new(br"a{1000000}").is_err());
+    assert!(Regex::new(br"[a-z]{0,10000}").is_ok());
+    // An escape, class, or property inside an unclosed `{`.
+    for pattern in [
+        &br"a{1\d"[..],
+        br"{\x41",
+        br"{\z",
+        br"{[:alpha:]",
+        br"{\p{L}",
+        br"a{1,\d",
+    ] {
+        let err = Regex::new(pattern).unwrap_err().to_string();
+        assert!(err.starts_with("missing closing `}` symbol"), "{}", err);
+    }
+    // An escape inside an incomplete escape.
+    for pattern in [&br"\x\."[..], br"\x4\d", br"\P\.", br"\p{L\}"] {
+        assert!(Regex::new(pattern).is_err());
+    }
+}
+
+#[test]
+fn match_methods() {
+    let regex = Regex::new(br"([a-z]+)=([0-9]+)?").unwrap();
+    assert!(regex.is_match(b"a=1"));
+    assert!(!regex.is_match(b"a"));
+    assert!(IsMatch::is_match(&regex, b"a="));
+    assert_eq!(Some(vec![0..1, 0..0]), regex.match_ranges(b"a="));
+    assert_eq!(Some(vec![Some(0..1), None]), regex.match_ranges_opt(b"a="));
+    assert_eq!(
+        Some(vec![b"ab".as_ref(), b"12".as_ref()]),
+        regex.match_slices(b"ab=12")
+    );
+    assert_eq!(
+        Some(vec![Some(b"ab".as_ref()), None]),
+        regex.match_slices_opt(b"ab=")
+    );
+    assert_eq!(None, regex.match_ranges(b"ab"));
+    assert_eq!(Some(1..4), regex.find(b" a=1 b="));
+    assert_eq!(Some(5..7), regex.find_at(b" a=1 b=", 2));
+    assert_eq!(None, regex.find_at(b" a=1 b=", 8));
+    assert_eq!(
+        vec![1..4, 5..7],
+        regex.find_iter(b" a=1 b=").collect::<Vec<_>>()
+    );
+    let rules: Vec<Box<dyn safe_regex::Regex>> = vec![
+        Box::new(regex!(br"a")),
+        Box::new(Regex::new(br"([a-z]+)=([0-9]+)?").unwrap()),
+    ];
+    let mut ranges = [7..7, 7..7, 7..7];
+    assert!(rules[1].match_ranges_into(b"ab=", &mut ranges));
+    assert_eq!([0..2, 0..0, 7..7], ranges);
+    assert!(!rules[1].match_ranges_into(b"ab", &mut ranges));
+    assert_eq!(2, rules[1].group_count());
+}
+
+#[test]
+fn lazy_counted_repetition() {
+    let regex = Regex::new(br"(a){0,2}?").unwrap();
+    assert_eq!(Some(vec![Some(0..1)]), regex.match_ranges_opt(b"a"));
+    assert_eq!(Some(vec![None]), regex.match_ranges_opt(b""));
+    let regex = Regex::new(br"(?:(a)b){0,2}?").unwrap();
+    assert_eq!(Some(vec![Some(0..1)]), regex.match_ranges_opt(b"ab"));
+    assert_eq!(Some(vec![Some(0..1)]), regex.match_ranges_opt(b"abab"));
+    let regex = Regex::new(br"(a){0,3}?b").unwrap();
+    assert_eq!(Some(vec![Some(0..1)]), regex.match_ranges_opt(b"aab"));
+    assert_eq!(Some((1..3, vec![1..2])), regex.captures_iter(b"Xab").next());
+}
+
+#[test]
+fn linear() {
+    let regex = Regex::new(br"(a*)*(a*)*b").unwrap();
+    let data = vec![b'a'; 100_000];
+    assert!(!regex.is_match(&data));
+    assert_eq!(None, regex.find(&data));
+}
@@ -1,17 +1,18 @@
 #![forbid(unsafe_code)]
+#![allow(clippy::single_range_in_vec_init)]
 use safe_regex::internal::escape_ascii;
 use safe_regex::{
-    regex, IsMatch, Matcher0, Matcher1, Matcher2, Matcher3, Matcher4, Matcher5, Matcher7,
+    lexer, regex, regex_set, regex_stream, FromRegex, FromRegexError, IsMatch, LexError, Matcher,
+    Matcher0, Matcher1, Matcher2, Matcher3, Matcher4, Matcher5, Matcher7, Regex, RegexSet,
+    StrMatcher0, StrMatcher1, StrMatcher2, StreamMatcher0, StreamMatcher1, StreamMatcher2,
 };
 
 // TODO(mleonhard) Test regexes that could match the empty string:
 // - ""
 // - a?
 // - a?b?
-// - ()
 // - ()?
 // - (a?)
-// - (a)?
 // - (ab)?
 // - ()a?
 // - ()?a?
@@ -19,11 +20,8 @@ use safe_regex::{
 // - (a?)(a?)
 // - (a)?(b)?
 // - (ab)?(cd)?
-// - (|a)
 // - a{,1}
 
-// TODO(mleonhard) Test alternate with empty arm, br"a{0}|b"
-
 #[must_use]
 fn check_non_matching_permutations(
     matcher: &impl IsMatch,
@@ -131,6 +129,76 @@ fn class_exclusive() {
     re.match_slices(b"5").unwrap();
 }
 
+#[test]
+fn class_shorthand() {
+    let re: Matcher0<_> = regex!(br"\d");
+    for b in 0..=255_u8 {
+        assert_eq!(b.is_ascii_digit(), re.is_match(&[b]), "{}", b);
+    }
+    let re: Matcher0<_> = regex!(br"\D");
+    for b in 0..=255_u8 {
+        assert_eq!(!b.is_ascii_digit(), re.is_match(&[b]), "{}", b);
+    }
+    let re: Matcher0<_> = regex!(br"\w");
+    for b in 0..=255_u8 {
+        let expected = b.is_ascii_alphanumeric() || b == b'_';
+        assert_eq!(expected, re.is_match(&[b]), "{}", b);
+    }
+    let re: Matcher0<_> = regex!(br"[^\W]");
+    for b in 0..=255_u8 {
+        let expected = b.is_ascii_alphanumeric() || b == b'_';
+        assert_eq!(expected, re.is_match(&[b]), "{}", b);
+    }
+    let re: Matcher0<_> = regex!(br"\s");
+    for b in 0..=255_u8 {
+        let expected = b.is_ascii_whitespace() || b == 0x0B;
+        assert_eq!(expected, re.is_match(&[b]), "{}", b);
+    }
+    let re: Matcher0<_> = regex!(br"\S");
+    for b in 0..=255_u8 {
+        let expected = !(b.is_ascii_whitespace() || b == 0x0B);
+        assert_eq!(expected, re.is_match(&[b]), "{}", b);
+    }
+    let re: Matcher2<_> = regex!(br"(\w+)\s*=\s*(\d+)");
+    assert_eq!(
+        Some([b"id_2".as_ref(), b"42".as_ref()]),
+        re.match_slices(b"id_2 =\t42")
+    );
+    assert!(!re.is_match(b"id=4x"));
+}
+
+#[test]
+fn class_posix() {
+    let re: Matcher0<_> = regex!(br"[[:alpha:]]");
+    for b in 0..=255_u8 {
+        assert_eq!(b.is_ascii_alphabetic(), re.is_match(&[b]), "{}", b);
+    }
+    let re: Matcher0<_> = regex!(br"[[:punct:]]");
+    for b in 0..=255_u8 {
+        assert_eq!(b.is_ascii_punctuation(), re.is_match(&[b]), "{}", b);
+    }
+    let re: Matcher0<_> = regex!(br"[[:xdigit:]]");
+    for b in 0..=255_u8 {
+        assert_eq!(b.is_ascii_hexdigit(), re.is_match(&[b]), "{}", b);
+    }
+    let re: Matcher0<_> = regex!(br"[[:graph:]]");
+    for b in 0..=255_u8 {
+        assert_eq!(b.is_ascii_graphic(), re.is_match(&[b]), "{}", b);
+    }
+    let re: Matcher0<_> = regex!(br"[[:cntrl:]]");
+    for b in 0..=255_u8 {
+        assert_eq!(b.is_ascii_control(), re.is_match(&[b]), "{}", b);
+    }
+    let re: Matcher0<_> = regex!(br"[^[:lower:][:upper:]]");
+    for b in 0..=255_u8 {
+        assert_eq!(!b.is_ascii_alphabetic(), re.is_match(&[b]), "{}", b);
+    }
+    let re: Matcher0<_> = regex!(br"[_[:^alnum:]]");
+    for b in 0..=255_u8 {
+        assert_eq!(!b.is_ascii_alphanumeric(), re.is_match(&[b]), "{}", b);
+    }
+}
+
 #[test]
 fn seq() {
     check_permutations(&regex!(br"ab"), b"abX", 5, &[b"ab"]).unwrap();
@@ -138,7 +206,7 @@ fn seq() {
         let re: Matcher1<_> = regex!(br"(ab)");
         check_permutations(&re, b"abX", 4, &[b"ab"]).unwrap();
         let groups = re.match_slices(b"ab").unwrap();
-        assert_eq!("ab", escape_ascii(groups.0));
+        assert_eq!("ab", escape_ascii(groups[0]));
     }
 }
 
@@ -158,11 +226,11 @@ fn alt() {
 
         let groups = re.match_slices(b"a").unwrap();
         // assert_eq!(0..1, groups.group_range(0).unwrap());
-        assert_eq!("a", escape_ascii(groups.0));
+        assert_eq!("a", escape_ascii(groups[0]));
 
         let groups = re.match_slices(b"b").unwrap();
         // assert_eq!(0..1, groups.group_range(0).unwrap());
-        assert_eq!("b", escape_ascii(groups.0));
+        assert_eq!("b", escape_ascii(groups[0]));
     }
 }
 
@@ -527,14 +595,14 @@ fn repeat_in_group() {
         check_permutations(&re, b"abX", 6, &[b"ab", b"aab"]).unwrap();
 
         let groups = re.match_slices(b"ab").unwrap();
-        assert_eq!("", escape_ascii(groups.0));
-        assert_eq!("ab", escape_ascii(groups.1));
+        assert_eq!("", escape_ascii(groups[0]));
+        assert_eq!("ab", escape_ascii(groups[1]));
 
         let groups = re.match_slices(b"aab").unwrap();
         // assert_eq!(0..1, groups.group_range(0).unwrap());
         // assert_eq!(1..3, groups.group_range(1).unwrap());
-        assert_eq!("a", escape_ascii(groups.0));
-        assert_eq!("ab", escape_ascii(groups.1));
+        assert_eq!("a", escape_ascii(groups[0]));
+        assert_eq!("ab", escape_ascii(groups[1]));
     }
     {
         let re: Matcher1<_> = regex!(br"a(a?)a");
@@ -542,11 +610,11 @@ fn repeat_in_group() {
 
         let groups = re.match_slices(b"aa").unwrap();
         // assert_eq!(1..1, groups.group_range(0).unwrap());
-        assert_eq!("", escape_ascii(groups.0));
+        assert_eq!("", escape_ascii(groups[0]));
 
         let groups = re.match_slices(b"aaa").unwrap();
         // assert_eq!(1..2, groups.group_range(0).unwrap());
-        assert_eq!("a", escape_ascii(groups.0));
+        assert_eq!("a", escape_ascii(groups[0]));
     }
 }
 
@@ -556,7 +624,7 @@ fn group() {
         let re: Matcher1<_> = regex!(br"(a)");
         check_permutations(&re, b"aX", 2, &[b"a"]).unwrap();
         let groups = re.match_slices(b"a").unwrap();
-        assert_eq!("a", escape_ascii(groups.0));
+        assert_eq!("a", escape_ascii(groups[0]));
         // assert_eq!(0..1, groups.group_range(0).unwrap());
     }
     {
@@ -565,8 +633,8 @@ fn group() {
         let groups = re.match_slices(b"a").unwrap();
         // assert_eq!(0..1, groups.group_range(0).unwrap());
         // assert_eq!(0..1, groups.group_range(1).unwrap());
-        assert_eq!("a", escape_ascii(groups.0));
-        assert_eq!("a", escape_ascii(groups.1));
+        assert_eq!("a", escape_ascii(groups[0]));
+        assert_eq!("a", escape_ascii(groups[1]));
     }
     {
         let re: Matcher2<_> = regex!(br"(a(b))");
@@ -574,8 +642,8 @@ fn group() {
         let groups = re.match_slices(b"ab").unwrap();
         // assert_eq!(0..2, groups.group_range(0).unwrap());
         // assert_eq!(1..2, groups.group_range(1).unwrap());
-        assert_eq!("ab", escape_ascii(groups.0));
-        assert_eq!("b", escape_ascii(groups.1));
+        assert_eq!("ab", escape_ascii(groups[0]));
+        assert_eq!("b", escape_ascii(groups[1]));
     }
     {
         let re: Matcher2<_> = regex!(br"((a)b)");
@@ -583,8 +651,8 @@ fn group() {
         let groups = re.match_slices(b"ab").unwrap();
         // assert_eq!(0..2, groups.group_range(0).unwrap());
         // assert_eq!(0..1, groups.group_range(1).unwrap());
-        assert_eq!("ab", escape_ascii(groups.0));
-        assert_eq!("a", escape_ascii(groups.1));
+        assert_eq!("ab", escape_ascii(groups[0]));
+        assert_eq!("a", escape_ascii(groups[1]));
     }
     {
         let re: Matcher3<_> = regex!(br"((a)(b))");
@@ -593,9 +661,9 @@ fn group() {
         // assert_eq!(0..2, groups.group_range(0).unwrap());
         // assert_eq!(0..1, groups.group_range(1).unwrap());
         // assert_eq!(1..2, groups.group_range(2).unwrap());
-        assert_eq!("ab", escape_ascii(groups.0));
-        assert_eq!("a", escape_ascii(groups.1));
-        assert_eq!("b", escape_ascii(groups.2));
+        assert_eq!("ab", escape_ascii(groups[0]));
+        assert_eq!("a", escape_ascii(groups[1]));
+        assert_eq!("b", escape_ascii(groups[2]));
     }
     {
         let re: Matcher4<_> = regex!(br"((a(b))(c))");
@@ -605,10 +673,10 @@ fn group() {
         // assert_eq!(0..2, groups.group_range(1).unwrap());
         // assert_eq!(1..2, groups.group_range(2).unwrap());
         // assert_eq!(2..3, groups.group_range(3).unwrap());
-        assert_eq!("abc", escape_ascii(groups.0));
-        assert_eq!("ab", escape_ascii(groups.1));
-        assert_eq!("b", escape_ascii(groups.2));
-        assert_eq!("c", escape_ascii(groups.3));
+        assert_eq!("abc", escape_ascii(groups[0]));
+        assert_eq!("ab", escape_ascii(groups[1]));
+        assert_eq!("b", escape_ascii(groups[2]));
+        assert_eq!("c", escape_ascii(groups[3]));
     }
     {
         let re: Matcher4<_> = regex!(br"((a)((b)c))");
@@ -618,10 +686,10 @@ fn group() {
         // assert_eq!(0..1, groups.group_range(1).unwrap());
         // assert_eq!(1..3, groups.group_range(2).unwrap());
         // assert_eq!(1..2, groups.group_range(3).unwrap());
-        assert_eq!("abc", escape_ascii(groups.0));
-        assert_eq!("a", escape_ascii(groups.1));
-        assert_eq!("bc", escape_ascii(groups.2));
-        assert_eq!("b", escape_ascii(groups.3));
+        assert_eq!("abc", escape_ascii(groups[0]));
+        assert_eq!("a", escape_ascii(groups[1]));
+        assert_eq!("bc", escape_ascii(groups[2]));
+        assert_eq!("b", escape_ascii(groups[3]));
     }
     {
         let re: Matcher5<_> = regex!(br"((a(b))((c)d))");
@@ -633,11 +701,11 @@ fn group() {
         // assert_eq!(1..2, groups.group_range(2).unwrap());
         // assert_eq!(2..4, groups.group_range(3).unwrap());
         // assert_eq!(2..3, groups.group_range(4).unwrap());
-        assert_eq!("abcd", escape_ascii(groups.0));
-        assert_eq!("ab", escape_ascii(groups.1));
-        assert_eq!("b", escape_ascii(groups.2));
-        assert_eq!("cd", escape_ascii(groups.3));
-        assert_eq!("c", escape_ascii(groups.4));
+        assert_eq!("abcd", escape_ascii(groups[0]));
+        assert_eq!("ab", escape_ascii(groups[1]));
+        assert_eq!("b", escape_ascii(groups[2]));
+        assert_eq!("cd", escape_ascii(groups[3]));
+        assert_eq!("c", escape_ascii(groups[4]));
     }
     {
         let re: Matcher3<_> = regex!(br"(a(b(c)))");
@@ -646,9 +714,9 @@ fn group() {
         // assert_eq!(0..3, groups.group_range(0).unwrap());
         // assert_eq!(1..3, groups.group_range(1).unwrap());
         // assert_eq!(2..3, groups.group_range(2).unwrap());
-        assert_eq!("abc", escape_ascii(groups.0));
-        assert_eq!("bc", escape_ascii(groups.1));
-        assert_eq!("c", escape_ascii(groups.2));
+        assert_eq!("abc", escape_ascii(groups[0]));
+        assert_eq!("bc", escape_ascii(groups[1]));
+        assert_eq!("c", escape_ascii(groups[2]));
     }
     {
         let re: Matcher7<_> = regex!(br"(a(b))((c)d)((e)(f))");
@@ -665,16 +733,103 @@ fn group() {
         // assert_eq!(4..6, groups.group_range(4).unwrap());
         // assert_eq!(4..5, groups.group_range(5).unwrap());
         // assert_eq!(5..6, groups.group_range(6).unwrap());
-        assert_eq!("ab", escape_ascii(groups.0));
-        assert_eq!("b", escape_ascii(groups.1));
-        assert_eq!("cd", escape_ascii(groups.2));
-        assert_eq!("c", escape_ascii(groups.3));
-        assert_eq!("ef", escape_ascii(groups.4));
-        assert_eq!("e", escape_ascii(groups.5));
-        assert_eq!("f", escape_ascii(groups.6));
+        assert_eq!("ab", escape_ascii(groups[0]));
+        assert_eq!("b", escape_ascii(groups[1]));
+        assert_eq!("cd", escape_ascii(groups[2]));
+        assert_eq!("c", escape_ascii(groups[3]));
+        assert_eq!("ef", escape_ascii(groups[4]));
+        assert_eq!("e", escape_ascii(groups[5]));
+        assert_eq!("f", escape_ascii(groups[6]));
     }
 }
 
+#[test]
+fn many_groups() {
+    let re: Matcher<_, 12> =
+        regex!(br"([0-9]+)-([0-9]+)-([0-9]+) ([0-9]+):([0-9]+):([0-9]+) (a)(b)(c)(d)(e)(f)");
+    let groups = re.match_slices(b"2021-04-05 12:34:56 abcdef").unwrap();
+    assert_eq!("2021", escape_ascii(groups[0]));
+    assert_eq!("05", escape_ascii(groups[2]));
+    assert_eq!("56", escape_ascii(groups[5]));
+    assert_eq!("a", escape_ascii(groups[6]));
+    assert_eq!("f", escape_ascii(groups[11]));
+    let ranges = re.match_ranges(b"1-2-3 4:5:6 abcdef").unwrap();
+    assert_eq!(0..1, ranges[0]);
+    assert_eq!(17..18, ranges[11]);
+    let re: Matcher<_, 20> = regex!(
+        br"(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)(a?)aaaaaaaaaaaaaaaaaaaa"
+    );
+    assert!(!re.is_match(b"aaaaaaaaaaaaaaaaaaa"));
+    let groups = re.match_ranges(b"aaaaaaaaaaaaaaaaaaaaaa").unwrap();
+    assert_eq!(0..1, groups[0]);
+    assert_eq!(1..2, groups[1]);
+    assert_eq!(2..2, groups[2]);
+    assert_eq!(2..2, groups[19]);
+    assert_eq!(
+        Some(0..40),
+        re.find(b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa")
+    );
+}
+
+#[test]
+fn match_ranges_opt() {
+    let re: Matcher1<_> = regex!(br"(a)?b");
+    assert_eq!(Some([None]), re.match_ranges_opt(b"b"));
+    assert_eq!(Some([Some(0..1)]), re.match_ranges_opt(b"ab"));
+    assert_eq!(None, re.match_ranges_opt(b"a"));
+    assert_eq!(Some([0..0]), re.match_ranges(b"b"));
+    let re: Matcher1<_> = regex!(br"x()");
+    assert_eq!(Some([Some(1..1)]), re.match_ranges_opt(b"x"));
+    assert_eq!(Some([1..1]), re.match_ranges(b"x"));
+    let re: Matcher1<_> = regex!(br"()");
+    assert_eq!(Some([Some(0..0)]), re.match_ranges_opt(b""));
+    assert_eq!(None, re.match_ranges_opt(b"a"));
+    assert_eq!(Some(0..0), re.find(b"a"));
+    let re: Matcher1<_> = regex!(br"b(a?)");
+    assert_eq!(Some([Some(1..1)]), re.match_ranges_opt(b"b"));
+    let re: Matcher1<_> = regex!(br"(a{0}|b)c");
+    assert_eq!(Some([Some(0..0)]), re.match_ranges_opt(b"c"));
+    assert_eq!(Some([Some(0..1)]), re.match_ranges_opt(b"bc"));
+    assert_eq!(None, re.match_ranges_opt(b"ac"));
+}
+
+#[test]
+fn match_slices_opt() {
+    let re: Matcher2<_> = regex!(br"(a)?(b*)");
+    let [a, b] = re.match_slices_opt(b"").unwrap();
+    assert_eq!(None, a);
+    assert_eq!(Some(b"".as_ref()), b);
+    let [a, b] = re.match_slices_opt(b"abb").unwrap();
+    assert_eq!(Some(b"a".as_ref()), a);
+    assert_eq!(Some(b"bb".as_ref()), b);
+    assert_eq!(None, re.match_slices_opt(b"ba"));
+}
+
+#[test]
+fn named_groups() {
+    let re = regex!(br"(?P<year>[0-9]{4})-(?<month>[0-9]{2})-([0-9]{2})(?P<zone>Z)?");
+    check_permutations(&re, b"0-Z", 4, &[]).unwrap();
+    assert!(re.is_match(b"2021-05-06"));
+    assert!(!re.is_match(b"2021-05"));
+    assert_eq!(None, re.match_ranges(b"2021-05"));
+    let ranges = re.match_ranges(b"2021-05-06").unwrap();
+    assert_eq!(0..4, ranges.year());
+    assert_eq!(5..7, ranges.month());
+    assert_eq!(0..0, ranges.zone());
+    let slices = re.match_slices(b"2021-05-06Z").unwrap();
+    assert_eq!(b"2021", slices.year());
+    assert_eq!(b"05", slices.month());
+    assert_eq!(b"Z", slices.zone());
+    assert_eq!(
+        Some([Some(0..4), Some(5..7), Some(8..10), None]),
+        re.match_ranges_opt(b"2021-05-06")
+    );
+    assert_eq!(Some(1..11), re.find(b"x2021-05-06x"));
+    let re = regex!(br"(?P<a>x)*");
+    assert_eq!(2..3, re.match_ranges(b"xxx").unwrap().a());
+    assert_eq!(b"", re.match_slices(b"").unwrap().a());
+}
+
 #[test]
 fn non_capturing_group() {
     check_permutations(&regex!(br"(?:a)"), b"aX", 2, &[b"a"]).unwrap();
@@ -683,42 +838,42 @@ fn non_capturing_group() {
         let re: Matcher1<_> = regex!(br"((?:a))");
         check_permutations(&re, b"aX", 3, &[b"a"]).unwrap();
         let groups = re.match_slices(b"a").unwrap();
-        assert_eq!("a", escape_ascii(groups.0));
+        assert_eq!("a", escape_ascii(groups[0]));
     }
     check_permutations(&regex!(br"(?:a(?:b))"), b"abX", 4, &[b"ab"]).unwrap();
     {
         let re: Matcher1<_> = regex!(br"(a(?:b))");
         check_permutations(&re, b"abX", 4, &[b"ab"]).unwrap();
         let groups = re.match_slices(b"ab").unwrap();
-        assert_eq!("ab", escape_ascii(groups.0));
+        assert_eq!("ab", escape_ascii(groups[0]));
     }
     check_permutations(&regex!(br"(?:(?:a)b)"), b"abX", 4, &[b"ab"]).unwrap();
     {
         let re: Matcher1<_> = regex!(br"((?:a)b)");
         check_permutations(&re, b"abX", 4, &[b"ab"]).unwrap();
         let groups = re.match_slices(b"ab").unwrap();
-        assert_eq!("ab", escape_ascii(groups.0));
+        assert_eq!("ab", escape_ascii(groups[0]));
     }
     check_permutations(&regex!(br"(?:(?:a)(?:b))"), b"abX", 4, &[b"ab"]).unwrap();
     {
         let re: Matcher1<_> = regex!(br"((?:a)(?:b))");
         check_permutations(&re, b"abX", 4, &[b"ab"]).unwrap();
         let groups = re.match_slices(b"ab").unwrap();
-        assert_eq!("ab", escape_ascii(groups.0));
+        assert_eq!("ab", escape_ascii(groups[0]));
     }
     check_permutations(&regex!(br"(?:(?:a(?:b))(?:c))"), b"abcX", 6, &[b"abc"]).unwrap();
     {
         let re: Matcher1<_> = regex!(br"((?:a(?:b))(?:c))");
         check_permutations(&re, b"abcX", 6, &[b"abc"]).unwrap();
         let groups = re.match_slices(b"abc").unwrap();
-        assert_eq!("abc", escape_ascii(groups.0));
+        assert_eq!("abc", escape_ascii(groups[0]));
     }
     check_permutations(&regex!(br"(?:(?:a)(?:(?:b)c))"), b"abcX", 6, &[b"abc"]).unwrap();
     {
         let re: Matcher1<_> = regex!(br"((?:a)(?:(?:b)c))");
         check_permutations(&re, b"abcX", 6, &[b"abc"]).unwrap();
         let groups = re.match_slices(b"abc").unwrap();
-        assert_eq!("abc", escape_ascii(groups.0));
+        assert_eq!("abc", escape_ascii(groups[0]));
     }
     check_permutations(
         &regex!(br"(?:(?:a(?:b))(?:(?:c)d))"),
@@ -732,14 +887,14 @@ fn non_capturing_group() {
         check_permutations(&re, b"abcdX", 5, &[b"abcd"]).unwrap();
         assert!(!re.is_match(b"abcdabcd"));
         let groups = re.match_slices(b"abcd").unwrap();
-        assert_eq!("abcd", escape_ascii(groups.0));
+        assert_eq!("abcd", escape_ascii(groups[0]));
     }
     check_permutations(&regex!(br"(?:a(?:b(?:c)))"), b"abcX", 6, &[b"abc"]).unwrap();
     {
         let re: Matcher1<_> = regex!(br"(a(?:b(?:c)))");
         check_permutations(&re, b"abcX", 6, &[b"abc"]).unwrap();
         let groups = re.match_slices(b"abc").unwrap();
-        assert_eq!("abc", escape_ascii(groups.0));
+        assert_eq!("abc", escape_ascii(groups[0]));
     }
     check_permutations(
         &regex!(br"(?:a(?:b))(?:(?:c)d)((?:e)(?:f))"),
@@ -756,9 +911,9 @@ fn non_capturing_group() {
         assert!(!re.is_match(b"abcdefabcdef"));
 
         let groups = re.match_slices(b"abcdef").unwrap();
-        assert_eq!("ab", escape_ascii(groups.0));
-        assert_eq!("cd", escape_ascii(groups.1));
-        assert_eq!("ef", escape_ascii(groups.2));
+        assert_eq!("ab", escape_ascii(groups[0]));
+        assert_eq!("cd", escape_ascii(groups[1]));
+        assert_eq!("ef", escape_ascii(groups[2]));
     }
 }
 
@@ -773,8 +928,8 @@ fn group_repeat() {
     assert!(!re.is_match(b"ab"));
     assert!(!re.is_match(b"ba"));
     assert!(!re.is_match(b"aaaa"));
-    assert_eq!("a", escape_ascii(re.match_slices(b"aa").unwrap().0));
-    assert_eq!("a", escape_ascii(re.match_slices(b"aaa").unwrap().0));
+    assert_eq!("a", escape_ascii(re.match_slices(b"aa").unwrap()[0]));
+    assert_eq!("a", escape_ascii(re.match_slices(b"aaa").unwrap()[0]));
 }
 
 #[test]
@@ -803,9 +958,863 @@ fn seq_in_star() {
 #[test]
 fn greediness() {
     let re: Matcher1<_> = regex!(br"(.*)C.*");
-    assert_eq!(0..0_usize, re.match_ranges(b"C").unwrap().0);
-    assert_eq!(0..1_usize, re.match_ranges(b"aCX").unwrap().0);
-    assert_eq!(0..1_usize, re.match_ranges(b"CC").unwrap().0);
-    assert_eq!(0..3_usize, re.match_ranges(b"aCbC").unwrap().0);
-    assert_eq!(0..3_usize, re.match_ranges(b"aCbCX").unwrap().0);
+    assert_eq!(0..0_usize, re.match_ranges(b"C").unwrap()[0]);
+    assert_eq!(0..1_usize, re.match_ranges(b"aCX").unwrap()[0]);
+    assert_eq!(0..1_usize, re.match_ranges(b"CC").unwrap()[0]);
+    assert_eq!(0..3_usize, re.match_ranges(b"aCbC").unwrap()[0]);
+    assert_eq!(0..3_usize, re.match_ranges(b"aCbCX").unwrap()[0]);
+}
+
+#[test]
+fn laziness() {
+    let re: Matcher2<_> = regex!(br"(.*?),(.*)");
+    assert_eq!([0..1, 2..5], re.match_ranges(b"a,b,c").unwrap());
+    assert_eq!([0..0, 1..3], re.match_ranges(b",,,").unwrap());
+    let re: Matcher2<_> = regex!(br"(.*),(.*)");
+    assert_eq!([0..3, 4..5], re.match_ranges(b"a,b,c").unwrap());
+    let re: Matcher2<_> = regex!(br"(a+?)(a*)");
+    assert_eq!([0..1, 1..3], re.match_ranges(b"aaa").unwrap());
+    let re: Matcher2<_> = regex!(br"(a??)(a*)");
+    assert_eq!([0..0, 0..2], re.match_ranges(b"aa").unwrap());
+    let re: Matcher2<_> = regex!(br"(a{1,3}?)(a*)");
+    assert_eq!([0..1, 1..4], re.match_ranges(b"aaaa").unwrap());
+    let re: Matcher2<_> = regex!(br"(a{2,}?)(a*)");
+    assert_eq!([0..2, 2..4], re.match_ranges(b"aaaa").unwrap());
+    let re: Matcher2<_> = regex!(br"([a-z]*?) *(;.*)?");
+    assert_eq!([0..2, 4..6], re.match_ranges(b"ab  ;c").unwrap());
+    assert_eq!([0..2, 0..0], re.match_ranges(b"ab  ").unwrap());
+    // The first copy of a lazy counted repetition captures.
+    let re: Matcher1<_> = regex!(br"(a){0,2}?");
+    assert_eq!([Some(0..1)], re.match_ranges_opt(b"a").unwrap());
+    assert_eq!([Some(0..1)], re.match_ranges_opt(b"aa").unwrap());
+    assert_eq!([None], re.match_ranges_opt(b"").unwrap());
+    let re: Matcher1<_> = regex!(br"(a){0,3}?");
+    assert_eq!([Some(0..1)], re.match_ranges_opt(b"aa").unwrap());
+    let re: Matcher1<_> = regex!(br"(?:(a)b){0,2}?");
+    assert_eq!([Some(0..1)], re.match_ranges_opt(b"ab").unwrap());
+    assert_eq!([Some(0..1)], re.match_ranges_opt(b"abab").unwrap());
+    let re: Matcher1<_> = regex!(br"(a){0,2}?b");
+    assert_eq!(Some((1..3, [1..2])), re.captures_iter(b"Xab").next());
+    let re: Matcher1<_> = regex!(br"(.*?)C.*");
+    assert_eq!(0..1_usize, re.match_ranges(b"aCbC").unwrap()[0]);
+    assert_eq!(0..0_usize, re.match_ranges(b"CC").unwrap()[0]);
+    // Search
+    let re: Matcher2<_> = regex!(br"(.*?),(.*)");
+    assert_eq!(
+        Some((0..6, [0..2, 3..6])),
+        re.captures_iter(b" a,b,c").next()
+    );
+    let re: Matcher0<_> = regex!(br"<.*?>");
+    assert_eq!(
+        vec![0..3, 3..7],
+        re.find_iter(b"<a><bc>").collect::<Vec<_>>()
+    );
+    let re: Matcher0<_> = regex!(br"<.*>");
+    assert_eq!(vec![0..7], re.find_iter(b"<a><bc>").collect::<Vec<_>>());
+    let re: Matcher0<_> = regex!(br"a+?");
+    assert_eq!(Some(1..2), re.find(b"Xaa"));
+    let re: Matcher1<_> = regex!(br"a(b*?)");
+    assert_eq!(
+        vec![(0..1, [1..1]), (3..4, [4..4])],
+        re.captures_iter(b"abbab").collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn find() {
+    let re: Matcher0<_> = regex!(br"ab");
+    assert_eq!(None, re.find(b""));
+    assert_eq!(None, re.find(b"a"));
+    assert_eq!(None, re.find(b"aXb"));
+    assert_eq!(Some(0..2), re.find(b"ab"));
+    assert_eq!(Some(1..3), re.find(b"aab"));
+    assert_eq!(Some(2..4), re.find(b"XXabXab"));
+    // Leftmost
+    let re: Matcher0<_> = regex!(br"a*b");
+    assert_eq!(Some(0..3), re.find(b"aab"));
+    assert_eq!(Some(1..4), re.find(b"Xaab"));
+    assert_eq!(Some(1..2), re.find(b"XbXab"));
+    let re: Matcher0<_> = regex!(br"b|ab|abc");
+    assert_eq!(Some(1..3), re.find(b"Xabc"));
+    assert_eq!(Some(1..3), re.find(b"XabXb"));
+    // First
+    let re: Matcher0<_> = regex!(br"a|ab|abc");
+    assert_eq!(Some(0..1), re.find(b"abc"));
+    assert_eq!(Some(0..1), re.find(b"abX"));
+    let re: Matcher0<_> = regex!(br"abc|ab|a");
+    assert_eq!(Some(0..3), re.find(b"abc"));
+    assert_eq!(Some(0..2), re.find(b"abX"));
+    let re: Matcher0<_> = regex!(br"a+");
+    assert_eq!(Some(1..4), re.find(b"XaaaXaa"));
+    let re: Matcher0<_> = regex!(br"a+?b?");
+    assert_eq!(Some(0..1), re.find(b"aab"));
+    assert_eq!(Some(0..2), re.find(b"ab"));
+    let re: Matcher2<_> = regex!(br"(a|ab)(c|bcd)?");
+    assert_eq!(Some((0..4, [0..1, 1..4])), re.captures_iter(b"abcd").next());
+    assert_eq!(Some((0..1, [0..1, 0..0])), re.captures_iter(b"abc").next());
+    let re: Matcher1<_> = regex!(br"(a|ab)(?:c|bcd)");
+    assert_eq!(Some((0..4, [0..1])), re.captures_iter(b"abcd").next());
+    let re: Matcher2<_> = regex!(br"\b(a*?)(a*)\b");
+    assert_eq!(Some((1..3, [1..1, 1..3])), re.captures_iter(b" aa").next());
+    // Repetitions of expressions that match the empty string
+    let re: Matcher0<_> = regex!(br"(?:c*|b)*");
+    assert_eq!(Some(0..0), re.find(b"b"));
+    let re: Matcher0<_> = regex!(br"(?:c*|b)+");
+    assert_eq!(Some(0..2), re.find(b"cb"));
+    let re: Matcher0<_> = regex!(br"(?:a??)+");
+    assert_eq!(Some(0..0), re.find(b"a"));
+    let re: Matcher0<_> = regex!(br"(?:b?a??)+");
+    assert_eq!(Some(0..1), re.find(b"ba"));
+    let re: Matcher1<_> = regex!(br"b*?(b??a*b*?)*c");
+    assert_eq!(Some((0..4, [1..3])), re.captures_iter(b"bbac").next());
+    // Empty matches
+    let re: Matcher0<_> = regex!(br"a*");
+    assert_eq!(Some(0..0), re.find(b""));
+    assert_eq!(Some(0..0), re.find(b"Xa"));
+    assert_eq!(Some(0..2), re.find(b"aaX"));
+    let re: Matcher0<_> = regex!(br"");
+    assert_eq!(Some(0..0), re.find(b""));
+    assert_eq!(Some(0..0), re.find(b"X"));
+}
+
+#[test]
+fn find_at() {
+    let re: Matcher0<_> = regex!(br"ab");
+    assert_eq!(Some(0..2), re.find_at(b"abXab", 0));
+    assert_eq!(Some(3..5), re.find_at(b"abXab", 1));
+    assert_eq!(Some(3..5), re.find_at(b"abXab", 3));
+    assert_eq!(None, re.find_at(b"abXab", 4));
+    assert_eq!(None, re.find_at(b"abXab", 5));
+    assert_eq!(None, re.find_at(b"abXab", 6));
+    assert_eq!(None, re.find_at(b"abXab", usize::MAX));
+    let re: Matcher0<_> = regex!(br"a*");
+    assert_eq!(Some(2..2), re.find_at(b"aX", 2));
+    assert_eq!(None, re.find_at(b"aX", 3));
+    let re: Matcher0<_> = regex!(br"");
+    assert_eq!(Some(1..1), re.find_at(b"X", 1));
+    assert_eq!(None, re.find_at(b"X", 2));
+}
+
+#[test]
+fn find_iter() {
+    let re: Matcher0<_> = regex!(br"ab");
+    assert_eq!(0, re.find_iter(b"").count());
+    assert_eq!(0, re.find_iter(b"aXb").count());
+    assert_eq!(
+        vec![0..2, 3..5, 5..7],
+        re.find_iter(b"abXabab").collect::<Vec<_>>()
+    );
+    let re: Matcher0<_> = regex!(br"aa");
+    assert_eq!(vec![0..2, 2..4], re.find_iter(b"aaaaa").collect::<Vec<_>>());
+    // Empty matches
+    let re: Matcher0<_> = regex!(br"a*");
+    assert_eq!(vec![0..0], re.find_iter(b"").collect::<Vec<_>>());
+    assert_eq!(
+        vec![0..2, 3..3, 4..5],
+        re.find_iter(b"aaXYa").collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![0..0, 1..2, 3..3],
+        re.find_iter(b"XaY").collect::<Vec<_>>()
+    );
+    let re: Matcher0<_> = regex!(br"");
+    assert_eq!(
+        vec![0..0, 1..1, 2..2],
+        re.find_iter(b"XY").collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn captures_iter() {
+    let re: Matcher0<_> = regex!(br"a");
+    assert_eq!(
+        vec![(1..2, []), (2..3, [])],
+        re.captures_iter(b"Xaa").collect::<Vec<_>>()
+    );
+    let re: Matcher2<_> = regex!(br"([a-z]+)=([0-9]*)");
+    assert_eq!(
+        vec![
+            (0..4, [0..1, 2..4]),
+            (5..8, [5..7, 8..8]),
+            (10..13, [10..11, 12..13])
+        ],
+        re.captures_iter(b"a=12 bc= =d=3").collect::<Vec<_>>()
+    );
+    let re: Matcher1<_> = regex!(br"(.*)C.*");
+    assert_eq!(
+        vec![(0..6, [0..4])],
+        re.captures_iter(b"XaCbCX").collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn anchors() {
+    let re: Matcher0<_> = regex!(br"^ab$");
+    assert!(re.is_match(b"ab"));
+    assert!(!re.is_match(b"abX"));
+    assert_eq!(Some(0..2), re.find(b"ab"));
+    assert_eq!(None, re.find(b"Xab"));
+    assert_eq!(None, re.find(b"abX"));
+    let re: Matcher0<_> = regex!(br"^a");
+    assert_eq!(vec![0..1], re.find_iter(b"aaXa").collect::<Vec<_>>());
+    assert_eq!(None, re.find_at(b"aa", 1));
+    let re: Matcher0<_> = regex!(br"a$");
+    assert_eq!(vec![3..4], re.find_iter(b"aaXa").collect::<Vec<_>>());
+    let re: Matcher0<_> = regex!(br"\Aa|b\z");
+    assert_eq!(vec![0..1, 3..4], re.find_iter(b"abab").collect::<Vec<_>>());
+    let re: Matcher0<_> = regex!(br"^");
+    assert!(re.is_match(b""));
+    assert!(!re.is_match(b"X"));
+    assert_eq!(vec![0..0], re.find_iter(b"XY").collect::<Vec<_>>());
+    let re: Matcher0<_> = regex!(br"$");
+    assert_eq!(vec![2..2], re.find_iter(b"XY").collect::<Vec<_>>());
+    let re: Matcher0<_> = regex!(br"a^b");
+    assert!(!re.is_match(b"ab"));
+    assert_eq!(None, re.find(b"ab"));
+    let re: Matcher1<_> = regex!(br"(^a|b)+");
+    assert_eq!(Some(0..3), re.find(b"abbX"));
+    assert_eq!(Some(1..3), re.find(b"Xbba"));
+    assert_eq!([0..1], re.match_ranges(b"abb").unwrap());
+    assert!(!re.is_match(b"ba"));
+}
+
+#[test]
+fn word_boundary() {
+    let re: Matcher0<_> = regex!(br"\b[a-z]+\b");
+    assert!(re.is_match(b"ab"));
+    assert_eq!(Some(4..7), re.find(b"a1b abc"));
+    assert_eq!(
+        vec![0..2, 8..9],
+        re.find_iter(b"ab c_ 9 d.").collect::<Vec<_>>()
+    );
+    let re: Matcher0<_> = regex!(br"\b");
+    assert!(!re.is_match(b""));
+    assert_eq!(vec![1..1, 3..3], re.find_iter(b" ab-").collect::<Vec<_>>());
+    let re: Matcher0<_> = regex!(br"\B");
+    assert!(re.is_match(b""));
+    assert_eq!(
+        vec![0..0, 2..2, 4..4, 5..5],
+        re.find_iter(b" ab-.").collect::<Vec<_>>()
+    );
+    let re: Matcher1<_> = regex!(br"\B([a-z]+)\B");
+    assert_eq!([1..3], re.captures_iter(b"abcd").next().unwrap().1);
+    assert!(!re.is_match(b"abc"));
+    let re: Matcher0<_> = regex!(br"a\bb");
+    assert_eq!(None, re.find(b"ab"));
+    let re: Matcher0<_> = regex!(br"a\b-");
+    assert!(re.is_match(b"a-"));
+    let re: Matcher0<_> = regex!(br".\b");
+    for b in 0..=255_u8 {
+        assert_eq!(
+            b.is_ascii_alphanumeric() || b == b'_',
+            re.is_match(&[b]),
+            "{}",
+            b
+        );
+    }
+}
+
+#[test]
+fn case_insensitive() {
+    let re: Matcher0<_> = regex!(br"(?i)get");
+    assert!(re.is_match(b"get"));
+    assert!(re.is_match(b"GET"));
+    assert!(re.is_match(b"gEt"));
+    assert!(!re.is_match(b"get "));
+    let re: Matcher1<_> = regex!(br"(?i:content-type): *([a-z/]+)");
+    assert_eq!(
+        b"text/html",
+        re.match_slices(b"Content-Type: text/html").unwrap()[0]
+    );
+    assert!(!re.is_match(b"Content-Type: TEXT/HTML"));
+    let re: Matcher0<_> = regex!(br"(?i)[^a-c]");
+    for b in 0..=255_u8 {
+        assert_eq!(!b"abcABC".contains(&b), re.is_match(&[b]), "{}", b);
+    }
+    let re: Matcher0<_> = regex!(br"(?i)[-x]\W");
+    assert!(re.is_match(b"X."));
+    assert!(re.is_match(b"-."));
+    assert!(!re.is_match(b"Xa"));
+    let re: Matcher0<_> = regex!(br"(?i)a(?-i)a");
+    assert!(re.is_match(b"Aa"));
+    assert!(!re.is_match(b"AA"));
+}
+
+#[test]
+fn multiline() {
+    let re: Matcher0<_> = regex!(br"(?m)^a$");
+    assert!(re.is_match(b"a"));
+    assert!(!re.is_match(b"a\n"));
+    assert_eq!(
+        vec![0..1, 5..6, 7..8],
+        re.find_iter(b"a\nab\na\na").collect::<Vec<_>>()
+    );
+    let re: Matcher1<_> = regex!(br"(?m)^([a-z]*)$");
+    assert_eq!(
+        vec![(0..2, [0..2]), (3..3, [3..3]), (4..5, [4..5])],
+        re.captures_iter(b"ab\n\nc").collect::<Vec<_>>()
+    );
+    let re: Matcher0<_> = regex!(br"(?m)\Aa\z");
+    assert_eq!(None, re.find(b"\na\n"));
+    let re: Matcher0<_> = regex!(br"(?m:^a)|b$");
+    assert_eq!(
+        vec![2..3, 6..7],
+        re.find_iter(b"b\nab\nbb").collect::<Vec<_>>()
+    );
+    let re: Matcher0<_> = regex!(br"(?m)(?-m)^a");
+    assert_eq!(None, re.find(b"\na"));
+}
+
+#[test]
+fn strings() {
+    let re: StrMatcher0<_> = regex!(r"naïve");
+    assert!(re.is_match("naïve"));
+    assert!(!re.is_match("naive"));
+    let re: StrMatcher0<_> = regex!(r"é+");
+    assert!(re.is_match("ééé"));
+    assert!(!re.is_match("é\u{301}"));
+    let re: StrMatcher2<_> = regex!(r"(.)(.*)");
+    assert_eq!(["€", "uro"], re.match_slices("€uro").unwrap());
+    assert_eq!(["😀", ""], re.match_slices("😀").unwrap());
+    assert_eq!([0..4, 4..8], re.match_ranges("😀😀").unwrap());
+    let re: StrMatcher0<_> = regex!(r".");
+    for c in [
+        'a', '\n', '\u{7F}', '\u{80}', 'é', '\u{7FF}', '\u{800}', '€', '\u{FFFF}', '😀',
+    ]
+    .iter()
+    .copied()
+    {
+        assert!(re.is_match(c.encode_utf8(&mut [0; 4])), "{:?}", c);
+    }
+    assert!(!re.is_match(""));
+    assert!(!re.is_match("ab"));
+    let re: StrMatcher1<_> = regex!(r"([^a])");
+    assert_eq!(["ü"], re.match_slices("ü").unwrap());
+    assert!(!re.is_match("a"));
+    let re: StrMatcher0<_> = regex!(r"[à-ÿ]+");
+    assert!(re.is_match("àéÿ"));
+    assert!(!re.is_match("aé"));
+    assert!(!re.is_match("Ā"));
+    let re: StrMatcher0<_> = regex!(r"\xE9");
+    assert!(re.is_match("é"));
+    let re: StrMatcher1<_> = regex!(r"(?i)stra(ß|ss)e");
+    assert_eq!(["ß"], re.match_slices("STRAßE").unwrap());
+}
+
+#[test]
+fn string_search() {
+    let re: StrMatcher0<_> = regex!(r"[^ ]+");
+    let data = "Grüße aus Köln";
+    let words: Vec<&str> = re.find_iter(data).map(|range| &data[range]).collect();
+    assert_eq!(vec!["Grüße", "aus", "Köln"], words);
+    // Empty matches fall on char boundaries.
+    let re: StrMatcher0<_> = regex!(r"x*");
+    assert_eq!(
+        vec![0..0, 2..2, 5..5, 9..9],
+        re.find_iter("é€😀").collect::<Vec<_>>()
+    );
+    assert_eq!(Some(2..2), re.find_at("é€", 1));
+    let re: StrMatcher1<_> = regex!(r"ö(.)");
+    assert_eq!(
+        vec![(1..4, [3..4]), (4..7, [6..7])],
+        re.captures_iter("jösöt").collect::<Vec<_>>()
+    );
+    assert_eq!(Some(1..4), re.find("jösöt"));
+}
+
+#[test]
+fn prefilter() {
+    use safe_regex::internal::{find_any_byte, find_in_set, find_literal};
+    let data = b"0123456789abcdefghijklmnopqrstuvwxyz".to_vec();
+    for (n, b) in data.iter().enumerate() {
+        assert_eq!(Some(n), find_any_byte(&[*b], &data));
+        assert_eq!(Some(n), find_any_byte(&[b'!', *b, b'Z'], &data));
+        assert_eq!(Some(n), find_literal(&data[n..], &data));
+        let mut set = [false; 256];
+        set[usize::from(*b)] = true;
+        assert_eq!(Some(n), find_in_set(&set, &data));
+    }
+    assert_eq!(None, find_any_byte(b"!Z", &data));
+    assert_eq!(Some(9), find_any_byte(b"a9", &data));
+    assert_eq!(Some(0), find_literal(b"", &data));
+    assert_eq!(Some(2), find_literal(b"aab", b"aaaab"));
+    assert_eq!(None, find_literal(b"ab", b"aaaaaaaaaaaaaaaaaaa"));
+    // Matchers skip to the bytes that can start a match.
+    let re: Matcher1<_> = regex!(br".*(2G8H81RFNZ).*");
+    let mut data = vec![b'x'; 100];
+    assert_eq!(None, re.match_slices(&data));
+    data[50..60].copy_from_slice(b"2G8H81RFNZ");
+    assert_eq!(Some([b"2G8H81RFNZ".as_ref()]), re.match_slices(&data));
+    let re: Matcher1<_> = regex!(br"[0-9]+-([0-9]+)");
+    assert_eq!(
+        vec![(20..26, [24..26]), (40..47, [42..47])],
+        re.captures_iter(b"...................x123-45..............1-12345")
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn counted_repeat() {
+    use safe_regex::internal::CountedRepeat;
+    let mut counter = CountedRepeat::<char, 1, 2, false>::new();
+    assert!(counter.is_empty());
+    counter.push(true, Some('a'));
+    assert_eq!(None, counter.exit());
+    counter.push(true, Some('b'));
+    assert_eq!(Some('a'), counter.exit());
+    counter.push(true, Some('c'));
+    assert_eq!(Some('b'), counter.exit());
+    counter.push(true, None);
+    assert_eq!(Some('c'), counter.exit());
+    counter.push(true, None);
+    assert_eq!(Some('c'), counter.exit());
+    assert!(!counter.is_empty());
+    counter.push(true, None);
+    assert_eq!(None, counter.exit());
+    assert!(counter.is_empty());
+    counter.push(true, Some('d'));
+    counter.push(false, Some('e'));
+    counter.push(true, None);
+    assert_eq!(None, counter.exit());
+    assert!(counter.is_empty());
+    // `exit` returns the newest thread, or the oldest one when `OLDEST` is
+    // true.
+    let mut newest = CountedRepeat::<char, 0, 3, false>::new();
+    let mut oldest = CountedRepeat::<char, 0, 3, true>::new();
+    for data in ['a', 'b', 'c'] {
+        newest.push(true, Some(data));
+        oldest.push(true, Some(data));
+    }
+    assert_eq!(Some('c'), newest.exit());
+    assert_eq!(Some('a'), oldest.exit());
+    // Long repetitions compile quickly and match in linear time.
+    let re: Matcher0<_> = regex!(br"\b[a-z]{0,1000}\b");
+    let mut data = vec![b'a'; 1000];
+    assert!(re.is_match(&data));
+    data.push(b'a');
+    assert!(!re.is_match(&data));
+    let data = [b"b ".as_ref(), &[b'a'; 2000], b" ", &[b'a'; 1000]].concat();
+    assert_eq!(
+        vec![0..1, 2..2, 2002..2002, 2003..3003],
+        re.find_iter(&data).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn small_stack() {
+    // The search of a long repetition keeps at most
+    // `safe_regex_compiler::program::MAX_SEARCH_MEMORY` bytes on the stack,
+    // and whole-data matches do not reserve them.
+    std::thread::Builder::new()
+        .stack_size(512 * 1024)
+        .spawn(|| {
+            let re: Matcher1<_> = regex!(br"([a-z]{0,1000})x");
+            let data = [&[b'a'; 1200][..], b"x"].concat();
+            assert!(!re.is_match(&data));
+            assert_eq!(Some([&data[200..1200]]), re.match_slices(&data[200..]));
+            assert_eq!(Some(200..1201), re.find(&data));
+            assert_eq!(
+                Some((200..1201, [200..1200])),
+                re.captures_iter(&data).next()
+            );
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+#[test]
+fn string_named_groups() {
+    let re = regex!(r"(?P<key>[^=]+)=(?P<value>.*)");
+    let slices = re.match_slices("clé=värde").unwrap();
+    assert_eq!("clé", slices.key());
+    assert_eq!("värde", slices.value());
+    assert_eq!(0..4, re.match_ranges("clé=värde").unwrap().key());
+    assert!(re.is_match("ü="));
+}
+
+#[test]
+fn unicode_properties() {
+    let re: Matcher0<_> = regex!(br"\p{Greek}+");
+    assert!(re.is_match("αβγ".as_bytes()));
+    assert!(!re.is_match(b"abc"));
+    assert!(!re.is_match("αbγ".as_bytes()));
+    let re: Matcher0<_> = regex!(br"\P{Greek}");
+    assert!(re.is_match(b"a"));
+    assert!(re.is_match("Ж".as_bytes()));
+    assert!(!re.is_match("α".as_bytes()));
+    let re: Matcher0<_> = regex!(br"\p{Nd}+");
+    assert!(re.is_match(b"123"));
+    assert!(re.is_match("١٢٣".as_bytes()));
+    assert!(!re.is_match(b"1a"));
+    let re: Matcher1<_> = regex!(br"([\p{Greek}\d]+) ");
+    assert_eq!(Some(["αβ1".as_bytes()]), re.match_slices("αβ1 ".as_bytes()));
+    let re: StrMatcher1<_> = regex!(r".*?(\p{Greek}+).*");
+    assert_eq!(Some(["αβ"]), re.match_slices("abc αβ def"));
+    let re: StrMatcher0<_> = regex!(r"(?u)\w+");
+    assert!(re.is_match("hello_αβγ_Жж_123"));
+    assert!(!re.is_match("a b"));
+}
+
+/// Checks that `stream` gives the same result as `matcher` on `data`, no
+/// matter how `data` is split into chunks.
+fn check_stream<S: safe_regex::internal::StreamState<N>, const N: usize>(
+    stream: &safe_regex::StreamMatcher<S, N>,
+    matcher: impl Fn(&[u8]) -> Option<[Option<core::ops::Range<usize>>; N]>,
+    data: &[u8],
+) {
+    let expected = matcher(data)
+        .map(|ranges| ranges.map(|range| range.map(|range| range.start as u64..range.end as u64)));
+    for split in 0..=data.len() {
+        let mut stream = stream.clone();
+        stream.push(&data[..split]);
+        stream.push(&data[split..]);
+        assert_eq!(
+            expected,
+            stream.finish_opt(),
+            "{:?} {}",
+            escape_ascii(data),
+            split
+        );
+    }
+    let mut stream = stream.clone();
+    for b in data {
+        stream.push(&[*b]);
+    }
+    assert_eq!(expected, stream.finish_opt(), "{:?}", escape_ascii(data));
+}
+
+#[test]
+fn stream() {
+    let stream: StreamMatcher0<_> = regex_stream!(br"");
+    assert_eq!(Some([]), stream.clone().finish());
+    check_stream(&stream, |d| regex!(br"").match_ranges_opt(d), b"a");
+    let stream: StreamMatcher0<_> = regex_stream!(br"ab+c");
+    let matcher: Matcher0<_> = regex!(br"ab+c");
+    for data in [&b""[..], b"a", b"ac", b"abc", b"abbbc", b"abcX"] {
+        check_stream(&stream, |d| matcher.match_ranges_opt(d), data);
+    }
+    let stream: StreamMatcher2<_> = regex_stream!(br"([a-z]+)=([0-9]*)?;?");
+    let matcher: Matcher2<_> = regex!(br"([a-z]+)=([0-9]*)?;?");
+    for data in [&b"a="[..], b"ab=", b"ab=12", b"ab=12;", b"=1", b"ab=1x"] {
+        check_stream(&stream, |d| matcher.match_ranges_opt(d), data);
+    }
+    let stream: StreamMatcher1<_> = regex_stream!(br".*?(a+?)(?:b|ab)\b.*");
+    let matcher: Matcher1<_> = regex!(br".*?(a+?)(?:b|ab)\b.*");
+    for data in [&b"aab"[..], b"xaaab y", b"aabc", b"ab-aab"] {
+        check_stream(&stream, |d| matcher.match_ranges_opt(d), data);
+    }
+    let stream: StreamMatcher0<_> = regex_stream!(br"(?m)^a$\n^b\B.$");
+    let matcher: Matcher0<_> = regex!(br"(?m)^a$\n^b\B.$");
+    for data in [&b"a\nbc"[..], b"a\nb-", b"a\nb"] {
+        check_stream(&stream, |d| matcher.match_ranges_opt(d), data);
+    }
+    let stream: StreamMatcher1<_> = regex_stream!(r"(\p{Greek}+)!");
+    let matcher: StrMatcher1<_> = regex!(r"(\p{Greek}+)!");
+    check_stream(
+        &stream,
+        |d| matcher.match_ranges_opt(core::str::from_utf8(d).ok()?),
+        "αβ!".as_bytes(),
+    );
+    let stream: StreamMatcher2<_> = regex_stream!(br"(k)=([a-z]{0,20})");
+    let matcher: Matcher2<_> = regex!(br"(k)=([a-z]{0,20})");
+    let long = [&b"k="[..], &[b'a'; 20]].concat();
+    let too_long = [&long[..], b"a"].concat();
+    for data in [&b"k="[..], b"k=abc", b"k=a1", &long, &too_long] {
+        check_stream(&stream, |d| matcher.match_ranges_opt(d), data);
+    }
+    let stream: StreamMatcher2<_> = regex_stream!(br"(a*?)(a{1,16}?)a*");
+    let matcher: Matcher2<_> = regex!(br"(a*?)(a{1,16}?)a*");
+    for data in [&b"a"[..], &[b'a'; 16], &[b'a'; 30]] {
+        check_stream(&stream, |d| matcher.match_ranges_opt(d), data);
+    }
+    let mut stream: StreamMatcher1<_> = regex_stream!(br".*(x)");
+    let chunk = [0_u8; 1000];
+    for _ in 0..1000 {
+        stream.push(&chunk);
+    }
+    stream.push(b"x");
+    assert_eq!(Some([1_000_000..1_000_001]), stream.finish());
+}
+
+#[test]
+fn stream_reset() {
+    let mut stream: StreamMatcher1<_> = regex_stream!(br"a(b*)");
+    stream.push(b"X");
+    // The matcher ignores input after it cannot match.
+    stream.push(b"abb");
+    assert_eq!(None, stream.clone().finish());
+    stream.reset();
+    stream.push(b"ab");
+    stream.push(b"b");
+    assert_eq!(Some([1..3]), stream.clone().finish());
+    stream.reset();
+    stream.push(b"a");
+    assert_eq!(Some([Some(1..1)]), stream.finish_opt());
+}
+
+#[test]
+fn regex_set() {
+    let set: RegexSet<_, 1> = regex_set!(br"");
+    assert_eq!([true], set.matches(b"").into_array());
+    assert_eq!([false], set.matches(b"a").into_array());
+    let set: RegexSet<_, 6> = regex_set!(
+        br"/",
+        br"/users/([0-9]+)",
+        br"/users/.*",
+        br"(?i)/ADMIN/?",
+        br".*\bedit$",
+        br"/users/[0-9]+/edit",
+    );
+    let matchers: [&dyn IsMatch; 6] = [
+        &regex!(br"/"),
+        &regex!(br"/users/([0-9]+)"),
+        &regex!(br"/users/.*"),
+        &regex!(br"(?i)/ADMIN/?"),
+        &regex!(br".*\bedit$"),
+        &regex!(br"/users/[0-9]+/edit"),
+    ];
+    for data in [
+        &b""[..],
+        b"/",
+        b"/users/",
+        b"/users/42",
+        b"/users/42/edit",
+        b"/users/42/redit",
+        b"/admin/",
+        b"/Admin",
+        b"/about",
+    ] {
+        let matches = set.matches(data);
+        assert_eq!(6, matches.len());
+        assert_eq!(
+            matchers.map(|m| m.is_match(data)),
+            matches.into_array(),
+            "{}",
+            escape_ascii(data)
+        );
+        assert_eq!(
+            matchers.iter().any(|m| m.is_match(data)),
+            set.is_match(data)
+        );
+    }
+    assert_eq!(
+        vec![2, 4, 5],
+        set.matches(b"/users/42/edit").iter().collect::<Vec<_>>()
+    );
+    assert!(set.matches(b"/users/42").matched(1));
+    assert!(!set.matches(b"/about").matched_any());
+}
+
+#[test]
+fn lexer() {
+    #[derive(Debug, PartialEq)]
+    enum Token {
+        Else,
+        ElseIf,
+        Ident,
+        Num,
+        Float,
+        Op,
+        Space,
+    }
+    let lexer = lexer!(
+        Token::Else => br"else",
+        Token::ElseIf => br"else if\b",
+        Token::Ident => br"[a-z][a-z0-9]*",
+        Token::Num => br"[0-9]+",
+        Token::Float => br"[0-9]+\.[0-9]+",
+        Token::Op => br"[-+*/=]|==",
+        Token::Space => br" +|\b",
+    );
+    let tokens = |data: &[u8]| -> Vec<Result<(Token, core::ops::Range<usize>), LexError>> {
+        lexer.tokens(data).collect()
+    };
+    assert_eq!(Vec::<Result<_, _>>::new(), tokens(b""));
+    // Longest match wins.  `12.` backs up to `12`, the last accepted match.
+    assert_eq!(
+        vec![Ok((Token::Num, 0..2)), Err(LexError { position: 2 })],
+        tokens(b"12.")
+    );
+    assert_eq!(vec![Ok((Token::Float, 0..4))], tokens(b"12.5"));
+    assert_eq!(
+        vec![
+            Ok((Token::ElseIf, 0..7)),
+            Ok((Token::Space, 7..8)),
+            Ok((Token::Ident, 8..9)),
+            Ok((Token::Op, 9..11)),
+            Ok((Token::Num, 11..12)),
+        ],
+        tokens(b"else if x==1")
+    );
+    // `else` matches `Else` and `Ident`.  The first rule wins.
+    // `else ifx` is not `ElseIf` because of the `\b`.
+    assert_eq!(
+        vec![
+            Ok((Token::Else, 0..4)),
+            Ok((Token::Space, 4..5)),
+            Ok((Token::Ident, 5..8)),
+            Ok((Token::Space, 8..9)),
+            Ok((Token::Ident, 9..14)),
+        ],
+        tokens(b"else ifx elses")
+    );
+    // Empty matches like `\b` never become tokens.
+    assert_eq!(
+        vec![Ok((Token::Ident, 0..1)), Err(LexError { position: 1 })],
+        tokens(b"a!b")
+    );
+    assert_eq!(
+        "no token matches at byte 1",
+        LexError { position: 1 }.to_string()
+    );
+}
+
+#[test]
+fn from_regex() {
+    #[derive(Debug, FromRegex, PartialEq)]
+    #[regex(br"([0-9]{4})-([0-9]{2})-([0-9]{2})")]
+    struct Date {
+        year: u16,
+        month: u8,
+        day: u8,
+    }
+    assert_eq!(
+        Ok(Date {
+            year: 2021,
+            month: 5,
+            day: 17
+        }),
+        Date::from_bytes(b"2021-05-17")
+    );
+    assert_eq!(
+        Err(FromRegexError::NoMatch),
+        Date::from_bytes(b"2021-05-17X")
+    );
+    // Fields without `FromStr` convert with `TryFrom<&[u8]>`.
+    // Named groups and non-capturing groups work, too.
+    #[derive(Debug, FromRegex, PartialEq)]
+    #[regex(br"(?P<key>[a-z]+)(?:=([0-9]{2}))?")]
+    pub(crate) struct Entry(pub String, [u8; 2]);
+    assert_eq!(
+        Ok(Entry("ab".to_string(), *b"42")),
+        Entry::from_bytes(b"ab=42")
+    );
+    // A group that did not participate in the match captures no bytes.
+    assert_eq!(
+        Err(FromRegexError::InvalidField("1")),
+        Entry::from_bytes(b"ab")
+    );
+    // A string literal matches `&str` with `FromStr`.
+    #[derive(Debug, FromRegex, PartialEq)]
+    #[regex(r"(.+)=(\p{Greek})")]
+    struct Pair {
+        pub key: Vec<u8>,
+        value: char,
+    }
+    assert_eq!(
+        Ok(Pair {
+            key: b"ab".to_vec(),
+            value: 'α'
+        }),
+        "ab=α".parse::<Pair>()
+    );
+    assert_eq!(Err(FromRegexError::NoMatch), "ab=a".parse::<Pair>());
+    #[derive(Debug, FromRegex, PartialEq)]
+    #[regex(br"ok")]
+    struct Unit;
+    assert_eq!(Ok(Unit), Unit::from_bytes(b"ok"));
+    assert_eq!(Err(FromRegexError::NoMatch), Unit::from_bytes(b"no"));
+    assert_eq!(
+        "invalid value for field `day`",
+        FromRegexError::InvalidField("day").to_string()
+    );
+}
+
+safe_regex::define_regex!(
+    /// A key and a value.
+    #[derive(Clone, Copy, Debug, Default)]
+    pub(crate) KeyValue = br"(?P<key>[a-z]+)=([0-9]*)";
+    Empty = br"";
+    StrWord = r"([^ ]+)";
+);
+static KEY_VALUE: KeyValue = KeyValue::new();
+
+trait Pattern {
+    fn pattern(&self) -> KeyValue;
+}
+struct Config;
+impl Pattern for Config {
+    fn pattern(&self) -> KeyValue {
+        KeyValue::new()
+    }
+}
+
+#[test]
+fn define_regex() {
+    assert_eq!(0, core::mem::size_of::<KeyValue>());
+    assert!(KEY_VALUE.is_match(b"a=1"));
+    assert!(!KEY_VALUE.is_match(b"a"));
+    assert_eq!([0..2, 3..5], KEY_VALUE.match_ranges(b"ab=12").unwrap());
+    assert_eq!(
+        [Some(b"ab".as_ref()), Some(b"".as_ref())],
+        KEY_VALUE.match_slices_opt(b"ab=").unwrap()
+    );
+    let key_value = KEY_VALUE;
+    assert_eq!(
+        vec![1..4, 5..8],
+        key_value.find_iter(b" a=1 b=2").collect::<Vec<_>>()
+    );
+    let is_match: &dyn IsMatch = &Config.pattern();
+    assert!(is_match.is_match(b"x="));
+    assert!(Empty::new().is_match(b""));
+    assert!(!Empty::new().is_match(b"a"));
+    assert_eq!(
+        vec![0..2, 3..5],
+        StrWord::new().find_iter("é à").collect::<Vec<_>>()
+    );
+    assert_eq!(["à"], StrWord::new().match_slices("à").unwrap());
+}
+
+#[test]
+fn regex_trait() {
+    let rules: Vec<Box<dyn Regex>> = vec![
+        Box::new(regex!(br"a")),
+        Box::new(regex!(br"(?P<key>[a-z]+)=([0-9]+)?")),
+        Box::new(regex!(r"(é)")),
+        Box::new(KeyValue::new()),
+        Box::new(Empty::new()),
+    ];
+    assert_eq!(
+        vec![
+            "a",
+            "(?P<key>[a-z]+)=([0-9]+)?",
+            "(é)",
+            "(?P<key>[a-z]+)=([0-9]*)",
+            ""
+        ],
+        rules.iter().map(|rule| rule.pattern()).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![0, 2, 1, 2, 0],
+        rules
+            .iter()
+            .map(|rule| rule.group_count())
+            .collect::<Vec<_>>()
+    );
+    let mut ranges = [7..7, 7..7, 7..7];
+    assert!(rules[0].match_ranges_into(b"a", &mut ranges));
+    assert_eq!([7..7, 7..7, 7..7], ranges);
+    assert!(!rules[1].match_ranges_into(b"ab", &mut ranges));
+    assert_eq!([7..7, 7..7, 7..7], ranges);
+    assert!(rules[1].match_ranges_into(b"ab=12", &mut ranges));
+    assert_eq!([0..2, 3..5, 7..7], ranges);
+    assert!(rules[1].match_ranges_into(b"ab=", &mut ranges));
+    assert_eq!([0..2, 0..0, 7..7], ranges);
+    assert!(rules[2].match_ranges_into("é".as_bytes(), &mut ranges[..1]));
+    assert_eq!([0..2, 0..0, 7..7], ranges);
+    assert!(rules[3].match_ranges_into(b"a=1", &mut ranges[..1]));
+    assert_eq!([0..1, 0..0, 7..7], ranges);
+    assert!(rules[4].match_ranges_into(b"", &mut []));
+    assert!(!rules[4].match_ranges_into(b"a", &mut []));
 }